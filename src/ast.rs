@@ -1,5 +1,7 @@
+use std::fmt;
+
 #[allow(dead_code)]
-use crate::lexer::Token;
+use crate::lexer::{Token, TokenKind};
 
 #[derive(Debug)]
 pub struct Ast {
@@ -11,6 +13,13 @@ pub struct Statement {
     pub create: Option<Create>,
     pub insert: Option<Insert>,
     pub select: Option<Select>,
+    pub update: Option<Update>,
+    pub delete: Option<Delete>,
+    pub create_type: Option<CreateType>,
+    pub create_sequence: Option<CreateSequence>,
+    pub create_index: Option<CreateIndex>,
+    pub drop_index: Option<DropIndex>,
+    pub explain: Option<Explain>,
     pub kind: StatementKind,
 }
 
@@ -19,39 +28,689 @@ pub enum StatementKind {
     Create,
     Insert,
     Select,
+    Update,
+    Delete,
+    CreateType,
+    CreateSequence,
+    CreateIndex,
+    DropIndex,
+    Explain,
 }
 
 #[derive(Debug, Clone)]
 pub struct Insert {
     pub table: Token,
+    // Columns the values go to, every column in order when empty
+    pub columns: Vec<Token>,
     pub values: Vec<Expression>,
+    pub on_conflict: Option<OnConflict>,
+    // Items of the RETURNING clause, none without one
+    pub returning: Vec<Expression>,
+}
+
+/// `ON CONFLICT [(columns)] DO NOTHING` or
+/// `ON CONFLICT (columns) DO UPDATE SET column = value, ... [WHERE ...]`
+#[derive(Debug, Clone)]
+pub struct OnConflict {
+    // Columns of the unique constraint to check, any one when empty
+    pub columns: Vec<Token>,
+    pub action: ConflictAction,
+    pub assignments: Vec<Assignment>,
+    pub where_clause: Option<Expression>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConflictAction {
+    DoNothing,
+    DoUpdate,
+}
+
+/// `UPDATE table SET column = value, ... [WHERE ...] [RETURNING ...]`
+#[derive(Debug, Clone)]
+pub struct Update {
+    pub table: Token,
+    pub assignments: Vec<Assignment>,
+    pub where_clause: Option<Expression>,
+    pub returning: Vec<Expression>,
+}
+
+/// `DELETE FROM table [WHERE ...] [RETURNING ...]`
+#[derive(Debug, Clone)]
+pub struct Delete {
+    pub table: Token,
+    pub where_clause: Option<Expression>,
+    pub returning: Vec<Expression>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Assignment {
+    pub column: Token,
+    pub value: Expression,
 }
 
 #[derive(Debug, Clone)]
 pub struct Select {
     pub from: Token,
+    // Set when FROM names a table-valued function rather than a table
+    pub from_function: Option<FunctionCall>,
+    // Tables joined to the FROM one, in order
+    pub joins: Vec<Join>,
     pub items: Vec<Expression>,
+    pub where_clause: Option<Expression>,
+    pub order_by: Vec<OrderingTerm>,
+    pub limit: Option<Expression>,
+    pub offset: Option<Expression>,
+}
+
+/// `[INNER] JOIN table ON condition`
+#[derive(Debug, Clone)]
+pub struct Join {
+    pub table: Token,
+    pub on: Expression,
+}
+
+/// `EXPLAIN [ANALYZE]` followed by a SELECT, INSERT, UPDATE or DELETE.
+/// One of the statements is set.
+#[derive(Debug, Clone)]
+pub struct Explain {
+    pub select: Option<Select>,
+    pub insert: Option<Insert>,
+    pub update: Option<Update>,
+    pub delete: Option<Delete>,
+    // Whether to also run the query and report what each operator did
+    pub analyze: bool,
 }
 
 #[derive(Debug, Clone)]
 pub struct Create {
     pub name: Token,
     pub cols: Vec<ColDefinition>,
+    // Constraints written after the columns
+    pub constraints: Vec<Constraint>,
+}
+
+/// `CREATE TYPE name AS ENUM ('label', ...)`
+#[derive(Debug, Clone)]
+pub struct CreateType {
+    pub name: Token,
+    pub labels: Vec<Token>,
 }
 
 #[derive(Debug, Clone)]
 pub struct ColDefinition {
     pub name: Token,
-    pub data_type: Token,
+    pub data_type: DataType,
+    pub constraints: Vec<Constraint>,
+}
+
+/// A constraint from CREATE TABLE. Written on a column it has no column
+/// list and applies to that column.
+#[derive(Debug, Clone)]
+pub struct Constraint {
+    // From `CONSTRAINT name`, generated when absent
+    pub name: Option<Token>,
+    pub kind: ConstraintKind,
+    pub columns: Vec<Token>,
+    // The value of a DEFAULT or the condition of a CHECK
+    pub expression: Option<Expression>,
+    pub references: Option<References>,
+    pub identity: Option<Identity>,
+}
+
+/// `GENERATED { ALWAYS | BY DEFAULT } AS IDENTITY [(options)]`
+#[derive(Debug, Clone)]
+pub struct Identity {
+    // Whether values may only come from the sequence
+    pub always: bool,
+    pub options: SequenceOptions,
+}
+
+/// `CREATE SEQUENCE name [options]`
+/// `CREATE [UNIQUE] INDEX [name] ON table [USING method] (element, ...)
+/// [WHERE ...]`
+#[derive(Debug, Clone)]
+pub struct CreateIndex {
+    // Named after the table and columns when left out
+    pub name: Option<Token>,
+    pub table: Token,
+    pub unique: bool,
+    // btree when left out
+    pub method: Option<Token>,
+    // Columns, or expressions of them
+    pub elements: Vec<Expression>,
+    // Makes it a partial index over the rows satisfying it
+    pub where_clause: Option<Expression>,
+}
+
+/// `DROP INDEX name`
+#[derive(Debug, Clone)]
+pub struct DropIndex {
+    pub name: Token,
+}
+
+#[derive(Debug, Clone)]
+pub struct CreateSequence {
+    pub name: Token,
+    pub options: SequenceOptions,
+}
+
+/// `[START [WITH] n] [INCREMENT [BY] n]`, defaulting to 1 and 1.
+#[derive(Debug, Clone, Default)]
+pub struct SequenceOptions {
+    pub start: Option<Expression>,
+    pub increment: Option<Expression>,
+}
+
+/// The target of a FOREIGN KEY: `REFERENCES table [(column, ...)]` with its
+/// actions. Without columns the primary key of the table is referenced.
+#[derive(Debug, Clone)]
+pub struct References {
+    pub table: Token,
+    pub columns: Vec<Token>,
+    pub on_delete: ReferentialAction,
+    pub on_update: ReferentialAction,
+}
+
+/// What happens to referencing rows when the row they reference is deleted
+/// or its key is updated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReferentialAction {
+    NoAction,
+    Restrict,
+    Cascade,
+    SetNull,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConstraintKind {
+    PrimaryKey,
+    Unique,
+    NotNull,
+    Default,
+    Check,
+    ForeignKey,
+    Identity,
+}
+
+/// A type name with its optional modifiers, such as `numeric(10, 2)`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DataType {
+    pub name: Token,
+    pub modifiers: Vec<Token>,
+    // An array of the type, such as `int[]`
+    pub array: bool,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Expression {
     pub literal: Token,
+    pub function: Option<FunctionCall>,
+    pub binary: Option<Box<BinaryExpression>>,
+    pub unary: Option<Box<UnaryExpression>>,
+    pub pattern_match: Option<Box<PatternMatch>>,
+    pub in_list: Option<Box<InList>>,
+    pub between: Option<Box<Between>>,
+    pub cast: Option<Box<Cast>>,
+    pub is_null: Option<Box<IsNull>>,
+    pub array: Option<Vec<Expression>>,
+    pub subscript: Option<Box<Subscript>>,
+    pub quantified: Option<Box<Quantified>>,
     pub kind: ExpressionKind,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ExpressionKind {
     Literal,
+    Function,
+    // A function call with an OVER clause
+    Window,
+    Binary,
+    Unary,
+    PatternMatch,
+    InList,
+    Between,
+    Cast,
+    IsNull,
+    // ARRAY[...]
+    Array,
+    Subscript,
+    Quantified,
+}
+
+impl Expression {
+    /// An expression of the given kind with all of its parts unset.
+    pub fn new(literal: Token, kind: ExpressionKind) -> Expression {
+        Expression {
+            literal,
+            function: None,
+            binary: None,
+            unary: None,
+            pattern_match: None,
+            in_list: None,
+            between: None,
+            cast: None,
+            is_null: None,
+            array: None,
+            subscript: None,
+            quantified: None,
+            kind,
+        }
+    }
+
+    /// The direct subexpressions, in evaluation order.
+    pub fn children(&self) -> Vec<&Expression> {
+        let mut children = Vec::new();
+        if let Some(function) = &self.function {
+            children.extend(function.args.iter());
+            if let Some(over) = &function.over {
+                children.extend(over.partition_by.iter());
+                children.extend(over.order_by.iter().map(|term| &term.expression));
+            }
+        }
+        if let Some(binary) = &self.binary {
+            children.push(&binary.a);
+            children.push(&binary.b);
+        }
+        if let Some(unary) = &self.unary {
+            children.push(&unary.operand);
+        }
+        if let Some(pattern_match) = &self.pattern_match {
+            children.push(&pattern_match.value);
+            children.push(&pattern_match.pattern);
+            children.extend(pattern_match.escape.iter());
+        }
+        if let Some(in_list) = &self.in_list {
+            children.push(&in_list.value);
+            children.extend(in_list.list.iter());
+        }
+        if let Some(between) = &self.between {
+            children.push(&between.value);
+            children.push(&between.low);
+            children.push(&between.high);
+        }
+        if let Some(cast) = &self.cast {
+            children.push(&cast.value);
+        }
+        if let Some(is_null) = &self.is_null {
+            children.push(&is_null.value);
+        }
+        if let Some(array) = &self.array {
+            children.extend(array.iter());
+        }
+        if let Some(subscript) = &self.subscript {
+            children.push(&subscript.value);
+            children.push(&subscript.index);
+        }
+        if let Some(quantified) = &self.quantified {
+            children.push(&quantified.value);
+            children.push(&quantified.array);
+        }
+        children
+    }
+
+    /// Whether `self` and `other` are the same expression, wherever they
+    /// were written and whatever the case of their function names.
+    pub fn same_as(&self, other: &Expression) -> bool {
+        let same_token =
+            |a: &Token, b: &Token| a.token_kind == b.token_kind && a.literal == b.literal;
+        let same_node = match (self, other) {
+            (a, b) if a.kind != b.kind => false,
+            (
+                Expression {
+                    function: Some(a), ..
+                },
+                Expression {
+                    function: Some(b), ..
+                },
+            ) => {
+                a.name.literal.eq_ignore_ascii_case(&b.name.literal)
+                    && match (&a.over, &b.over) {
+                        (Some(a), Some(b)) => a.same_as(b),
+                        (None, None) => true,
+                        _ => false,
+                    }
+            }
+            (Expression { cast: Some(a), .. }, Expression { cast: Some(b), .. }) => {
+                a.data_type
+                    .name
+                    .literal
+                    .eq_ignore_ascii_case(&b.data_type.name.literal)
+                    && a.data_type.array == b.data_type.array
+                    && a.data_type.modifiers.len() == b.data_type.modifiers.len()
+                    && a.data_type
+                        .modifiers
+                        .iter()
+                        .zip(&b.data_type.modifiers)
+                        .all(|(a, b)| a.literal == b.literal)
+            }
+            (a, b) => match a.kind {
+                ExpressionKind::InList => {
+                    a.in_list.as_ref().unwrap().negated == b.in_list.as_ref().unwrap().negated
+                }
+                ExpressionKind::Between => {
+                    a.between.as_ref().unwrap().negated == b.between.as_ref().unwrap().negated
+                }
+                ExpressionKind::IsNull => {
+                    a.is_null.as_ref().unwrap().negated == b.is_null.as_ref().unwrap().negated
+                }
+                ExpressionKind::PatternMatch => {
+                    same_token(&a.literal, &b.literal)
+                        && a.pattern_match.as_ref().unwrap().negated
+                            == b.pattern_match.as_ref().unwrap().negated
+                }
+                ExpressionKind::Quantified => {
+                    let (x, y) = (
+                        a.quantified.as_ref().unwrap(),
+                        b.quantified.as_ref().unwrap(),
+                    );
+                    same_token(&x.op, &y.op) && x.all == y.all
+                }
+                ExpressionKind::Array | ExpressionKind::Subscript => true,
+                _ => same_token(&a.literal, &b.literal),
+            },
+        };
+
+        let (children, other_children) = (self.children(), other.children());
+        same_node
+            && children.len() == other_children.len()
+            && children
+                .iter()
+                .zip(other_children)
+                .all(|(a, b)| a.same_as(b))
+    }
+}
+
+impl WindowSpec {
+    /// The parts of `same_as` that the partition and order expressions,
+    /// compared as children of the call, leave out.
+    fn same_as(&self, other: &WindowSpec) -> bool {
+        let same_bound = |a: &FrameBound, b: &FrameBound| {
+            a.kind == b.kind
+                && match (&a.offset, &b.offset) {
+                    (Some(a), Some(b)) => a.same_as(b),
+                    (None, None) => true,
+                    _ => false,
+                }
+        };
+        self.partition_by.len() == other.partition_by.len()
+            && self.order_by.len() == other.order_by.len()
+            && self
+                .order_by
+                .iter()
+                .zip(&other.order_by)
+                .all(|(a, b)| a.descending == b.descending)
+            && match (&self.frame, &other.frame) {
+                (Some(a), Some(b)) => {
+                    a.units.literal == b.units.literal
+                        && same_bound(&a.start, &b.start)
+                        && same_bound(&a.end, &b.end)
+                }
+                (None, None) => true,
+                _ => false,
+            }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BinaryExpression {
+    pub a: Expression,
+    pub b: Expression,
+    pub op: Token,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnaryExpression {
+    pub operand: Expression,
+    pub op: Token,
+}
+
+/// `value [NOT] LIKE|ILIKE|GLOB|REGEXP pattern [ESCAPE escape]` as well as the
+/// `~`, `~*`, `!~` and `!~*` regular expression operators.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatternMatch {
+    pub value: Expression,
+    pub pattern: Expression,
+    pub escape: Option<Expression>,
+    pub op: Token,
+    pub negated: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FunctionCall {
+    pub name: Token,
+    pub args: Vec<Expression>,
+    pub over: Option<Box<WindowSpec>>,
+}
+
+/// `OVER ([PARTITION BY ...] [ORDER BY ...] [frame])`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WindowSpec {
+    pub partition_by: Vec<Expression>,
+    pub order_by: Vec<OrderingTerm>,
+    pub frame: Option<WindowFrame>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OrderingTerm {
+    pub expression: Expression,
+    pub descending: bool,
+}
+
+/// `ROWS|RANGE BETWEEN start AND end`, or just `ROWS|RANGE start` in which
+/// case the frame ends at the current row.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WindowFrame {
+    pub units: Token,
+    pub start: FrameBound,
+    pub end: FrameBound,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrameBound {
+    pub kind: FrameBoundKind,
+    pub offset: Option<Expression>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FrameBoundKind {
+    UnboundedPreceding,
+    Preceding,
+    CurrentRow,
+    Following,
+    UnboundedFollowing,
+}
+
+/// `value [NOT] IN (list...)`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InList {
+    pub value: Expression,
+    pub list: Vec<Expression>,
+    pub negated: bool,
+}
+
+/// `value [NOT] BETWEEN low AND high`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Between {
+    pub value: Expression,
+    pub low: Expression,
+    pub high: Expression,
+    pub negated: bool,
+}
+
+/// `CAST(value AS data_type)` or `value::data_type`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cast {
+    pub value: Expression,
+    pub data_type: DataType,
+}
+
+/// `value IS [NOT] NULL`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IsNull {
+    pub value: Expression,
+    pub negated: bool,
+}
+
+/// `value[index]`, counting from 1
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Subscript {
+    pub value: Expression,
+    pub index: Expression,
+}
+
+/// `value op ANY (array)` or `value op ALL (array)`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Quantified {
+    pub value: Expression,
+    pub op: Token,
+    pub array: Expression,
+    pub all: bool,
+}
+
+fn join(expressions: &[Expression]) -> String {
+    let texts: Vec<String> = expressions.iter().map(|exp| exp.to_string()).collect();
+    texts.join(", ")
+}
+
+/// Writes an expression back as SQL, with every operation in parentheses.
+impl fmt::Display for Expression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.kind {
+            ExpressionKind::Literal => write!(f, "{}", self.literal.literal),
+            ExpressionKind::Function | ExpressionKind::Window => {
+                write!(f, "{}", self.function.as_ref().unwrap())
+            }
+            ExpressionKind::Binary => {
+                let binary = self.binary.as_ref().unwrap();
+                write!(f, "({} {} {})", binary.a, binary.op.literal, binary.b)
+            }
+            ExpressionKind::Unary => {
+                let unary = self.unary.as_ref().unwrap();
+                match unary.op.token_kind {
+                    TokenKind::Keyword => write!(f, "({} {})", unary.op.literal, unary.operand),
+                    _ => write!(f, "{}{}", unary.op.literal, unary.operand),
+                }
+            }
+            ExpressionKind::PatternMatch => {
+                let pattern_match = self.pattern_match.as_ref().unwrap();
+                let not = if pattern_match.negated { "not " } else { "" };
+                write!(
+                    f,
+                    "({} {}{} {}",
+                    pattern_match.value, not, pattern_match.op.literal, pattern_match.pattern
+                )?;
+                if let Some(escape) = &pattern_match.escape {
+                    write!(f, " escape {}", escape)?;
+                }
+                write!(f, ")")
+            }
+            ExpressionKind::InList => {
+                let in_list = self.in_list.as_ref().unwrap();
+                let not = if in_list.negated { "not " } else { "" };
+                write!(f, "({} {}in ({}))", in_list.value, not, join(&in_list.list))
+            }
+            ExpressionKind::Between => {
+                let between = self.between.as_ref().unwrap();
+                let not = if between.negated { "not " } else { "" };
+                write!(
+                    f,
+                    "({} {}between {} and {})",
+                    between.value, not, between.low, between.high
+                )
+            }
+            ExpressionKind::Cast => {
+                let cast = self.cast.as_ref().unwrap();
+                write!(f, "{}::{}", cast.value, cast.data_type)
+            }
+            ExpressionKind::IsNull => {
+                let is_null = self.is_null.as_ref().unwrap();
+                let not = if is_null.negated { "not " } else { "" };
+                write!(f, "({} is {}null)", is_null.value, not)
+            }
+            ExpressionKind::Array => write!(f, "array[{}]", join(self.array.as_ref().unwrap())),
+            ExpressionKind::Subscript => {
+                let subscript = self.subscript.as_ref().unwrap();
+                write!(f, "{}[{}]", subscript.value, subscript.index)
+            }
+            ExpressionKind::Quantified => {
+                let quantified = self.quantified.as_ref().unwrap();
+                let quantifier = if quantified.all { "all" } else { "any" };
+                write!(
+                    f,
+                    "({} {} {} ({}))",
+                    quantified.value, quantified.op.literal, quantifier, quantified.array
+                )
+            }
+        }
+    }
+}
+
+impl fmt::Display for FunctionCall {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // count(*) is parsed as a call without arguments
+        if self.args.is_empty() && self.name.literal == "count" {
+            write!(f, "count(*)")?;
+        } else {
+            write!(f, "{}({})", self.name.literal, join(&self.args))?;
+        }
+        let over = match &self.over {
+            Some(over) => over,
+            None => return Ok(()),
+        };
+        let mut clauses = Vec::new();
+        if !over.partition_by.is_empty() {
+            clauses.push(format!("partition by {}", join(&over.partition_by)));
+        }
+        if !over.order_by.is_empty() {
+            let terms: Vec<String> = over.order_by.iter().map(|t| t.to_string()).collect();
+            clauses.push(format!("order by {}", terms.join(", ")));
+        }
+        if let Some(frame) = &over.frame {
+            clauses.push(format!(
+                "{} between {} and {}",
+                frame.units.literal, frame.start, frame.end
+            ));
+        }
+        write!(f, " over ({})", clauses.join(" "))
+    }
+}
+
+impl fmt::Display for OrderingTerm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.expression)?;
+        if self.descending {
+            write!(f, " desc")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for FrameBound {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let offset = match &self.offset {
+            Some(offset) => offset.to_string(),
+            None => String::new(),
+        };
+        match self.kind {
+            FrameBoundKind::UnboundedPreceding => write!(f, "unbounded preceding"),
+            FrameBoundKind::Preceding => write!(f, "{} preceding", offset),
+            FrameBoundKind::CurrentRow => write!(f, "current row"),
+            FrameBoundKind::Following => write!(f, "{} following", offset),
+            FrameBoundKind::UnboundedFollowing => write!(f, "unbounded following"),
+        }
+    }
+}
+
+impl fmt::Display for DataType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name.literal)?;
+        if !self.modifiers.is_empty() {
+            let modifiers: Vec<&str> = self.modifiers.iter().map(|m| m.literal.as_str()).collect();
+            write!(f, "({})", modifiers.join(", "))?;
+        }
+        if self.array {
+            write!(f, "[]")?;
+        }
+        Ok(())
+    }
 }
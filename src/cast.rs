@@ -0,0 +1,559 @@
+use crate::{
+    datetime::{Date, Interval, Time, Timestamp, MICROS_PER_DAY},
+    json::Json,
+    mem_backend::{out_of_range, ColumnType, SQLError, Value},
+    numeric::{self, Decimal, Float},
+    uuid::Uuid,
+};
+
+/// How freely a conversion may be applied, from most to least restrictive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum CastLevel {
+    // Applied silently inside expressions, e.g. when comparing values
+    Implicit,
+    // Applied when storing a value into a column on INSERT
+    Assignment,
+    // Only applied through CAST(x AS type) or x::type
+    Explicit,
+}
+
+/// The conversion table. Returns the level at which `from` may be converted
+/// to `to`, or `None` when no conversion exists.
+fn conversion_level(from: &ColumnType, to: &ColumnType) -> Option<CastLevel> {
+    match (from, to) {
+        (from, to) if from == to => Some(CastLevel::Implicit),
+        // Arrays convert like their elements
+        (ColumnType::ArrayType(from), ColumnType::ArrayType(to)) => conversion_level(from, to),
+        (ColumnType::TextType, ColumnType::ArrayType(_)) => Some(CastLevel::Implicit),
+        (ColumnType::ArrayType(_), ColumnType::TextType) => Some(CastLevel::Assignment),
+        (from, to) if from.is_string() && to.is_string() => Some(CastLevel::Implicit),
+        // The length-limited character types convert like text
+        (from, to) if to.is_string() && *to != ColumnType::TextType => {
+            conversion_level(from, &ColumnType::TextType)
+        }
+        // Numbers widen implicitly, the other direction may fail or lose
+        // precision
+        (from, to) if from.is_number() && to.is_number() => {
+            if number_rank(from) < number_rank(to) {
+                Some(CastLevel::Implicit)
+            } else {
+                Some(CastLevel::Assignment)
+            }
+        }
+        (ColumnType::TextType, to) if to.is_number() => Some(CastLevel::Implicit),
+        (from, ColumnType::TextType) if from.is_number() => Some(CastLevel::Assignment),
+        (ColumnType::TextType, to) if to.is_temporal() => Some(CastLevel::Implicit),
+        (from, ColumnType::TextType) if from.is_temporal() => Some(CastLevel::Assignment),
+        (ColumnType::DateType, ColumnType::TimestampType) => Some(CastLevel::Implicit),
+        (ColumnType::TimestampType, ColumnType::DateType | ColumnType::TimeType) => {
+            Some(CastLevel::Assignment)
+        }
+        (ColumnType::TimeType, ColumnType::IntervalType) => Some(CastLevel::Implicit),
+        (ColumnType::IntervalType, ColumnType::TimeType) => Some(CastLevel::Assignment),
+        (ColumnType::TextType, ColumnType::BlobType) => Some(CastLevel::Implicit),
+        (ColumnType::BlobType, ColumnType::TextType) => Some(CastLevel::Assignment),
+        (ColumnType::TextType, ColumnType::JsonType) => Some(CastLevel::Implicit),
+        (ColumnType::JsonType, ColumnType::TextType) => Some(CastLevel::Assignment),
+        (ColumnType::TextType, ColumnType::EnumType(_)) => Some(CastLevel::Implicit),
+        (ColumnType::EnumType(_), ColumnType::TextType) => Some(CastLevel::Assignment),
+        (ColumnType::TextType, ColumnType::UuidType) => Some(CastLevel::Implicit),
+        (ColumnType::UuidType, ColumnType::TextType) => Some(CastLevel::Assignment),
+        // Only JSON numbers and booleans convert, so these are explicit
+        (ColumnType::JsonType, to) if to.is_number() => Some(CastLevel::Explicit),
+        (ColumnType::JsonType, ColumnType::BoolType) => Some(CastLevel::Explicit),
+        (ColumnType::TextType, ColumnType::BoolType) => Some(CastLevel::Implicit),
+        (ColumnType::BoolType, ColumnType::TextType) => Some(CastLevel::Assignment),
+        (ColumnType::IntType, ColumnType::BoolType) => Some(CastLevel::Explicit),
+        (ColumnType::BoolType, ColumnType::IntType) => Some(CastLevel::Explicit),
+        _ => None,
+    }
+}
+
+// smallint < int < bigint < numeric < real < double precision
+fn number_rank(col_type: &ColumnType) -> usize {
+    match col_type {
+        ColumnType::SmallIntType => 0,
+        ColumnType::IntType => 1,
+        ColumnType::BigIntType => 2,
+        ColumnType::NumericType(_) => 3,
+        ColumnType::RealType => 4,
+        _ => 5,
+    }
+}
+
+fn convert(value: &Value, to: &ColumnType) -> Result<Value, SQLError> {
+    let is_number = value.column_type().is_some_and(|from| from.is_number());
+    match (value, to) {
+        (Value::Null, _) => Ok(Value::Null),
+        (value, to) if to.is_temporal() && value.column_type().as_ref() == Some(to) => {
+            Ok(value.clone())
+        }
+        (value, to) if is_number && to.is_number() => convert_number(value, to),
+        (value, ColumnType::TextType) if is_number => Ok(Value::Text(number_text(value))),
+        (Value::Int(i), ColumnType::BoolType) => Ok(Value::Bool(*i != 0)),
+        (Value::Date(d), ColumnType::TextType) => Ok(Value::Text(d.to_string())),
+        (Value::Time(t), ColumnType::TextType) => Ok(Value::Text(t.to_string())),
+        (Value::Timestamp(ts), ColumnType::TextType) => Ok(Value::Text(ts.to_string())),
+        (Value::Interval(i), ColumnType::TextType) => Ok(Value::Text(i.to_string())),
+        (Value::Date(d), ColumnType::TimestampType) => {
+            Ok(Value::Timestamp(Timestamp::new(*d, Time(0))))
+        }
+        (Value::Timestamp(ts), ColumnType::DateType) => Ok(Value::Date(ts.date())),
+        (Value::Timestamp(ts), ColumnType::TimeType) => Ok(Value::Time(ts.time())),
+        (Value::Time(t), ColumnType::IntervalType) => Ok(Value::Interval(Interval::new(0, 0, t.0))),
+        (Value::Interval(i), ColumnType::TimeType) => {
+            Ok(Value::Time(Time(i.micros.rem_euclid(MICROS_PER_DAY))))
+        }
+        (Value::Text(s), ColumnType::DateType) => Date::parse(s)
+            .map(Value::Date)
+            .ok_or_else(|| invalid_input(s, to)),
+        (Value::Text(s), ColumnType::TimeType) => Time::parse(s)
+            .map(Value::Time)
+            .ok_or_else(|| invalid_input(s, to)),
+        (Value::Text(s), ColumnType::TimestampType) => Timestamp::parse(s)
+            .map(Value::Timestamp)
+            .ok_or_else(|| invalid_input(s, to)),
+        (Value::Text(s), ColumnType::IntervalType) => Interval::parse(s)
+            .map(Value::Interval)
+            .ok_or_else(|| invalid_input(s, to)),
+        (Value::Text(s), ColumnType::TextType) => Ok(Value::Text(s.clone())),
+        (Value::Text(s), ColumnType::BlobType) => parse_bytea(s)
+            .map(Value::Blob)
+            .ok_or_else(|| invalid_input(s, to)),
+        (Value::Blob(b), ColumnType::BlobType) => Ok(Value::Blob(b.clone())),
+        (Value::Blob(b), ColumnType::TextType) => Ok(Value::Text(bytea_text(b))),
+        (Value::Text(s), ColumnType::JsonType) => Json::parse(s)
+            .map(Value::Json)
+            .ok_or_else(|| invalid_input(s, to)),
+        (Value::Text(s), ColumnType::EnumType(definition)) => {
+            match definition.labels.iter().position(|label| label == s) {
+                Some(position) => Ok(Value::Enum(definition.clone(), position as u32)),
+                None => Err(invalid_input(s, to)),
+            }
+        }
+        (Value::Enum(definition, position), ColumnType::EnumType(_)) => {
+            Ok(Value::Enum(definition.clone(), *position))
+        }
+        (Value::Enum(definition, position), ColumnType::TextType) => {
+            Ok(Value::Text(definition.labels[*position as usize].clone()))
+        }
+        (Value::Text(s), ColumnType::UuidType) => Uuid::parse(s)
+            .map(Value::Uuid)
+            .ok_or_else(|| invalid_input(s, to)),
+        (Value::Array(_, elements), ColumnType::TextType) => Ok(Value::Text(array_text(elements))),
+        (Value::Uuid(uuid), ColumnType::UuidType) => Ok(Value::Uuid(*uuid)),
+        (Value::Uuid(uuid), ColumnType::TextType) => Ok(Value::Text(uuid.to_string())),
+        (Value::Json(json), ColumnType::JsonType) => Ok(Value::Json(json.clone())),
+        (Value::Json(json), ColumnType::TextType) => Ok(Value::Text(json.to_string())),
+        (Value::Json(Json::Number(n)), to) if to.is_number() => {
+            convert(&Value::Text(n.clone()), to)
+        }
+        (Value::Json(Json::Bool(b)), ColumnType::BoolType) => Ok(Value::Bool(*b)),
+        (Value::Bool(b), ColumnType::BoolType) => Ok(Value::Bool(*b)),
+        (Value::Bool(b), ColumnType::TextType) => Ok(Value::Text(b.to_string())),
+        (Value::Bool(b), ColumnType::IntType) => Ok(Value::Int(*b as i32)),
+        (Value::Text(s), ColumnType::BoolType) => match s.trim().to_lowercase().as_str() {
+            "t" | "true" | "y" | "yes" | "on" | "1" => Ok(Value::Bool(true)),
+            "f" | "false" | "n" | "no" | "off" | "0" => Ok(Value::Bool(false)),
+            _ => Err(invalid_input(s, to)),
+        },
+        (Value::Text(s), to) if to.is_integer() => match s.trim().parse::<i64>() {
+            Ok(i) => Value::integer(i, to),
+            Err(_) => Err(invalid_input(s, to)),
+        },
+        (Value::Text(s), ColumnType::NumericType(modifiers)) => match Decimal::parse(s) {
+            Some(d) => Value::numeric(d, *modifiers),
+            None => Err(invalid_input(s, to)),
+        },
+        (Value::Text(s), to) if to.is_number() => match s.trim().parse::<f64>() {
+            Ok(f) => convert_number(&Value::Double(Float(f)), to),
+            Err(_) => Err(invalid_input(s, to)),
+        },
+        (value, to) => Err(SQLError::InvalidCast(format!(
+            "cannot cast {} to {}",
+            value.column_type().unwrap(),
+            to
+        ))),
+    }
+}
+
+fn convert_number(value: &Value, to: &ColumnType) -> Result<Value, SQLError> {
+    match to {
+        // Numerics round half away from zero and floats half to even, as in
+        // PostgreSQL
+        to if to.is_integer() => {
+            let i = match value {
+                Value::Numeric(d) => d.round().and_then(|i| i64::try_from(i).ok()),
+                Value::Real(f) | Value::Double(f) => {
+                    let rounded = f.0.round_ties_even();
+                    if rounded >= i64::MIN as f64 && rounded < i64::MAX as f64 {
+                        Some(rounded as i64)
+                    } else {
+                        None
+                    }
+                }
+                value => value.as_i64(),
+            };
+            match i {
+                Some(i) => Value::integer(i, to),
+                None => Err(out_of_range(to)),
+            }
+        }
+        ColumnType::RealType => Value::real(value.as_f64().unwrap()),
+        ColumnType::DoubleType => Ok(Value::Double(Float(value.as_f64().unwrap()))),
+        ColumnType::NumericType(modifiers) => {
+            let d = match value {
+                Value::Real(f) | Value::Double(f) => match Decimal::from_f64(f.0) {
+                    Some(d) => d,
+                    None => {
+                        return Err(SQLError::InvalidCast(format!(
+                            "cannot represent {} as numeric",
+                            numeric::format_float(f.0, false)
+                        )))
+                    }
+                },
+                value => value.as_decimal().unwrap(),
+            };
+            Value::numeric(d, *modifiers)
+        }
+        to => Err(SQLError::InvalidCast(format!(
+            "{} is not a number type",
+            to
+        ))),
+    }
+}
+
+/// The text form of a number, as PostgreSQL prints it.
+pub fn number_text(value: &Value) -> String {
+    match value {
+        Value::Real(f) => numeric::format_float(f.0, true),
+        Value::Double(f) => numeric::format_float(f.0, false),
+        Value::Numeric(d) => d.to_string(),
+        value => value.as_i64().unwrap().to_string(),
+    }
+}
+
+// Converts every element, parsing text in the `{a,b}` array format first
+fn convert_array(value: &Value, element: &ColumnType, level: CastLevel) -> Result<Value, SQLError> {
+    let elements = match value {
+        Value::Array(_, elements) => elements.clone(),
+        Value::Text(s) => match parse_array(s) {
+            Some(elements) => elements
+                .into_iter()
+                .map(|element| element.map_or(Value::Null, Value::Text))
+                .collect(),
+            None => {
+                return Err(invalid_input(
+                    s,
+                    &ColumnType::ArrayType(Box::new(element.clone())),
+                ))
+            }
+        },
+        value => {
+            return Err(SQLError::InvalidCast(format!(
+                "cannot cast {} to {}[]",
+                value.column_type().unwrap(),
+                element
+            )))
+        }
+    };
+
+    let mut converted = Vec::new();
+    for value in &elements {
+        converted.push(cast(value, element, level)?);
+    }
+    Ok(Value::Array(element.clone(), converted))
+}
+
+/// The text form of an array as PostgreSQL prints it, such as
+/// `{1,NULL,"a b"}`.
+pub fn array_text(elements: &[Value]) -> String {
+    let texts: Vec<String> = elements
+        .iter()
+        .map(|element| {
+            let text = match convert(element, &ColumnType::TextType) {
+                Ok(Value::Text(text)) => text,
+                Ok(Value::Null) => return String::from("NULL"),
+                _ => unreachable!("every element type converts to text"),
+            };
+            let needs_quotes = text.is_empty()
+                || text.eq_ignore_ascii_case("null")
+                || text
+                    .chars()
+                    .any(|c| c.is_whitespace() || matches!(c, ',' | '{' | '}' | '"' | '\\'));
+            if !needs_quotes {
+                return text;
+            }
+            format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+        })
+        .collect();
+    format!("{{{}}}", texts.join(","))
+}
+
+// Splits `{a,"b c",NULL}` into its elements. Quoted elements may contain
+// anything, with `\` escaping the next character.
+fn parse_array(s: &str) -> Option<Vec<Option<String>>> {
+    let inner = s.trim().strip_prefix('{')?.strip_suffix('}')?;
+    let chars: Vec<char> = inner.chars().collect();
+    let mut elements = Vec::new();
+    if inner.trim().is_empty() {
+        return Some(elements);
+    }
+
+    let mut pos = 0;
+    loop {
+        while pos < chars.len() && chars[pos].is_whitespace() {
+            pos += 1;
+        }
+        if chars.get(pos) == Some(&'"') {
+            let mut element = String::new();
+            pos += 1;
+            loop {
+                match chars.get(pos)? {
+                    '"' => break,
+                    '\\' => {
+                        pos += 1;
+                        element.push(*chars.get(pos)?);
+                    }
+                    c => element.push(*c),
+                }
+                pos += 1;
+            }
+            pos += 1;
+            while pos < chars.len() && chars[pos].is_whitespace() {
+                pos += 1;
+            }
+            elements.push(Some(element));
+        } else {
+            let start = pos;
+            while pos < chars.len() && chars[pos] != ',' {
+                if matches!(chars[pos], '{' | '}' | '"') {
+                    return None;
+                }
+                pos += 1;
+            }
+            let element: String = chars[start..pos].iter().collect();
+            let element = element.trim();
+            if element.is_empty() {
+                return None;
+            }
+            if element.eq_ignore_ascii_case("null") {
+                elements.push(None);
+            } else {
+                elements.push(Some(element.to_string()));
+            }
+        }
+
+        match chars.get(pos) {
+            None => return Some(elements),
+            Some(',') => pos += 1,
+            Some(_) => return None,
+        }
+    }
+}
+
+/// The text form of bytes in PostgreSQL's hex format, such as `\xdeadbeef`.
+pub fn bytea_text(bytes: &[u8]) -> String {
+    let mut text = String::from("\\x");
+    for byte in bytes {
+        text.push_str(&format!("{:02x}", byte));
+    }
+    text
+}
+
+/// Decodes an even number of hex digits.
+pub fn decode_hex(digits: &str) -> Option<Vec<u8>> {
+    if !digits.len().is_multiple_of(2) || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    (0..digits.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&digits[i..i + 2], 16).ok())
+        .collect()
+}
+
+// Text in the hex format becomes the bytes it spells out, any other text
+// becomes its own UTF-8 bytes
+fn parse_bytea(s: &str) -> Option<Vec<u8>> {
+    match s.strip_prefix("\\x") {
+        Some(digits) => decode_hex(digits),
+        None => Some(s.as_bytes().to_vec()),
+    }
+}
+
+fn invalid_input(s: &str, to: &ColumnType) -> SQLError {
+    SQLError::InvalidCast(format!("invalid input for type {}: '{}'", to, s))
+}
+
+/// Checks a string against the length of a character type. Explicit casts
+/// truncate, other conversions may only drop trailing spaces. The trailing
+/// spaces of char(n) are not significant, so it is kept without them and
+/// padded only when output.
+fn fit_length(value: Value, to: &ColumnType, level: CastLevel) -> Result<Value, SQLError> {
+    let (mut s, length) = match (value, to) {
+        (Value::Text(s), ColumnType::VarCharType(Some(length)) | ColumnType::CharType(length)) => {
+            (s, *length as usize)
+        }
+        (value, _) => return Ok(value),
+    };
+    if let Some((end, _)) = s.char_indices().nth(length) {
+        if level != CastLevel::Explicit && !s[end..].chars().all(|c| c == ' ') {
+            return Err(SQLError::ValueTooLong(to.clone()));
+        }
+        s.truncate(end);
+    }
+    if let ColumnType::CharType(_) = to {
+        s.truncate(s.trim_end_matches(' ').len());
+    }
+    Ok(Value::Text(s))
+}
+
+/// Text without its trailing spaces, as it compares with char(n).
+pub fn without_padding(value: Value) -> Value {
+    match value {
+        Value::Text(s) => Value::Text(String::from(s.trim_end_matches(' '))),
+        value => value,
+    }
+}
+
+/// A char(n) value as it is output, padded with spaces to its length.
+pub fn pad_char(value: Value, col_type: &ColumnType) -> Value {
+    match (value, col_type) {
+        (Value::Text(mut s), ColumnType::CharType(length)) => {
+            let padding = (*length as usize).saturating_sub(s.chars().count());
+            s.extend(std::iter::repeat_n(' ', padding));
+            Value::Text(s)
+        }
+        (value, _) => value,
+    }
+}
+
+/// Converts `value` to `to` if the table allows it at `level`. NULL converts
+/// to every type.
+pub fn cast(value: &Value, to: &ColumnType, level: CastLevel) -> Result<Value, SQLError> {
+    let from = match value.column_type() {
+        Some(from) => from,
+        None => return Ok(Value::Null),
+    };
+    match conversion_level(&from, to) {
+        Some(allowed) if allowed <= level => match to {
+            ColumnType::VarCharType(_) | ColumnType::CharType(_) => {
+                fit_length(convert(value, &ColumnType::TextType)?, to, level)
+            }
+            ColumnType::ArrayType(element) => convert_array(value, element, level),
+            to => convert(value, to),
+        },
+        _ => Err(SQLError::InvalidCast(format!(
+            "cannot cast {} to {}",
+            from, to
+        ))),
+    }
+}
+
+/// Whether `from` converts to `to` implicitly.
+pub fn is_implicit(from: &ColumnType, to: &ColumnType) -> bool {
+    conversion_level(from, to) == Some(CastLevel::Implicit)
+}
+
+/// Brings two values to a common type for comparison, converting the right
+/// side first and the left side otherwise.
+pub fn unify(a: &Value, b: &Value) -> Result<(Value, Value), SQLError> {
+    let (a_type, b_type) = match (a.column_type(), b.column_type()) {
+        (Some(a_type), Some(b_type)) => (a_type, b_type),
+        _ => return Ok((a.clone(), b.clone())),
+    };
+    if a_type == b_type {
+        return Ok((a.clone(), b.clone()));
+    }
+    if is_implicit(&b_type, &a_type) {
+        return Ok((a.clone(), cast(b, &a_type, CastLevel::Implicit)?));
+    }
+    if is_implicit(&a_type, &b_type) {
+        return Ok((cast(a, &b_type, CastLevel::Implicit)?, b.clone()));
+    }
+    Err(SQLError::TypeMismatch(format!(
+        "cannot compare {} with {}",
+        a_type, b_type
+    )))
+}
+
+#[cfg(test)]
+mod cast_test {
+    use crate::cast::{array_text, cast, parse_array, unify, CastLevel};
+    use crate::mem_backend::{ColumnType, Value};
+
+    #[test]
+    fn test_cast() {
+        let text = Value::Text(String::from(" 42 "));
+        assert_eq!(
+            cast(&text, &ColumnType::IntType, CastLevel::Implicit).unwrap(),
+            Value::Int(42)
+        );
+
+        let int = Value::Int(7);
+        assert!(cast(&int, &ColumnType::TextType, CastLevel::Implicit).is_err());
+        assert_eq!(
+            cast(&int, &ColumnType::TextType, CastLevel::Assignment).unwrap(),
+            Value::Text(String::from("7"))
+        );
+
+        let text = Value::Text(String::from("Off"));
+        assert_eq!(
+            cast(&text, &ColumnType::BoolType, CastLevel::Implicit).unwrap(),
+            Value::Bool(false)
+        );
+        assert!(cast(&Value::Int(1), &ColumnType::BoolType, CastLevel::Assignment).is_err());
+
+        let bad = Value::Text(String::from("abc"));
+        assert!(cast(&bad, &ColumnType::IntType, CastLevel::Explicit).is_err());
+    }
+
+    #[test]
+    fn test_array_text() {
+        let tests = vec![
+            ("{1,2,3}", Some(vec![Some("1"), Some("2"), Some("3")])),
+            (
+                " { a , \"b,c\" ,NULL, \"NULL\"} ",
+                Some(vec![Some("a"), Some("b,c"), None, Some("NULL")]),
+            ),
+            ("{\"say \\\"hi\\\"\"}", Some(vec![Some("say \"hi\"")])),
+            ("{}", Some(vec![])),
+            ("{a,}", None),
+            ("{a", None),
+            ("{{1}}", None),
+            ("{\"a\" b}", None),
+        ];
+        for (input, expected) in tests {
+            let expected: Option<Vec<Option<String>>> = expected.map(|elements| {
+                elements
+                    .into_iter()
+                    .map(|element| element.map(String::from))
+                    .collect()
+            });
+            assert_eq!(parse_array(input), expected, "{}", input);
+        }
+
+        let elements = vec![
+            Value::Text(String::from("a")),
+            Value::Null,
+            Value::Text(String::from("b c")),
+            Value::Text(String::from("null")),
+            Value::Text(String::from("q\"")),
+            Value::Text(String::new()),
+        ];
+        assert_eq!(
+            array_text(&elements),
+            "{a,NULL,\"b c\",\"null\",\"q\\\"\",\"\"}"
+        );
+        assert_eq!(array_text(&[Value::Bool(true), Value::Int(2)]), "{true,2}");
+    }
+
+    #[test]
+    fn test_unify() {
+        let (a, b) = unify(&Value::Int(1), &Value::Text(String::from("1"))).unwrap();
+        assert_eq!(a, b);
+
+        let (a, b) = unify(&Value::Text(String::from("2")), &Value::Int(2)).unwrap();
+        assert_eq!(a, b);
+    }
+}
@@ -0,0 +1,847 @@
+use core::fmt;
+use std::{
+    cmp::Ordering,
+    hash::{Hash, Hasher},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::numeric::Decimal;
+
+pub const MICROS_PER_SECOND: i64 = 1_000_000;
+pub const MICROS_PER_MINUTE: i64 = 60 * MICROS_PER_SECOND;
+pub const MICROS_PER_HOUR: i64 = 60 * MICROS_PER_MINUTE;
+pub const MICROS_PER_DAY: i64 = 24 * MICROS_PER_HOUR;
+
+// Dates are limited to years 1 through 9999
+const MIN_YEAR: i64 = 1;
+const MAX_YEAR: i64 = 9999;
+
+/// A calendar date, stored as days since 1970-01-01.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Date(pub i32);
+
+/// A time of day, stored as microseconds since midnight.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Time(pub i64);
+
+/// A date and time without time zone, stored as microseconds since
+/// 1970-01-01 00:00:00.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Timestamp(pub i64);
+
+/// A span of time. Months and days are kept apart from the microseconds
+/// because their length depends on the date they are added to. Like
+/// PostgreSQL, intervals compare as if a month were 30 days.
+#[derive(Debug, Clone, Copy)]
+pub struct Interval {
+    pub months: i32,
+    pub days: i32,
+    pub micros: i64,
+}
+
+fn is_leap_year(year: i64) -> bool {
+    (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
+}
+
+fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+// Days since 1970-01-01 of a proleptic Gregorian date
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let day_of_year = (153 * ((month as i64 + 9) % 12) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * shifted_month + 2) / 5 + 1) as u32;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    } as u32;
+    let year = year_of_era + era * 400;
+    (if month <= 2 { year + 1 } else { year }, month, day)
+}
+
+fn parse_number<T: std::str::FromStr>(
+    s: &str,
+    digits: std::ops::RangeInclusive<usize>,
+) -> Option<T> {
+    if !digits.contains(&s.len()) || !s.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    s.parse().ok()
+}
+
+/// Parses `HH:MM[:SS[.ffffff]]` into microseconds since midnight. Hours are
+/// not limited to a day so that intervals can use the same format.
+fn parse_clock(s: &str) -> Option<i64> {
+    let mut parts = s.split(':');
+    let hours: i64 = parse_number(parts.next()?, 1..=9)?;
+    let minutes: i64 = parse_number(parts.next()?, 2..=2)?;
+    let micros = match parts.next() {
+        Some(seconds) => {
+            let (whole, fraction) = seconds.split_once('.').unwrap_or((seconds, ""));
+            let whole: i64 = parse_number(whole, 2..=2)?;
+            if whole >= 60 {
+                return None;
+            }
+            let fraction = if fraction.is_empty() {
+                0
+            } else {
+                if !fraction.chars().all(|c| c.is_ascii_digit()) {
+                    return None;
+                }
+                // Rounded to microseconds
+                let digits: String = fraction
+                    .chars()
+                    .chain(std::iter::repeat('0'))
+                    .take(7)
+                    .collect();
+                (digits.parse::<i64>().ok()? + 5) / 10
+            };
+            whole * MICROS_PER_SECOND + fraction
+        }
+        None => 0,
+    };
+    if parts.next().is_some() || minutes >= 60 {
+        return None;
+    }
+    hours
+        .checked_mul(MICROS_PER_HOUR)?
+        .checked_add(minutes * MICROS_PER_MINUTE + micros)
+}
+
+/// Writes microseconds as `HH:MM:SS[.ffffff]`, without trailing zeros in the
+/// fraction.
+fn format_clock(f: &mut fmt::Formatter, micros: i64) -> fmt::Result {
+    let micros = micros.unsigned_abs();
+    let seconds = micros / MICROS_PER_SECOND as u64;
+    write!(
+        f,
+        "{:02}:{:02}:{:02}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )?;
+    let fraction = micros % MICROS_PER_SECOND as u64;
+    if fraction != 0 {
+        let fraction = format!("{:06}", fraction);
+        write!(f, ".{}", fraction.trim_end_matches('0'))?;
+    }
+    Ok(())
+}
+
+impl Date {
+    /// The date for a year, month and day, or `None` when there is no such
+    /// day or it is out of range.
+    pub fn from_ymd(year: i64, month: u32, day: u32) -> Option<Date> {
+        if !(MIN_YEAR..=MAX_YEAR).contains(&year)
+            || !(1..=12).contains(&month)
+            || day == 0
+            || day > days_in_month(year, month)
+        {
+            return None;
+        }
+        Some(Date(days_from_civil(year, month, day) as i32))
+    }
+
+    /// Parses an ISO-8601 date such as `2024-03-04`.
+    pub fn parse(s: &str) -> Option<Date> {
+        let mut parts = s.trim().split('-');
+        let year = parse_number(parts.next()?, 4..=4)?;
+        let month = parse_number(parts.next()?, 1..=2)?;
+        let day = parse_number(parts.next()?, 1..=2)?;
+        if parts.next().is_some() {
+            return None;
+        }
+        Date::from_ymd(year, month, day)
+    }
+
+    pub fn ymd(&self) -> (i64, u32, u32) {
+        civil_from_days(self.0 as i64)
+    }
+
+    /// The date `days` later, or `None` when that is out of range.
+    pub fn checked_add_days(&self, days: i64) -> Option<Date> {
+        let (year, _, _) = civil_from_days((self.0 as i64).checked_add(days)?);
+        if !(MIN_YEAR..=MAX_YEAR).contains(&year) {
+            return None;
+        }
+        Some(Date((self.0 as i64 + days) as i32))
+    }
+
+    /// Day of the week from 0 for Sunday to 6 for Saturday.
+    pub fn weekday(&self) -> i64 {
+        // 1970-01-01 was a Thursday
+        (self.0 as i64 + 4).rem_euclid(7)
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (year, month, day) = self.ymd();
+        write!(f, "{:04}-{:02}-{:02}", year, month, day)
+    }
+}
+
+impl Time {
+    /// Parses `HH:MM[:SS[.ffffff]]`.
+    pub fn parse(s: &str) -> Option<Time> {
+        let micros = parse_clock(s.trim())?;
+        if micros >= MICROS_PER_DAY {
+            return None;
+        }
+        Some(Time(micros))
+    }
+
+    /// `extract(field from time)`.
+    pub fn field(&self, field: &str) -> Option<Decimal> {
+        match field {
+            "epoch" => Some(Decimal::new(self.0 as i128, 6)),
+            field => clock_field(self.0, field),
+        }
+    }
+
+    /// The time `micros` later, wrapping around midnight.
+    pub fn wrapping_add_micros(&self, micros: i64) -> Time {
+        Time((self.0 + micros % MICROS_PER_DAY).rem_euclid(MICROS_PER_DAY))
+    }
+}
+
+impl fmt::Display for Time {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        format_clock(f, self.0)
+    }
+}
+
+impl Timestamp {
+    pub fn new(date: Date, time: Time) -> Timestamp {
+        Timestamp(date.0 as i64 * MICROS_PER_DAY + time.0)
+    }
+
+    /// The current time in UTC.
+    pub fn now() -> Timestamp {
+        let elapsed = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+        Timestamp(elapsed.as_micros() as i64)
+    }
+
+    /// Parses an ISO-8601 timestamp such as `2024-03-04 10:30:00` or
+    /// `2024-03-04T10:30:00Z`. The time may be left out.
+    pub fn parse(s: &str) -> Option<Timestamp> {
+        let s = s.trim();
+        let s = s.strip_suffix(['Z', 'z']).unwrap_or(s);
+        match s.split_once([' ', 'T', 't']) {
+            Some((date, time)) => Some(Timestamp::new(Date::parse(date)?, Time::parse(time)?)),
+            None => Some(Timestamp::new(Date::parse(s)?, Time(0))),
+        }
+    }
+
+    pub fn date(&self) -> Date {
+        Date(self.0.div_euclid(MICROS_PER_DAY) as i32)
+    }
+
+    pub fn time(&self) -> Time {
+        Time(self.0.rem_euclid(MICROS_PER_DAY))
+    }
+
+    /// Adds the months, then the days and then the time of an interval. A
+    /// day past the end of the resulting month is moved back to its last
+    /// day, so that one month after January 31st is the end of February.
+    pub fn checked_add(&self, interval: &Interval) -> Option<Timestamp> {
+        let (year, month, day) = self.date().ymd();
+        let months = (year * 12 + month as i64 - 1).checked_add(interval.months as i64)?;
+        let (year, month) = (months.div_euclid(12), months.rem_euclid(12) as u32 + 1);
+        if !(MIN_YEAR..=MAX_YEAR).contains(&year) {
+            return None;
+        }
+        let date = Date::from_ymd(year, month, day.min(days_in_month(year, month)))?
+            .checked_add_days(interval.days as i64)?;
+        let micros = Timestamp::new(date, self.time())
+            .0
+            .checked_add(interval.micros)?;
+        Timestamp(micros).in_range()
+    }
+
+    /// The interval between two timestamps, in days and microseconds.
+    pub fn checked_sub(&self, other: &Timestamp) -> Option<Interval> {
+        let micros = self.0.checked_sub(other.0)?;
+        Some(Interval {
+            months: 0,
+            days: i32::try_from(micros / MICROS_PER_DAY).ok()?,
+            micros: micros % MICROS_PER_DAY,
+        })
+    }
+
+    fn in_range(self) -> Option<Timestamp> {
+        let (year, _, _) = self.date().ymd();
+        (MIN_YEAR..=MAX_YEAR).contains(&year).then_some(self)
+    }
+
+    /// `extract(field from timestamp)`.
+    pub fn field(&self, field: &str) -> Option<Decimal> {
+        let date = self.date();
+        let (year, month, day) = date.ymd();
+        let micros = self.time().0;
+        let value = match field {
+            "millennium" => (year - 1) / 1000 + 1,
+            "century" => (year - 1) / 100 + 1,
+            "decade" => year / 10,
+            "year" => year,
+            "isoyear" => iso_week(date).0,
+            "quarter" => (month as i64 - 1) / 3 + 1,
+            "month" => month as i64,
+            "week" => iso_week(date).1,
+            "day" => day as i64,
+            "dow" => date.weekday(),
+            "isodow" => (date.weekday() + 6) % 7 + 1,
+            "doy" => date.0 as i64 - days_from_civil(year, 1, 1) + 1,
+            "epoch" => return Some(Decimal::new(self.0 as i128, 6)),
+            field => return clock_field(micros, field),
+        };
+        Some(Decimal::from_i64(value))
+    }
+
+    /// `date_trunc(field, timestamp)`.
+    pub fn truncate(&self, field: &str) -> Option<Timestamp> {
+        let date = self.date();
+        let (year, month, _) = date.ymd();
+        let first_of = |year: i64, month: u32| {
+            Date::from_ymd(year, month, 1).map(|date| Timestamp::new(date, Time(0)))
+        };
+        match field {
+            "millennium" => first_of((year - 1) / 1000 * 1000 + 1, 1),
+            "century" => first_of((year - 1) / 100 * 100 + 1, 1),
+            "decade" => first_of((year / 10 * 10).max(MIN_YEAR), 1),
+            "year" => first_of(year, 1),
+            "quarter" => first_of(year, (month - 1) / 3 * 3 + 1),
+            "month" => first_of(year, month),
+            "week" => Some(Timestamp::new(
+                date.checked_add_days(-((date.weekday() + 6) % 7))?,
+                Time(0),
+            )),
+            "day" => Some(Timestamp::new(date, Time(0))),
+            field => Some(Timestamp(self.0 - self.0.rem_euclid(clock_unit(field)?))),
+        }
+    }
+}
+
+impl fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}", self.date(), self.time())
+    }
+}
+
+// The ISO year and week of a date. Weeks start on Monday and the first week
+// of a year is the one with its first Thursday.
+fn iso_week(date: Date) -> (i64, i64) {
+    let thursday = date.0 as i64 - (date.weekday() + 6) % 7 + 3;
+    let (year, _, _) = civil_from_days(thursday);
+    (year, (thursday - days_from_civil(year, 1, 1)) / 7 + 1)
+}
+
+// The time of day fields shared by timestamps, times and intervals
+fn clock_field(micros: i64, field: &str) -> Option<Decimal> {
+    let within_minute = micros % MICROS_PER_MINUTE;
+    Some(match field {
+        "hour" => Decimal::from_i64(micros / MICROS_PER_HOUR),
+        "minute" => Decimal::from_i64(micros % MICROS_PER_HOUR / MICROS_PER_MINUTE),
+        "second" => Decimal::new(within_minute as i128, 6),
+        "milliseconds" => Decimal::new(within_minute as i128, 3),
+        "microseconds" => Decimal::from_i64(within_minute),
+        _ => return None,
+    })
+}
+
+// The length of the time units that date_trunc can truncate to
+fn clock_unit(field: &str) -> Option<i64> {
+    match field {
+        "hour" => Some(MICROS_PER_HOUR),
+        "minute" => Some(MICROS_PER_MINUTE),
+        "second" => Some(MICROS_PER_SECOND),
+        "milliseconds" => Some(1000),
+        "microseconds" => Some(1),
+        _ => None,
+    }
+}
+
+enum IntervalUnit {
+    Months(f64),
+    Days(f64),
+    Micros(f64),
+}
+
+impl IntervalUnit {
+    fn from_name(name: &str) -> Option<IntervalUnit> {
+        let unit = match name.to_lowercase().as_str() {
+            "microsecond" | "microseconds" | "us" | "usec" | "usecs" => IntervalUnit::Micros(1.0),
+            "millisecond" | "milliseconds" | "ms" | "msec" | "msecs" => {
+                IntervalUnit::Micros(1000.0)
+            }
+            "second" | "seconds" | "sec" | "secs" | "s" => {
+                IntervalUnit::Micros(MICROS_PER_SECOND as f64)
+            }
+            "minute" | "minutes" | "min" | "mins" | "m" => {
+                IntervalUnit::Micros(MICROS_PER_MINUTE as f64)
+            }
+            "hour" | "hours" | "hr" | "hrs" | "h" => IntervalUnit::Micros(MICROS_PER_HOUR as f64),
+            "day" | "days" | "d" => IntervalUnit::Days(1.0),
+            "week" | "weeks" | "w" => IntervalUnit::Days(7.0),
+            "month" | "months" | "mon" | "mons" => IntervalUnit::Months(1.0),
+            "year" | "years" | "yr" | "yrs" | "y" => IntervalUnit::Months(12.0),
+            "decade" | "decades" => IntervalUnit::Months(120.0),
+            "century" | "centuries" => IntervalUnit::Months(1200.0),
+            "millennium" | "millennia" | "millenniums" => IntervalUnit::Months(12000.0),
+            _ => return None,
+        };
+        Some(unit)
+    }
+}
+
+impl Interval {
+    pub fn new(months: i32, days: i32, micros: i64) -> Interval {
+        Interval {
+            months,
+            days,
+            micros,
+        }
+    }
+
+    /// Builds an interval from fractional months, days and microseconds. A
+    /// fraction of a month becomes 30ths of it in days and a fraction of a
+    /// day becomes microseconds.
+    fn spread(months: f64, days: f64, micros: f64) -> Option<Interval> {
+        let whole_months = months.trunc();
+        let days = days + (months - whole_months) * 30.0;
+        let whole_days = days.trunc();
+        let micros = (micros + (days - whole_days) * MICROS_PER_DAY as f64).round();
+        let fits = |f: f64, max: f64| f.is_finite() && f.abs() <= max;
+        if !fits(whole_months, i32::MAX as f64)
+            || !fits(whole_days, i32::MAX as f64)
+            || !fits(micros, i64::MAX as f64 / 2.0)
+        {
+            return None;
+        }
+        Some(Interval::new(
+            whole_months as i32,
+            whole_days as i32,
+            micros as i64,
+        ))
+    }
+
+    /// Parses PostgreSQL's `1 year 2 mons 3 days 04:05:06` format, where
+    /// units may be abbreviated and `ago` negates the whole interval, and
+    /// ISO-8601 durations such as `P1Y2M3DT4H5M6S`.
+    pub fn parse(s: &str) -> Option<Interval> {
+        let s = s.trim();
+        if s.starts_with(['P', 'p']) {
+            return Interval::parse_iso(&s[1..]);
+        }
+
+        let (mut months, mut days, mut micros) = (0.0, 0.0, 0.0);
+        let mut words = s.split_whitespace().peekable();
+        if words.peek() == Some(&"@") {
+            words.next();
+        }
+        let mut negated = false;
+        let mut empty = true;
+        while let Some(word) = words.next() {
+            if word.eq_ignore_ascii_case("ago") && words.peek().is_none() && !empty {
+                negated = true;
+                break;
+            }
+            empty = false;
+            if word.contains(':') {
+                let (sign, clock) = match word.strip_prefix('-') {
+                    Some(clock) => (-1.0, clock),
+                    None => (1.0, word.strip_prefix('+').unwrap_or(word)),
+                };
+                micros += sign * parse_clock(clock)? as f64;
+                continue;
+            }
+            let amount: f64 = word.parse().ok()?;
+            if !amount.is_finite() {
+                return None;
+            }
+            match words.next().and_then(IntervalUnit::from_name) {
+                Some(IntervalUnit::Months(unit)) => months += amount * unit,
+                Some(IntervalUnit::Days(unit)) => days += amount * unit,
+                Some(IntervalUnit::Micros(unit)) => micros += amount * unit,
+                None => return None,
+            }
+        }
+        if empty {
+            return None;
+        }
+
+        let interval = Interval::spread(months, days, micros)?;
+        if negated {
+            interval.checked_neg()
+        } else {
+            Some(interval)
+        }
+    }
+
+    fn parse_iso(s: &str) -> Option<Interval> {
+        let (date, time) = match s.split_once(['T', 't']) {
+            Some((date, time)) => (date, time),
+            None => (s, ""),
+        };
+        if date.is_empty() && time.is_empty() {
+            return None;
+        }
+
+        let (mut months, mut days, mut micros) = (0.0, 0.0, 0.0);
+        for (part, is_time) in [(date, false), (time, true)] {
+            let mut rest = part;
+            while !rest.is_empty() {
+                let end = rest.find(|c: char| c.is_ascii_alphabetic())?;
+                let amount: f64 = rest[..end].parse().ok()?;
+                if !amount.is_finite() {
+                    return None;
+                }
+                match (rest[end..].chars().next()?.to_ascii_uppercase(), is_time) {
+                    ('Y', false) => months += amount * 12.0,
+                    ('M', false) => months += amount,
+                    ('W', false) => days += amount * 7.0,
+                    ('D', false) => days += amount,
+                    ('H', true) => micros += amount * MICROS_PER_HOUR as f64,
+                    ('M', true) => micros += amount * MICROS_PER_MINUTE as f64,
+                    ('S', true) => micros += amount * MICROS_PER_SECOND as f64,
+                    _ => return None,
+                }
+                rest = &rest[end + 1..];
+            }
+        }
+        Interval::spread(months, days, micros)
+    }
+
+    pub fn checked_add(&self, other: &Interval) -> Option<Interval> {
+        Some(Interval::new(
+            self.months.checked_add(other.months)?,
+            self.days.checked_add(other.days)?,
+            self.micros.checked_add(other.micros)?,
+        ))
+    }
+
+    pub fn checked_sub(&self, other: &Interval) -> Option<Interval> {
+        self.checked_add(&other.checked_neg()?)
+    }
+
+    pub fn checked_neg(&self) -> Option<Interval> {
+        Some(Interval::new(
+            self.months.checked_neg()?,
+            self.days.checked_neg()?,
+            self.micros.checked_neg()?,
+        ))
+    }
+
+    pub fn checked_mul(&self, factor: f64) -> Option<Interval> {
+        Interval::spread(
+            self.months as f64 * factor,
+            self.days as f64 * factor,
+            self.micros as f64 * factor,
+        )
+    }
+
+    pub fn checked_div(&self, divisor: f64) -> Option<Interval> {
+        if divisor == 0.0 {
+            return None;
+        }
+        Interval::spread(
+            self.months as f64 / divisor,
+            self.days as f64 / divisor,
+            self.micros as f64 / divisor,
+        )
+    }
+
+    // The length used for comparisons, with 30 day months
+    fn total_micros(&self) -> i128 {
+        (self.months as i128 * 30 + self.days as i128) * MICROS_PER_DAY as i128
+            + self.micros as i128
+    }
+
+    /// `extract(field from interval)`.
+    pub fn field(&self, field: &str) -> Option<Decimal> {
+        let years = self.months as i64 / 12;
+        let value = match field {
+            "millennium" => years / 1000,
+            "century" => years / 100,
+            "decade" => years / 10,
+            "year" => years,
+            "quarter" => (self.months as i64 % 12) / 3 + 1,
+            "month" => self.months as i64 % 12,
+            "day" => self.days as i64,
+            // A year counts as 365.25 days
+            "epoch" => {
+                let micros = years as i128 * 36525 * (MICROS_PER_DAY as i128 / 100)
+                    + ((self.months as i128 % 12) * 30 + self.days as i128)
+                        * MICROS_PER_DAY as i128;
+                return Some(Decimal::new(micros + self.micros as i128, 6));
+            }
+            field => return clock_field(self.micros, field),
+        };
+        Some(Decimal::from_i64(value))
+    }
+
+    /// `date_trunc(field, interval)`.
+    pub fn truncate(&self, field: &str) -> Option<Interval> {
+        let truncated = match field {
+            "millennium" => Interval::new(self.months / 12000 * 12000, 0, 0),
+            "century" => Interval::new(self.months / 1200 * 1200, 0, 0),
+            "decade" => Interval::new(self.months / 120 * 120, 0, 0),
+            "year" => Interval::new(self.months / 12 * 12, 0, 0),
+            "quarter" => Interval::new(self.months / 3 * 3, 0, 0),
+            "month" => Interval::new(self.months, 0, 0),
+            "day" => Interval::new(self.months, self.days, 0),
+            field => {
+                let unit = clock_unit(field)?;
+                Interval::new(self.months, self.days, self.micros / unit * unit)
+            }
+        };
+        Some(truncated)
+    }
+}
+
+impl PartialEq for Interval {
+    fn eq(&self, other: &Self) -> bool {
+        self.total_micros() == other.total_micros()
+    }
+}
+
+impl Eq for Interval {}
+
+impl PartialOrd for Interval {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Interval {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.total_micros().cmp(&other.total_micros())
+    }
+}
+
+impl Hash for Interval {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.total_micros().hash(state)
+    }
+}
+
+impl fmt::Display for Interval {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let plural = |n: i64| if n == 1 { "" } else { "s" };
+        let mut parts = Vec::new();
+        let (years, months) = (self.months / 12, self.months % 12);
+        if years != 0 {
+            parts.push(format!("{} year{}", years, plural(years as i64)));
+        }
+        if months != 0 {
+            parts.push(format!("{} mon{}", months, plural(months as i64)));
+        }
+        if self.days != 0 {
+            parts.push(format!("{} day{}", self.days, plural(self.days as i64)));
+        }
+        write!(f, "{}", parts.join(" "))?;
+
+        if self.micros != 0 || parts.is_empty() {
+            if !parts.is_empty() {
+                write!(f, " ")?;
+            }
+            // Like PostgreSQL, a positive time after a negative part is
+            // marked with a plus
+            if self.micros < 0 {
+                write!(f, "-")?;
+            } else if self.months < 0 || self.days < 0 {
+                write!(f, "+")?;
+            }
+            format_clock(f, self.micros)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod datetime_test {
+    use crate::datetime::{Date, Interval, Time, Timestamp};
+
+    #[test]
+    fn test_parse_and_format() {
+        let dates = vec![
+            ("2024-03-04", "2024-03-04"),
+            ("1970-01-01", "1970-01-01"),
+            ("2000-2-29", "2000-02-29"),
+            ("0001-01-01", "0001-01-01"),
+        ];
+        for (input, expected) in dates {
+            assert_eq!(Date::parse(input).unwrap().to_string(), expected);
+        }
+        for bad in ["2023-02-29", "2024-13-01", "24-01-01", "2024-01-01x", ""] {
+            assert!(Date::parse(bad).is_none(), "{}", bad);
+        }
+        assert_eq!(Date::parse("1970-01-02").unwrap(), Date(1));
+        assert_eq!(Date::parse("1969-12-31").unwrap(), Date(-1));
+
+        assert_eq!(Time::parse("10:30").unwrap().to_string(), "10:30:00");
+        assert_eq!(Time::parse("23:59:59.5").unwrap().to_string(), "23:59:59.5");
+        assert!(Time::parse("24:00:00").is_none());
+        assert!(Time::parse("10:60").is_none());
+
+        let timestamps = vec![
+            ("2024-03-04 10:30:00", "2024-03-04 10:30:00"),
+            ("2024-03-04T10:30:00.123456Z", "2024-03-04 10:30:00.123456"),
+            ("2024-03-04", "2024-03-04 00:00:00"),
+            ("1969-12-31 23:00", "1969-12-31 23:00:00"),
+        ];
+        for (input, expected) in timestamps {
+            assert_eq!(Timestamp::parse(input).unwrap().to_string(), expected);
+        }
+
+        let intervals = vec![
+            ("1 day", "1 day"),
+            ("2 hours 30 minutes", "02:30:00"),
+            (
+                "1 year 2 months 3 days 04:05:06",
+                "1 year 2 mons 3 days 04:05:06",
+            ),
+            ("1.5 days", "1 day 12:00:00"),
+            ("-1 day +2 hours", "-1 days +02:00:00"),
+            ("3 days ago", "-3 days"),
+            ("P1Y2M10DT2H30M", "1 year 2 mons 10 days 02:30:00"),
+            ("PT0.5S", "00:00:00.5"),
+            ("0 seconds", "00:00:00"),
+        ];
+        for (input, expected) in intervals {
+            assert_eq!(
+                Interval::parse(input).unwrap().to_string(),
+                expected,
+                "{}",
+                input
+            );
+        }
+        for bad in ["", "1 fortnight", "P", "day", "1"] {
+            assert!(Interval::parse(bad).is_none(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        let ts = Timestamp::parse("2024-01-31 12:00:00").unwrap();
+        let month = Interval::parse("1 month").unwrap();
+        assert_eq!(
+            ts.checked_add(&month).unwrap().to_string(),
+            "2024-02-29 12:00:00"
+        );
+        let later = Timestamp::parse("2024-03-02 18:30:00").unwrap();
+        assert_eq!(
+            later.checked_sub(&ts).unwrap().to_string(),
+            "31 days 06:30:00"
+        );
+        assert!(Timestamp::parse("9999-12-31")
+            .unwrap()
+            .checked_add(&Interval::parse("1 day").unwrap())
+            .is_none());
+
+        assert_eq!(
+            Interval::parse("1 mon").unwrap(),
+            Interval::parse("30 days").unwrap()
+        );
+        assert!(Interval::parse("1 day").unwrap() < Interval::parse("25 hours").unwrap());
+        assert_eq!(
+            Interval::parse("1 hour")
+                .unwrap()
+                .checked_div(4.0)
+                .unwrap()
+                .to_string(),
+            "00:15:00"
+        );
+        assert_eq!(
+            Interval::parse("1 mon")
+                .unwrap()
+                .checked_mul(1.5)
+                .unwrap()
+                .to_string(),
+            "1 mon 15 days"
+        );
+        assert_eq!(
+            Time::parse("23:00")
+                .unwrap()
+                .wrapping_add_micros(2 * 3_600_000_000),
+            Time::parse("01:00").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_fields() {
+        let ts = Timestamp::parse("2024-03-04 10:30:15.25").unwrap();
+        let tests = vec![
+            ("year", "2024"),
+            ("quarter", "1"),
+            ("month", "3"),
+            ("day", "4"),
+            ("dow", "1"),
+            ("doy", "64"),
+            ("week", "10"),
+            ("hour", "10"),
+            ("minute", "30"),
+            ("second", "15.250000"),
+            ("epoch", "1709548215.250000"),
+        ];
+        for (field, expected) in tests {
+            assert_eq!(ts.field(field).unwrap().to_string(), expected, "{}", field);
+        }
+        assert!(ts.field("fortnight").is_none());
+
+        let truncated = vec![
+            ("year", "2024-01-01 00:00:00"),
+            ("quarter", "2024-01-01 00:00:00"),
+            ("month", "2024-03-01 00:00:00"),
+            ("week", "2024-03-04 00:00:00"),
+            ("day", "2024-03-04 00:00:00"),
+            ("hour", "2024-03-04 10:00:00"),
+            ("second", "2024-03-04 10:30:15"),
+        ];
+        for (field, expected) in truncated {
+            assert_eq!(
+                ts.truncate(field).unwrap().to_string(),
+                expected,
+                "{}",
+                field
+            );
+        }
+
+        let interval = Interval::parse("1 year 2 mons 3 days 04:05:06").unwrap();
+        assert_eq!(interval.field("month").unwrap().to_string(), "2");
+        assert_eq!(interval.field("hour").unwrap().to_string(), "4");
+        assert_eq!(
+            interval.truncate("day").unwrap().to_string(),
+            "1 year 2 mons 3 days"
+        );
+        assert_eq!(
+            Interval::parse("1 year")
+                .unwrap()
+                .field("epoch")
+                .unwrap()
+                .to_string(),
+            "31557600.000000"
+        );
+    }
+}
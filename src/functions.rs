@@ -0,0 +1,560 @@
+use std::{collections::HashMap, rc::Rc};
+
+use crate::{
+    cast::{self, CastLevel},
+    datetime::{Time, Timestamp},
+    json::Json,
+    mem_backend::{evaluate_arithmetic, out_of_range, ColumnType, SQLError, Table, Value},
+    numeric::Float,
+    uuid::Uuid,
+};
+
+pub type ScalarFunction = Box<dyn Fn(&[Value]) -> Result<Value, SQLError>>;
+/// A function used in FROM, producing the rows of a table.
+pub type TableFunction = Box<dyn Fn(&[Value]) -> Result<Table, SQLError>>;
+
+type StepFn<S> = Rc<dyn Fn(&mut S, &[Value]) -> Result<(), SQLError>>;
+type FinalizeFn<S> = Rc<dyn Fn(S) -> Result<Value, SQLError>>;
+
+pub trait Accumulator {
+    fn step(&mut self, args: &[Value]) -> Result<(), SQLError>;
+    fn finalize(self: Box<Self>) -> Result<Value, SQLError>;
+}
+
+struct ClosureAccumulator<S> {
+    state: S,
+    step: StepFn<S>,
+    finalize: FinalizeFn<S>,
+}
+
+impl<S> Accumulator for ClosureAccumulator<S> {
+    fn step(&mut self, args: &[Value]) -> Result<(), SQLError> {
+        (self.step)(&mut self.state, args)
+    }
+
+    fn finalize(self: Box<Self>) -> Result<Value, SQLError> {
+        (self.finalize)(self.state)
+    }
+}
+
+pub struct AggregateFunction {
+    init: Box<dyn Fn() -> Box<dyn Accumulator>>,
+}
+
+impl AggregateFunction {
+    pub fn accumulator(&self) -> Box<dyn Accumulator> {
+        (self.init)()
+    }
+}
+
+pub struct FunctionRegistry {
+    scalars: HashMap<String, ScalarFunction>,
+    aggregates: HashMap<String, AggregateFunction>,
+    tables: HashMap<String, TableFunction>,
+}
+
+impl FunctionRegistry {
+    pub fn new() -> FunctionRegistry {
+        let mut registry = FunctionRegistry {
+            scalars: HashMap::new(),
+            aggregates: HashMap::new(),
+            tables: HashMap::new(),
+        };
+        register_builtins(&mut registry);
+        registry
+    }
+
+    /// Registers a scalar function, replacing any function with the same name.
+    pub fn register_scalar<F>(&mut self, name: &str, function: F)
+    where
+        F: Fn(&[Value]) -> Result<Value, SQLError> + 'static,
+    {
+        let name = name.to_lowercase();
+        self.aggregates.remove(&name);
+        self.scalars.insert(name, Box::new(function));
+    }
+
+    /// Registers an aggregate function. `init` creates the state for every
+    /// group, `step` folds one row into it and `finalize` produces the result.
+    pub fn register_aggregate<S, I, St, F>(&mut self, name: &str, init: I, step: St, finalize: F)
+    where
+        S: 'static,
+        I: Fn() -> S + 'static,
+        St: Fn(&mut S, &[Value]) -> Result<(), SQLError> + 'static,
+        F: Fn(S) -> Result<Value, SQLError> + 'static,
+    {
+        let step: StepFn<S> = Rc::new(step);
+        let finalize: FinalizeFn<S> = Rc::new(finalize);
+
+        let name = name.to_lowercase();
+        self.scalars.remove(&name);
+        self.aggregates.insert(
+            name,
+            AggregateFunction {
+                init: Box::new(move || {
+                    Box::new(ClosureAccumulator {
+                        state: init(),
+                        step: step.clone(),
+                        finalize: finalize.clone(),
+                    })
+                }),
+            },
+        );
+    }
+
+    /// Registers a table-valued function. Table functions live in their own
+    /// namespace since they can only appear in FROM.
+    pub fn register_table<F>(&mut self, name: &str, function: F)
+    where
+        F: Fn(&[Value]) -> Result<Table, SQLError> + 'static,
+    {
+        self.tables.insert(name.to_lowercase(), Box::new(function));
+    }
+
+    pub fn scalar(&self, name: &str) -> Option<&ScalarFunction> {
+        self.scalars.get(&name.to_lowercase())
+    }
+
+    pub fn aggregate(&self, name: &str) -> Option<&AggregateFunction> {
+        self.aggregates.get(&name.to_lowercase())
+    }
+
+    pub fn table(&self, name: &str) -> Option<&TableFunction> {
+        self.tables.get(&name.to_lowercase())
+    }
+}
+
+impl Default for FunctionRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn expect_args(name: &str, args: &[Value], count: usize) -> Result<(), SQLError> {
+    if args.len() != count {
+        return Err(SQLError::InvalidFunctionArguments(format!(
+            "{} expects {} argument(s), got {}",
+            name,
+            count,
+            args.len()
+        )));
+    }
+    Ok(())
+}
+
+fn unsupported_unit(field: &str, value: &Value) -> SQLError {
+    SQLError::InvalidFunctionArguments(format!(
+        "unit \"{}\" is not supported for type {}",
+        field,
+        value.column_type().unwrap()
+    ))
+}
+
+// The JSON argument of a json function, where text is parsed like a json
+// literal. `None` for NULL.
+fn json_arg(arg: &Value) -> Result<Option<Json>, SQLError> {
+    match cast::cast(arg, &ColumnType::JsonType, CastLevel::Implicit)? {
+        Value::Json(json) => Ok(Some(json)),
+        _ => Ok(None),
+    }
+}
+
+fn register_builtins(registry: &mut FunctionRegistry) {
+    registry.register_scalar("lower", |args| {
+        expect_args("lower", args, 1)?;
+        match &args[0] {
+            Value::Null => Ok(Value::Null),
+            Value::Text(s) => Ok(Value::Text(s.to_lowercase())),
+            _ => Err(SQLError::InvalidFunctionArguments(String::from(
+                "lower expects text",
+            ))),
+        }
+    });
+
+    registry.register_scalar("upper", |args| {
+        expect_args("upper", args, 1)?;
+        match &args[0] {
+            Value::Null => Ok(Value::Null),
+            Value::Text(s) => Ok(Value::Text(s.to_uppercase())),
+            _ => Err(SQLError::InvalidFunctionArguments(String::from(
+                "upper expects text",
+            ))),
+        }
+    });
+
+    registry.register_scalar("length", |args| {
+        expect_args("length", args, 1)?;
+        match &args[0] {
+            Value::Null => Ok(Value::Null),
+            Value::Text(s) => Ok(Value::Int(s.chars().count() as i32)),
+            Value::Blob(b) => Ok(Value::Int(b.len() as i32)),
+            _ => Err(SQLError::InvalidFunctionArguments(String::from(
+                "length expects text or bytea",
+            ))),
+        }
+    });
+
+    // substr(s, start[, count]) counts characters of text and bytes of
+    // bytea from 1. Like PostgreSQL, positions before the start are allowed
+    // and shorten the result.
+    registry.register_scalar("substr", |args| {
+        if args.len() != 2 {
+            expect_args("substr", args, 3)?;
+        }
+        if args.contains(&Value::Null) {
+            return Ok(Value::Null);
+        }
+        let bound = |arg: &Value| {
+            arg.as_i64().ok_or_else(|| {
+                SQLError::InvalidFunctionArguments(String::from("substr expects integer positions"))
+            })
+        };
+        let start = bound(&args[1])?;
+        let end = match args.get(2) {
+            Some(count) => {
+                let count = bound(count)?;
+                if count < 0 {
+                    return Err(SQLError::InvalidFunctionArguments(String::from(
+                        "negative substring length not allowed",
+                    )));
+                }
+                start.saturating_add(count)
+            }
+            None => i64::MAX,
+        };
+        let range = |len: usize| {
+            let clamp = |i: i64| (i.max(1) - 1).min(len as i64) as usize;
+            clamp(start)..clamp(end).max(clamp(start))
+        };
+        match &args[0] {
+            Value::Text(s) => {
+                let chars: Vec<char> = s.chars().collect();
+                Ok(Value::Text(chars[range(chars.len())].iter().collect()))
+            }
+            Value::Blob(b) => Ok(Value::Blob(b[range(b.len())].to_vec())),
+            _ => Err(SQLError::InvalidFunctionArguments(String::from(
+                "substr expects text or bytea",
+            ))),
+        }
+    });
+
+    // json_extract(json, '$.a[0]') follows a path, NULL when it leads nowhere
+    registry.register_scalar("json_extract", |args| {
+        expect_args("json_extract", args, 2)?;
+        let json = match json_arg(&args[0])? {
+            Some(json) => json,
+            None => return Ok(Value::Null),
+        };
+        let path = match &args[1] {
+            Value::Null => return Ok(Value::Null),
+            Value::Text(path) => path,
+            _ => {
+                return Err(SQLError::InvalidFunctionArguments(String::from(
+                    "json_extract expects a text path",
+                )))
+            }
+        };
+        let steps = match Json::parse_path(path) {
+            Some(steps) => steps,
+            None => {
+                return Err(SQLError::InvalidFunctionArguments(format!(
+                    "invalid json path '{}'",
+                    path
+                )))
+            }
+        };
+
+        let mut current = &json;
+        for step in &steps {
+            match current.step(step) {
+                Some(json) => current = json,
+                None => return Ok(Value::Null),
+            }
+        }
+        Ok(Value::Json(current.clone()))
+    });
+
+    registry.register_scalar("json_array_length", |args| {
+        expect_args("json_array_length", args, 1)?;
+        match json_arg(&args[0])? {
+            None => Ok(Value::Null),
+            Some(Json::Array(elements)) => Ok(Value::Int(elements.len() as i32)),
+            Some(_) => Err(SQLError::InvalidFunctionArguments(String::from(
+                "cannot get array length of a non-array",
+            ))),
+        }
+    });
+
+    // json_each(json) has a row with the key and value of every member of
+    // an object
+    registry.register_table("json_each", |args| {
+        expect_args("json_each", args, 1)?;
+        let mut table = Table::new();
+        table.name = String::from("json_each");
+        table.columns = vec![String::from("key"), String::from("value")];
+        table.column_types = vec![ColumnType::TextType, ColumnType::JsonType];
+        match json_arg(&args[0])? {
+            None => {}
+            Some(Json::Object(members)) => {
+                for (key, value) in members {
+                    table.rows.push(vec![
+                        Value::Text(key).to_cell(),
+                        Value::Json(value).to_cell(),
+                    ]);
+                }
+            }
+            Some(_) => {
+                return Err(SQLError::InvalidFunctionArguments(String::from(
+                    "cannot call json_each on a non-object",
+                )))
+            }
+        }
+        Ok(table)
+    });
+
+    // Only one-dimensional arrays exist, so any other dimension is NULL like
+    // the length of an empty array
+    registry.register_scalar("array_length", |args| {
+        if args.len() != 1 {
+            expect_args("array_length", args, 2)?;
+        }
+        let dimension = match args.get(1) {
+            None => 1,
+            Some(Value::Null) => return Ok(Value::Null),
+            Some(dimension) => match dimension.as_i64() {
+                Some(dimension) => dimension,
+                None => {
+                    return Err(SQLError::InvalidFunctionArguments(String::from(
+                        "array_length expects an integer dimension",
+                    )))
+                }
+            },
+        };
+        match &args[0] {
+            Value::Null => Ok(Value::Null),
+            Value::Array(_, elements) if elements.is_empty() || dimension != 1 => Ok(Value::Null),
+            Value::Array(_, elements) => Ok(Value::Int(elements.len() as i32)),
+            _ => Err(SQLError::InvalidFunctionArguments(String::from(
+                "array_length expects an array",
+            ))),
+        }
+    });
+
+    // unnest(array) has a row for every element
+    registry.register_table("unnest", |args| {
+        expect_args("unnest", args, 1)?;
+        let mut table = Table::new();
+        table.name = String::from("unnest");
+        table.columns = vec![String::from("unnest")];
+        match &args[0] {
+            Value::Null => table.column_types = vec![ColumnType::TextType],
+            Value::Array(element, elements) => {
+                table.column_types = vec![element.clone()];
+                table.rows = elements.iter().map(|value| vec![value.to_cell()]).collect();
+            }
+            _ => {
+                return Err(SQLError::InvalidFunctionArguments(String::from(
+                    "unnest expects an array",
+                )))
+            }
+        }
+        Ok(table)
+    });
+
+    registry.register_scalar("abs", |args| {
+        expect_args("abs", args, 1)?;
+        match &args[0] {
+            Value::Null => Ok(Value::Null),
+            value if value.as_i64().is_some() => {
+                let col_type = value.column_type().unwrap();
+                match value.as_i64().unwrap().checked_abs() {
+                    Some(i) => Value::integer(i, &col_type),
+                    None => Err(out_of_range(&col_type)),
+                }
+            }
+            Value::Numeric(d) => Ok(Value::Numeric(d.abs())),
+            Value::Real(f) => Ok(Value::Real(Float(f.0.abs()))),
+            Value::Double(f) => Ok(Value::Double(Float(f.0.abs()))),
+            _ => Err(SQLError::InvalidFunctionArguments(String::from(
+                "abs expects a number",
+            ))),
+        }
+    });
+
+    registry.register_scalar("now", |args| {
+        expect_args("now", args, 0)?;
+        Ok(Value::Timestamp(Timestamp::now()))
+    });
+
+    registry.register_scalar("gen_random_uuid", |args| {
+        expect_args("gen_random_uuid", args, 0)?;
+        Ok(Value::Uuid(Uuid::new_v4()))
+    });
+
+    registry.register_scalar("current_date", |args| {
+        expect_args("current_date", args, 0)?;
+        Ok(Value::Date(Timestamp::now().date()))
+    });
+
+    // extract(field FROM source) is parsed into extract('field', source)
+    registry.register_scalar("extract", |args| {
+        expect_args("extract", args, 2)?;
+        let field = match &args[0] {
+            Value::Text(field) => field.to_lowercase(),
+            _ => {
+                return Err(SQLError::InvalidFunctionArguments(String::from(
+                    "extract expects a field name",
+                )))
+            }
+        };
+        let value = match &args[1] {
+            Value::Null => return Ok(Value::Null),
+            Value::Date(d) => Timestamp::new(*d, Time(0)).field(&field),
+            Value::Timestamp(ts) => ts.field(&field),
+            Value::Time(t) => t.field(&field),
+            Value::Interval(i) => i.field(&field),
+            _ => {
+                return Err(SQLError::InvalidFunctionArguments(String::from(
+                    "extract expects a date, time, timestamp or interval",
+                )))
+            }
+        };
+        match value {
+            Some(value) => Ok(Value::Numeric(value)),
+            None => Err(unsupported_unit(&field, &args[1])),
+        }
+    });
+
+    registry.register_scalar("date_trunc", |args| {
+        expect_args("date_trunc", args, 2)?;
+        let field = match &args[0] {
+            Value::Null => return Ok(Value::Null),
+            Value::Text(field) => field.to_lowercase(),
+            _ => {
+                return Err(SQLError::InvalidFunctionArguments(String::from(
+                    "date_trunc expects a field name",
+                )))
+            }
+        };
+        let value = match &args[1] {
+            Value::Null => return Ok(Value::Null),
+            Value::Date(d) => Timestamp::new(*d, Time(0))
+                .truncate(&field)
+                .map(Value::Timestamp),
+            Value::Timestamp(ts) => ts.truncate(&field).map(Value::Timestamp),
+            Value::Interval(i) => i.truncate(&field).map(Value::Interval),
+            _ => {
+                return Err(SQLError::InvalidFunctionArguments(String::from(
+                    "date_trunc expects a date, timestamp or interval",
+                )))
+            }
+        };
+        value.ok_or_else(|| unsupported_unit(&field, &args[1]))
+    });
+
+    // count(*) is called without arguments and counts every row, count(x)
+    // skips NULLs like the other built-in aggregates
+    registry.register_aggregate(
+        "count",
+        || 0,
+        |count: &mut i64, args| {
+            if args.iter().all(|arg| *arg != Value::Null) {
+                *count += 1;
+            }
+            Ok(())
+        },
+        |count| Ok(Value::BigInt(count)),
+    );
+
+    // round(x) rounds half away from zero for numeric and half to even for
+    // floats, round(x, digits) keeps `digits` places after the point
+    registry.register_scalar("round", |args| {
+        if args.len() != 1 {
+            expect_args("round", args, 2)?;
+        }
+        let digits = match args.get(1) {
+            None => 0,
+            Some(Value::Null) => return Ok(Value::Null),
+            Some(digits) => match digits.as_i64().and_then(|d| u32::try_from(d).ok()) {
+                Some(digits) => digits,
+                None => {
+                    return Err(SQLError::InvalidFunctionArguments(String::from(
+                        "round expects a non-negative number of digits",
+                    )))
+                }
+            },
+        };
+        match &args[0] {
+            Value::Null => Ok(Value::Null),
+            value if value.as_i64().is_some() => Ok(value.clone()),
+            Value::Numeric(d) => match d.rescale(digits) {
+                Some(d) => Ok(Value::Numeric(d)),
+                None => Err(SQLError::OutOfRange(String::from("numeric field overflow"))),
+            },
+            Value::Real(f) | Value::Double(f) if args.len() == 1 => {
+                let rounded = f.0.round_ties_even();
+                if matches!(args[0], Value::Real(_)) {
+                    Value::real(rounded)
+                } else {
+                    Ok(Value::Double(Float(rounded)))
+                }
+            }
+            _ => Err(SQLError::InvalidFunctionArguments(String::from(
+                "round expects a number, and numeric when digits are given",
+            ))),
+        }
+    });
+
+    // Like PostgreSQL, sums of any integer type are bigint, sums of numeric
+    // are numeric and sums of floats are double precision
+    registry.register_aggregate(
+        "sum",
+        || None,
+        |sum: &mut Option<Value>, args| {
+            expect_args("sum", args, 1)?;
+            let value = match &args[0] {
+                Value::Null => return Ok(()),
+                value if value.as_i64().is_some() => Value::BigInt(value.as_i64().unwrap()),
+                Value::Real(f) => Value::Double(*f),
+                value @ (Value::Numeric(_) | Value::Double(_)) => value.clone(),
+                _ => {
+                    return Err(SQLError::InvalidFunctionArguments(String::from(
+                        "sum expects a number",
+                    )))
+                }
+            };
+            *sum = Some(match sum.take() {
+                Some(total) => evaluate_arithmetic("+", &total, &value)?,
+                None => value,
+            });
+            Ok(())
+        },
+        |sum| Ok(sum.unwrap_or(Value::Null)),
+    );
+
+    registry.register_aggregate(
+        "min",
+        || None,
+        |min: &mut Option<Value>, args| {
+            expect_args("min", args, 1)?;
+            if args[0] != Value::Null && min.as_ref().is_none_or(|m| args[0] < *m) {
+                *min = Some(args[0].clone());
+            }
+            Ok(())
+        },
+        |min| Ok(min.unwrap_or(Value::Null)),
+    );
+
+    registry.register_aggregate(
+        "max",
+        || None,
+        |max: &mut Option<Value>, args| {
+            expect_args("max", args, 1)?;
+            if args[0] != Value::Null && max.as_ref().is_none_or(|m| args[0] > *m) {
+                *max = Some(args[0].clone());
+            }
+            Ok(())
+        },
+        |max| Ok(max.unwrap_or(Value::Null)),
+    );
+}
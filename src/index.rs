@@ -0,0 +1,371 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    ops::Bound,
+};
+
+use crate::{
+    ast::Expression,
+    mem_backend::{ColumnType, Row, Value},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IndexMethod {
+    // Ordered, so that it also answers range predicates
+    BTree,
+    Hash,
+}
+
+impl fmt::Display for IndexMethod {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            IndexMethod::BTree => write!(f, "btree"),
+            IndexMethod::Hash => write!(f, "hash"),
+        }
+    }
+}
+
+#[derive(Clone)]
+enum Entries {
+    BTree(BTreeMap<Vec<Value>, Vec<usize>>),
+    Hash(HashMap<Vec<Value>, Vec<usize>>),
+}
+
+/// Maps the values of some columns of a table, or of expressions of them, to
+/// the positions of the rows holding them, so that rows can be found without
+/// scanning the table.
+#[derive(Clone)]
+pub struct Index {
+    pub name: String,
+    // Positions of the indexed columns in the table
+    pub columns: Vec<usize>,
+    pub unique: bool,
+    pub primary: bool,
+    // Whether a PRIMARY KEY or UNIQUE constraint owns the index, rather
+    // than CREATE INDEX
+    pub constraint: bool,
+    pub method: IndexMethod,
+    // What the keys are made of instead of columns, for expression indexes
+    pub expressions: Vec<Expression>,
+    // Only the rows satisfying it are indexed, for partial indexes
+    pub predicate: Option<Expression>,
+    entries: Entries,
+    // The key of every indexed row, by position
+    keys: HashMap<usize, Vec<Value>>,
+    // The type of each part of the keys, once a value that is not NULL is
+    // seen, and whether values of different types were seen
+    key_types: Vec<Option<ColumnType>>,
+    mixed_types: bool,
+}
+
+impl Index {
+    /// The B-tree index backing a PRIMARY KEY or UNIQUE constraint.
+    pub fn new(name: String, columns: Vec<usize>, unique: bool, primary: bool) -> Index {
+        let mut index = Index::secondary(name, columns, unique, IndexMethod::BTree);
+        index.primary = primary;
+        index.constraint = true;
+        index
+    }
+
+    /// An index made with CREATE INDEX.
+    pub fn secondary(
+        name: String,
+        columns: Vec<usize>,
+        unique: bool,
+        method: IndexMethod,
+    ) -> Index {
+        Index {
+            name,
+            columns,
+            unique,
+            primary: false,
+            constraint: false,
+            method,
+            expressions: Vec::new(),
+            predicate: None,
+            entries: match method {
+                IndexMethod::BTree => Entries::BTree(BTreeMap::new()),
+                IndexMethod::Hash => Entries::Hash(HashMap::new()),
+            },
+            keys: HashMap::new(),
+            key_types: Vec::new(),
+            mixed_types: false,
+        }
+    }
+
+    /// Whether the index holds every row and its keys are plain columns.
+    pub fn is_plain(&self) -> bool {
+        self.expressions.is_empty() && self.predicate.is_none()
+    }
+
+    /// The type of the values of the `i`th part of the keys, when all of the
+    /// ones seen so far that are not NULL share it.
+    pub fn key_type(&self, i: usize) -> Option<&ColumnType> {
+        match self.mixed_types {
+            true => None,
+            false => self.key_types.get(i)?.as_ref(),
+        }
+    }
+
+    /// The values of the indexed columns of a row.
+    pub fn key(&self, row: &Row, column_types: &[ColumnType]) -> Vec<Value> {
+        self.columns
+            .iter()
+            .map(|i| Value::from_cell(&row[*i], &column_types[*i]))
+            .collect()
+    }
+
+    /// Positions of the rows whose indexed columns hold exactly `key`.
+    pub fn get(&self, key: &[Value]) -> &[usize] {
+        let positions = match &self.entries {
+            Entries::BTree(entries) => entries.get(key),
+            Entries::Hash(entries) => entries.get(key),
+        };
+        positions.map_or(&[], Vec::as_slice)
+    }
+
+    /// Positions of the rows whose first indexed column lies between the
+    /// bounds, NULLs excluded. `None` when the index is not ordered.
+    pub fn range(&self, lower: Bound<&Value>, upper: Bound<&Value>) -> Option<Vec<usize>> {
+        let entries = match &self.entries {
+            Entries::BTree(entries) => entries,
+            Entries::Hash(_) => return None,
+        };
+        // Keys with more columns sort after their first value alone, so the
+        // scan starts there and checks the bounds on the first column
+        let start = match lower {
+            Bound::Included(value) | Bound::Excluded(value) => Bound::Included(vec![value.clone()]),
+            Bound::Unbounded => Bound::Unbounded,
+        };
+        let below_upper = |value: &Value| match upper {
+            Bound::Included(upper) => value <= upper,
+            Bound::Excluded(upper) => value < upper,
+            Bound::Unbounded => true,
+        };
+        let above_lower = |value: &Value| match lower {
+            Bound::Included(lower) => value >= lower,
+            Bound::Excluded(lower) => value > lower,
+            Bound::Unbounded => true,
+        };
+
+        let mut positions = Vec::new();
+        for (key, found) in entries.range((start, Bound::Unbounded)) {
+            if !below_upper(&key[0]) {
+                break;
+            }
+            if key[0] != Value::Null && above_lower(&key[0]) {
+                positions.extend(found);
+            }
+        }
+        Some(positions)
+    }
+
+    /// Positions of every indexed row.
+    pub fn positions(&self) -> Vec<usize> {
+        self.keys.keys().copied().collect()
+    }
+
+    pub fn insert(&mut self, key: Vec<Value>, position: usize) {
+        self.key_types.resize(key.len(), None);
+        for (key_type, value) in self.key_types.iter_mut().zip(&key) {
+            match (key_type.as_ref(), value.column_type()) {
+                (_, None) => {}
+                (None, Some(value_type)) => *key_type = Some(value_type),
+                (Some(known), Some(value_type)) => self.mixed_types |= *known != value_type,
+            }
+        }
+
+        self.keys.insert(position, key.clone());
+        match &mut self.entries {
+            Entries::BTree(entries) => entries.entry(key).or_default().push(position),
+            Entries::Hash(entries) => entries.entry(key).or_default().push(position),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.keys.clear();
+        self.key_types.clear();
+        self.mixed_types = false;
+        match &mut self.entries {
+            Entries::BTree(entries) => entries.clear(),
+            Entries::Hash(entries) => entries.clear(),
+        }
+    }
+
+    /// Takes the row at `position` out of the index, returning its key.
+    pub fn remove(&mut self, position: usize) -> Option<Vec<Value>> {
+        let key = self.keys.remove(&position)?;
+        let positions = match &mut self.entries {
+            Entries::BTree(entries) => entries.get_mut(&key),
+            Entries::Hash(entries) => entries.get_mut(&key),
+        };
+        let emptied = match positions {
+            Some(positions) => {
+                positions.retain(|p| *p != position);
+                positions.is_empty()
+            }
+            None => false,
+        };
+        if emptied {
+            match &mut self.entries {
+                Entries::BTree(entries) => entries.remove(&key),
+                Entries::Hash(entries) => entries.remove(&key),
+            };
+        }
+        Some(key)
+    }
+
+    /// Takes out the rows at `removed` and moves the rows after them down,
+    /// the way the table does when deleting them.
+    pub fn remove_positions(&mut self, removed: &[usize]) {
+        let mut removed = removed.to_vec();
+        removed.sort();
+        let keys = std::mem::take(&mut self.keys);
+        self.clear();
+        for (position, key) in keys {
+            if removed.binary_search(&position).is_err() {
+                let shift = removed.partition_point(|r| *r < position);
+                self.insert(key, position - shift);
+            }
+        }
+    }
+
+    /// Moves rows up to make room for rows put back at `inserted`, undoing
+    /// `remove_positions`.
+    pub fn insert_positions(&mut self, inserted: &[usize]) {
+        let mut inserted = inserted.to_vec();
+        inserted.sort();
+        let keys = std::mem::take(&mut self.keys);
+        self.clear();
+        for (mut position, key) in keys {
+            for slot in &inserted {
+                if *slot > position {
+                    break;
+                }
+                position += 1;
+            }
+            self.insert(key, position);
+        }
+    }
+}
+
+#[cfg(test)]
+mod index_test {
+    use crate::{
+        index::{Index, IndexMethod},
+        mem_backend::{ColumnType, Value},
+    };
+    use std::ops::Bound;
+
+    #[test]
+    fn test_insert_get_remove() {
+        let types = vec![ColumnType::IntType, ColumnType::TextType];
+        let rows = [
+            vec![Value::Int(1).to_cell(), Value::Text("a".into()).to_cell()],
+            vec![Value::Int(2).to_cell(), Value::Text("b".into()).to_cell()],
+            vec![Value::Int(1).to_cell(), None],
+        ];
+
+        let mut index = Index::new(String::from("t_a_key"), vec![0], false, false);
+        for (position, row) in rows.iter().enumerate() {
+            index.insert(index.key(row, &types), position);
+        }
+        assert_eq!(index.get(&[Value::Int(1)]), &[0, 2]);
+        assert_eq!(index.get(&[Value::Int(2)]), &[1]);
+        assert!(index.get(&[Value::Int(3)]).is_empty());
+
+        assert_eq!(index.remove(0), Some(vec![Value::Int(1)]));
+        assert_eq!(index.get(&[Value::Int(1)]), &[2]);
+        index.remove(2);
+        assert!(index.get(&[Value::Int(1)]).is_empty());
+        assert_eq!(index.remove(2), None);
+
+        let composite = Index::new(String::from("t_a_b_key"), vec![0, 1], true, false);
+        assert_eq!(
+            composite.key(&rows[2], &types),
+            vec![Value::Int(1), Value::Null]
+        );
+
+        let mut hash = Index::secondary(String::from("t_a_idx"), vec![0], false, IndexMethod::Hash);
+        hash.insert(vec![Value::Int(1)], 0);
+        assert_eq!(hash.get(&[Value::Int(1)]), &[0]);
+        assert!(hash.range(Bound::Unbounded, Bound::Unbounded).is_none());
+    }
+
+    #[test]
+    fn test_remove_positions() {
+        let mut index =
+            Index::secondary(String::from("t_a_idx"), vec![0], false, IndexMethod::BTree);
+        for (position, value) in [10, 20, 10, 30, 40].into_iter().enumerate() {
+            index.insert(vec![Value::Int(value)], position);
+        }
+        index.remove_positions(&[3, 0]);
+
+        let mut positions = index.positions();
+        positions.sort();
+        assert_eq!(positions, vec![0, 1, 2]);
+        assert_eq!(index.get(&[Value::Int(10)]), &[1]);
+        assert_eq!(index.get(&[Value::Int(20)]), &[0]);
+        assert_eq!(index.get(&[Value::Int(40)]), &[2]);
+        assert!(index.get(&[Value::Int(30)]).is_empty());
+
+        index.insert_positions(&[0, 3]);
+        index.insert(vec![Value::Int(10)], 0);
+        index.insert(vec![Value::Int(30)], 3);
+        for (position, value) in [10, 20, 10, 30, 40].into_iter().enumerate() {
+            assert!(index.get(&[Value::Int(value)]).contains(&position));
+        }
+    }
+
+    #[test]
+    fn test_key_types() {
+        let mut index = Index::secondary(
+            String::from("t_expr_idx"),
+            vec![],
+            false,
+            IndexMethod::BTree,
+        );
+        assert_eq!(index.key_type(0), None);
+        index.insert(vec![Value::Null], 0);
+        assert_eq!(index.key_type(0), None);
+        index.insert(vec![Value::Text(String::from("a"))], 1);
+        assert_eq!(index.key_type(0), Some(&ColumnType::TextType));
+        index.insert(vec![Value::Int(1)], 2);
+        assert_eq!(index.key_type(0), None);
+    }
+
+    #[test]
+    fn test_range() {
+        let mut index = Index::secondary(
+            String::from("t_a_b_idx"),
+            vec![0, 1],
+            false,
+            IndexMethod::BTree,
+        );
+        let keys = [(1, "x"), (2, "y"), (2, "z"), (3, "x"), (5, "y")];
+        for (position, (a, b)) in keys.iter().enumerate() {
+            index.insert(vec![Value::Int(*a), Value::Text(b.to_string())], position);
+        }
+        index.insert(vec![Value::Null, Value::Null], 5);
+
+        let tests = vec![
+            (Bound::Included(2), Bound::Included(3), vec![1, 2, 3]),
+            (Bound::Excluded(2), Bound::Unbounded, vec![3, 4]),
+            (Bound::Unbounded, Bound::Excluded(2), vec![0]),
+            (Bound::Unbounded, Bound::Included(2), vec![0, 1, 2]),
+            (Bound::Included(4), Bound::Included(4), vec![]),
+            (Bound::Unbounded, Bound::Unbounded, vec![0, 1, 2, 3, 4]),
+        ];
+        for (lower, upper, expected) in tests {
+            let lower = lower.map(Value::Int);
+            let upper = upper.map(Value::Int);
+            assert_eq!(
+                index.range(lower.as_ref(), upper.as_ref()),
+                Some(expected),
+                "{:?} {:?}",
+                lower,
+                upper
+            );
+        }
+    }
+}
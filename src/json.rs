@@ -0,0 +1,532 @@
+use core::fmt;
+use std::{
+    cmp::Ordering,
+    hash::{Hash, Hasher},
+};
+
+use crate::numeric::Decimal;
+
+/// A parsed JSON document. Object members keep their input order.
+#[derive(Debug, Clone)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    // Kept as written so that no precision is lost
+    Number(String),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+/// One step of a path such as `$.events[0].name`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathStep {
+    Key(String),
+    // Counts from the end when negative
+    Index(i64),
+}
+
+impl Json {
+    pub fn parse(s: &str) -> Option<Json> {
+        let mut parser = Parser {
+            chars: s.chars().collect(),
+            pos: 0,
+        };
+        let json = parser.value()?;
+        parser.skip_whitespace();
+        if parser.pos != parser.chars.len() {
+            return None;
+        }
+        Some(json)
+    }
+
+    /// The member of an object with the given key. Like PostgreSQL, the
+    /// last one wins when a key is repeated.
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(members) => members
+                .iter()
+                .rev()
+                .find(|(name, _)| name == key)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    /// The element of an array, counting from the end when negative.
+    pub fn index(&self, i: i64) -> Option<&Json> {
+        match self {
+            Json::Array(elements) => {
+                let i = if i < 0 { elements.len() as i64 + i } else { i };
+                usize::try_from(i).ok().and_then(|i| elements.get(i))
+            }
+            _ => None,
+        }
+    }
+
+    pub fn step(&self, step: &PathStep) -> Option<&Json> {
+        match step {
+            PathStep::Key(key) => self.get(key),
+            PathStep::Index(i) => self.index(*i),
+        }
+    }
+
+    /// Parses a path such as `$.events[0].name` or `$."odd key"[-1]`.
+    pub fn parse_path(path: &str) -> Option<Vec<PathStep>> {
+        let chars: Vec<char> = path.trim().chars().collect();
+        if chars.first() != Some(&'$') {
+            return None;
+        }
+
+        let mut steps = Vec::new();
+        let mut pos = 1;
+        while pos < chars.len() {
+            match chars[pos] {
+                '.' if chars.get(pos + 1) == Some(&'"') => {
+                    let end = (pos + 2..chars.len()).find(|&i| chars[i] == '"')?;
+                    steps.push(PathStep::Key(chars[pos + 2..end].iter().collect()));
+                    pos = end + 1;
+                }
+                '.' => {
+                    let end = (pos + 1..chars.len())
+                        .find(|&i| matches!(chars[i], '.' | '['))
+                        .unwrap_or(chars.len());
+                    if end == pos + 1 {
+                        return None;
+                    }
+                    steps.push(PathStep::Key(chars[pos + 1..end].iter().collect()));
+                    pos = end;
+                }
+                '[' => {
+                    let end = (pos + 1..chars.len()).find(|&i| chars[i] == ']')?;
+                    let index: String = chars[pos + 1..end].iter().collect();
+                    steps.push(PathStep::Index(index.trim().parse().ok()?));
+                    pos = end + 1;
+                }
+                _ => return None,
+            }
+        }
+        Some(steps)
+    }
+
+    /// The text `->>` produces: strings without their quotes and anything
+    /// else as JSON. `None` for a JSON null.
+    pub fn as_text(&self) -> Option<String> {
+        match self {
+            Json::Null => None,
+            Json::String(s) => Some(s.clone()),
+            json => Some(json.to_string()),
+        }
+    }
+}
+
+/// Documents of different kinds are ordered null, booleans, numbers,
+/// strings, arrays, objects. Numbers compare by value, so 2 sorts before 10
+/// and 1.0 equals 1.
+impl Ord for Json {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Json::Null, Json::Null) => Ordering::Equal,
+            (Json::Bool(a), Json::Bool(b)) => a.cmp(b),
+            (Json::Number(a), Json::Number(b)) => compare_numbers(a, b),
+            (Json::String(a), Json::String(b)) => a.cmp(b),
+            (Json::Array(a), Json::Array(b)) => a.cmp(b),
+            (Json::Object(a), Json::Object(b)) => a.cmp(b),
+            _ => self.rank().cmp(&other.rank()),
+        }
+    }
+}
+
+impl PartialOrd for Json {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Json {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Json {}
+
+impl Hash for Json {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.rank().hash(state);
+        match self {
+            Json::Null => {}
+            Json::Bool(b) => b.hash(state),
+            Json::Number(s) => match Decimal::parse(s) {
+                Some(number) => number.hash(state),
+                None => s.hash(state),
+            },
+            Json::String(s) => s.hash(state),
+            Json::Array(elements) => elements.hash(state),
+            Json::Object(members) => members.hash(state),
+        }
+    }
+}
+
+impl Json {
+    fn rank(&self) -> u8 {
+        match self {
+            Json::Null => 0,
+            Json::Bool(_) => 1,
+            Json::Number(_) => 2,
+            Json::String(_) => 3,
+            Json::Array(_) => 4,
+            Json::Object(_) => 5,
+        }
+    }
+}
+
+/// Compares numbers exactly, or as floats when one has more digits than a
+/// `Decimal` holds. Those are told apart by their text so that only equal
+/// texts are equal, matching the hash.
+fn compare_numbers(a: &str, b: &str) -> Ordering {
+    match (Decimal::parse(a), Decimal::parse(b)) {
+        (Some(x), Some(y)) => x.cmp(&y),
+        _ => {
+            let float = |s: &str| s.parse::<f64>().unwrap_or(f64::NAN);
+            float(a).total_cmp(&float(b)).then_with(|| a.cmp(b))
+        }
+    }
+}
+
+impl fmt::Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Json::Null => write!(f, "null"),
+            Json::Bool(b) => write!(f, "{}", b),
+            Json::Number(n) => write!(f, "{}", n),
+            Json::String(s) => write_string(f, s),
+            Json::Array(elements) => {
+                write!(f, "[")?;
+                for (i, element) in elements.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", element)?;
+                }
+                write!(f, "]")
+            }
+            Json::Object(members) => {
+                write!(f, "{{")?;
+                for (i, (key, value)) in members.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write_string(f, key)?;
+                    write!(f, ": {}", value)?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+fn write_string(f: &mut fmt::Formatter, s: &str) -> fmt::Result {
+    write!(f, "\"")?;
+    for c in s.chars() {
+        match c {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\r' => write!(f, "\\r")?,
+            '\t' => write!(f, "\\t")?,
+            '\u{8}' => write!(f, "\\b")?,
+            '\u{c}' => write!(f, "\\f")?,
+            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+            c => write!(f, "{}", c)?,
+        }
+    }
+    write!(f, "\"")
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self
+            .peek()
+            .is_some_and(|c| matches!(c, ' ' | '\t' | '\n' | '\r'))
+        {
+            self.pos += 1;
+        }
+    }
+
+    fn consume(&mut self, expected: char) -> Option<()> {
+        self.skip_whitespace();
+        if self.peek() != Some(expected) {
+            return None;
+        }
+        self.pos += 1;
+        Some(())
+    }
+
+    fn keyword(&mut self, word: &str, json: Json) -> Option<Json> {
+        let end = self.pos + word.len();
+        if end > self.chars.len() || self.chars[self.pos..end].iter().copied().ne(word.chars()) {
+            return None;
+        }
+        self.pos = end;
+        Some(json)
+    }
+
+    fn value(&mut self) -> Option<Json> {
+        self.skip_whitespace();
+        match self.peek()? {
+            'n' => self.keyword("null", Json::Null),
+            't' => self.keyword("true", Json::Bool(true)),
+            'f' => self.keyword("false", Json::Bool(false)),
+            '"' => self.string().map(Json::String),
+            '[' => self.array(),
+            '{' => self.object(),
+            _ => self.number(),
+        }
+    }
+
+    fn array(&mut self) -> Option<Json> {
+        self.consume('[')?;
+        let mut elements = Vec::new();
+        if self.consume(']').is_some() {
+            return Some(Json::Array(elements));
+        }
+        loop {
+            elements.push(self.value()?);
+            if self.consume(']').is_some() {
+                return Some(Json::Array(elements));
+            }
+            self.consume(',')?;
+        }
+    }
+
+    fn object(&mut self) -> Option<Json> {
+        self.consume('{')?;
+        let mut members = Vec::new();
+        if self.consume('}').is_some() {
+            return Some(Json::Object(members));
+        }
+        loop {
+            self.skip_whitespace();
+            let key = self.string()?;
+            self.consume(':')?;
+            members.push((key, self.value()?));
+            if self.consume('}').is_some() {
+                return Some(Json::Object(members));
+            }
+            self.consume(',')?;
+        }
+    }
+
+    fn string(&mut self) -> Option<String> {
+        if self.peek() != Some('"') {
+            return None;
+        }
+        self.pos += 1;
+
+        let mut s = String::new();
+        loop {
+            let c = self.peek()?;
+            self.pos += 1;
+            match c {
+                '"' => return Some(s),
+                '\\' => {
+                    let escaped = self.peek()?;
+                    self.pos += 1;
+                    match escaped {
+                        '"' | '\\' | '/' => s.push(escaped),
+                        'b' => s.push('\u{8}'),
+                        'f' => s.push('\u{c}'),
+                        'n' => s.push('\n'),
+                        'r' => s.push('\r'),
+                        't' => s.push('\t'),
+                        'u' => s.push(self.unicode_escape()?),
+                        _ => return None,
+                    }
+                }
+                c if (c as u32) < 0x20 => return None,
+                c => s.push(c),
+            }
+        }
+    }
+
+    // The four hex digits after `\u`, combining surrogate pairs
+    fn unicode_escape(&mut self) -> Option<char> {
+        let high = self.hex4()?;
+        if !(0xd800..0xdc00).contains(&high) {
+            return char::from_u32(high);
+        }
+        if self.chars.get(self.pos..self.pos + 2)? != ['\\', 'u'] {
+            return None;
+        }
+        self.pos += 2;
+        let low = self.hex4()?;
+        if !(0xdc00..0xe000).contains(&low) {
+            return None;
+        }
+        char::from_u32(0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00))
+    }
+
+    fn hex4(&mut self) -> Option<u32> {
+        let digits: String = self.chars.get(self.pos..self.pos + 4)?.iter().collect();
+        if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        self.pos += 4;
+        u32::from_str_radix(&digits, 16).ok()
+    }
+
+    // -?(0|[1-9][0-9]*)(.[0-9]+)?([eE][+-]?[0-9]+)?
+    fn number(&mut self) -> Option<Json> {
+        let start = self.pos;
+        let digits = |parser: &mut Parser| {
+            let from = parser.pos;
+            while parser.peek().is_some_and(|c| c.is_ascii_digit()) {
+                parser.pos += 1;
+            }
+            parser.pos - from
+        };
+
+        if self.peek() == Some('-') {
+            self.pos += 1;
+        }
+        if self.peek() == Some('0') {
+            self.pos += 1;
+        } else if digits(self) == 0 {
+            return None;
+        }
+        if self.peek() == Some('.') {
+            self.pos += 1;
+            if digits(self) == 0 {
+                return None;
+            }
+        }
+        if matches!(self.peek(), Some('e' | 'E')) {
+            self.pos += 1;
+            if matches!(self.peek(), Some('+' | '-')) {
+                self.pos += 1;
+            }
+            if digits(self) == 0 {
+                return None;
+            }
+        }
+        Some(Json::Number(self.chars[start..self.pos].iter().collect()))
+    }
+}
+
+#[cfg(test)]
+mod json_test {
+    use crate::json::{Json, PathStep};
+
+    #[test]
+    fn test_parse_and_format() {
+        let tests = vec![
+            ("null", "null"),
+            (" true ", "true"),
+            ("-0.5e+10", "-0.5e+10"),
+            ("\"a\\\"b\\u00e9\\n\"", "\"a\\\"bé\\n\""),
+            ("\"\\ud83d\\ude00\"", "\"😀\""),
+            ("[1,2 , [] ]", "[1, 2, []]"),
+            (
+                "{\"a\":1,\"b\":{\"c\":[true,null]}}",
+                "{\"a\": 1, \"b\": {\"c\": [true, null]}}",
+            ),
+            ("{}", "{}"),
+        ];
+        for (input, expected) in tests {
+            assert_eq!(
+                Json::parse(input).map(|json| json.to_string()),
+                Some(expected.to_string()),
+                "{}",
+                input
+            );
+        }
+
+        for bad in [
+            "", "nul", "01", "1.", "-", "[1,]", "{\"a\"}", "{a:1}", "\"abc", "[1] 2", "'a'",
+            "\"\\x\"",
+        ] {
+            assert!(Json::parse(bad).is_none(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_order() {
+        let mut documents: Vec<Json> = ["10", "\"a\"", "2", "9.5", "null", "1e1", "[2]", "[10]"]
+            .iter()
+            .map(|s| Json::parse(s).unwrap())
+            .collect();
+        documents.sort();
+        let texts: Vec<String> = documents.iter().map(|json| json.to_string()).collect();
+        assert_eq!(
+            texts,
+            vec!["null", "2", "9.5", "10", "1e1", "\"a\"", "[2]", "[10]"]
+        );
+
+        assert_eq!(Json::parse("1.0"), Json::parse("1"));
+        assert_ne!(Json::parse("1"), Json::parse("\"1\""));
+        let set: std::collections::HashSet<Json> = ["1", "1.00", "1e0", "2"]
+            .iter()
+            .map(|s| Json::parse(s).unwrap())
+            .collect();
+        assert_eq!(set.len(), 2);
+    }
+
+    #[test]
+    fn test_paths() {
+        let json =
+            Json::parse("{\"a\": [10, {\"b\": \"x\"}], \"odd key\": 1, \"a\": [20]}").unwrap();
+        let tests = vec![
+            (
+                "$",
+                Some("{\"a\": [10, {\"b\": \"x\"}], \"odd key\": 1, \"a\": [20]}"),
+            ),
+            ("$.a", Some("[20]")),
+            ("$.a[0]", Some("20")),
+            ("$.a[-1]", Some("20")),
+            ("$.\"odd key\"", Some("1")),
+            ("$.missing", None),
+            ("$.a[5]", None),
+        ];
+        for (path, expected) in tests {
+            let steps = Json::parse_path(path).unwrap();
+            let mut current = Some(&json);
+            for step in &steps {
+                current = current.and_then(|json| json.step(step));
+            }
+            assert_eq!(
+                current.map(|json| json.to_string()),
+                expected.map(String::from),
+                "{}",
+                path
+            );
+        }
+
+        assert_eq!(
+            Json::parse_path("$.a[1].b"),
+            Some(vec![
+                PathStep::Key(String::from("a")),
+                PathStep::Index(1),
+                PathStep::Key(String::from("b")),
+            ])
+        );
+        for bad in ["a.b", "$.", "$[x]", "$.a[1", "$x"] {
+            assert!(Json::parse_path(bad).is_none(), "{}", bad);
+        }
+
+        assert_eq!(
+            Json::String(String::from("x")).as_text(),
+            Some(String::from("x"))
+        );
+        assert_eq!(Json::Null.as_text(), None);
+    }
+}
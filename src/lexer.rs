@@ -1,6 +1,6 @@
 use core::fmt;
 
-#[derive(Clone, PartialEq, Eq, Debug)]
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
 pub struct Location {
     pub col: u32,
     pub line: u32,
@@ -26,6 +26,7 @@ pub enum TokenKind {
     Identifier,
     String,
     Numeric,
+    Blob,
 }
 
 pub enum Symbol {
@@ -34,6 +35,27 @@ pub enum Symbol {
     Comma,
     LeftParen,
     RightParen,
+    Equal,
+    NotEqual,
+    BangEqual,
+    LessThan,
+    LessThanEqual,
+    GreaterThan,
+    GreaterThanEqual,
+    Tilde,
+    TildeAsterisk,
+    BangTilde,
+    BangTildeAsterisk,
+    DoubleColon,
+    Plus,
+    Minus,
+    Slash,
+    Percent,
+    Arrow,
+    DoubleArrow,
+    LeftBracket,
+    RightBracket,
+    Dot,
 }
 
 pub enum Keyword {
@@ -47,8 +69,148 @@ pub enum Keyword {
     Values,
     Int,
     Text,
+    Where,
+    And,
+    Or,
+    Not,
+    Like,
+    Ilike,
+    Glob,
+    Regexp,
+    Escape,
+    In,
+    Between,
+    Cast,
+    Null,
+    Is,
+    Over,
+    Partition,
+    By,
+    Order,
+    Asc,
+    Desc,
+    Rows,
+    Range,
+    Unbounded,
+    Preceding,
+    Following,
+    Current,
+    Row,
+    Boolean,
+    Bool,
+    True,
+    False,
+    Smallint,
+    Integer,
+    Bigint,
+    Int2,
+    Int4,
+    Int8,
+    Real,
+    Float,
+    Float4,
+    Float8,
+    Numeric,
+    Decimal,
+    Date,
+    Time,
+    Timestamp,
+    Interval,
+    Extract,
+    CurrentDate,
+    Update,
+    Set,
+    Varchar,
+    Char,
+    Character,
+    Blob,
+    Bytea,
+    Json,
+    Uuid,
+    Array,
+    Any,
+    All,
+    Type,
+    Enum,
+    Primary,
+    Unique,
+    Default,
+    Check,
+    Constraint,
+    Delete,
+    On,
+    References,
+    Foreign,
+    Cascade,
+    Restrict,
+    Sequence,
+    Generated,
+    Always,
+    Identity,
+    Returning,
+    Index,
+    Drop,
+    Using,
+    Limit,
+    Offset,
+    Explain,
+    Analyze,
+    Join,
+    Inner,
 }
 
+/// Keywords that may still name a column, table, index or type, as in
+/// PostgreSQL. Every other keyword is reserved.
+pub const NON_RESERVED_KEYWORDS: &[Keyword] = &[
+    Keyword::Int,
+    Keyword::Text,
+    Keyword::Escape,
+    Keyword::Glob,
+    Keyword::Partition,
+    Keyword::Rows,
+    Keyword::Range,
+    Keyword::Unbounded,
+    Keyword::Preceding,
+    Keyword::Following,
+    Keyword::Current,
+    Keyword::Row,
+    Keyword::Boolean,
+    Keyword::Bool,
+    Keyword::Smallint,
+    Keyword::Integer,
+    Keyword::Bigint,
+    Keyword::Int2,
+    Keyword::Int4,
+    Keyword::Int8,
+    Keyword::Real,
+    Keyword::Float,
+    Keyword::Float4,
+    Keyword::Float8,
+    Keyword::Numeric,
+    Keyword::Decimal,
+    Keyword::Date,
+    Keyword::Time,
+    Keyword::Timestamp,
+    Keyword::Interval,
+    Keyword::Varchar,
+    Keyword::Char,
+    Keyword::Character,
+    Keyword::Blob,
+    Keyword::Bytea,
+    Keyword::Json,
+    Keyword::Uuid,
+    Keyword::Type,
+    Keyword::Enum,
+    Keyword::Cascade,
+    Keyword::Restrict,
+    Keyword::Sequence,
+    Keyword::Generated,
+    Keyword::Always,
+    Keyword::Identity,
+    Keyword::Index,
+    Keyword::Analyze,
+];
+
 impl fmt::Display for Symbol {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -57,6 +219,27 @@ impl fmt::Display for Symbol {
             Symbol::Asterisk => write!(f, "*"),
             Symbol::LeftParen => write!(f, "("),
             Symbol::RightParen => write!(f, ")"),
+            Symbol::Equal => write!(f, "="),
+            Symbol::NotEqual => write!(f, "<>"),
+            Symbol::BangEqual => write!(f, "!="),
+            Symbol::LessThan => write!(f, "<"),
+            Symbol::LessThanEqual => write!(f, "<="),
+            Symbol::GreaterThan => write!(f, ">"),
+            Symbol::GreaterThanEqual => write!(f, ">="),
+            Symbol::Tilde => write!(f, "~"),
+            Symbol::TildeAsterisk => write!(f, "~*"),
+            Symbol::BangTilde => write!(f, "!~"),
+            Symbol::BangTildeAsterisk => write!(f, "!~*"),
+            Symbol::DoubleColon => write!(f, "::"),
+            Symbol::Plus => write!(f, "+"),
+            Symbol::Minus => write!(f, "-"),
+            Symbol::Slash => write!(f, "/"),
+            Symbol::Percent => write!(f, "%"),
+            Symbol::Arrow => write!(f, "->"),
+            Symbol::DoubleArrow => write!(f, "->>"),
+            Symbol::LeftBracket => write!(f, "["),
+            Symbol::RightBracket => write!(f, "]"),
+            Symbol::Dot => write!(f, "."),
         }
     }
 }
@@ -74,6 +257,94 @@ impl fmt::Display for Keyword {
             Keyword::Insert => write!(f, "insert"),
             Keyword::Table => write!(f, "table"),
             Keyword::Text => write!(f, "text"),
+            Keyword::Where => write!(f, "where"),
+            Keyword::And => write!(f, "and"),
+            Keyword::Or => write!(f, "or"),
+            Keyword::Not => write!(f, "not"),
+            Keyword::Like => write!(f, "like"),
+            Keyword::Ilike => write!(f, "ilike"),
+            Keyword::Glob => write!(f, "glob"),
+            Keyword::Regexp => write!(f, "regexp"),
+            Keyword::Escape => write!(f, "escape"),
+            Keyword::In => write!(f, "in"),
+            Keyword::Between => write!(f, "between"),
+            Keyword::Cast => write!(f, "cast"),
+            Keyword::Null => write!(f, "null"),
+            Keyword::Is => write!(f, "is"),
+            Keyword::Over => write!(f, "over"),
+            Keyword::Partition => write!(f, "partition"),
+            Keyword::By => write!(f, "by"),
+            Keyword::Order => write!(f, "order"),
+            Keyword::Asc => write!(f, "asc"),
+            Keyword::Desc => write!(f, "desc"),
+            Keyword::Rows => write!(f, "rows"),
+            Keyword::Range => write!(f, "range"),
+            Keyword::Unbounded => write!(f, "unbounded"),
+            Keyword::Preceding => write!(f, "preceding"),
+            Keyword::Following => write!(f, "following"),
+            Keyword::Current => write!(f, "current"),
+            Keyword::Row => write!(f, "row"),
+            Keyword::Boolean => write!(f, "boolean"),
+            Keyword::Bool => write!(f, "bool"),
+            Keyword::True => write!(f, "true"),
+            Keyword::False => write!(f, "false"),
+            Keyword::Smallint => write!(f, "smallint"),
+            Keyword::Integer => write!(f, "integer"),
+            Keyword::Bigint => write!(f, "bigint"),
+            Keyword::Int2 => write!(f, "int2"),
+            Keyword::Int4 => write!(f, "int4"),
+            Keyword::Int8 => write!(f, "int8"),
+            Keyword::Real => write!(f, "real"),
+            Keyword::Float => write!(f, "float"),
+            Keyword::Float4 => write!(f, "float4"),
+            Keyword::Float8 => write!(f, "float8"),
+            Keyword::Numeric => write!(f, "numeric"),
+            Keyword::Decimal => write!(f, "decimal"),
+            Keyword::Date => write!(f, "date"),
+            Keyword::Time => write!(f, "time"),
+            Keyword::Timestamp => write!(f, "timestamp"),
+            Keyword::Interval => write!(f, "interval"),
+            Keyword::Extract => write!(f, "extract"),
+            Keyword::CurrentDate => write!(f, "current_date"),
+            Keyword::Update => write!(f, "update"),
+            Keyword::Set => write!(f, "set"),
+            Keyword::Varchar => write!(f, "varchar"),
+            Keyword::Char => write!(f, "char"),
+            Keyword::Character => write!(f, "character"),
+            Keyword::Blob => write!(f, "blob"),
+            Keyword::Bytea => write!(f, "bytea"),
+            Keyword::Json => write!(f, "json"),
+            Keyword::Uuid => write!(f, "uuid"),
+            Keyword::Array => write!(f, "array"),
+            Keyword::Any => write!(f, "any"),
+            Keyword::All => write!(f, "all"),
+            Keyword::Type => write!(f, "type"),
+            Keyword::Enum => write!(f, "enum"),
+            Keyword::Primary => write!(f, "primary"),
+            Keyword::Unique => write!(f, "unique"),
+            Keyword::Default => write!(f, "default"),
+            Keyword::Check => write!(f, "check"),
+            Keyword::Constraint => write!(f, "constraint"),
+            Keyword::Delete => write!(f, "delete"),
+            Keyword::On => write!(f, "on"),
+            Keyword::References => write!(f, "references"),
+            Keyword::Foreign => write!(f, "foreign"),
+            Keyword::Cascade => write!(f, "cascade"),
+            Keyword::Restrict => write!(f, "restrict"),
+            Keyword::Sequence => write!(f, "sequence"),
+            Keyword::Generated => write!(f, "generated"),
+            Keyword::Always => write!(f, "always"),
+            Keyword::Identity => write!(f, "identity"),
+            Keyword::Returning => write!(f, "returning"),
+            Keyword::Index => write!(f, "index"),
+            Keyword::Drop => write!(f, "drop"),
+            Keyword::Using => write!(f, "using"),
+            Keyword::Limit => write!(f, "limit"),
+            Keyword::Offset => write!(f, "offset"),
+            Keyword::Explain => write!(f, "explain"),
+            Keyword::Analyze => write!(f, "analyze"),
+            Keyword::Join => write!(f, "join"),
+            Keyword::Inner => write!(f, "inner"),
         }
     }
 }
@@ -95,7 +366,7 @@ impl Token {
     }
 }
 
-pub fn lex(source: &String) -> Result<Vec<Token>, String> {
+pub fn lex(source: &str) -> Result<Vec<Token>, String> {
     let mut tokens = Vec::new();
     let mut cur = Cursor {
         loc: Location { col: 0, line: 0 },
@@ -103,16 +374,18 @@ pub fn lex(source: &String) -> Result<Vec<Token>, String> {
     };
 
     'outer: while cur.pos < source.len() as u32 {
+        // Numbers go before symbols so that `.5` is not lexed as a dot
         let lexers = Vec::from([
             lex_keyword,
-            lex_symbols,
             lex_numeric,
+            lex_symbols,
             lex_string,
+            lex_blob,
             lex_identifier,
         ]);
 
         for l in lexers {
-            match l(&source, &mut cur) {
+            match l(source, &mut cur) {
                 Ok(token) => {
                     if token.token_kind != TokenKind::Nil {
                         tokens.push(token);
@@ -126,7 +399,7 @@ pub fn lex(source: &String) -> Result<Vec<Token>, String> {
         }
 
         let mut hint = String::new();
-        if tokens.len() > 0 {
+        if !tokens.is_empty() {
             hint = " after ".to_string() + &tokens[tokens.len() - 1].literal.clone();
         }
 
@@ -138,22 +411,47 @@ pub fn lex(source: &String) -> Result<Vec<Token>, String> {
     Ok(tokens)
 }
 
-fn lex_numeric(source: &String, cur: &mut Cursor) -> Result<Token, String> {
+/// Lexes `12`, `3.14`, `.5`, `1.` and `1e10` style numbers.
+fn lex_numeric(source: &str, cur: &mut Cursor) -> Result<Token, String> {
     let old_cur = cur.clone();
+    let chars: Vec<char> = source.chars().collect();
+    let skip_digits = |from: usize| {
+        let mut i = from;
+        while i < chars.len() && chars[i].is_ascii_digit() {
+            i += 1;
+        }
+        i
+    };
 
-    while (cur.pos as usize) < source.len() {
-        if let Some(curr_char) = source.chars().nth(cur.pos as usize) {
-            if !curr_char.is_ascii_digit() {
-                if old_cur.pos == cur.pos {
-                    return Err("Not a digit".to_string());
-                }
-                break;
-            }
+    let start = cur.pos as usize;
+    let mut end = skip_digits(start);
+    let mut has_digits = end > start;
+
+    if end < chars.len() && chars[end] == '.' {
+        let fraction_end = skip_digits(end + 1);
+        has_digits = has_digits || fraction_end > end + 1;
+        end = fraction_end;
+    }
+
+    if !has_digits {
+        return Err("Not a digit".to_string());
+    }
+
+    // The exponent needs digits, otherwise the `e` starts the next token
+    if end < chars.len() && (chars[end] == 'e' || chars[end] == 'E') {
+        let mut exponent = end + 1;
+        if exponent < chars.len() && (chars[exponent] == '+' || chars[exponent] == '-') {
+            exponent += 1;
+        }
+        let exponent_end = skip_digits(exponent);
+        if exponent_end > exponent {
+            end = exponent_end;
         }
-        cur.pos += 1;
-        cur.loc.col += 1;
     }
 
+    cur.pos = end as u32;
+    cur.loc.col += (end - start) as u32;
+
     let tok = Token {
         literal: source[old_cur.pos as usize..cur.pos as usize].to_string(),
         token_kind: TokenKind::Numeric,
@@ -163,15 +461,44 @@ fn lex_numeric(source: &String, cur: &mut Cursor) -> Result<Token, String> {
     Ok(tok)
 }
 
-fn lex_string(source: &String, cursor: &mut Cursor) -> Result<Token, String> {
-    lex_char_delimited(&source, cursor, '\'')
+fn lex_string(source: &str, cursor: &mut Cursor) -> Result<Token, String> {
+    lex_char_delimited(source, cursor, '\'')
 }
 
-fn lex_char_delimited(source: &String, cur: &mut Cursor, delimiter: char) -> Result<Token, String> {
+/// Lexes `X'DEADBEEF'` style binary strings, an even number of hex digits.
+fn lex_blob(source: &str, cur: &mut Cursor) -> Result<Token, String> {
+    let old_cur = cur.clone();
+    let prefix = source[cur.pos as usize..].chars().next();
+    if !matches!(prefix, Some('x' | 'X')) {
+        return Err(String::from("Not a binary string"));
+    }
+
+    let mut string_cur = Cursor {
+        pos: cur.pos + 1,
+        loc: Location {
+            col: cur.loc.col + 1,
+            line: cur.loc.line,
+        },
+    };
+    let string = lex_string(source, &mut string_cur)?;
+    let digits = &string.literal[1..string.literal.len() - 1];
+    if !digits.len().is_multiple_of(2) || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(String::from("Invalid binary string"));
+    }
+
+    *cur = string_cur;
+    Ok(Token {
+        literal: source[old_cur.pos as usize..cur.pos as usize].to_string(),
+        token_kind: TokenKind::Blob,
+        loc: old_cur.loc,
+    })
+}
+
+fn lex_char_delimited(source: &str, cur: &mut Cursor, delimiter: char) -> Result<Token, String> {
     let old_cur = cur.clone();
     let mut value = String::new();
 
-    if source[cur.pos as usize..].len() == 0 {
+    if source[cur.pos as usize..].is_empty() {
         return Err(String::from("Empty string"));
     }
 
@@ -222,7 +549,7 @@ fn lex_char_delimited(source: &String, cur: &mut Cursor, delimiter: char) -> Res
     Err(String::from("Invalid string"))
 }
 
-fn lex_keyword(source: &String, cursor_in: &mut Cursor) -> Result<Token, String> {
+fn lex_keyword(source: &str, cursor_in: &mut Cursor) -> Result<Token, String> {
     let cursor = cursor_in.clone();
     let keywords = Vec::from([
         Keyword::Select.to_string(),
@@ -235,6 +562,94 @@ fn lex_keyword(source: &String, cursor_in: &mut Cursor) -> Result<Token, String>
         Keyword::Int.to_string(),
         Keyword::Text.to_string(),
         Keyword::Table.to_string(),
+        Keyword::Where.to_string(),
+        Keyword::And.to_string(),
+        Keyword::Or.to_string(),
+        Keyword::Not.to_string(),
+        Keyword::Like.to_string(),
+        Keyword::Ilike.to_string(),
+        Keyword::Glob.to_string(),
+        Keyword::Regexp.to_string(),
+        Keyword::Escape.to_string(),
+        Keyword::In.to_string(),
+        Keyword::Between.to_string(),
+        Keyword::Cast.to_string(),
+        Keyword::Null.to_string(),
+        Keyword::Is.to_string(),
+        Keyword::Over.to_string(),
+        Keyword::Partition.to_string(),
+        Keyword::By.to_string(),
+        Keyword::Order.to_string(),
+        Keyword::Asc.to_string(),
+        Keyword::Desc.to_string(),
+        Keyword::Rows.to_string(),
+        Keyword::Range.to_string(),
+        Keyword::Unbounded.to_string(),
+        Keyword::Preceding.to_string(),
+        Keyword::Following.to_string(),
+        Keyword::Current.to_string(),
+        Keyword::Row.to_string(),
+        Keyword::Boolean.to_string(),
+        Keyword::Bool.to_string(),
+        Keyword::True.to_string(),
+        Keyword::False.to_string(),
+        Keyword::Smallint.to_string(),
+        Keyword::Integer.to_string(),
+        Keyword::Bigint.to_string(),
+        Keyword::Int2.to_string(),
+        Keyword::Int4.to_string(),
+        Keyword::Int8.to_string(),
+        Keyword::Real.to_string(),
+        Keyword::Float.to_string(),
+        Keyword::Float4.to_string(),
+        Keyword::Float8.to_string(),
+        Keyword::Numeric.to_string(),
+        Keyword::Decimal.to_string(),
+        Keyword::Date.to_string(),
+        Keyword::Time.to_string(),
+        Keyword::Timestamp.to_string(),
+        Keyword::Interval.to_string(),
+        Keyword::Extract.to_string(),
+        Keyword::CurrentDate.to_string(),
+        Keyword::Update.to_string(),
+        Keyword::Set.to_string(),
+        Keyword::Varchar.to_string(),
+        Keyword::Char.to_string(),
+        Keyword::Character.to_string(),
+        Keyword::Blob.to_string(),
+        Keyword::Bytea.to_string(),
+        Keyword::Json.to_string(),
+        Keyword::Uuid.to_string(),
+        Keyword::Array.to_string(),
+        Keyword::Any.to_string(),
+        Keyword::All.to_string(),
+        Keyword::Type.to_string(),
+        Keyword::Enum.to_string(),
+        Keyword::Primary.to_string(),
+        Keyword::Unique.to_string(),
+        Keyword::Default.to_string(),
+        Keyword::Check.to_string(),
+        Keyword::Constraint.to_string(),
+        Keyword::Delete.to_string(),
+        Keyword::On.to_string(),
+        Keyword::References.to_string(),
+        Keyword::Foreign.to_string(),
+        Keyword::Cascade.to_string(),
+        Keyword::Restrict.to_string(),
+        Keyword::Sequence.to_string(),
+        Keyword::Generated.to_string(),
+        Keyword::Always.to_string(),
+        Keyword::Identity.to_string(),
+        Keyword::Returning.to_string(),
+        Keyword::Index.to_string(),
+        Keyword::Drop.to_string(),
+        Keyword::Using.to_string(),
+        Keyword::Limit.to_string(),
+        Keyword::Offset.to_string(),
+        Keyword::Explain.to_string(),
+        Keyword::Analyze.to_string(),
+        Keyword::Join.to_string(),
+        Keyword::Inner.to_string(),
     ]);
 
     let keyword_match = longest_match(source, cursor, keywords);
@@ -243,6 +658,15 @@ fn lex_keyword(source: &String, cursor_in: &mut Cursor) -> Result<Token, String>
         return Err(String::from("No keyword found"));
     }
 
+    // A keyword must end at a word boundary, otherwise `notes` would lex as
+    // `not` followed by the identifier `es`.
+    let end = cursor_in.pos as usize + keyword_match.len();
+    if let Some(next_char) = source[end..].chars().next() {
+        if next_char.is_alphanumeric() || next_char == '$' || next_char == '_' {
+            return Err(String::from("No keyword found"));
+        }
+    }
+
     let tok = Token {
         token_kind: TokenKind::Keyword,
         literal: keyword_match.clone(),
@@ -258,7 +682,7 @@ fn lex_keyword(source: &String, cursor_in: &mut Cursor) -> Result<Token, String>
     Ok(tok)
 }
 
-fn longest_match(source: &String, cursor_in: Cursor, options: Vec<String>) -> String {
+fn longest_match(source: &str, cursor_in: Cursor, options: Vec<String>) -> String {
     let mut substr: String = String::new();
     let mut skip_list: Vec<usize> = Vec::new();
     let mut str_match = String::new();
@@ -299,7 +723,7 @@ fn longest_match(source: &String, cursor_in: Cursor, options: Vec<String>) -> St
     str_match
 }
 
-fn lex_symbols(source: &String, cursor_in: &mut Cursor) -> Result<Token, String> {
+fn lex_symbols(source: &str, cursor_in: &mut Cursor) -> Result<Token, String> {
     let c = match source.chars().nth(cursor_in.pos as usize) {
         Some(c) => c,
         None => {
@@ -335,6 +759,27 @@ fn lex_symbols(source: &String, cursor_in: &mut Cursor) -> Result<Token, String>
         Symbol::RightParen.to_string(),
         Symbol::Semicolon.to_string(),
         Symbol::Comma.to_string(),
+        Symbol::Equal.to_string(),
+        Symbol::NotEqual.to_string(),
+        Symbol::BangEqual.to_string(),
+        Symbol::LessThan.to_string(),
+        Symbol::LessThanEqual.to_string(),
+        Symbol::GreaterThan.to_string(),
+        Symbol::GreaterThanEqual.to_string(),
+        Symbol::Tilde.to_string(),
+        Symbol::TildeAsterisk.to_string(),
+        Symbol::BangTilde.to_string(),
+        Symbol::BangTildeAsterisk.to_string(),
+        Symbol::DoubleColon.to_string(),
+        Symbol::Plus.to_string(),
+        Symbol::Minus.to_string(),
+        Symbol::Slash.to_string(),
+        Symbol::Percent.to_string(),
+        Symbol::Arrow.to_string(),
+        Symbol::DoubleArrow.to_string(),
+        Symbol::LeftBracket.to_string(),
+        Symbol::RightBracket.to_string(),
+        Symbol::Dot.to_string(),
     ]);
 
     let sym_match = longest_match(source, cursor_in.clone(), symbols);
@@ -356,7 +801,7 @@ fn lex_symbols(source: &String, cursor_in: &mut Cursor) -> Result<Token, String>
     })
 }
 
-fn lex_identifier(source: &String, cursor_in: &mut Cursor) -> Result<Token, String> {
+fn lex_identifier(source: &str, cursor_in: &mut Cursor) -> Result<Token, String> {
     if let Ok(token) = lex_char_delimited(source, cursor_in, '\"') {
         return Ok(token);
     }
@@ -386,7 +831,7 @@ fn lex_identifier(source: &String, cursor_in: &mut Cursor) -> Result<Token, Stri
         }
     }
 
-    if value.len() == 0 {
+    if value.is_empty() {
         return Err(String::from("No identifier found"));
     }
 
@@ -407,8 +852,9 @@ mod lexer_test {
     use std::{collections::HashMap, ops::Index};
 
     use {
-        lexer::lex, lexer::lex_identifier, lexer::lex_keyword, lexer::lex_string,
-        lexer::longest_match, lexer::Cursor, lexer::Location, lexer::Token, lexer::TokenKind,
+        lexer::lex, lexer::lex_blob, lexer::lex_identifier, lexer::lex_keyword, lexer::lex_numeric,
+        lexer::lex_string, lexer::longest_match, lexer::Cursor, lexer::Location, lexer::Token,
+        lexer::TokenKind,
     };
 
     #[test]
@@ -438,6 +884,36 @@ mod lexer_test {
         }
     }
 
+    #[test]
+    fn test_lex_blob() {
+        let tests = vec![
+            ("X'DEADBEEF'", Ok("X'DEADBEEF'")),
+            ("x'00ff')", Ok("x'00ff'")),
+            ("X''", Ok("X''")),
+            ("X'ABC'", Err(())),
+            ("X'GG'", Err(())),
+            ("X 'AB'", Err(())),
+            ("'AB'", Err(())),
+            ("xyz", Err(())),
+        ];
+
+        for (source, expected) in tests {
+            let res = lex_blob(
+                source,
+                &mut Cursor {
+                    pos: 0,
+                    loc: Location { col: 0, line: 0 },
+                },
+            );
+            assert_eq!(
+                res.map(|token| token.literal).map_err(|_| ()),
+                expected.map(String::from),
+                "{}",
+                source
+            );
+        }
+    }
+
     #[test]
     fn test_longest_match() {
         let options = vec![
@@ -463,6 +939,37 @@ mod lexer_test {
         }
     }
 
+    #[test]
+    fn test_lex_numeric() {
+        let tests = vec![
+            ("123", Ok("123")),
+            ("3.14)", Ok("3.14")),
+            (".5", Ok(".5")),
+            ("1.", Ok("1.")),
+            ("1e10", Ok("1e10")),
+            ("2.5E-3,", Ok("2.5E-3")),
+            ("1ex", Ok("1")),
+            (".", Err(())),
+            ("abc", Err(())),
+        ];
+
+        for (source, expected) in tests {
+            let res = lex_numeric(
+                source,
+                &mut Cursor {
+                    pos: 0,
+                    loc: Location { col: 0, line: 0 },
+                },
+            );
+            assert_eq!(
+                res.map(|token| token.literal).map_err(|_| ()),
+                expected.map(String::from),
+                "{}",
+                source
+            );
+        }
+    }
+
     #[test]
     fn test_lex_keyword() {
         let tests = HashMap::from([
@@ -479,12 +986,29 @@ mod lexer_test {
                 }),
             ),
             (String::new(), Err(String::from("No keyword found"))),
+            (String::from("notes"), Err(String::from("No keyword found"))),
+            (
+                String::from("boolean"),
+                Ok(Token {
+                    literal: String::from("boolean"),
+                    token_kind: TokenKind::Keyword,
+                    loc: Location { col: 0, line: 0 },
+                }),
+            ),
+            (
+                String::from("not("),
+                Ok(Token {
+                    literal: String::from("not"),
+                    token_kind: TokenKind::Keyword,
+                    loc: Location { col: 0, line: 0 },
+                }),
+            ),
         ]);
 
         for t in tests {
             println!("Testing: {}", t.0);
             let lex_res = lex_keyword(
-                &String::from(t.0),
+                &t.0,
                 &mut Cursor {
                     pos: 0,
                     loc: Location { line: 0, col: 0 },
@@ -0,0 +1,15 @@
+pub mod ast;
+pub mod cast;
+pub mod datetime;
+pub mod functions;
+pub mod index;
+pub mod json;
+pub mod lexer;
+pub mod mem_backend;
+pub mod numeric;
+pub mod parser;
+pub mod pattern;
+pub mod planner;
+pub mod sequence;
+pub mod uuid;
+pub mod window;
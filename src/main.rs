@@ -1,16 +1,16 @@
-mod ast;
-mod lexer;
-mod mem_backend;
-mod parser;
 use std::io::{self, Write};
 
-use mem_backend::MemCell;
-use mem_backend::MemoryBackend;
+use sqldb_rs::mem_backend::MemCell;
+use sqldb_rs::mem_backend::MemoryBackend;
 
-use crate::mem_backend::Cell;
-use crate::{
+use sqldb_rs::mem_backend::Cell;
+use sqldb_rs::{
     ast::StatementKind,
-    mem_backend::{Backend, ColumnType},
+    cast,
+    datetime::{Date, Time, Timestamp},
+    mem_backend::{Backend, ColumnType, Results, Value},
+    numeric, parser,
+    uuid::Uuid,
 };
 
 fn main() {
@@ -31,9 +31,12 @@ fn main() {
         io::stdout().flush().unwrap(); // Ensure the prompt is displayed
 
         let mut input = String::new();
-        io::stdin()
+        let read = io::stdin()
             .read_line(&mut input)
             .expect("Failed to read line");
+        if read == 0 {
+            break;
+        }
 
         input = input.trim().to_string();
 
@@ -55,13 +58,60 @@ fn main() {
                     let table_name = &statement.create.unwrap().name.literal.clone();
                     println!("{} created", table_name);
                 }
-                StatementKind::Insert => {
-                    if let Err(e) = mb.insert(&statement.insert.unwrap()) {
+                StatementKind::Insert => match mb.insert(&statement.insert.unwrap()) {
+                    Ok(Some(results)) => print_results(results),
+                    Ok(None) => println!("Ok"),
+                    Err(e) => println!("{}", e),
+                },
+                StatementKind::CreateType => {
+                    let create_type = statement.create_type.unwrap();
+                    if let Err(e) = mb.create_type(&create_type) {
+                        println!("{}", e);
+                        continue;
+                    }
+                    println!("{} created", create_type.name.literal);
+                }
+                StatementKind::CreateSequence => {
+                    let create_sequence = statement.create_sequence.unwrap();
+                    if let Err(e) = mb.create_sequence(&create_sequence) {
+                        println!("{}", e);
+                        continue;
+                    }
+                    println!("{} created", create_sequence.name.literal);
+                }
+                StatementKind::CreateIndex => {
+                    let create_index = statement.create_index.unwrap();
+                    if let Err(e) = mb.create_index(&create_index) {
+                        println!("{}", e);
+                        continue;
+                    }
+                    match create_index.name {
+                        Some(name) => println!("{} created", name.literal),
+                        None => println!("Ok"),
+                    }
+                }
+                StatementKind::DropIndex => {
+                    let drop_index = statement.drop_index.unwrap();
+                    if let Err(e) = mb.drop_index(&drop_index) {
                         println!("{}", e);
                         continue;
                     }
-                    println!("Ok");
+                    println!("{} dropped", drop_index.name.literal);
                 }
+                StatementKind::Explain => match mb.explain(&statement.explain.unwrap()) {
+                    Ok(results) => print_results(results),
+                    Err(e) => println!("{}", e),
+                },
+                StatementKind::Update => match mb.update(&statement.update.unwrap()) {
+                    Ok(Some(results)) => print_results(results),
+                    Ok(None) => println!("Ok"),
+                    Err(e) => println!("{}", e),
+                },
+                StatementKind::Delete => match mb.delete(&statement.delete.unwrap()) {
+                    Ok(Some(results)) => print_results(results),
+                    Ok(None) => println!("Ok"),
+                    Err(e) => println!("{}", e),
+                },
                 StatementKind::Select => {
                     let results = match mb.select(&statement.select.unwrap()) {
                         Ok(results) => results,
@@ -70,34 +120,96 @@ fn main() {
                             continue;
                         }
                     };
-                    for col in &results.columns {
-                        print!("| {}", col.col_name);
-                    }
-                    print!(" |");
-                    println!("");
-                    for _ in 0..20 {
-                        print!("-");
-                    }
-                    println!("");
+                    print_results(results);
+                }
+            }
+        }
+    }
+}
 
-                    for row in results.rows {
-                        for (i, cell) in row.into_iter().enumerate() {
-                            let typ = &results.columns.get(i).unwrap().col_type;
-                            match *typ {
-                                ColumnType::IntType => {
-                                    let mc: MemCell = cell;
-                                    print!("{} | ", mc.as_int());
-                                }
-                                ColumnType::TextType => {
-                                    let mc: MemCell = cell;
-                                    print!("{} | ", mc.as_text());
-                                }
-                            }
-                        }
-                        println!("");
+fn print_results(results: Results) {
+    for col in &results.columns {
+        print!("| {}", col.col_name);
+    }
+    print!(" |");
+    println!();
+    for _ in 0..20 {
+        print!("-");
+    }
+    println!();
+
+    for row in results.rows {
+        for (i, cell) in row.into_iter().enumerate() {
+            let cell = match cell {
+                Some(cell) => cell,
+                None => {
+                    print!("NULL | ");
+                    continue;
+                }
+            };
+            let typ = &results.columns.get(i).unwrap().col_type;
+            match *typ {
+                ColumnType::SmallIntType | ColumnType::IntType | ColumnType::BigIntType => {
+                    let mc: MemCell = cell;
+                    print!("{} | ", mc.as_int());
+                }
+                ColumnType::TextType | ColumnType::VarCharType(_) | ColumnType::CharType(_) => {
+                    let mc: MemCell = cell;
+                    print!("{} | ", mc.as_text());
+                }
+                ColumnType::RealType | ColumnType::DoubleType => {
+                    let mc: MemCell = cell;
+                    let single = *typ == ColumnType::RealType;
+                    print!("{} | ", numeric::format_float(mc.as_float(), single));
+                }
+                ColumnType::NumericType(_) => {
+                    let mc: MemCell = cell;
+                    print!("{} | ", mc.as_decimal());
+                }
+                ColumnType::BoolType => {
+                    let mc: MemCell = cell;
+                    print!("{} | ", if mc.as_bool() { "t" } else { "f" });
+                }
+                ColumnType::DateType => {
+                    let mc: MemCell = cell;
+                    print!("{} | ", Date(mc.as_int() as i32));
+                }
+                ColumnType::TimeType => {
+                    let mc: MemCell = cell;
+                    print!("{} | ", Time(mc.as_int()));
+                }
+                ColumnType::TimestampType => {
+                    let mc: MemCell = cell;
+                    print!("{} | ", Timestamp(mc.as_int()));
+                }
+                ColumnType::IntervalType => {
+                    let mc: MemCell = cell;
+                    print!("{} | ", mc.as_interval());
+                }
+                ColumnType::JsonType => {
+                    let mc: MemCell = cell;
+                    print!("{} | ", mc.as_text());
+                }
+                ColumnType::UuidType => {
+                    let mc: MemCell = cell;
+                    print!("{} | ", Uuid::from_slice(&mc).unwrap());
+                }
+                ColumnType::ArrayType(_) => {
+                    let mc: MemCell = cell;
+                    if let Value::Array(_, elements) = Value::from_cell(&Some(mc), typ) {
+                        print!("{} | ", cast::array_text(&elements));
                     }
                 }
+                ColumnType::EnumType(ref definition) => {
+                    let mc: MemCell = cell;
+                    print!("{} | ", definition.labels[mc.as_int() as usize]);
+                }
+                ColumnType::BlobType => {
+                    let mc: MemCell = cell;
+                    print!("{} | ", cast::bytea_text(&mc));
+                }
             }
         }
+        println!();
     }
 }
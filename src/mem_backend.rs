@@ -1,26 +1,246 @@
 use core::fmt;
 use std::{
-    collections::HashMap,
+    borrow::Cow,
+    cell::RefCell,
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
     io::{Cursor, Read},
+    ops,
+    rc::Rc,
+    time::{Duration, Instant},
 };
 
+use regex::{Regex, RegexBuilder};
+
 use crate::{
-    ast::{Create, ExpressionKind, Insert, Select},
-    lexer::{Token, TokenKind},
+    ast::{
+        Assignment, ConflictAction, ConstraintKind, Create, CreateIndex, CreateSequence,
+        CreateType, DataType, Delete, DropIndex, Explain, Expression, ExpressionKind, FrameBound,
+        FrameBoundKind, FunctionCall, Identity, InList, Insert, OnConflict, ReferentialAction,
+        Select, SequenceOptions, Update,
+    },
+    cast::{self, CastLevel},
+    datetime::{Date, Interval, Time, Timestamp},
+    functions::FunctionRegistry,
+    index::{Index, IndexMethod},
+    json::{Json, PathStep},
+    lexer::{Keyword, Symbol, Token, TokenKind},
+    numeric::{self, Decimal, Float},
+    pattern,
+    planner::{selectivity, Access, Annotation, IndexLookup, LogicalPlan, PhysicalPlan, Source},
+    sequence::{self, Sequence, Sequences},
+    uuid::Uuid,
+    window::{self, Bound, Frame, FrameUnits, WindowFunction, WindowRow},
 };
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum ColumnType {
     TextType,
+    // Maximum length in characters, unlimited when absent
+    VarCharType(Option<u32>),
+    // Fixed length in characters, padded with spaces
+    CharType(u32),
+    SmallIntType,
     IntType,
+    BigIntType,
+    RealType,
+    DoubleType,
+    // Precision and scale, unconstrained when absent
+    NumericType(Option<(u32, u32)>),
+    BoolType,
+    DateType,
+    TimeType,
+    TimestampType,
+    IntervalType,
+    // Arbitrary bytes
+    BlobType,
+    // A JSON document, validated when stored
+    JsonType,
+    UuidType,
+    // A one-dimensional array of the element type
+    ArrayType(Box<ColumnType>),
+    // A type from CREATE TYPE ... AS ENUM
+    EnumType(Rc<EnumDefinition>),
+}
+
+/// A user-defined enum: its name and its labels in declaration order.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct EnumDefinition {
+    pub name: String,
+    pub labels: Vec<String>,
+}
+
+impl ColumnType {
+    pub fn from_data_type(data_type: &DataType) -> Result<ColumnType, SQLError> {
+        let invalid = || {
+            let modifiers: Vec<&str> = data_type
+                .modifiers
+                .iter()
+                .map(|modifier| modifier.literal.as_str())
+                .collect();
+            SQLError::InvalidDataType(format!(
+                "{}({})",
+                data_type.name.literal,
+                modifiers.join(", ")
+            ))
+        };
+
+        let mut modifiers = Vec::new();
+        for modifier in &data_type.modifiers {
+            modifiers.push(modifier.literal.parse::<u32>().map_err(|_| invalid())?);
+        }
+
+        let col_type = match (data_type.name.literal.as_str(), modifiers.as_slice()) {
+            ("numeric" | "decimal", []) => ColumnType::NumericType(None),
+            ("numeric" | "decimal", [precision]) => ColumnType::NumericType(Some((*precision, 0))),
+            ("numeric" | "decimal", [precision, scale]) => {
+                ColumnType::NumericType(Some((*precision, *scale)))
+            }
+            ("varchar" | "character varying", []) => ColumnType::VarCharType(None),
+            ("varchar" | "character varying", [length]) if *length > 0 => {
+                ColumnType::VarCharType(Some(*length))
+            }
+            ("char" | "character", []) => ColumnType::CharType(1),
+            ("char" | "character", [length]) if *length > 0 => ColumnType::CharType(*length),
+            ("float", [precision]) if (1..=24).contains(precision) => ColumnType::RealType,
+            ("float", [precision]) if (25..=53).contains(precision) => ColumnType::DoubleType,
+            (name, []) => match name {
+                "smallint" | "int2" => ColumnType::SmallIntType,
+                "int" | "integer" | "int4" => ColumnType::IntType,
+                "bigint" | "int8" => ColumnType::BigIntType,
+                "real" | "float4" => ColumnType::RealType,
+                "double precision" | "float" | "float8" => ColumnType::DoubleType,
+                "text" => ColumnType::TextType,
+                "boolean" | "bool" => ColumnType::BoolType,
+                "date" => ColumnType::DateType,
+                "time" => ColumnType::TimeType,
+                "timestamp" => ColumnType::TimestampType,
+                "interval" => ColumnType::IntervalType,
+                "bytea" | "blob" => ColumnType::BlobType,
+                "json" => ColumnType::JsonType,
+                "uuid" => ColumnType::UuidType,
+                other => return Err(SQLError::InvalidDataType(other.to_string())),
+            },
+            _ => return Err(invalid()),
+        };
+
+        if let ColumnType::NumericType(Some((precision, scale))) = col_type {
+            if precision == 0 || precision > numeric::MAX_PRECISION || scale > precision {
+                return Err(invalid());
+            }
+        }
+        if data_type.array {
+            return Ok(ColumnType::ArrayType(Box::new(col_type)));
+        }
+        Ok(col_type)
+    }
+
+    pub fn is_integer(&self) -> bool {
+        matches!(
+            self,
+            ColumnType::SmallIntType | ColumnType::IntType | ColumnType::BigIntType
+        )
+    }
+
+    /// Integer, exact and floating point types.
+    pub fn is_number(&self) -> bool {
+        self.is_integer()
+            || matches!(
+                self,
+                ColumnType::RealType | ColumnType::DoubleType | ColumnType::NumericType(_)
+            )
+    }
+
+    /// text and the length-limited character types.
+    pub fn is_string(&self) -> bool {
+        matches!(
+            self,
+            ColumnType::TextType | ColumnType::VarCharType(_) | ColumnType::CharType(_)
+        )
+    }
+
+    /// Dates, times, timestamps and intervals.
+    pub fn is_temporal(&self) -> bool {
+        matches!(
+            self,
+            ColumnType::DateType
+                | ColumnType::TimeType
+                | ColumnType::TimestampType
+                | ColumnType::IntervalType
+        )
+    }
+}
+
+impl fmt::Display for ColumnType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ColumnType::TextType => write!(f, "text"),
+            ColumnType::VarCharType(None) => write!(f, "character varying"),
+            ColumnType::VarCharType(Some(length)) => write!(f, "character varying({})", length),
+            ColumnType::CharType(length) => write!(f, "character({})", length),
+            ColumnType::SmallIntType => write!(f, "smallint"),
+            ColumnType::IntType => write!(f, "int"),
+            ColumnType::BigIntType => write!(f, "bigint"),
+            ColumnType::RealType => write!(f, "real"),
+            ColumnType::DoubleType => write!(f, "double precision"),
+            ColumnType::NumericType(None) => write!(f, "numeric"),
+            ColumnType::NumericType(Some((precision, scale))) => {
+                write!(f, "numeric({},{})", precision, scale)
+            }
+            ColumnType::BoolType => write!(f, "boolean"),
+            ColumnType::DateType => write!(f, "date"),
+            ColumnType::TimeType => write!(f, "time"),
+            ColumnType::TimestampType => write!(f, "timestamp"),
+            ColumnType::IntervalType => write!(f, "interval"),
+            ColumnType::BlobType => write!(f, "bytea"),
+            ColumnType::JsonType => write!(f, "json"),
+            ColumnType::UuidType => write!(f, "uuid"),
+            ColumnType::ArrayType(element) => write!(f, "{}[]", element),
+            ColumnType::EnumType(definition) => write!(f, "{}", definition.name),
+        }
+    }
 }
 
+#[derive(Debug)]
 pub enum SQLError {
     TableDoesNotExist(String),
     TableAlreadyExists(String),
     ColumnDoesNotExist(String),
     InvalidDataType(String),
     MissingValues,
+    FunctionDoesNotExist(String),
+    InvalidFunctionArguments(String),
+    InvalidAggregation(String),
+    TypeMismatch(String),
+    InvalidPattern(String),
+    InvalidCast(String),
+    InvalidWindowFunction(String),
+    OutOfRange(String),
+    DivisionByZero,
+    // A string longer than its character type allows
+    ValueTooLong(ColumnType),
+    TypeAlreadyExists(String),
+    // The constraint name and the duplicated key, such as `(id)=(1)`
+    UniqueViolation(String, String),
+    // The column and the failing row
+    NotNullViolation(String, String),
+    // The constraint name and the failing row
+    CheckViolation(String, String),
+    InvalidConstraint(String),
+    DuplicateColumn(String),
+    // The constraint name and what is wrong with the key
+    ForeignKeyViolation(String, String),
+    SequenceDoesNotExist(String),
+    SequenceAlreadyExists(String),
+    // A value given for an identity column that is GENERATED ALWAYS
+    GeneratedAlways(String),
+    IndexDoesNotExist(String),
+    IndexAlreadyExists(String),
+    InvalidIndex(String),
+    // A column named without its table that more than one joined table has
+    AmbiguousColumn(String),
+    // A table joined to itself, whose columns could not be told apart
+    DuplicateTable(String),
 }
 
 impl fmt::Display for SQLError {
@@ -37,204 +257,6797 @@ impl fmt::Display for SQLError {
             SQLError::ColumnDoesNotExist(col_name) => {
                 write!(f, "Column does not exists: {}", col_name)
             }
+            SQLError::FunctionDoesNotExist(name) => {
+                write!(f, "Function does not exists: {}", name)
+            }
+            SQLError::InvalidFunctionArguments(msg) => {
+                write!(f, "Invalid function arguments: {}", msg)
+            }
+            SQLError::InvalidAggregation(msg) => write!(f, "Invalid aggregation: {}", msg),
+            SQLError::TypeMismatch(msg) => write!(f, "Type mismatch: {}", msg),
+            SQLError::InvalidPattern(msg) => write!(f, "Invalid pattern: {}", msg),
+            SQLError::InvalidCast(msg) => write!(f, "Invalid cast: {}", msg),
+            SQLError::InvalidWindowFunction(msg) => write!(f, "Invalid window function: {}", msg),
+            SQLError::OutOfRange(msg) => write!(f, "Out of range: {}", msg),
+            SQLError::DivisionByZero => write!(f, "Division by zero"),
+            SQLError::ValueTooLong(col_type) => write!(f, "Value too long for type {}", col_type),
+            SQLError::TypeAlreadyExists(name) => write!(f, "Type already exists: {}", name),
+            SQLError::UniqueViolation(constraint, key) => write!(
+                f,
+                "Duplicate key violates unique constraint {}: {} already exists",
+                constraint, key
+            ),
+            SQLError::NotNullViolation(column, row) => write!(
+                f,
+                "Null value in column {} violates not-null constraint, failing row: {}",
+                column, row
+            ),
+            SQLError::CheckViolation(constraint, row) => write!(
+                f,
+                "New row violates check constraint {}, failing row: {}",
+                constraint, row
+            ),
+            SQLError::InvalidConstraint(msg) => write!(f, "Invalid constraint: {}", msg),
+            SQLError::DuplicateColumn(name) => {
+                write!(f, "Column specified more than once: {}", name)
+            }
+            SQLError::ForeignKeyViolation(constraint, detail) => {
+                write!(
+                    f,
+                    "Foreign key constraint {} violated: {}",
+                    constraint, detail
+                )
+            }
+            SQLError::SequenceDoesNotExist(name) => write!(f, "Sequence does not exists: {}", name),
+            SQLError::SequenceAlreadyExists(name) => {
+                write!(f, "Sequence already exists: {}", name)
+            }
+            SQLError::GeneratedAlways(column) => write!(
+                f,
+                "Column {} is an identity column generated always and cannot be set",
+                column
+            ),
+            SQLError::IndexDoesNotExist(name) => write!(f, "Index does not exists: {}", name),
+            SQLError::IndexAlreadyExists(name) => write!(f, "Index already exists: {}", name),
+            SQLError::InvalidIndex(msg) => write!(f, "Invalid index: {}", msg),
+            SQLError::AmbiguousColumn(name) => write!(f, "Column reference is ambiguous: {}", name),
+            SQLError::DuplicateTable(name) => {
+                write!(f, "Table name specified more than once: {}", name)
+            }
+        }
+    }
+}
+
+/// State shared by every evaluation within a single statement.
+#[derive(Default)]
+struct StatementCache {
+    // Constant IN lists hashed on first use, keyed by their AST node
+    in_lists: RefCell<HashMap<*const InList, Rc<InSet>>>,
+    // Window function results for every result row, keyed by their AST node
+    windows: RefCell<HashMap<*const Expression, Vec<Value>>>,
+}
+
+/// The items of a constant IN list, converted to the type they have in
+/// common and hashed. Integers of every width are kept as bigint, so that a
+/// probe of any width is found without converting the list.
+struct InSet {
+    values: HashSet<Value>,
+    // None when the items have no common type
+    common_type: Option<ColumnType>,
+    has_null: bool,
+}
+
+impl InSet {
+    fn new(items: Vec<Value>) -> InSet {
+        let has_null = items.contains(&Value::Null);
+        let items: Vec<Value> = items
+            .into_iter()
+            .filter(|item| *item != Value::Null)
+            .map(|item| in_set_key(&item))
+            .collect();
+
+        let mut common_type: Option<ColumnType> = None;
+        for item in &items {
+            let item_type = item.column_type().unwrap();
+            common_type = match common_type {
+                None => Some(item_type),
+                Some(common) if common == item_type || cast::is_implicit(&item_type, &common) => {
+                    Some(common)
+                }
+                Some(common) if cast::is_implicit(&common, &item_type) => Some(item_type),
+                Some(_) => {
+                    return InSet {
+                        values: HashSet::new(),
+                        common_type: None,
+                        has_null,
+                    }
+                }
+            };
+        }
+
+        let values: Option<HashSet<Value>> = match &common_type {
+            Some(common) => items
+                .iter()
+                .map(|item| cast::cast(item, common, CastLevel::Implicit).ok())
+                .collect(),
+            None => Some(HashSet::new()),
+        };
+        InSet {
+            common_type: common_type.filter(|_| values.is_some()),
+            values: values.unwrap_or_default(),
+            has_null,
+        }
+    }
+
+    /// Whether the list holds `value`, `None` when that takes comparing it
+    /// with every item.
+    fn contains(&self, value: &Value) -> Option<Option<bool>> {
+        let common = self.common_type.as_ref()?;
+        let value = in_set_key(value);
+        let value_type = value.column_type()?;
+        let found = if value_type == *common {
+            self.values.contains(&value)
+        } else if cast::is_implicit(&value_type, common) {
+            let value = cast::cast(&value, common, CastLevel::Implicit).ok()?;
+            self.values.contains(&value)
+        } else {
+            return None;
+        };
+        // Like comparing with each item, a miss is unknown when one is NULL
+        Some(match found {
+            true => Some(true),
+            false if self.has_null => None,
+            false => Some(false),
+        })
+    }
+}
+
+/// What an expression sees while it is evaluated: the current row and, when
+/// aggregating, the group of rows that aggregate calls fold. `index` is the
+/// position of the result row, used to look up window function results.
+#[derive(Clone, Copy)]
+struct Scope<'a> {
+    table: &'a Table,
+    row: &'a [Option<MemCell>],
+    group: Option<&'a [Row]>,
+    index: usize,
+    cache: &'a StatementCache,
+}
+
+impl<'a> Scope<'a> {
+    fn new(table: &'a Table, cache: &'a StatementCache) -> Scope<'a> {
+        Scope {
+            table,
+            row: &[],
+            group: None,
+            index: 0,
+            cache,
         }
     }
 }
 
+/// Rows passed up a physical plan. Scans and filters pass stored rows along
+/// with the table naming their columns, and a projection turns them into
+/// result values.
+enum Rows<'a> {
+    Stored {
+        table: Box<Cow<'a, Table>>,
+        rows: Vec<Row>,
+        // Whether the rows are a single group for aggregates to fold
+        grouped: bool,
+        // Where a scan found the rows in its table, which UPDATE and DELETE
+        // change them by
+        positions: Option<Vec<usize>>,
+    },
+    Projected {
+        columns: Vec<Column>,
+        values: Vec<Vec<Value>>,
+        // What each result row is sorted by
+        keys: Vec<Vec<Value>>,
+    },
+}
+
+/// The rows each operator of a plan produced and the time it took, keyed by
+/// the operator.
+#[derive(Default)]
+struct Profile {
+    operators: RefCell<HashMap<*const PhysicalPlan, (usize, Duration)>>,
+}
+
 pub trait Backend {
     fn create(&mut self, create: &Create) -> Result<(), SQLError>;
-    fn insert(&mut self, insert: &Insert) -> Result<(), SQLError>;
+    // Data-modifying statements produce results only with RETURNING
+    fn insert(&mut self, insert: &Insert) -> Result<Option<Results>, SQLError>;
     fn select(&self, select: &Select) -> Result<Results, SQLError>;
+    fn update(&mut self, update: &Update) -> Result<Option<Results>, SQLError>;
+    fn delete(&mut self, delete: &Delete) -> Result<Option<Results>, SQLError>;
+    fn create_type(&mut self, create_type: &CreateType) -> Result<(), SQLError>;
+    fn create_sequence(&mut self, create_sequence: &CreateSequence) -> Result<(), SQLError>;
+    fn create_index(&mut self, create_index: &CreateIndex) -> Result<(), SQLError>;
+    fn drop_index(&mut self, drop_index: &DropIndex) -> Result<(), SQLError>;
+    // The plan of the query, one line per row
+    fn explain(&mut self, explain: &Explain) -> Result<Results, SQLError>;
 }
 
 pub struct MemoryBackend {
     pub tables: HashMap<String, Table>,
+    // User-defined types by name
+    pub types: HashMap<String, ColumnType>,
+    pub functions: FunctionRegistry,
+    regexes: RefCell<HashMap<(String, bool), Regex>>,
+    // Shared with nextval, currval and setval
+    pub sequences: Sequences,
+    // The row changes of the statement running atomically, to undo them
+    // when it fails
+    undo_log: Option<Vec<Undo>>,
+}
+
+/// Rows a statement changed, with their index keys, as they were before.
+enum Undo {
+    Replaced(String, Vec<(usize, Row, IndexKeys)>),
+    Removed(String, Vec<(usize, Row, IndexKeys)>),
+    // A row added at the end of the table
+    Inserted(String),
 }
+
 impl MemoryBackend {
     pub fn new() -> MemoryBackend {
+        let sequences = Sequences::default();
+        let mut functions = FunctionRegistry::new();
+        sequence::register_functions(&mut functions, &sequences);
         MemoryBackend {
             tables: HashMap::new(),
+            types: HashMap::new(),
+            functions,
+            regexes: RefCell::new(HashMap::new()),
+            sequences,
+            undo_log: None,
         }
     }
-}
 
-impl Backend for MemoryBackend {
-    fn create(&mut self, create: &Create) -> Result<(), SQLError> {
-        if self.tables.contains_key(&create.name.literal) {
-            return Err(SQLError::TableAlreadyExists(create.name.literal.clone()));
+    /// Makes a Rust closure callable from SQL as a scalar function.
+    pub fn register_scalar_function<F>(&mut self, name: &str, function: F)
+    where
+        F: Fn(&[Value]) -> Result<Value, SQLError> + 'static,
+    {
+        self.functions.register_scalar(name, function);
+    }
+
+    /// Makes a Rust aggregate callable from SQL. See
+    /// [`FunctionRegistry::register_aggregate`].
+    pub fn register_aggregate_function<S, I, St, F>(
+        &mut self,
+        name: &str,
+        init: I,
+        step: St,
+        finalize: F,
+    ) where
+        S: 'static,
+        I: Fn() -> S + 'static,
+        St: Fn(&mut S, &[Value]) -> Result<(), SQLError> + 'static,
+        F: Fn(S) -> Result<Value, SQLError> + 'static,
+    {
+        self.functions
+            .register_aggregate(name, init, step, finalize);
+    }
+
+    /// Makes a Rust closure usable in FROM as a table-valued function.
+    pub fn register_table_function<F>(&mut self, name: &str, function: F)
+    where
+        F: Fn(&[Value]) -> Result<Table, SQLError> + 'static,
+    {
+        self.functions.register_table(name, function);
+    }
+
+    /// The column type a type name refers to, looking in the catalog of
+    /// user-defined types before the built-in ones.
+    fn resolve_type(&self, data_type: &DataType) -> Result<ColumnType, SQLError> {
+        match self.types.get(&data_type.name.literal) {
+            Some(col_type) if data_type.modifiers.is_empty() => match data_type.array {
+                true => Ok(ColumnType::ArrayType(Box::new(col_type.clone()))),
+                false => Ok(col_type.clone()),
+            },
+            _ => ColumnType::from_data_type(data_type),
         }
-        let mut table = Table::new();
-        table.name = create.name.literal.clone();
-        for col in create.cols.clone() {
-            table.columns.push(col.name.literal);
-            match col.data_type.literal.as_str() {
-                "int" => table.column_types.push(ColumnType::IntType),
-                "text" => table.column_types.push(ColumnType::TextType),
-                other => return Err(SQLError::InvalidDataType(other.to_string())),
+    }
+
+    /// Checks the NOT NULL and CHECK constraints of a row about to be
+    /// stored. A CHECK passes unless its condition is false.
+    fn check_row(&self, table: &Table, row: &Row) -> Result<(), SQLError> {
+        if let Some(i) = (0..row.len()).find(|i| row[*i].is_none() && table.not_null.contains(i)) {
+            return Err(SQLError::NotNullViolation(
+                table.columns[i].clone(),
+                table.row_text(row),
+            ));
+        }
+
+        let cache = StatementCache::default();
+        let scope = Scope {
+            row,
+            ..Scope::new(table, &cache)
+        };
+        for check in &table.checks {
+            if self.evaluate(&check.condition, &scope)?.as_bool()? == Some(false) {
+                return Err(SQLError::CheckViolation(
+                    check.name.clone(),
+                    table.row_text(row),
+                ));
             }
         }
+        Ok(())
+    }
 
-        self.tables.insert(table.name.clone(), table);
+    /// Checks that the foreign keys of a row about to be stored reference
+    /// existing rows. A key with a NULL in it references nothing.
+    fn check_references(&self, table: &Table, row: &Row) -> Result<(), SQLError> {
+        for foreign_key in &table.foreign_keys {
+            let parent = &self.tables[&foreign_key.table];
+            let key = foreign_key.key(table, parent, row)?;
+            if key.contains(&Value::Null) {
+                continue;
+            }
+            let index = parent.unique_index(&foreign_key.referenced).unwrap();
+            if !index.get(&key).is_empty() {
+                continue;
+            }
+            // A row may reference itself
+            if foreign_key.table == table.name && index.key(row, &table.column_types) == key {
+                continue;
+            }
+            return Err(SQLError::ForeignKeyViolation(
+                foreign_key.name.clone(),
+                format!(
+                    "key {} is not present in table {}",
+                    key_text(table, &foreign_key.columns, &key),
+                    parent.name
+                ),
+            ));
+        }
         Ok(())
     }
 
-    fn insert(&mut self, insert: &Insert) -> Result<(), SQLError> {
-        if !self.tables.contains_key(&insert.table.literal) {
-            return Err(SQLError::TableDoesNotExist(insert.table.literal.clone()));
+    /// The foreign keys referencing a table, with the name of the table
+    /// each is on, ordered by that name.
+    fn referencing(&self, parent: &str) -> Vec<(String, ForeignKey)> {
+        let mut referencing = Vec::new();
+        for table in self.tables.values() {
+            for foreign_key in &table.foreign_keys {
+                if foreign_key.table == parent {
+                    referencing.push((table.name.clone(), foreign_key.clone()));
+                }
+            }
         }
+        referencing.sort_by(|a, b| a.0.cmp(&b.0));
+        referencing
+    }
 
-        let table = self.tables.get_mut(&insert.table.literal).unwrap();
-        let mut row = Vec::new();
+    /// The key of a row in an index, `None` when the index is partial and
+    /// leaves the row out.
+    fn index_key(
+        &self,
+        table: &Table,
+        index: &Index,
+        row: &Row,
+    ) -> Result<Option<Vec<Value>>, SQLError> {
+        if index.expressions.is_empty() && index.predicate.is_none() {
+            return Ok(Some(index.key(row, &table.column_types)));
+        }
 
-        if insert.values.len() != table.columns.len() {
-            return Err(SQLError::MissingValues);
+        let cache = StatementCache::default();
+        let scope = Scope {
+            row,
+            ..Scope::new(table, &cache)
+        };
+        if let Some(predicate) = &index.predicate {
+            if self.evaluate(predicate, &scope)?.as_bool()? != Some(true) {
+                return Ok(None);
+            }
+        }
+        match index.expressions.is_empty() {
+            true => Ok(Some(index.key(row, &table.column_types))),
+            false => {
+                let mut key = Vec::new();
+                for exp in &index.expressions {
+                    key.push(self.evaluate(exp, &scope)?);
+                }
+                Ok(Some(key))
+            }
+        }
+    }
+
+    fn index_keys(&self, table: &Table, row: &Row) -> Result<IndexKeys, SQLError> {
+        table
+            .indexes
+            .iter()
+            .map(|index| self.index_key(table, index, row))
+            .collect()
+    }
+
+    /// The plan that finds the rows an UPDATE or DELETE changes: a scan of
+    /// `table`, using an index when one answers part of `where_clause`.
+    fn modification_plan(
+        &self,
+        table: &Token,
+        where_clause: Option<&Expression>,
+    ) -> Result<PhysicalPlan, SQLError> {
+        let scan = LogicalPlan::Scan {
+            source: Source::Table(table.literal.clone()),
+            filter: where_clause.cloned(),
+            columns: None,
+        };
+        PhysicalPlan::from_logical(scan, &|source: &Source, filter: Option<&Expression>| {
+            self.access(source, filter)
+        })
+    }
+
+    /// Positions of the rows the scan of a modification plan finds, in
+    /// table order.
+    fn scanned_positions(
+        &self,
+        plan: &PhysicalPlan,
+        profile: &Profile,
+    ) -> Result<Vec<usize>, SQLError> {
+        match self.execute_plan(plan, profile)? {
+            Rows::Stored {
+                positions: Some(positions),
+                ..
+            } => Ok(positions),
+            _ => unreachable!("modifications scan a table"),
+        }
+    }
+
+    /// The positions among `candidates` of the rows satisfying `filter`.
+    fn check_rows(
+        &self,
+        table: &Table,
+        candidates: Vec<usize>,
+        filter: Option<&Expression>,
+    ) -> Result<Vec<usize>, SQLError> {
+        let filter = match filter {
+            Some(filter) => filter,
+            None => return Ok(candidates),
+        };
+        let cache = StatementCache::default();
+        let scope = Scope::new(table, &cache);
+        let mut positions = Vec::new();
+        for position in candidates {
+            let row_scope = Scope {
+                row: &table.rows[position],
+                ..scope
+            };
+            if self.evaluate(filter, &row_scope)?.as_bool()? == Some(true) {
+                positions.push(position);
+            }
+        }
+        Ok(positions)
+    }
+
+    /// An index to find the rows that may satisfy `where_clause` with, and
+    /// what to look up in it: preferably one with every part of its key
+    /// compared for equality, otherwise an ordered one whose first part is
+    /// bounded, otherwise a partial one. A partial index is only used when
+    /// `where_clause` has every condition of its predicate. `None` when no
+    /// index fits.
+    fn choose_index(
+        &self,
+        table: &Table,
+        where_clause: &Expression,
+    ) -> Option<(String, IndexLookup)> {
+        let mut conditions = Vec::new();
+        self.index_conditions(table, where_clause, &mut conditions);
+        let where_conjuncts = conjuncts(where_clause);
+
+        let mut indexes: Vec<&Index> = table
+            .indexes
+            .iter()
+            .filter(|index| match &index.predicate {
+                Some(predicate) => conjuncts(predicate).into_iter().all(|c| {
+                    where_conjuncts.iter().any(|w| w.same_as(c))
+                        || self.implied_by(table, c, &conditions)
+                }),
+                None => true,
+            })
+            .collect();
+        indexes.sort_by_key(|index| !index.unique);
+
+        // The condition values of the `i`th part of the key of an index,
+        // converted to its type
+        let part_conditions = |index: &Index, i: usize| -> Vec<(&'static str, Value)> {
+            let key_type = match index.expressions.get(i) {
+                Some(_) => index.key_type(i),
+                None => Some(&table.column_types[index.columns[i]]),
+            };
+            let key_type = match key_type {
+                Some(key_type) => key_type,
+                None => return Vec::new(),
+            };
+            conditions
+                .iter()
+                .filter(|c| match index.expressions.get(i) {
+                    Some(exp) => c.target.same_as(exp),
+                    None => {
+                        c.target.kind == ExpressionKind::Literal
+                            && c.target.literal.token_kind == TokenKind::Identifier
+                            && table
+                                .column_position(&c.target.literal.literal)
+                                .is_ok_and(|position| position == index.columns[i])
+                    }
+                })
+                .filter_map(|c| Some((c.op, index_key_value(&c.value, key_type)?)))
+                .collect()
+        };
+        let key_len = |index: &Index| index.columns.len().max(index.expressions.len());
+
+        for index in &indexes {
+            let key: Option<Vec<Value>> = (0..key_len(index))
+                .map(|i| {
+                    part_conditions(index, i)
+                        .into_iter()
+                        .find(|(op, _)| *op == "=")
+                        .map(|(_, value)| value)
+                })
+                .collect();
+            if let Some(key) = key {
+                return Some((index.name.clone(), IndexLookup::Key(key)));
+            }
         }
 
-        for e in insert.values.clone() {
-            if e.kind != ExpressionKind::Literal {
-                println!("Skipping non literal");
+        for index in &indexes {
+            if index.method != IndexMethod::BTree {
                 continue;
             }
-            row.push(token_to_cell(e.literal));
+            let (mut lower, mut upper) = (ops::Bound::Unbounded, ops::Bound::Unbounded);
+            for (op, value) in part_conditions(index, 0) {
+                match op {
+                    "=" => {
+                        (lower, upper) = (
+                            ops::Bound::Included(value.clone()),
+                            ops::Bound::Included(value),
+                        )
+                    }
+                    ">" => lower = ops::Bound::Excluded(value),
+                    ">=" => lower = ops::Bound::Included(value),
+                    "<" => upper = ops::Bound::Excluded(value),
+                    _ => upper = ops::Bound::Included(value),
+                }
+            }
+            if !matches!(
+                (&lower, &upper),
+                (ops::Bound::Unbounded, ops::Bound::Unbounded)
+            ) {
+                return Some((index.name.clone(), IndexLookup::Range(lower, upper)));
+            }
         }
 
-        table.rows.push(row);
-        Ok(())
+        indexes
+            .iter()
+            .find(|index| index.predicate.is_some())
+            .map(|index| (index.name.clone(), IndexLookup::All))
     }
 
-    fn select(&self, select: &Select) -> Result<Results, SQLError> {
-        if !self.tables.contains_key(&select.from.literal) {
-            return Err(SQLError::TableDoesNotExist(select.from.literal.clone()));
+    /// Whether `conditions`, those of a WHERE clause, imply `predicate`.
+    /// Comparing the conditions of both sides, rather than their text, lets
+    /// `x = false`, `false = x`, `not x` and `x = 'f'` match each other.
+    fn implied_by(
+        &self,
+        table: &Table,
+        predicate: &Expression,
+        conditions: &[IndexCondition],
+    ) -> bool {
+        let mut wanted = Vec::new();
+        self.index_conditions(table, predicate, &mut wanted);
+        !wanted.is_empty()
+            && wanted.iter().all(|want| {
+                conditions.iter().any(|c| {
+                    c.target.same_as(want.target)
+                        && c.op == want.op
+                        && matches!(compare(&c.value, &want.value), Ok(Ordering::Equal))
+                })
+            })
+    }
+
+    /// The comparisons of an expression with a constant that every row
+    /// matching `exp` satisfies. A boolean column `x` and `not x` count as
+    /// `x = true` and `x = false`.
+    fn index_conditions<'a>(
+        &self,
+        table: &Table,
+        exp: &'a Expression,
+        conditions: &mut Vec<IndexCondition<'a>>,
+    ) {
+        let cache = StatementCache::default();
+        let scope = Scope::new(table, &cache);
+        let mut push = |target: &'a Expression, op: &'static str, constant: &Expression| {
+            if is_constant(target) || !is_constant(constant) {
+                return;
+            }
+            // Constants that fail to evaluate are left to the scan
+            if let Ok(value) = self.evaluate(constant, &scope) {
+                conditions.push(IndexCondition { target, op, value });
+            }
+        };
+
+        match exp.kind {
+            ExpressionKind::Binary => {
+                let binary = exp.binary.as_ref().unwrap();
+                if binary.op.literal == Keyword::And.to_string() {
+                    self.index_conditions(table, &binary.a, conditions);
+                    self.index_conditions(table, &binary.b, conditions);
+                    return;
+                }
+                let (op, flipped) = match binary.op.literal.as_str() {
+                    "=" => ("=", "="),
+                    "<" => ("<", ">"),
+                    "<=" => ("<=", ">="),
+                    ">" => (">", "<"),
+                    ">=" => (">=", "<="),
+                    _ => return,
+                };
+                push(&binary.a, op, &binary.b);
+                push(&binary.b, flipped, &binary.a);
+            }
+            ExpressionKind::Between => {
+                let between = exp.between.as_ref().unwrap();
+                if !between.negated {
+                    push(&between.value, ">=", &between.low);
+                    push(&between.value, "<=", &between.high);
+                }
+            }
+            ExpressionKind::Unary => {
+                let unary = exp.unary.as_ref().unwrap();
+                if unary.op.literal == Keyword::Not.to_string() && !is_constant(&unary.operand) {
+                    conditions.push(IndexCondition {
+                        target: &unary.operand,
+                        op: "=",
+                        value: Value::Bool(false),
+                    });
+                }
+            }
+            ExpressionKind::Literal
+                if exp.literal.token_kind == TokenKind::Identifier
+                    && expression_type(exp, table) == ColumnType::BoolType =>
+            {
+                conditions.push(IndexCondition {
+                    target: exp,
+                    op: "=",
+                    value: Value::Bool(true),
+                });
+            }
+            _ => {}
         }
+    }
 
-        let mut columns: Vec<Column> = Vec::new();
-        let mut rows = Vec::new();
+    /// Evaluates the RETURNING items of a statement against the rows it
+    /// wrote or removed. `*` stands for every column of the table.
+    fn returning(
+        &self,
+        table: &Table,
+        items: &[Expression],
+        rows: &[Row],
+    ) -> Result<Option<Results>, SQLError> {
+        if items.is_empty() {
+            return Ok(None);
+        }
 
-        let table = self.tables.get(&select.from.literal).unwrap();
+        let mut expanded = Vec::new();
+        for item in items {
+            if item.kind == ExpressionKind::Literal
+                && item.literal.token_kind == TokenKind::Symbol
+                && item.literal.literal == Symbol::Asterisk.to_string()
+            {
+                for column in &table.columns {
+                    let token = Token {
+                        literal: column.clone(),
+                        token_kind: TokenKind::Identifier,
+                        loc: item.literal.loc.clone(),
+                    };
+                    expanded.push(Expression::new(token, ExpressionKind::Literal));
+                }
+            } else {
+                expanded.push(item.clone());
+            }
+        }
 
-        for (i, row) in table.rows.iter().enumerate() {
+        let cache = StatementCache::default();
+        let scope = Scope::new(table, &cache);
+        let mut values = Vec::new();
+        for row in rows {
+            let row_scope = Scope { row, ..scope };
             let mut result = Vec::new();
-            let is_first_row = i == 0;
+            for exp in &expanded {
+                result.push(self.evaluate(exp, &row_scope)?);
+            }
+            values.push(result);
+        }
 
-            for exp in &select.items {
-                if exp.kind != ExpressionKind::Literal {
-                    println!("Skipping non literal expressions");
-                    continue;
-                }
+        let columns = result_columns(&expanded, &values, table);
+        Ok(Some(Results {
+            rows: result_rows(&columns, values),
+            columns,
+        }))
+    }
+
+    /// Handles an INSERT whose row conflicts with the row at `position`:
+    /// leaves it alone for DO NOTHING, or applies the DO UPDATE assignments
+    /// to it, where `excluded.column` is the value the insert proposed.
+    fn resolve_conflict(
+        &mut self,
+        insert: &Insert,
+        position: usize,
+        proposed: Row,
+    ) -> Result<Option<Results>, SQLError> {
+        let on_conflict = insert.on_conflict.as_ref().unwrap();
+        let name = &insert.table.literal;
+        let table = &self.tables[name];
+        if on_conflict.action == ConflictAction::DoNothing {
+            return self.returning(table, &insert.returning, &[]);
+        }
+        let targets = table.assignment_targets(&on_conflict.assignments)?;
 
-                let lit = &exp.literal;
+        // Columns can be named plainly or qualified by the table name, and
+        // the proposed row is reached through excluded
+        let existing = &table.rows[position];
+        let mut combined = Table::new();
+        let mut combined_row = Vec::new();
+        let qualified = format!("{}.", name);
+        for (qualifier, row) in [
+            ("", existing),
+            (qualified.as_str(), existing),
+            ("excluded.", &proposed),
+        ] {
+            for (column, col_type) in table.columns.iter().zip(&table.column_types) {
+                combined.columns.push(format!("{}{}", qualifier, column));
+                combined.column_types.push(col_type.clone());
+            }
+            combined_row.extend(row.iter().cloned());
+        }
 
-                if lit.token_kind == TokenKind::Identifier {
-                    let mut found = false;
+        let cache = StatementCache::default();
+        let scope = Scope {
+            row: &combined_row,
+            ..Scope::new(&combined, &cache)
+        };
+        if let Some(where_clause) = &on_conflict.where_clause {
+            if self.evaluate(where_clause, &scope)?.as_bool()? != Some(true) {
+                return self.returning(table, &insert.returning, &[]);
+            }
+        }
 
-                    for (j, table_col) in table.columns.iter().enumerate() {
-                        if table_col == &lit.literal {
-                            if is_first_row {
-                                columns.push(Column {
-                                    col_type: table.column_types.get(j).to_owned().unwrap().clone(),
-                                    col_name: lit.literal.clone(),
-                                });
-                            }
+        let mut new_row = existing.clone();
+        for (assignment, i) in on_conflict.assignments.iter().zip(&targets) {
+            let value = self.evaluate(&assignment.value, &scope)?;
+            new_row[*i] =
+                cast::cast(&value, &table.column_types[*i], CastLevel::Assignment)?.to_cell();
+        }
+        let results = self.returning(table, &insert.returning, std::slice::from_ref(&new_row))?;
 
-                            result.push(row[j].clone());
-                            found = true;
-                            break;
-                        }
-                    }
+        self.atomically(|mb| mb.update_rows(name, vec![(position, new_row)]))?;
+        Ok(results)
+    }
 
-                    if !found {
-                        return Err(SQLError::ColumnDoesNotExist(lit.literal.clone()));
-                    }
+    /// Runs a change that may cascade to other tables. When it fails, the
+    /// rows it touched are put back from the undo log, so that statements
+    /// are all or nothing. Inside another change, such as EXPLAIN ANALYZE,
+    /// what succeeded is left for that one to undo.
+    fn atomically<F>(&mut self, change: F) -> Result<(), SQLError>
+    where
+        F: FnOnce(&mut Self) -> Result<(), SQLError>,
+    {
+        let outer = self.undo_log.replace(Vec::new());
+        let result = change(self);
+        let undo_log = self.undo_log.take().unwrap_or_default();
+        match (result.is_ok(), outer) {
+            (true, Some(mut outer)) => {
+                outer.extend(undo_log);
+                self.undo_log = Some(outer);
+            }
+            (true, None) => {}
+            (false, outer) => {
+                self.undo(undo_log);
+                self.undo_log = outer;
+            }
+        }
+        result
+    }
 
-                    continue;
+    /// Puts back the rows of an undo log, latest change first.
+    fn undo(&mut self, undo_log: Vec<Undo>) {
+        for undo in undo_log.into_iter().rev() {
+            match undo {
+                Undo::Replaced(name, rows) => {
+                    self.tables.get_mut(&name).unwrap().restore_replaced(rows)
+                }
+                Undo::Removed(name, rows) => {
+                    self.tables.get_mut(&name).unwrap().restore_removed(rows)
+                }
+                Undo::Inserted(name) => {
+                    let table = self.tables.get_mut(&name).unwrap();
+                    table.remove_rows(&[table.rows.len() - 1]);
                 }
-
-                return Err(SQLError::ColumnDoesNotExist(lit.literal.clone()));
             }
+        }
+    }
 
-            rows.push(result);
+    /// Overwrites rows by position after checking their constraints, then
+    /// applies the ON UPDATE actions of the rows referencing them.
+    fn update_rows(&mut self, name: &str, updated: Vec<(usize, Row)>) -> Result<(), SQLError> {
+        let table = &self.tables[name];
+        let mut changes = Vec::new();
+        let mut replaced = Vec::new();
+        for (position, row) in updated {
+            self.check_row(table, &row)?;
+            self.check_references(table, &row)?;
+            changes.push((position, table.rows[position].clone(), row.clone()));
+            let keys = self.index_keys(table, &row)?;
+            replaced.push((position, row, keys));
         }
 
-        Ok(Results { rows, columns })
+        let old_keys = self.tables.get_mut(name).unwrap().replace_rows(replaced)?;
+        if let Some(undo_log) = &mut self.undo_log {
+            let old_rows = changes
+                .iter()
+                .zip(old_keys)
+                .map(|((position, old_row, _), keys)| (*position, old_row.clone(), keys));
+            undo_log.push(Undo::Replaced(name.to_string(), old_rows.collect()));
+        }
+        for (_, old_row, new_row) in changes {
+            self.apply_referential_actions(name, &old_row, Some(&new_row))?;
+        }
+        Ok(())
     }
-}
 
-pub trait Cell {
-    fn as_text(&self) -> String;
-    fn as_int(&self) -> i32;
-}
+    /// Deletes rows by position, then applies the ON DELETE actions of the
+    /// rows referencing them.
+    fn delete_rows(&mut self, name: &str, positions: &[usize]) -> Result<(), SQLError> {
+        let removed = self.tables.get_mut(name).unwrap().remove_rows(positions);
+        let old_rows: Vec<Row> = removed.iter().map(|(_, row, _)| row.clone()).collect();
+        if let Some(undo_log) = &mut self.undo_log {
+            undo_log.push(Undo::Removed(name.to_string(), removed));
+        }
+        for old_row in old_rows {
+            self.apply_referential_actions(name, &old_row, None)?;
+        }
+        Ok(())
+    }
 
-#[derive(Debug)]
-pub struct Column {
-    pub col_type: ColumnType,
-    pub col_name: String,
-}
+    /// Runs an UPDATE on the rows its modification plan finds.
+    fn update_scanned(
+        &mut self,
+        update: &Update,
+        plan: &PhysicalPlan,
+        profile: &Profile,
+    ) -> Result<Option<Results>, SQLError> {
+        let positions = self.scanned_positions(plan, profile)?;
+        let table = &self.tables[&update.table.literal];
+        let targets = table.assignment_targets(&update.assignments)?;
 
-pub type MemCell = Vec<u8>;
+        // Every new row is computed from the old ones before any is written,
+        // so that a failing row leaves the table unchanged
+        let cache = StatementCache::default();
+        let scope = Scope::new(table, &cache);
+        let mut updated = Vec::new();
+        for index in positions {
+            let row = &table.rows[index];
+            let row_scope = Scope { row, ..scope };
+            let mut new_row = row.clone();
+            for (assignment, i) in update.assignments.iter().zip(&targets) {
+                let value = self.evaluate(&assignment.value, &row_scope)?;
+                new_row[*i] =
+                    cast::cast(&value, &table.column_types[*i], CastLevel::Assignment)?.to_cell();
+            }
+            updated.push((index, new_row));
+        }
+        let new_rows: Vec<Row> = updated.iter().map(|(_, row)| row.clone()).collect();
+        let results = self.returning(table, &update.returning, &new_rows)?;
 
-impl Cell for MemCell {
-    fn as_int(&self) -> i32 {
-        let mut cursor = Cursor::new(&self);
-        let mut buffer = [0; 4]; // Buffer to hold the 4 bytes for the int32
-        cursor.read_exact(&mut buffer).unwrap(); // Read the bytes into the buffer. Might panic
-        let i = i32::from_le_bytes(buffer); // Convert the bytes to i32
-        i
+        let name = &update.table.literal;
+        self.atomically(|mb| mb.update_rows(name, updated))?;
+        Ok(results)
     }
 
-    fn as_text(&self) -> String {
-        String::from_utf8(self.to_vec()).unwrap()
+    /// Runs a DELETE of the rows its modification plan finds.
+    fn delete_scanned(
+        &mut self,
+        delete: &Delete,
+        plan: &PhysicalPlan,
+        profile: &Profile,
+    ) -> Result<Option<Results>, SQLError> {
+        let positions = self.scanned_positions(plan, profile)?;
+        let table = &self.tables[&delete.table.literal];
+        let old_rows: Vec<Row> = positions.iter().map(|i| table.rows[*i].clone()).collect();
+        let results = self.returning(table, &delete.returning, &old_rows)?;
+
+        let name = &delete.table.literal;
+        self.atomically(|mb| mb.delete_rows(name, &positions))?;
+        Ok(results)
     }
-}
 
-pub struct Results {
-    pub columns: Vec<Column>,
-    pub rows: Vec<Vec<MemCell>>,
-}
+    /// Runs the statement of an EXPLAIN ANALYZE, recording in `profile` what
+    /// each operator of `plan` did. A modification gives the number of rows
+    /// it returned.
+    fn run_explained(
+        &mut self,
+        explain: &Explain,
+        plan: &PhysicalPlan,
+        profile: &Profile,
+    ) -> Result<Option<usize>, SQLError> {
+        let results = match (&explain.insert, &explain.update, &explain.delete) {
+            (Some(insert), _, _) => {
+                self.execute_plan(plan, profile)?;
+                self.insert(insert)?
+            }
+            (_, Some(update), _) => self.update_scanned(update, plan, profile)?,
+            (_, _, Some(delete)) => self.delete_scanned(delete, plan, profile)?,
+            _ => {
+                self.execute_plan(plan, profile)?;
+                return Ok(None);
+            }
+        };
+        Ok(Some(results.map_or(0, |results| results.rows.len())))
+    }
 
-pub struct Table {
-    pub name: String,
-    pub columns: Vec<String>,
-    pub rows: Vec<Vec<MemCell>>,
-    pub column_types: Vec<ColumnType>,
-}
+    /// Applies the actions of the foreign keys referencing a row that was
+    /// deleted, or updated to `new_row`.
+    fn apply_referential_actions(
+        &mut self,
+        name: &str,
+        old_row: &Row,
+        new_row: Option<&Row>,
+    ) -> Result<(), SQLError> {
+        for (child_name, foreign_key) in self.referencing(name) {
+            let parent = &self.tables[name];
+            let key_of = |row: &Row| -> Vec<Value> {
+                foreign_key
+                    .referenced
+                    .iter()
+                    .map(|r| Value::from_cell(&row[*r], &parent.column_types[*r]))
+                    .collect()
+            };
+            let old_key = key_of(old_row);
+            let new_key = new_row.map(key_of);
+            if old_key.contains(&Value::Null) || new_key.as_ref() == Some(&old_key) {
+                continue;
+            }
 
-impl Table {
-    pub fn new() -> Table {
-        Table {
-            name: String::new(),
-            columns: Vec::new(),
-            column_types: Vec::new(),
-            rows: Vec::new(),
+            let action = match new_key {
+                Some(_) => foreign_key.on_update,
+                None => foreign_key.on_delete,
+            };
+            // Unlike RESTRICT, NO ACTION is satisfied by another row with
+            // the same key
+            let index = parent.unique_index(&foreign_key.referenced).unwrap();
+            if action == ReferentialAction::NoAction && !index.get(&old_key).is_empty() {
+                continue;
+            }
+
+            let child = &self.tables[&child_name];
+            let positions = foreign_key.referencing_rows(child, parent, &old_key)?;
+            if positions.is_empty() {
+                continue;
+            }
+
+            let mut updated = Vec::new();
+            match (action, &new_key) {
+                (ReferentialAction::NoAction | ReferentialAction::Restrict, _) => {
+                    return Err(SQLError::ForeignKeyViolation(
+                        foreign_key.name.clone(),
+                        format!(
+                            "key {} is still referenced from table {}",
+                            key_text(parent, &foreign_key.referenced, &old_key),
+                            child_name
+                        ),
+                    ))
+                }
+                (ReferentialAction::Cascade, None) => {
+                    self.delete_rows(&child_name, &positions)?;
+                    continue;
+                }
+                (ReferentialAction::Cascade, Some(new_key)) => {
+                    for position in positions {
+                        let mut row = child.rows[position].clone();
+                        for (value, c) in new_key.iter().zip(&foreign_key.columns) {
+                            row[*c] =
+                                cast::cast(value, &child.column_types[*c], CastLevel::Assignment)?
+                                    .to_cell();
+                        }
+                        updated.push((position, row));
+                    }
+                }
+                (ReferentialAction::SetNull, _) => {
+                    for position in positions {
+                        let mut row = child.rows[position].clone();
+                        for c in &foreign_key.columns {
+                            row[*c] = None;
+                        }
+                        updated.push((position, row));
+                    }
+                }
+            }
+            self.update_rows(&child_name, updated)?;
         }
+        Ok(())
     }
-}
 
-fn token_to_cell(token: Token) -> MemCell {
-    if token.token_kind == TokenKind::String {
-        return token.literal.as_bytes().to_vec();
-    }
-    if token.token_kind == TokenKind::Numeric {
-        let num: i32 = token.literal.parse().unwrap();
-        return num.to_le_bytes().to_vec();
+    /// A sequence with the given options. It counts up from 1 by default,
+    /// and down from -1 when the increment is negative.
+    fn new_sequence(&self, options: &SequenceOptions) -> Result<Sequence, SQLError> {
+        let no_table = Table::new();
+        let cache = StatementCache::default();
+        let scope = Scope::new(&no_table, &cache);
+        let option = |value: &Option<Expression>| -> Result<Option<i64>, SQLError> {
+            match value {
+                Some(value) => match self.evaluate(value, &scope)?.as_i64() {
+                    Some(value) => Ok(Some(value)),
+                    None => Err(SQLError::TypeMismatch(String::from(
+                        "sequence options must be integers",
+                    ))),
+                },
+                None => Ok(None),
+            }
+        };
+
+        let increment = option(&options.increment)?.unwrap_or(1);
+        if increment == 0 {
+            return Err(SQLError::OutOfRange(String::from(
+                "sequence increment must not be zero",
+            )));
+        }
+        let start = option(&options.start)?.unwrap_or(increment.signum());
+        Ok(Sequence::new(start, increment))
     }
-    String::new().as_bytes().to_vec()
-}
 
-#[cfg(test)]
-mod mem_backend_test {
-    use crate::mem_backend::{Cell, MemCell};
+    /// Calls a table-valued function. Its arguments cannot refer to columns.
+    fn evaluate_table_function(&self, function: &FunctionCall) -> Result<Table, SQLError> {
+        let table_function = match self.functions.table(&function.name.literal) {
+            Some(table_function) => table_function,
+            None => {
+                return Err(SQLError::FunctionDoesNotExist(
+                    function.name.literal.clone(),
+                ))
+            }
+        };
 
-    #[test]
-    fn test_as_text() {
-        let mc: MemCell = [72, 101, 108, 108, 111].to_vec(); // ASCII for "Hello"
-        assert_eq!(mc.as_text(), String::from("Hello"));
+        let no_table = Table::new();
+        let cache = StatementCache::default();
+        let scope = Scope::new(&no_table, &cache);
+        let mut args = Vec::new();
+        for arg in &function.args {
+            args.push(self.evaluate(arg, &scope)?);
+        }
+        table_function(&args)
     }
 
-    #[test]
+    fn evaluate(&self, exp: &Expression, scope: &Scope) -> Result<Value, SQLError> {
+        match exp.kind {
+            ExpressionKind::Literal => {
+                if scope.group.is_some() && exp.literal.token_kind == TokenKind::Identifier {
+                    return Err(SQLError::InvalidAggregation(format!(
+                        "column {} must be used in an aggregate function",
+                        exp.literal.literal
+                    )));
+                }
+                evaluate_literal(&exp.literal, scope.table, scope.row)
+            }
+            ExpressionKind::Window => {
+                let windows = scope.cache.windows.borrow();
+                match windows.get(&(exp as *const Expression)) {
+                    Some(values) => Ok(values[scope.index].clone()),
+                    None => Err(SQLError::InvalidWindowFunction(format!(
+                        "{} is not allowed here",
+                        exp.literal.literal
+                    ))),
+                }
+            }
+            ExpressionKind::Function => {
+                let function = exp.function.as_ref().unwrap();
+                let name = &function.name.literal;
+
+                if let Some(aggregate) = self.functions.aggregate(name) {
+                    let rows = match scope.group {
+                        Some(rows) => rows,
+                        None => {
+                            return Err(SQLError::InvalidAggregation(format!(
+                                "{} is not allowed here",
+                                name
+                            )))
+                        }
+                    };
+
+                    let mut acc = aggregate.accumulator();
+                    for row in rows {
+                        let mut args = Vec::new();
+                        let row_scope = Scope {
+                            row,
+                            group: None,
+                            ..*scope
+                        };
+                        for arg in &function.args {
+                            args.push(self.evaluate(arg, &row_scope)?);
+                        }
+                        acc.step(&args)?;
+                    }
+                    return acc.finalize();
+                }
+
+                let scalar = match self.functions.scalar(name) {
+                    Some(scalar) => scalar,
+                    None if WindowFunction::is_window_only(name) => {
+                        return Err(SQLError::InvalidWindowFunction(format!(
+                            "{} requires an OVER clause",
+                            name
+                        )))
+                    }
+                    None => return Err(SQLError::FunctionDoesNotExist(name.clone())),
+                };
+
+                let mut args = Vec::new();
+                for arg in &function.args {
+                    args.push(self.evaluate(arg, scope)?);
+                }
+                scalar(&args)
+            }
+            ExpressionKind::Binary => {
+                let binary = exp.binary.as_ref().unwrap();
+                let a = self.evaluate(&binary.a, scope)?;
+
+                // AND and OR only look at the right operand when they have to
+                match binary.op.literal.as_str() {
+                    "and" if a.as_bool()? == Some(false) => return Ok(Value::from_bool(false)),
+                    "or" if a.as_bool()? == Some(true) => return Ok(Value::from_bool(true)),
+                    _ => {}
+                }
+
+                let b = self.evaluate(&binary.b, scope)?;
+                if is_comparison(&binary.op)
+                    && (is_char(&binary.a, scope.table) || is_char(&binary.b, scope.table))
+                {
+                    let (a, b) = (cast::without_padding(a), cast::without_padding(b));
+                    return evaluate_binary(&binary.op, &a, &b);
+                }
+                evaluate_binary(&binary.op, &a, &b)
+            }
+            ExpressionKind::Unary => {
+                let unary = exp.unary.as_ref().unwrap();
+                let operand = self.evaluate(&unary.operand, scope)?;
+                match unary.op.literal.as_str() {
+                    "-" => match operand.column_type() {
+                        // Negation is subtraction from zero of the same type
+                        Some(col_type) if col_type.is_number() => {
+                            let zero = cast::cast(&Value::Int(0), &col_type, CastLevel::Explicit)?;
+                            evaluate_arithmetic("-", &zero, &operand)
+                        }
+                        Some(col_type) => Err(SQLError::TypeMismatch(format!(
+                            "operator - expects a number, got {}",
+                            col_type
+                        ))),
+                        None => Ok(Value::Null),
+                    },
+                    "not" => Ok(Value::from_option_bool(operand.as_bool()?.map(|b| !b))),
+                    op => Err(SQLError::TypeMismatch(format!("unknown operator {}", op))),
+                }
+            }
+            ExpressionKind::PatternMatch => {
+                let pattern_match = exp.pattern_match.as_ref().unwrap();
+                let value = self.evaluate(&pattern_match.value, scope)?;
+                let pattern = self.evaluate(&pattern_match.pattern, scope)?;
+                let escape = match &pattern_match.escape {
+                    Some(escape) => Some(self.evaluate(escape, scope)?),
+                    None => None,
+                };
+
+                if value == Value::Null || pattern == Value::Null || escape == Some(Value::Null) {
+                    return Ok(Value::Null);
+                }
+
+                let matched = self.evaluate_pattern_match(
+                    &pattern_match.op,
+                    &value,
+                    &pattern,
+                    escape.as_ref(),
+                )?;
+                Ok(Value::from_bool(matched != pattern_match.negated))
+            }
+            ExpressionKind::Cast => {
+                let cast = exp.cast.as_ref().unwrap();
+                let value = self.evaluate(&cast.value, scope)?;
+                let col_type = self.resolve_type(&cast.data_type)?;
+                cast::cast(&value, &col_type, CastLevel::Explicit)
+            }
+            ExpressionKind::InList => {
+                let in_list = exp.in_list.as_ref().unwrap();
+                let found = self.evaluate_in_list(in_list, scope)?;
+                Ok(Value::from_option_bool(
+                    found.map(|found| found != in_list.negated),
+                ))
+            }
+            ExpressionKind::Between => {
+                let between = exp.between.as_ref().unwrap();
+                let value = self.evaluate(&between.value, scope)?;
+                let mut low = self.evaluate(&between.low, scope)?;
+                let mut high = self.evaluate(&between.high, scope)?;
+                if is_char(&between.value, scope.table) {
+                    low = cast::without_padding(low);
+                    high = cast::without_padding(high);
+                }
+                if value == Value::Null || low == Value::Null || high == Value::Null {
+                    return Ok(Value::Null);
+                }
+
+                let within = compare(&value, &low)? != Ordering::Less
+                    && compare(&value, &high)? != Ordering::Greater;
+                Ok(Value::from_bool(within != between.negated))
+            }
+            ExpressionKind::IsNull => {
+                let is_null = exp.is_null.as_ref().unwrap();
+                let value = self.evaluate(&is_null.value, scope)?;
+                Ok(Value::from_bool((value == Value::Null) != is_null.negated))
+            }
+            ExpressionKind::Array => {
+                let mut elements = Vec::new();
+                for element in exp.array.as_ref().unwrap() {
+                    elements.push(self.evaluate(element, scope)?);
+                }
+                Value::array(elements)
+            }
+            ExpressionKind::Subscript => {
+                let subscript = exp.subscript.as_ref().unwrap();
+                let value = self.evaluate(&subscript.value, scope)?;
+                let index = self.evaluate(&subscript.index, scope)?;
+                match (value, index.as_i64()) {
+                    (Value::Null, _) => Ok(Value::Null),
+                    _ if index == Value::Null => Ok(Value::Null),
+                    // Subscripts outside the array are NULL
+                    (Value::Array(_, elements), Some(i)) => Ok(usize::try_from(i - 1)
+                        .ok()
+                        .and_then(|i| elements.get(i).cloned())
+                        .unwrap_or(Value::Null)),
+                    (Value::Array(..), None) => Err(SQLError::TypeMismatch(format!(
+                        "array subscript must be an integer, got {}",
+                        index.column_type().unwrap()
+                    ))),
+                    (value, _) => Err(SQLError::TypeMismatch(format!(
+                        "cannot subscript type {}",
+                        value.column_type().unwrap()
+                    ))),
+                }
+            }
+            ExpressionKind::Quantified => {
+                let quantified = exp.quantified.as_ref().unwrap();
+                let value = self.evaluate(&quantified.value, scope)?;
+                let elements = match self.evaluate(&quantified.array, scope)? {
+                    Value::Null => return Ok(Value::Null),
+                    Value::Array(_, elements) => elements,
+                    other => {
+                        return Err(SQLError::TypeMismatch(format!(
+                            "{} {} expects an array, got {}",
+                            quantified.op.literal,
+                            exp.literal.literal.to_uppercase(),
+                            other.column_type().unwrap()
+                        )))
+                    }
+                };
+
+                // ANY is decided by the first true comparison and ALL by the
+                // first false one. Otherwise NULLs make the result unknown.
+                let mut unknown = false;
+                for element in &elements {
+                    match evaluate_binary(&quantified.op, &value, element)?.as_bool()? {
+                        Some(b) if b != quantified.all => return Ok(Value::from_bool(b)),
+                        None => unknown = true,
+                        _ => {}
+                    }
+                }
+                if unknown {
+                    return Ok(Value::Null);
+                }
+                Ok(Value::from_bool(quantified.all))
+            }
+        }
+    }
+
+    /// Whether the value is in the list, or `None` when that is unknown
+    /// because of NULLs.
+    fn evaluate_in_list(&self, in_list: &InList, scope: &Scope) -> Result<Option<bool>, SQLError> {
+        let value = self.evaluate(&in_list.value, scope)?;
+        if value == Value::Null {
+            return Ok(None);
+        }
+        // Items compared with a char(n) value lose their trailing spaces
+        let item_value = |item: Value| match is_char(&in_list.value, scope.table) {
+            true => cast::without_padding(item),
+            false => item,
+        };
+
+        if in_list.list.iter().all(is_constant) {
+            let key = in_list as *const InList;
+            let cached = scope.cache.in_lists.borrow().get(&key).cloned();
+            let set = match cached {
+                Some(set) => set,
+                None => {
+                    let mut items = Vec::new();
+                    for item in &in_list.list {
+                        items.push(item_value(self.evaluate(item, scope)?));
+                    }
+                    let set = Rc::new(InSet::new(items));
+                    scope.cache.in_lists.borrow_mut().insert(key, set.clone());
+                    set
+                }
+            };
+            if let Some(found) = set.contains(&value) {
+                return Ok(found);
+            }
+        }
+
+        let mut saw_null = false;
+        for item in &in_list.list {
+            let item = item_value(self.evaluate(item, scope)?);
+            if item == Value::Null {
+                saw_null = true;
+            } else if compare(&value, &item)? == Ordering::Equal {
+                return Ok(Some(true));
+            }
+        }
+        Ok(if saw_null { None } else { Some(false) })
+    }
+
+    fn evaluate_pattern_match(
+        &self,
+        op: &Token,
+        value: &Value,
+        pattern: &Value,
+        escape: Option<&Value>,
+    ) -> Result<bool, SQLError> {
+        let (value, pattern) = match (value, pattern) {
+            (Value::Text(value), Value::Text(pattern)) => (value, pattern),
+            _ => {
+                return Err(SQLError::TypeMismatch(format!(
+                    "{} expects text operands",
+                    op.literal
+                )))
+            }
+        };
+
+        match op.literal.as_str() {
+            "like" | "ilike" => {
+                // Like PostgreSQL the escape character defaults to a backslash
+                // and ESCAPE '' disables escaping altogether.
+                let escape = match escape {
+                    None => Some('\\'),
+                    Some(Value::Text(escape)) if escape.chars().count() <= 1 => {
+                        escape.chars().next()
+                    }
+                    Some(_) => {
+                        return Err(SQLError::InvalidPattern(String::from(
+                            "ESCAPE must be a single character",
+                        )))
+                    }
+                };
+                pattern::like(value, pattern, escape, op.literal == "ilike")
+            }
+            "glob" => pattern::glob(value, pattern),
+            "regexp" | "~" | "!~" => {
+                Ok(self.regex(pattern, false)?.is_match(value) != (op.literal == "!~"))
+            }
+            "~*" | "!~*" => Ok(self.regex(pattern, true)?.is_match(value) != (op.literal == "!~*")),
+            op => Err(SQLError::TypeMismatch(format!("unknown operator {}", op))),
+        }
+    }
+
+    /// Compiles a regular expression, reusing earlier compilations so a
+    /// filter does not recompile its pattern for every row.
+    fn regex(&self, pattern: &str, case_insensitive: bool) -> Result<Regex, SQLError> {
+        let key = (pattern.to_string(), case_insensitive);
+        if let Some(regex) = self.regexes.borrow().get(&key) {
+            return Ok(regex.clone());
+        }
+
+        let regex = match RegexBuilder::new(pattern)
+            .case_insensitive(case_insensitive)
+            .build()
+        {
+            Ok(regex) => regex,
+            Err(e) => return Err(SQLError::InvalidPattern(e.to_string())),
+        };
+        self.regexes.borrow_mut().insert(key, regex.clone());
+        Ok(regex)
+    }
+
+    fn is_aggregate(&self, exp: &Expression) -> bool {
+        // An aggregate with an OVER clause is a window function instead
+        if exp.kind == ExpressionKind::Function {
+            let function = exp.function.as_ref().unwrap();
+            if self.functions.aggregate(&function.name.literal).is_some() {
+                return true;
+            }
+        }
+        exp.children().iter().any(|child| self.is_aggregate(child))
+    }
+
+    /// Computes a window function for every result row, each described by
+    /// one of `scopes`.
+    fn evaluate_window(
+        &self,
+        exp: &Expression,
+        scope: &Scope,
+        scopes: &[Scope],
+    ) -> Result<Vec<Value>, SQLError> {
+        let function = exp.function.as_ref().unwrap();
+        let over = function.over.as_ref().unwrap();
+        let name = &function.name.literal;
+
+        let window_function = match WindowFunction::from_name(name, &self.functions) {
+            Some(window_function) => window_function,
+            None if self.functions.scalar(name).is_some() => {
+                return Err(SQLError::InvalidWindowFunction(format!(
+                    "{} is not a window function",
+                    name
+                )))
+            }
+            None => return Err(SQLError::FunctionDoesNotExist(name.clone())),
+        };
+
+        let frame = match &over.frame {
+            Some(frame) => Frame {
+                units: match frame.units.literal.as_str() {
+                    "rows" => FrameUnits::Rows,
+                    _ => FrameUnits::Range,
+                },
+                start: self.frame_bound(&frame.start, scope)?,
+                end: self.frame_bound(&frame.end, scope)?,
+            },
+            None => Frame::default(),
+        };
+        if frame.start == Bound::UnboundedFollowing || frame.end == Bound::UnboundedPreceding {
+            return Err(SQLError::InvalidWindowFunction(String::from(
+                "frame cannot start at UNBOUNDED FOLLOWING or end at UNBOUNDED PRECEDING",
+            )));
+        }
+
+        let mut rows = Vec::new();
+        for row_scope in scopes {
+            let mut row = WindowRow {
+                partition: Vec::new(),
+                order: Vec::new(),
+                args: Vec::new(),
+            };
+            for exp in &over.partition_by {
+                row.partition.push(self.evaluate(exp, row_scope)?);
+            }
+            for term in &over.order_by {
+                row.order.push(self.evaluate(&term.expression, row_scope)?);
+            }
+            for arg in &function.args {
+                row.args.push(self.evaluate(arg, row_scope)?);
+            }
+            rows.push(row);
+        }
+
+        let descending: Vec<bool> = over.order_by.iter().map(|term| term.descending).collect();
+        window::evaluate(&window_function, &rows, &descending, &frame)
+    }
+
+    fn frame_bound(&self, bound: &FrameBound, scope: &Scope) -> Result<Bound, SQLError> {
+        let offset = match &bound.offset {
+            Some(offset) => match self.evaluate(offset, scope)? {
+                value if value.as_i64().is_some_and(|i| i >= 0) => value.as_i64().unwrap(),
+                _ => {
+                    return Err(SQLError::InvalidWindowFunction(String::from(
+                        "frame offset must be a non-negative int",
+                    )))
+                }
+            },
+            None => 0,
+        };
+
+        Ok(match bound.kind {
+            FrameBoundKind::UnboundedPreceding => Bound::UnboundedPreceding,
+            FrameBoundKind::Preceding => Bound::Preceding(offset),
+            FrameBoundKind::CurrentRow => Bound::CurrentRow,
+            FrameBoundKind::Following => Bound::Following(offset),
+            FrameBoundKind::UnboundedFollowing => Bound::UnboundedFollowing,
+        })
+    }
+
+    /// Plans a SELECT: its logical plan is rewritten, then each scan is
+    /// given an index when one can find its rows.
+    pub fn plan(&self, select: &Select) -> Result<PhysicalPlan, SQLError> {
+        for (i, join) in select.joins.iter().enumerate() {
+            let name = &join.table.literal;
+            if *name == select.from.literal
+                || select.joins[..i].iter().any(|j| j.table.literal == *name)
+            {
+                return Err(SQLError::DuplicateTable(name.clone()));
+            }
+        }
+        let aggregate = select.items.iter().any(|exp| self.is_aggregate(exp));
+        let no_table = Table::new();
+        let cache = StatementCache::default();
+        let scope = Scope::new(&no_table, &cache);
+        let logical = LogicalPlan::from_select(select, aggregate).optimize(
+            &|exp: &Expression| self.evaluate(exp, &scope).ok(),
+            &|name: &str| match self.tables.get(name) {
+                Some(table) => table.columns.clone(),
+                None => Vec::new(),
+            },
+        );
+
+        PhysicalPlan::from_logical(logical, &|source: &Source, filter: Option<&Expression>| {
+            self.access(source, filter)
+        })
+    }
+
+    /// How a scan of `source` finds its rows: through an index when one
+    /// answers part of `filter`.
+    fn access(&self, source: &Source, filter: Option<&Expression>) -> Result<Access, SQLError> {
+        let name = match source {
+            Source::Table(name) => name,
+            Source::Nothing | Source::Function(_) => return Ok(Access::Sequential),
+        };
+        let table = match self.tables.get(name) {
+            Some(table) => table,
+            None => return Err(SQLError::TableDoesNotExist(name.clone())),
+        };
+        Ok(
+            match filter.and_then(|filter| self.choose_index(table, filter)) {
+                Some((name, lookup)) => Access::Index { name, lookup },
+                None => Access::Sequential,
+            },
+        )
+    }
+
+    /// Runs a plan, recording in `profile` what each operator did.
+    fn execute_plan(&self, plan: &PhysicalPlan, profile: &Profile) -> Result<Rows<'_>, SQLError> {
+        let start = Instant::now();
+        let rows = self.execute_operator(plan, profile)?;
+        let len = match &rows {
+            Rows::Stored { rows, .. } => rows.len(),
+            Rows::Projected { values, .. } => values.len(),
+        };
+        profile
+            .operators
+            .borrow_mut()
+            .insert(plan as *const PhysicalPlan, (len, start.elapsed()));
+        Ok(rows)
+    }
+
+    fn execute_operator(
+        &self,
+        plan: &PhysicalPlan,
+        profile: &Profile,
+    ) -> Result<Rows<'_>, SQLError> {
+        match plan {
+            PhysicalPlan::Scan {
+                source,
+                access,
+                filter,
+                columns,
+            } => {
+                let table = Box::new(match source {
+                    // Without FROM the items are evaluated once against an
+                    // empty row
+                    Source::Nothing => {
+                        let mut no_table = Table::new();
+                        no_table.rows.push(Vec::new());
+                        Cow::Owned(no_table)
+                    }
+                    Source::Table(name) => match self.tables.get(name) {
+                        Some(table) => Cow::Borrowed(table),
+                        None => return Err(SQLError::TableDoesNotExist(name.clone())),
+                    },
+                    Source::Function(function) => {
+                        Cow::Owned(self.evaluate_table_function(function)?)
+                    }
+                });
+                let candidates = match access {
+                    Access::Sequential => (0..table.rows.len()).collect(),
+                    Access::Index { name, lookup } => table.index_lookup(name, lookup),
+                    Access::Empty => Vec::new(),
+                };
+                let positions = self.check_rows(&table, candidates, filter.as_ref())?;
+
+                let columns = match columns {
+                    Some(columns) => columns,
+                    None => {
+                        let rows = positions.iter().map(|p| table.rows[*p].clone()).collect();
+                        return Ok(Rows::Stored {
+                            table,
+                            rows,
+                            grouped: false,
+                            positions: Some(positions),
+                        });
+                    }
+                };
+                // Only the columns used above are read
+                let kept: Vec<usize> = (0..table.columns.len())
+                    .filter(|i| columns.contains(&table.columns[*i]))
+                    .collect();
+                let rows = positions
+                    .iter()
+                    .map(|p| kept.iter().map(|i| table.rows[*p][*i].clone()).collect())
+                    .collect();
+                let mut narrowed = Table::new();
+                narrowed.name = table.name.clone();
+                narrowed.columns = kept.iter().map(|i| table.columns[*i].clone()).collect();
+                narrowed.column_types = kept
+                    .iter()
+                    .map(|i| table.column_types[*i].clone())
+                    .collect();
+                Ok(Rows::Stored {
+                    table: Box::new(Cow::Owned(narrowed)),
+                    rows,
+                    grouped: false,
+                    positions: Some(positions),
+                })
+            }
+            PhysicalPlan::Filter { input, predicate } => match self.execute_plan(input, profile)? {
+                Rows::Stored {
+                    table,
+                    rows,
+                    grouped,
+                    ..
+                } => {
+                    let cache = StatementCache::default();
+                    let scope = Scope::new(&table, &cache);
+                    let mut kept = Vec::new();
+                    for row in rows {
+                        let row_scope = Scope { row: &row, ..scope };
+                        if self.evaluate(predicate, &row_scope)?.as_bool()? == Some(true) {
+                            kept.push(row);
+                        }
+                    }
+                    Ok(Rows::Stored {
+                        table,
+                        rows: kept,
+                        grouped,
+                        positions: None,
+                    })
+                }
+                Rows::Projected { .. } => unreachable!("filters read stored rows"),
+            },
+            PhysicalPlan::NestedLoopJoin { left, right, on } => {
+                let (left, right) = match (
+                    self.execute_plan(left, profile)?,
+                    self.execute_plan(right, profile)?,
+                ) {
+                    (
+                        Rows::Stored {
+                            table: left,
+                            rows: left_rows,
+                            ..
+                        },
+                        Rows::Stored {
+                            table: right,
+                            rows: right_rows,
+                            ..
+                        },
+                    ) => ((left, left_rows), (right, right_rows)),
+                    _ => unreachable!("joins read stored rows"),
+                };
+
+                let mut joined = Table::new();
+                for table in [&left.0, &right.0] {
+                    for (column, col_type) in table.columns.iter().zip(&table.column_types) {
+                        joined.columns.push(match column.contains('.') {
+                            true => column.clone(),
+                            false => format!("{}.{}", table.name, column),
+                        });
+                        joined.column_types.push(col_type.clone());
+                    }
+                }
+
+                let cache = StatementCache::default();
+                let scope = Scope::new(&joined, &cache);
+                let mut rows = Vec::new();
+                for left_row in &left.1 {
+                    for right_row in &right.1 {
+                        let row: Row = left_row.iter().chain(right_row).cloned().collect();
+                        let row_scope = Scope { row: &row, ..scope };
+                        if self.evaluate(on, &row_scope)?.as_bool()? == Some(true) {
+                            rows.push(row);
+                        }
+                    }
+                }
+                Ok(Rows::Stored {
+                    table: Box::new(Cow::Owned(joined)),
+                    rows,
+                    grouped: false,
+                    positions: None,
+                })
+            }
+            PhysicalPlan::Aggregate { input } => match self.execute_plan(input, profile)? {
+                Rows::Stored { table, rows, .. } => Ok(Rows::Stored {
+                    table,
+                    rows,
+                    grouped: true,
+                    positions: None,
+                }),
+                Rows::Projected { .. } => unreachable!("aggregates read stored rows"),
+            },
+            PhysicalPlan::Project { input, items, keys } => {
+                match self.execute_plan(input, profile)? {
+                    Rows::Stored {
+                        table,
+                        rows,
+                        grouped,
+                        ..
+                    } => self.project(&table, &rows, grouped, items, keys),
+                    Rows::Projected { .. } => unreachable!("projections read stored rows"),
+                }
+            }
+            PhysicalPlan::Sort { input, descending } => match self.execute_plan(input, profile)? {
+                Rows::Projected {
+                    columns,
+                    values,
+                    keys,
+                } => {
+                    let mut sorted: Vec<(Vec<Value>, Vec<Value>)> =
+                        values.into_iter().zip(keys).collect();
+                    sorted.sort_by(|(_, a), (_, b)| compare_keys(a, b, descending));
+                    let (values, keys) = sorted.into_iter().unzip();
+                    Ok(Rows::Projected {
+                        columns,
+                        values,
+                        keys,
+                    })
+                }
+                Rows::Stored { .. } => unreachable!("sorts read projected rows"),
+            },
+            PhysicalPlan::Limit {
+                input,
+                limit,
+                offset,
+            } => {
+                let offset = match offset {
+                    Some(offset) => self.row_count(offset, "OFFSET")?.unwrap_or(0),
+                    None => 0,
+                };
+                let limit = match limit {
+                    Some(limit) => self.row_count(limit, "LIMIT")?,
+                    None => None,
+                };
+                let window = |len: usize| {
+                    let start = offset.min(len);
+                    start..limit.map_or(len, |limit| len.min(start.saturating_add(limit)))
+                };
+                Ok(match self.execute_plan(input, profile)? {
+                    Rows::Stored {
+                        table,
+                        rows,
+                        grouped,
+                        ..
+                    } => Rows::Stored {
+                        rows: rows[window(rows.len())].to_vec(),
+                        table,
+                        grouped,
+                        positions: None,
+                    },
+                    Rows::Projected {
+                        columns,
+                        values,
+                        keys,
+                    } => Rows::Projected {
+                        columns,
+                        keys: keys[window(values.len())].to_vec(),
+                        values: values[window(values.len())].to_vec(),
+                    },
+                })
+            }
+        }
+    }
+
+    /// Evaluates `items`, and the sort `keys` of each result row, over
+    /// `rows`: once for all of them when `grouped`, otherwise once per row.
+    fn project(
+        &self,
+        table: &Table,
+        rows: &[Row],
+        grouped: bool,
+        items: &[Expression],
+        keys: &[Expression],
+    ) -> Result<Rows<'static>, SQLError> {
+        let cache = StatementCache::default();
+        let scope = Scope::new(table, &cache);
+
+        // One scope per result row: a single group when aggregating
+        let scopes: Vec<Scope> = if grouped {
+            vec![Scope {
+                group: Some(rows),
+                ..scope
+            }]
+        } else {
+            rows.iter()
+                .enumerate()
+                .map(|(index, row)| Scope {
+                    row,
+                    index,
+                    ..scope
+                })
+                .collect()
+        };
+
+        // Window functions see every result row, so they are computed up
+        // front and looked up by row index during projection
+        let mut windows = Vec::new();
+        for exp in items.iter().chain(keys) {
+            collect_windows(exp, &mut windows);
+        }
+        for window in windows {
+            let results = self.evaluate_window(window, &scope, &scopes)?;
+            cache
+                .windows
+                .borrow_mut()
+                .insert(window as *const Expression, results);
+        }
+
+        let mut values = Vec::new();
+        let mut sort_keys = Vec::new();
+        for row_scope in &scopes {
+            let mut result = Vec::new();
+            for exp in items {
+                result.push(self.evaluate(exp, row_scope)?);
+            }
+
+            // ORDER BY 2 refers to the second result column
+            let mut row_keys = Vec::new();
+            for exp in keys {
+                let key = match exp.literal.literal.parse::<usize>() {
+                    Ok(position) if exp.kind == ExpressionKind::Literal => {
+                        match position.checked_sub(1).and_then(|i| result.get(i)) {
+                            Some(value) => value.clone(),
+                            None => {
+                                return Err(SQLError::ColumnDoesNotExist(format!(
+                                    "ORDER BY position {}",
+                                    position
+                                )))
+                            }
+                        }
+                    }
+                    _ => self.evaluate(exp, row_scope)?,
+                };
+                row_keys.push(key);
+            }
+            values.push(result);
+            sort_keys.push(row_keys);
+        }
+
+        Ok(Rows::Projected {
+            columns: result_columns(items, &values, table),
+            values,
+            keys: sort_keys,
+        })
+    }
+
+    /// The number of rows an operator is expected to produce. Index scans
+    /// count what the index finds, other filters guess their selectivity.
+    fn estimate_rows(&self, plan: &PhysicalPlan) -> usize {
+        let input_rows = |input: &PhysicalPlan| self.estimate_rows(input);
+        match plan {
+            PhysicalPlan::Scan {
+                source,
+                access,
+                filter,
+                ..
+            } => {
+                let table = match source {
+                    Source::Nothing => return 1,
+                    // Unknown until the function is called
+                    Source::Function(_) => return 100,
+                    Source::Table(name) => match self.tables.get(name) {
+                        Some(table) => table,
+                        None => return 0,
+                    },
+                };
+                match access {
+                    Access::Sequential => match filter {
+                        Some(filter) => estimate(table.rows.len(), selectivity(filter)),
+                        None => table.rows.len(),
+                    },
+                    Access::Index { name, lookup } => table.index_lookup(name, lookup).len(),
+                    Access::Empty => 0,
+                }
+            }
+            PhysicalPlan::Filter { input, predicate } => {
+                estimate(input_rows(input), selectivity(predicate))
+            }
+            PhysicalPlan::NestedLoopJoin { left, right, on } => estimate(
+                input_rows(left).saturating_mul(input_rows(right)),
+                selectivity(on),
+            ),
+            PhysicalPlan::Aggregate { .. } => 1,
+            PhysicalPlan::Project { input, .. } | PhysicalPlan::Sort { input, .. } => {
+                input_rows(input)
+            }
+            PhysicalPlan::Limit {
+                input,
+                limit,
+                offset,
+            } => {
+                let count = |exp: &Option<Expression>, clause| match exp {
+                    Some(exp) => self.row_count(exp, clause).ok().flatten(),
+                    None => None,
+                };
+                let rows = input_rows(input).saturating_sub(count(offset, "OFFSET").unwrap_or(0));
+                match count(limit, "LIMIT") {
+                    Some(limit) => rows.min(limit),
+                    None => rows,
+                }
+            }
+        }
+    }
+
+    /// What an index scan looks up, such as `(a = 1)`.
+    fn index_condition(&self, table: &Table, name: &str, lookup: &IndexLookup) -> Option<String> {
+        let index = table.indexes.iter().find(|index| index.name == name)?;
+        let part = |i: usize| match index.expressions.get(i) {
+            Some(exp) => exp.to_string(),
+            None => table.columns[index.columns[i]].clone(),
+        };
+        let mut conditions = Vec::new();
+        match lookup {
+            IndexLookup::Key(key) => {
+                for (i, value) in key.iter().enumerate() {
+                    conditions.push(format!("({} = {})", part(i), value_literal(value)));
+                }
+            }
+            IndexLookup::Range(lower, upper) => {
+                match lower {
+                    ops::Bound::Included(value) => {
+                        conditions.push(format!("({} >= {})", part(0), value_literal(value)))
+                    }
+                    ops::Bound::Excluded(value) => {
+                        conditions.push(format!("({} > {})", part(0), value_literal(value)))
+                    }
+                    ops::Bound::Unbounded => {}
+                }
+                match upper {
+                    ops::Bound::Included(value) => {
+                        conditions.push(format!("({} <= {})", part(0), value_literal(value)))
+                    }
+                    ops::Bound::Excluded(value) => {
+                        conditions.push(format!("({} < {})", part(0), value_literal(value)))
+                    }
+                    ops::Bound::Unbounded => {}
+                }
+            }
+            IndexLookup::All => return None,
+        }
+        Some(conditions.join(" and "))
+    }
+
+    /// The value of a LIMIT or OFFSET count, `None` when it is NULL.
+    fn row_count(&self, exp: &Expression, clause: &str) -> Result<Option<usize>, SQLError> {
+        let no_table = Table::new();
+        let cache = StatementCache::default();
+        match self.evaluate(exp, &Scope::new(&no_table, &cache))? {
+            Value::Null => Ok(None),
+            value => match value.as_i64() {
+                Some(count) if count >= 0 => Ok(Some(count as usize)),
+                Some(_) => Err(SQLError::OutOfRange(format!(
+                    "{} must not be negative",
+                    clause
+                ))),
+                None => Err(SQLError::TypeMismatch(format!(
+                    "argument of {} must be an integer",
+                    clause
+                ))),
+            },
+        }
+    }
+}
+
+impl Backend for MemoryBackend {
+    fn create(&mut self, create: &Create) -> Result<(), SQLError> {
+        if self.tables.contains_key(&create.name.literal) {
+            return Err(SQLError::TableAlreadyExists(create.name.literal.clone()));
+        }
+        let mut table = Table::new();
+        table.name = create.name.literal.clone();
+        let mut constraints = Vec::new();
+        let mut identities = Vec::new();
+        for col in create.cols.clone() {
+            for constraint in col.constraints {
+                constraints.push((constraint, Some(col.name.literal.clone())));
+            }
+            // The serial types are integers with an identity, as in
+            // GENERATED BY DEFAULT AS IDENTITY
+            let data_type = &col.data_type;
+            let serial = match data_type.name.literal.to_lowercase().as_str() {
+                _ if self.types.contains_key(&data_type.name.literal) => None,
+                _ if !data_type.modifiers.is_empty() || data_type.array => None,
+                "smallserial" | "serial2" => Some(ColumnType::SmallIntType),
+                "serial" | "serial4" => Some(ColumnType::IntType),
+                "bigserial" | "serial8" => Some(ColumnType::BigIntType),
+                _ => None,
+            };
+            let col_type = match serial {
+                Some(col_type) => {
+                    let identity = Identity {
+                        always: false,
+                        options: SequenceOptions::default(),
+                    };
+                    identities.push((table.columns.len(), identity));
+                    col_type
+                }
+                None => self.resolve_type(data_type)?,
+            };
+            table.columns.push(col.name.literal);
+            table.column_types.push(col_type);
+        }
+        constraints.extend(create.constraints.iter().map(|c| (c.clone(), None)));
+
+        let mut foreign = Vec::new();
+        for (constraint, column) in constraints {
+            let columns: Vec<String> = match &column {
+                Some(column) => vec![column.clone()],
+                None => constraint
+                    .columns
+                    .iter()
+                    .map(|c| c.literal.clone())
+                    .collect(),
+            };
+            let mut positions = Vec::new();
+            for column in &columns {
+                match table.columns.iter().position(|c| c == column) {
+                    Some(i) => positions.push(i),
+                    None => return Err(SQLError::ColumnDoesNotExist(column.clone())),
+                }
+            }
+
+            // Unnamed constraints are named the way PostgreSQL names them
+            let generated = match (constraint.kind, &column) {
+                (ConstraintKind::PrimaryKey, _) => format!("{}_pkey", table.name),
+                (ConstraintKind::Check, Some(column)) => format!("{}_{}_check", table.name, column),
+                (ConstraintKind::Check, None) => format!("{}_check", table.name),
+                (ConstraintKind::ForeignKey, _) => {
+                    format!("{}_{}_fkey", table.name, columns.join("_"))
+                }
+                _ => format!("{}_{}_key", table.name, columns.join("_")),
+            };
+            let taken = |name: &str| {
+                table.indexes.iter().any(|index| index.name == name)
+                    || table.checks.iter().any(|check| check.name == name)
+                    || foreign.iter().any(|(taken, _, _)| taken == name)
+            };
+            let name = match &constraint.name {
+                Some(name) if taken(&name.literal) => {
+                    return Err(SQLError::InvalidConstraint(format!(
+                        "constraint {} already exists",
+                        name.literal
+                    )))
+                }
+                Some(name) => name.literal.clone(),
+                None => (0..)
+                    .map(|n| match n {
+                        0 => generated.clone(),
+                        n => format!("{}{}", generated, n),
+                    })
+                    .find(|name| !taken(name))
+                    .unwrap(),
+            };
+
+            match constraint.kind {
+                ConstraintKind::PrimaryKey | ConstraintKind::Unique => {
+                    let primary = constraint.kind == ConstraintKind::PrimaryKey;
+                    if primary && table.indexes.iter().any(|index| index.primary) {
+                        return Err(SQLError::InvalidConstraint(format!(
+                            "multiple primary keys for table {}",
+                            table.name
+                        )));
+                    }
+                    if primary {
+                        table.not_null.extend(&positions);
+                    }
+                    table
+                        .indexes
+                        .push(Index::new(name, positions, true, primary));
+                }
+                ConstraintKind::NotNull => table.not_null.extend(&positions),
+                ConstraintKind::Default => {
+                    let value = constraint.expression.unwrap();
+                    let mut references = Vec::new();
+                    column_references(&value, &mut references);
+                    if !references.is_empty() {
+                        return Err(SQLError::InvalidConstraint(format!(
+                            "default of column {} cannot refer to columns",
+                            columns[0]
+                        )));
+                    }
+                    table.defaults.insert(positions[0], value);
+                }
+                ConstraintKind::Check => {
+                    let condition = constraint.expression.unwrap();
+                    let mut references = Vec::new();
+                    column_references(&condition, &mut references);
+                    if let Some(missing) = references
+                        .iter()
+                        .find(|c| !table.columns.iter().any(|column| column == **c))
+                    {
+                        return Err(SQLError::ColumnDoesNotExist(missing.to_string()));
+                    }
+                    table.checks.push(Check { name, condition });
+                }
+                // Resolved once every key of this table is known, as the
+                // table may reference itself
+                ConstraintKind::ForeignKey => {
+                    foreign.push((name, positions, constraint.references.unwrap()))
+                }
+                ConstraintKind::Identity => {
+                    identities.push((positions[0], constraint.identity.unwrap()))
+                }
+            }
+        }
+
+        let mut sequences = Vec::new();
+        for (i, identity) in identities {
+            let column = &table.columns[i];
+            if table.identities.contains_key(&i) || table.defaults.contains_key(&i) {
+                return Err(SQLError::InvalidConstraint(format!(
+                    "column {} has more than one default or identity",
+                    column
+                )));
+            }
+            if !matches!(
+                table.column_types[i],
+                ColumnType::SmallIntType | ColumnType::IntType | ColumnType::BigIntType
+            ) {
+                return Err(SQLError::InvalidConstraint(format!(
+                    "identity column {} must be an integer",
+                    column
+                )));
+            }
+
+            // Named like PostgreSQL's, so that nextval and setval find it
+            let sequence = format!("{}_{}_seq", table.name, column);
+            if self.sequences.borrow().contains_key(&sequence) {
+                return Err(SQLError::SequenceAlreadyExists(sequence));
+            }
+            sequences.push((sequence.clone(), self.new_sequence(&identity.options)?));
+            table.not_null.insert(i);
+            table.identities.insert(
+                i,
+                IdentityColumn {
+                    sequence,
+                    always: identity.always,
+                },
+            );
+        }
+
+        for (name, columns, references) in foreign {
+            let parent = match references.table.literal == table.name {
+                true => &table,
+                false => match self.tables.get(&references.table.literal) {
+                    Some(parent) => parent,
+                    None => {
+                        return Err(SQLError::TableDoesNotExist(
+                            references.table.literal.clone(),
+                        ))
+                    }
+                },
+            };
+
+            let mut referenced = Vec::new();
+            for column in &references.columns {
+                match parent.columns.iter().position(|c| c == &column.literal) {
+                    Some(i) => referenced.push(i),
+                    None => return Err(SQLError::ColumnDoesNotExist(column.literal.clone())),
+                }
+            }
+            if references.columns.is_empty() {
+                match parent.indexes.iter().find(|index| index.primary) {
+                    Some(index) => referenced = index.columns.clone(),
+                    None => {
+                        return Err(SQLError::InvalidConstraint(format!(
+                            "table {} has no primary key to reference",
+                            parent.name
+                        )))
+                    }
+                }
+            }
+            if referenced.len() != columns.len() {
+                return Err(SQLError::InvalidConstraint(format!(
+                    "{} references a different number of columns",
+                    name
+                )));
+            }
+            if parent.unique_index(&referenced).is_none() {
+                return Err(SQLError::InvalidConstraint(format!(
+                    "no unique constraint matches the columns {} references",
+                    name
+                )));
+            }
+            for (c, r) in columns.iter().zip(&referenced) {
+                let (from, to) = (&table.column_types[*c], &parent.column_types[*r]);
+                if from != to && !cast::is_implicit(from, to) {
+                    return Err(SQLError::InvalidConstraint(format!(
+                        "column {} of type {} cannot reference type {}",
+                        table.columns[*c], from, to
+                    )));
+                }
+            }
+
+            let foreign_key = ForeignKey {
+                name,
+                columns,
+                table: parent.name.clone(),
+                referenced,
+                on_delete: references.on_delete,
+                on_update: references.on_update,
+            };
+            // Referencing rows are looked up by their key when the parent
+            // row changes
+            if !table
+                .indexes
+                .iter()
+                .any(|index| index.is_plain() && index.columns == foreign_key.columns)
+            {
+                let index = Index::new(
+                    foreign_key.name.clone(),
+                    foreign_key.columns.clone(),
+                    false,
+                    false,
+                );
+                table.indexes.push(index);
+            }
+            table.foreign_keys.push(foreign_key);
+        }
+
+        self.sequences.borrow_mut().extend(sequences);
+
+        self.tables.insert(table.name.clone(), table);
+        Ok(())
+    }
+
+    fn insert(&mut self, insert: &Insert) -> Result<Option<Results>, SQLError> {
+        if !self.tables.contains_key(&insert.table.literal) {
+            return Err(SQLError::TableDoesNotExist(insert.table.literal.clone()));
+        }
+
+        let table = self.tables.get(&insert.table.literal).unwrap();
+
+        let mut targets = Vec::new();
+        for column in &insert.columns {
+            match table.columns.iter().position(|c| c == &column.literal) {
+                Some(i) if targets.contains(&i) => {
+                    return Err(SQLError::DuplicateColumn(column.literal.clone()))
+                }
+                Some(i) => targets.push(i),
+                None => return Err(SQLError::ColumnDoesNotExist(column.literal.clone())),
+            }
+        }
+        if insert.columns.is_empty() {
+            targets = (0..table.columns.len()).collect();
+        }
+        if insert.values.len() != targets.len() {
+            return Err(SQLError::MissingValues);
+        }
+        if let Some(i) = targets.iter().find(|i| table.is_generated_always(**i)) {
+            return Err(SQLError::GeneratedAlways(table.columns[*i].clone()));
+        }
+
+        // Columns left out get their identity or default value, or NULL
+        let no_table = Table::new();
+        let cache = StatementCache::default();
+        let scope = Scope::new(&no_table, &cache);
+        let mut row = Vec::new();
+        for (i, col_type) in table.column_types.iter().enumerate() {
+            let value = match targets.iter().position(|t| *t == i) {
+                Some(j) => self.evaluate(&insert.values[j], &scope)?,
+                None => match (table.identities.get(&i), table.defaults.get(&i)) {
+                    (Some(identity), _) => {
+                        let mut sequences = self.sequences.borrow_mut();
+                        match sequences.get_mut(&identity.sequence).unwrap().next_value() {
+                            Some(value) => Value::BigInt(value),
+                            None => {
+                                return Err(SQLError::OutOfRange(format!(
+                                    "sequence {} reached its limit",
+                                    identity.sequence
+                                )))
+                            }
+                        }
+                    }
+                    (None, Some(default)) => self.evaluate(default, &scope)?,
+                    (None, None) => Value::Null,
+                },
+            };
+            row.push(cast::cast(&value, col_type, CastLevel::Assignment)?.to_cell());
+        }
+        self.check_row(table, &row)?;
+        let keys = self.index_keys(table, &row)?;
+
+        if let Some(on_conflict) = &insert.on_conflict {
+            let conflict =
+                table
+                    .arbiter_indexes(on_conflict)?
+                    .into_iter()
+                    .find_map(|i| match &keys[i] {
+                        Some(key) if !key.contains(&Value::Null) => {
+                            table.indexes[i].get(key).first().copied()
+                        }
+                        _ => None,
+                    });
+            if let Some(position) = conflict {
+                return self.resolve_conflict(insert, position, row);
+            }
+        }
+
+        self.check_references(table, &row)?;
+        table.check_constraints(&keys)?;
+        let results = self.returning(table, &insert.returning, std::slice::from_ref(&row))?;
+
+        self.tables
+            .get_mut(&insert.table.literal)
+            .unwrap()
+            .push_row(row, keys);
+        if let Some(undo_log) = &mut self.undo_log {
+            undo_log.push(Undo::Inserted(insert.table.literal.clone()));
+        }
+        Ok(results)
+    }
+
+    fn select(&self, select: &Select) -> Result<Results, SQLError> {
+        let plan = self.plan(select)?;
+        match self.execute_plan(&plan, &Profile::default())? {
+            Rows::Projected {
+                columns, values, ..
+            } => Ok(Results {
+                rows: result_rows(&columns, values),
+                columns,
+            }),
+            Rows::Stored { .. } => unreachable!("a SELECT projects its rows"),
+        }
+    }
+
+    fn create_type(&mut self, create_type: &CreateType) -> Result<(), SQLError> {
+        let name = &create_type.name.literal;
+        let builtin = DataType {
+            name: create_type.name.clone(),
+            modifiers: Vec::new(),
+            array: false,
+        };
+        if self.types.contains_key(name) || ColumnType::from_data_type(&builtin).is_ok() {
+            return Err(SQLError::TypeAlreadyExists(name.clone()));
+        }
+
+        let mut labels = Vec::new();
+        for label in &create_type.labels {
+            let label = unquote(&label.literal);
+            if labels.contains(&label) {
+                return Err(SQLError::InvalidDataType(format!(
+                    "enum label '{}' is declared more than once",
+                    label
+                )));
+            }
+            labels.push(label);
+        }
+
+        let definition = EnumDefinition {
+            name: name.clone(),
+            labels,
+        };
+        self.types
+            .insert(name.clone(), ColumnType::EnumType(Rc::new(definition)));
+        Ok(())
+    }
+
+    fn create_sequence(&mut self, create_sequence: &CreateSequence) -> Result<(), SQLError> {
+        let name = &create_sequence.name.literal;
+        if self.sequences.borrow().contains_key(name) {
+            return Err(SQLError::SequenceAlreadyExists(name.clone()));
+        }
+        let sequence = self.new_sequence(&create_sequence.options)?;
+        self.sequences.borrow_mut().insert(name.clone(), sequence);
+        Ok(())
+    }
+
+    fn create_index(&mut self, create_index: &CreateIndex) -> Result<(), SQLError> {
+        let table = match self.tables.get(&create_index.table.literal) {
+            Some(table) => table,
+            None => {
+                return Err(SQLError::TableDoesNotExist(
+                    create_index.table.literal.clone(),
+                ))
+            }
+        };
+
+        // Elements that are all columns make a plain index, otherwise every
+        // element is evaluated as an expression
+        let mut columns = Vec::new();
+        let mut references = Vec::new();
+        for element in &create_index.elements {
+            if element.kind == ExpressionKind::Literal
+                && element.literal.token_kind == TokenKind::Identifier
+            {
+                if let Some(i) = table
+                    .columns
+                    .iter()
+                    .position(|c| c == &element.literal.literal)
+                {
+                    columns.push(i);
+                }
+            }
+            column_references(element, &mut references);
+        }
+        if let Some(where_clause) = &create_index.where_clause {
+            column_references(where_clause, &mut references);
+        }
+        if let Some(missing) = references
+            .iter()
+            .find(|c| !table.columns.iter().any(|column| column == **c))
+        {
+            return Err(SQLError::ColumnDoesNotExist(missing.to_string()));
+        }
+        let mut windows = Vec::new();
+        for exp in create_index
+            .elements
+            .iter()
+            .chain(&create_index.where_clause)
+        {
+            collect_windows(exp, &mut windows);
+            if self.is_aggregate(exp) || !windows.is_empty() {
+                return Err(SQLError::InvalidIndex(String::from(
+                    "aggregate and window functions are not allowed in indexes",
+                )));
+            }
+        }
+        let expressions = match columns.len() == create_index.elements.len() {
+            true => Vec::new(),
+            false => {
+                columns.clear();
+                create_index.elements.clone()
+            }
+        };
+
+        let method = match &create_index.method {
+            None => IndexMethod::BTree,
+            Some(method) if method.literal.eq_ignore_ascii_case("btree") => IndexMethod::BTree,
+            Some(method) if method.literal.eq_ignore_ascii_case("hash") => IndexMethod::Hash,
+            Some(method) => {
+                return Err(SQLError::InvalidIndex(format!(
+                    "access method {} does not exist",
+                    method.literal
+                )))
+            }
+        };
+        if method == IndexMethod::Hash && create_index.unique {
+            return Err(SQLError::InvalidIndex(String::from(
+                "hash indexes do not support UNIQUE",
+            )));
+        }
+        if method == IndexMethod::Hash && create_index.elements.len() > 1 {
+            return Err(SQLError::InvalidIndex(String::from(
+                "hash indexes do not support multiple columns",
+            )));
+        }
+
+        let index_exists = |name: &str| {
+            self.tables
+                .values()
+                .any(|table| table.indexes.iter().any(|index| index.name == name))
+        };
+        let name = match &create_index.name {
+            Some(name) if index_exists(&name.literal) => {
+                return Err(SQLError::IndexAlreadyExists(name.literal.clone()))
+            }
+            Some(name) => name.literal.clone(),
+            // Named the way PostgreSQL names them
+            None => {
+                let names: Vec<String> =
+                    create_index.elements.iter().map(expression_name).collect();
+                let generated = format!("{}_{}_idx", table.name, names.join("_"));
+                (0..)
+                    .map(|n| match n {
+                        0 => generated.clone(),
+                        n => format!("{}{}", generated, n),
+                    })
+                    .find(|name| !index_exists(name))
+                    .unwrap()
+            }
+        };
+
+        let mut index = Index::secondary(name, columns, create_index.unique, method);
+        index.expressions = expressions;
+        index.predicate = create_index.where_clause.clone();
+        for (position, row) in table.rows.iter().enumerate() {
+            let key = match self.index_key(table, &index, row)? {
+                Some(key) => key,
+                None => continue,
+            };
+            if index.unique && !key.contains(&Value::Null) && !index.get(&key).is_empty() {
+                return Err(SQLError::UniqueViolation(
+                    index.name.clone(),
+                    index_key_text(table, &index, &key),
+                ));
+            }
+            index.insert(key, position);
+        }
+
+        self.tables
+            .get_mut(&create_index.table.literal)
+            .unwrap()
+            .indexes
+            .push(index);
+        Ok(())
+    }
+
+    fn drop_index(&mut self, drop_index: &DropIndex) -> Result<(), SQLError> {
+        let name = &drop_index.name.literal;
+        let (table, position) = match self.tables.values().find_map(|table| {
+            let position = table.indexes.iter().position(|index| &index.name == name)?;
+            Some((table, position))
+        }) {
+            Some(found) => found,
+            None => return Err(SQLError::IndexDoesNotExist(name.clone())),
+        };
+
+        let index = &table.indexes[position];
+        if index.constraint {
+            return Err(SQLError::InvalidIndex(format!(
+                "cannot drop index {} because constraint {} on table {} requires it",
+                name, name, table.name
+            )));
+        }
+        // A foreign key may rely on a unique index for its referenced key
+        if table.unique_index(&index.columns).map(|index| &index.name) == Some(name) {
+            let referencing = self.referencing(&table.name);
+            if let Some((_, foreign_key)) = referencing
+                .iter()
+                .find(|(_, foreign_key)| foreign_key.referenced == index.columns)
+            {
+                return Err(SQLError::InvalidIndex(format!(
+                    "cannot drop index {} because constraint {} requires it",
+                    name, foreign_key.name
+                )));
+            }
+        }
+
+        let table_name = table.name.clone();
+        self.tables
+            .get_mut(&table_name)
+            .unwrap()
+            .indexes
+            .remove(position);
+        Ok(())
+    }
+
+    fn explain(&mut self, explain: &Explain) -> Result<Results, SQLError> {
+        let (plan, title) = match (
+            &explain.select,
+            &explain.insert,
+            &explain.update,
+            &explain.delete,
+        ) {
+            (Some(select), _, _, _) => (self.plan(select)?, None),
+            (_, Some(insert), _, _) => {
+                if !self.tables.contains_key(&insert.table.literal) {
+                    return Err(SQLError::TableDoesNotExist(insert.table.literal.clone()));
+                }
+                // The values are a single row computed without a table
+                let values = PhysicalPlan::Scan {
+                    source: Source::Nothing,
+                    access: Access::Sequential,
+                    filter: None,
+                    columns: None,
+                };
+                (values, Some(format!("Insert on {}", insert.table.literal)))
+            }
+            (_, _, Some(update), _) => (
+                self.modification_plan(&update.table, update.where_clause.as_ref())?,
+                Some(format!("Update on {}", update.table.literal)),
+            ),
+            (_, _, _, Some(delete)) => (
+                self.modification_plan(&delete.table, delete.where_clause.as_ref())?,
+                Some(format!("Delete on {}", delete.table.literal)),
+            ),
+            _ => unreachable!("EXPLAIN has a statement"),
+        };
+        let profile = Profile::default();
+        let start = Instant::now();
+        let mut returned = None;
+        let mut elapsed = start.elapsed();
+        if explain.analyze {
+            // A modification is run for real and then undone, so that the
+            // tables are left as they were
+            self.undo_log = Some(Vec::new());
+            let result = self.run_explained(explain, &plan, &profile);
+            elapsed = start.elapsed();
+            let undo_log = self.undo_log.take().unwrap_or_default();
+            self.undo(undo_log);
+            returned = result?;
+        }
+
+        let operators = profile.operators.borrow();
+        let annotate = |node: &PhysicalPlan| {
+            let mut details = Vec::new();
+            if let PhysicalPlan::Scan {
+                source: Source::Table(table),
+                access: Access::Index { name, lookup },
+                ..
+            } = node
+            {
+                let condition = self
+                    .tables
+                    .get(table)
+                    .and_then(|table| self.index_condition(table, name, lookup));
+                details.extend(condition.map(|condition| format!("Index Cond: {}", condition)));
+            }
+            Annotation {
+                estimated_rows: self.estimate_rows(node),
+                actual: operators.get(&(node as *const PhysicalPlan)).copied(),
+                details,
+            }
+        };
+        let mut lines = match title {
+            Some(title) => {
+                plan.explain_modification(&title, returned.map(|rows| (rows, elapsed)), &annotate)
+            }
+            None => plan.explain(&annotate),
+        };
+        if explain.analyze {
+            lines.push(format!(
+                "Execution Time: {:.3} ms",
+                elapsed.as_secs_f64() * 1000.0
+            ));
+        }
+
+        Ok(Results {
+            columns: vec![Column {
+                col_type: ColumnType::TextType,
+                col_name: String::from("QUERY PLAN"),
+            }],
+            rows: lines
+                .into_iter()
+                .map(|line| vec![Value::Text(line).to_cell()])
+                .collect(),
+        })
+    }
+
+    fn update(&mut self, update: &Update) -> Result<Option<Results>, SQLError> {
+        let plan = self.modification_plan(&update.table, update.where_clause.as_ref())?;
+        self.update_scanned(update, &plan, &Profile::default())
+    }
+
+    fn delete(&mut self, delete: &Delete) -> Result<Option<Results>, SQLError> {
+        let plan = self.modification_plan(&delete.table, delete.where_clause.as_ref())?;
+        self.delete_scanned(delete, &plan, &Profile::default())
+    }
+}
+
+impl Default for MemoryBackend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub trait Cell {
+    fn as_text(&self) -> String;
+    fn as_int(&self) -> i64;
+    fn as_bool(&self) -> bool;
+    fn as_float(&self) -> f64;
+    fn as_decimal(&self) -> Decimal;
+    fn as_interval(&self) -> Interval;
+}
+
+#[derive(Debug)]
+pub struct Column {
+    pub col_type: ColumnType,
+    pub col_name: String,
+}
+
+pub type MemCell = Vec<u8>;
+
+impl Cell for MemCell {
+    // Integers are stored little endian in 2, 4 or 8 bytes depending on the
+    // column type, so the width is taken from the length of the cell.
+    fn as_int(&self) -> i64 {
+        let mut cursor = Cursor::new(&self);
+        match self.len() {
+            2 => {
+                let mut buffer = [0; 2];
+                cursor.read_exact(&mut buffer).unwrap();
+                i16::from_le_bytes(buffer) as i64
+            }
+            8 => {
+                let mut buffer = [0; 8];
+                cursor.read_exact(&mut buffer).unwrap();
+                i64::from_le_bytes(buffer)
+            }
+            _ => {
+                let mut buffer = [0; 4]; // Buffer to hold the 4 bytes for the int32
+                cursor.read_exact(&mut buffer).unwrap(); // Read the bytes into the buffer. Might panic
+                i32::from_le_bytes(buffer) as i64
+            }
+        }
+    }
+
+    fn as_text(&self) -> String {
+        String::from_utf8_lossy(self).into_owned()
+    }
+
+    fn as_bool(&self) -> bool {
+        self.first() == Some(&1)
+    }
+
+    // real is stored in 4 bytes and double precision in 8
+    fn as_float(&self) -> f64 {
+        let mut cursor = Cursor::new(&self);
+        if self.len() == 4 {
+            let mut buffer = [0; 4];
+            cursor.read_exact(&mut buffer).unwrap();
+            return f32::from_le_bytes(buffer) as f64;
+        }
+        let mut buffer = [0; 8];
+        cursor.read_exact(&mut buffer).unwrap();
+        f64::from_le_bytes(buffer)
+    }
+
+    // 16 bytes of mantissa followed by 4 bytes of scale
+    fn as_decimal(&self) -> Decimal {
+        let mut cursor = Cursor::new(&self);
+        let mut mantissa = [0; 16];
+        cursor.read_exact(&mut mantissa).unwrap();
+        let mut scale = [0; 4];
+        cursor.read_exact(&mut scale).unwrap();
+        Decimal::new(i128::from_le_bytes(mantissa), u32::from_le_bytes(scale))
+    }
+
+    // 4 bytes of months, 4 of days and 8 of microseconds
+    fn as_interval(&self) -> Interval {
+        let mut cursor = Cursor::new(&self);
+        let mut months = [0; 4];
+        cursor.read_exact(&mut months).unwrap();
+        let mut days = [0; 4];
+        cursor.read_exact(&mut days).unwrap();
+        let mut micros = [0; 8];
+        cursor.read_exact(&mut micros).unwrap();
+        Interval::new(
+            i32::from_le_bytes(months),
+            i32::from_le_bytes(days),
+            i64::from_le_bytes(micros),
+        )
+    }
+}
+
+/// A stored row. `None` cells are NULL.
+pub type Row = Vec<Option<MemCell>>;
+
+pub struct Results {
+    pub columns: Vec<Column>,
+    pub rows: Vec<Row>,
+}
+
+#[derive(Clone)]
+pub struct Table {
+    pub name: String,
+    pub columns: Vec<String>,
+    pub rows: Vec<Row>,
+    pub column_types: Vec<ColumnType>,
+    // Kept up to date with the rows; unique ones enforce their constraint
+    pub indexes: Vec<Index>,
+    // Positions of the columns that cannot be NULL
+    pub not_null: HashSet<usize>,
+    // Values for columns left out of an INSERT, by column position
+    pub defaults: HashMap<usize, Expression>,
+    pub checks: Vec<Check>,
+    pub foreign_keys: Vec<ForeignKey>,
+    // Columns filled from a sequence, by column position
+    pub identities: HashMap<usize, IdentityColumn>,
+}
+
+/// A column filled from a sequence when an INSERT leaves it out.
+#[derive(Clone)]
+pub struct IdentityColumn {
+    pub sequence: String,
+    // Whether the column only takes values from the sequence
+    pub always: bool,
+}
+
+/// A CHECK constraint.
+#[derive(Clone)]
+pub struct Check {
+    pub name: String,
+    pub condition: Expression,
+}
+
+/// A FOREIGN KEY constraint, kept on the referencing table.
+#[derive(Clone)]
+pub struct ForeignKey {
+    pub name: String,
+    pub columns: Vec<usize>,
+    // The referenced table and the positions of its referenced columns
+    pub table: String,
+    pub referenced: Vec<usize>,
+    pub on_delete: ReferentialAction,
+    pub on_update: ReferentialAction,
+}
+
+impl ForeignKey {
+    /// The values a row of `table` references, as the types of the
+    /// referenced columns.
+    fn key(&self, table: &Table, parent: &Table, row: &Row) -> Result<Vec<Value>, SQLError> {
+        let mut key = Vec::new();
+        for (c, r) in self.columns.iter().zip(&self.referenced) {
+            let value = Value::from_cell(&row[*c], &table.column_types[*c]);
+            key.push(cast::cast(
+                &value,
+                &parent.column_types[*r],
+                CastLevel::Assignment,
+            )?);
+        }
+        Ok(key)
+    }
+
+    /// Positions of the rows of `table` that reference `key`, found through
+    /// the index on the foreign key columns.
+    fn referencing_rows(
+        &self,
+        table: &Table,
+        parent: &Table,
+        key: &[Value],
+    ) -> Result<Vec<usize>, SQLError> {
+        let index = table
+            .indexes
+            .iter()
+            .find(|index| index.is_plain() && index.columns == self.columns);
+        let candidates = match index {
+            Some(index) => {
+                // A key the columns cannot hold is referenced by no row
+                let lookup: Option<Vec<Value>> = key
+                    .iter()
+                    .zip(&self.columns)
+                    .map(|(value, c)| index_key_value(value, &table.column_types[*c]))
+                    .collect();
+                match lookup {
+                    Some(lookup) => index.get(&lookup).to_vec(),
+                    None => Vec::new(),
+                }
+            }
+            None => (0..table.rows.len()).collect(),
+        };
+
+        let mut positions = Vec::new();
+        for position in candidates {
+            if self.key(table, parent, &table.rows[position])? == key {
+                positions.push(position);
+            }
+        }
+        positions.sort();
+        Ok(positions)
+    }
+}
+
+impl Table {
+    pub fn new() -> Table {
+        Table {
+            name: String::new(),
+            columns: Vec::new(),
+            column_types: Vec::new(),
+            rows: Vec::new(),
+            indexes: Vec::new(),
+            not_null: HashSet::new(),
+            defaults: HashMap::new(),
+            checks: Vec::new(),
+            foreign_keys: Vec::new(),
+            identities: HashMap::new(),
+        }
+    }
+
+    /// The position of a column. Columns of joined tables are qualified by
+    /// their table, and may be named without it when only one has the name.
+    /// Columns of a single table may be qualified by its name.
+    fn column_position(&self, name: &str) -> Result<usize, SQLError> {
+        if let Some(i) = self.columns.iter().position(|column| column == name) {
+            return Ok(i);
+        }
+        if let Some((table, column)) = name.split_once('.') {
+            if table == self.name {
+                if let Some(i) = self.columns.iter().position(|c| c == column) {
+                    return Ok(i);
+                }
+            }
+        }
+        let mut qualified = self
+            .columns
+            .iter()
+            .enumerate()
+            .filter(|(_, column)| column.rsplit_once('.').is_some_and(|(_, c)| c == name))
+            .map(|(i, _)| i);
+        match (qualified.next(), qualified.next()) {
+            (Some(i), None) => Ok(i),
+            (Some(_), Some(_)) => Err(SQLError::AmbiguousColumn(name.to_string())),
+            (None, _) => Err(SQLError::ColumnDoesNotExist(name.to_string())),
+        }
+    }
+
+    fn is_generated_always(&self, column: usize) -> bool {
+        self.identities
+            .get(&column)
+            .is_some_and(|identity| identity.always)
+    }
+
+    /// The unique index on exactly these columns, in this order.
+    fn unique_index(&self, columns: &[usize]) -> Option<&Index> {
+        self.indexes
+            .iter()
+            .find(|index| index.unique && index.is_plain() && index.columns == columns)
+    }
+
+    /// Checks the keys of a row that is about to be stored against the
+    /// unique indexes. NULLs are never duplicates.
+    fn check_constraints(&self, keys: &IndexKeys) -> Result<(), SQLError> {
+        for (index, key) in self.indexes.iter().zip(keys) {
+            let key = match key {
+                Some(key) if index.unique => key,
+                _ => continue,
+            };
+            if key.contains(&Value::Null) || index.get(key).is_empty() {
+                continue;
+            }
+
+            return Err(SQLError::UniqueViolation(
+                index.name.clone(),
+                index_key_text(self, index, key),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Positions of the columns that assignments write to.
+    fn assignment_targets(&self, assignments: &[Assignment]) -> Result<Vec<usize>, SQLError> {
+        let mut targets = Vec::new();
+        for assignment in assignments {
+            match self
+                .columns
+                .iter()
+                .position(|c| c == &assignment.column.literal)
+            {
+                Some(i) if self.is_generated_always(i) => {
+                    return Err(SQLError::GeneratedAlways(assignment.column.literal.clone()))
+                }
+                Some(i) => targets.push(i),
+                None => {
+                    return Err(SQLError::ColumnDoesNotExist(
+                        assignment.column.literal.clone(),
+                    ))
+                }
+            }
+        }
+        Ok(targets)
+    }
+
+    /// Positions of the unique indexes an ON CONFLICT clause checks: the
+    /// one on exactly its columns, or every one when it names none.
+    fn arbiter_indexes(&self, on_conflict: &OnConflict) -> Result<Vec<usize>, SQLError> {
+        let mut columns = Vec::new();
+        for column in &on_conflict.columns {
+            match self.columns.iter().position(|c| c == &column.literal) {
+                Some(i) => columns.push(i),
+                None => return Err(SQLError::ColumnDoesNotExist(column.literal.clone())),
+            }
+        }
+        columns.sort();
+        columns.dedup();
+
+        let arbiters: Vec<usize> = (0..self.indexes.len())
+            .filter(|i| {
+                let index = &self.indexes[*i];
+                let mut indexed = index.columns.clone();
+                indexed.sort();
+                index.unique && (columns.is_empty() || (index.is_plain() && indexed == columns))
+            })
+            .collect();
+        if arbiters.is_empty() && !columns.is_empty() {
+            return Err(SQLError::InvalidConstraint(String::from(
+                "there is no unique constraint matching the ON CONFLICT columns",
+            )));
+        }
+        Ok(arbiters)
+    }
+
+    /// A row as text, such as `(1, null, abc)`.
+    fn row_text(&self, row: &Row) -> String {
+        let values: Vec<String> = row
+            .iter()
+            .zip(&self.column_types)
+            .map(|(cell, col_type)| match Value::from_cell(cell, col_type) {
+                Value::Null => String::from("null"),
+                value => value_text(&value),
+            })
+            .collect();
+        format!("({})", values.join(", "))
+    }
+
+    /// Positions of the rows found by looking `lookup` up in the index
+    /// called `name`, in table order.
+    fn index_lookup(&self, name: &str, lookup: &IndexLookup) -> Vec<usize> {
+        let index = match self.indexes.iter().find(|index| index.name == name) {
+            Some(index) => index,
+            None => return Vec::new(),
+        };
+        let mut positions = match lookup {
+            IndexLookup::Key(key) => index.get(key).to_vec(),
+            IndexLookup::Range(lower, upper) => index
+                .range(lower.as_ref(), upper.as_ref())
+                .unwrap_or_default(),
+            IndexLookup::All => index.positions(),
+        };
+        positions.sort();
+        positions
+    }
+
+    /// Removes rows by position, returning them with their positions and
+    /// index keys, in table order. The rows after them move down, and the
+    /// indexes with them.
+    fn remove_rows(&mut self, positions: &[usize]) -> Vec<(usize, Row, IndexKeys)> {
+        let mut positions = positions.to_vec();
+        positions.sort();
+        positions.dedup();
+        let keys: Vec<IndexKeys> = positions.iter().map(|p| self.unindex_row(*p)).collect();
+
+        let removed_positions: HashSet<&usize> = positions.iter().collect();
+        let (removed, kept): (Vec<_>, Vec<_>) = std::mem::take(&mut self.rows)
+            .into_iter()
+            .enumerate()
+            .partition(|(position, _)| removed_positions.contains(position));
+
+        for index in &mut self.indexes {
+            index.remove_positions(&positions);
+        }
+        self.rows = kept.into_iter().map(|(_, row)| row).collect();
+        removed
+            .into_iter()
+            .zip(keys)
+            .map(|((position, row), keys)| (position, row, keys))
+            .collect()
+    }
+
+    /// Puts rows taken out by `remove_rows` back where they were.
+    fn restore_removed(&mut self, removed: Vec<(usize, Row, IndexKeys)>) {
+        let positions: Vec<usize> = removed.iter().map(|(position, _, _)| *position).collect();
+        for index in &mut self.indexes {
+            index.insert_positions(&positions);
+        }
+        let mut kept = std::mem::take(&mut self.rows).into_iter();
+        for (position, row, keys) in removed {
+            while self.rows.len() < position {
+                self.rows.push(kept.next().unwrap());
+            }
+            self.index_row(position, keys);
+            self.rows.push(row);
+        }
+        self.rows.extend(kept);
+    }
+
+    fn push_row(&mut self, row: Row, keys: IndexKeys) {
+        self.index_row(self.rows.len(), keys);
+        self.rows.push(row);
+    }
+
+    /// Overwrites rows by position, all or none of them, returning their old
+    /// index keys. Every old key is taken out of the indexes first, so that
+    /// rows may swap key values.
+    fn replace_rows(
+        &mut self,
+        updated: Vec<(usize, Row, IndexKeys)>,
+    ) -> Result<Vec<IndexKeys>, SQLError> {
+        let old_keys: Vec<IndexKeys> = updated
+            .iter()
+            .map(|(position, _, _)| self.unindex_row(*position))
+            .collect();
+
+        for (done, (position, _, keys)) in updated.iter().enumerate() {
+            if let Err(err) = self.check_constraints(keys) {
+                for (position, _, _) in &updated[..done] {
+                    self.unindex_row(*position);
+                }
+                for ((position, _, _), keys) in updated.iter().zip(old_keys) {
+                    self.index_row(*position, keys);
+                }
+                return Err(err);
+            }
+            self.index_row(*position, keys.clone());
+        }
+
+        for (position, row, _) in updated {
+            self.rows[position] = row;
+        }
+        Ok(old_keys)
+    }
+
+    /// Puts rows overwritten by `replace_rows` back as they were.
+    fn restore_replaced(&mut self, replaced: Vec<(usize, Row, IndexKeys)>) {
+        for (position, _, _) in &replaced {
+            self.unindex_row(*position);
+        }
+        for (position, row, keys) in replaced {
+            self.index_row(position, keys);
+            self.rows[position] = row;
+        }
+    }
+
+    fn index_row(&mut self, position: usize, keys: IndexKeys) {
+        for (index, key) in self.indexes.iter_mut().zip(keys) {
+            if let Some(key) = key {
+                index.insert(key, position);
+            }
+        }
+    }
+
+    /// Takes a row out of every index, returning its keys.
+    fn unindex_row(&mut self, position: usize) -> IndexKeys {
+        self.indexes
+            .iter_mut()
+            .map(|index| index.remove(position))
+            .collect()
+    }
+}
+
+/// The key of a row in each index of its table, in order. `None` for a
+/// partial index that leaves the row out.
+type IndexKeys = Vec<Option<Vec<Value>>>;
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    SmallInt(i16),
+    Int(i32),
+    BigInt(i64),
+    // Always holds a value representable as f32
+    Real(Float),
+    Double(Float),
+    Numeric(Decimal),
+    Date(Date),
+    Time(Time),
+    Timestamp(Timestamp),
+    Interval(Interval),
+    Text(String),
+    Blob(Vec<u8>),
+    Json(Json),
+    Uuid(Uuid),
+    // The element type and the elements
+    Array(ColumnType, Vec<Value>),
+    // The position of the label in its enum, which is also its order
+    Enum(Rc<EnumDefinition>, u32),
+}
+
+impl Value {
+    pub fn from_cell(cell: &Option<MemCell>, col_type: &ColumnType) -> Value {
+        let cell = match cell {
+            Some(cell) => cell,
+            None => return Value::Null,
+        };
+        match col_type {
+            ColumnType::SmallIntType => Value::SmallInt(cell.as_int() as i16),
+            ColumnType::IntType => Value::Int(cell.as_int() as i32),
+            ColumnType::BigIntType => Value::BigInt(cell.as_int()),
+            ColumnType::RealType => Value::Real(Float(cell.as_float())),
+            ColumnType::DoubleType => Value::Double(Float(cell.as_float())),
+            ColumnType::NumericType(_) => Value::Numeric(cell.as_decimal()),
+            ColumnType::TextType | ColumnType::VarCharType(_) | ColumnType::CharType(_) => {
+                Value::Text(cell.as_text())
+            }
+            ColumnType::BoolType => Value::Bool(cell.as_bool()),
+            ColumnType::DateType => Value::Date(Date(cell.as_int() as i32)),
+            ColumnType::TimeType => Value::Time(Time(cell.as_int())),
+            ColumnType::TimestampType => Value::Timestamp(Timestamp(cell.as_int())),
+            ColumnType::IntervalType => Value::Interval(cell.as_interval()),
+            ColumnType::BlobType => Value::Blob(cell.clone()),
+            ColumnType::JsonType => Value::Json(Json::parse(&cell.as_text()).unwrap()),
+            ColumnType::UuidType => Value::Uuid(Uuid::from_slice(cell).unwrap()),
+            ColumnType::ArrayType(element) => {
+                let mut reader = Cursor::new(cell);
+                let read_u32 = |reader: &mut Cursor<&MemCell>| {
+                    let mut bytes = [0; 4];
+                    reader.read_exact(&mut bytes).unwrap();
+                    u32::from_le_bytes(bytes)
+                };
+                let count = read_u32(&mut reader);
+                let mut elements = Vec::new();
+                for _ in 0..count {
+                    let mut flag = [0; 1];
+                    reader.read_exact(&mut flag).unwrap();
+                    if flag[0] == 0 {
+                        elements.push(Value::Null);
+                        continue;
+                    }
+                    let mut bytes = vec![0; read_u32(&mut reader) as usize];
+                    reader.read_exact(&mut bytes).unwrap();
+                    elements.push(Value::from_cell(&Some(bytes), element));
+                }
+                Value::Array(*element.clone(), elements)
+            }
+            ColumnType::EnumType(definition) => {
+                Value::Enum(definition.clone(), cell.as_int() as u32)
+            }
+        }
+    }
+
+    pub fn to_cell(&self) -> Option<MemCell> {
+        match self {
+            Value::Null => None,
+            Value::Bool(b) => Some(vec![*b as u8]),
+            Value::SmallInt(i) => Some(i.to_le_bytes().to_vec()),
+            Value::Int(i) => Some(i.to_le_bytes().to_vec()),
+            Value::BigInt(i) => Some(i.to_le_bytes().to_vec()),
+            Value::Real(f) => Some((f.0 as f32).to_le_bytes().to_vec()),
+            Value::Double(f) => Some(f.0.to_le_bytes().to_vec()),
+            Value::Numeric(d) => {
+                let mut cell = d.mantissa().to_le_bytes().to_vec();
+                cell.extend(d.scale().to_le_bytes());
+                Some(cell)
+            }
+            Value::Date(d) => Some(d.0.to_le_bytes().to_vec()),
+            Value::Time(t) => Some(t.0.to_le_bytes().to_vec()),
+            Value::Timestamp(ts) => Some(ts.0.to_le_bytes().to_vec()),
+            Value::Interval(i) => {
+                let mut cell = i.months.to_le_bytes().to_vec();
+                cell.extend(i.days.to_le_bytes());
+                cell.extend(i.micros.to_le_bytes());
+                Some(cell)
+            }
+            Value::Text(s) => Some(s.as_bytes().to_vec()),
+            Value::Blob(b) => Some(b.clone()),
+            Value::Json(json) => Some(json.to_string().into_bytes()),
+            Value::Uuid(uuid) => Some(uuid.0.to_vec()),
+            // The element count, then a NULL flag, length and cell for
+            // every element
+            Value::Array(_, elements) => {
+                let mut cell = (elements.len() as u32).to_le_bytes().to_vec();
+                for element in elements {
+                    match element.to_cell() {
+                        Some(bytes) => {
+                            cell.push(1);
+                            cell.extend((bytes.len() as u32).to_le_bytes());
+                            cell.extend(bytes);
+                        }
+                        None => cell.push(0),
+                    }
+                }
+                Some(cell)
+            }
+            Value::Enum(_, position) => Some(position.to_le_bytes().to_vec()),
+        }
+    }
+
+    /// An integer of the given integer type, or an error when it does not
+    /// fit.
+    pub fn integer(i: i64, col_type: &ColumnType) -> Result<Value, SQLError> {
+        let value = match col_type {
+            ColumnType::SmallIntType => i16::try_from(i).ok().map(Value::SmallInt),
+            ColumnType::IntType => i32::try_from(i).ok().map(Value::Int),
+            ColumnType::BigIntType => Some(Value::BigInt(i)),
+            other => {
+                return Err(SQLError::TypeMismatch(format!(
+                    "{} is not an integer type",
+                    other
+                )))
+            }
+        };
+        value.ok_or_else(|| out_of_range(col_type))
+    }
+
+    /// The value of any integer type, widened to 64 bits.
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Value::SmallInt(i) => Some(*i as i64),
+            Value::Int(i) => Some(*i as i64),
+            Value::BigInt(i) => Some(*i),
+            _ => None,
+        }
+    }
+
+    /// The value of any number type as a float.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Value::Real(f) | Value::Double(f) => Some(f.0),
+            Value::Numeric(d) => Some(d.to_f64()),
+            value => value.as_i64().map(|i| i as f64),
+        }
+    }
+
+    /// The value of an integer or numeric as an exact decimal.
+    pub fn as_decimal(&self) -> Option<Decimal> {
+        match self {
+            Value::Numeric(d) => Some(*d),
+            value => value.as_i64().map(Decimal::from_i64),
+        }
+    }
+
+    /// A real, or an error when the value is too large for one.
+    pub fn real(f: f64) -> Result<Value, SQLError> {
+        let real = f as f32;
+        if real.is_infinite() && f.is_finite() {
+            return Err(out_of_range(&ColumnType::RealType));
+        }
+        Ok(Value::Real(Float(real as f64)))
+    }
+
+    /// Rounds a decimal to the scale of a numeric column and checks that it
+    /// fits the precision.
+    pub fn numeric(d: Decimal, modifiers: Option<(u32, u32)>) -> Result<Value, SQLError> {
+        let (precision, scale) = match modifiers {
+            Some(modifiers) => modifiers,
+            None => return Ok(Value::Numeric(d)),
+        };
+        let overflow = || {
+            SQLError::OutOfRange(format!(
+                "numeric field overflow for numeric({},{})",
+                precision, scale
+            ))
+        };
+        let rounded = d.rescale(scale).ok_or_else(overflow)?;
+        if rounded.whole_digits() > precision - scale {
+            return Err(overflow());
+        }
+        Ok(Value::Numeric(rounded))
+    }
+
+    /// An array of the values, converted to the one type they all convert
+    /// to implicitly. Arrays without elements of a known type are text[].
+    pub fn array(values: Vec<Value>) -> Result<Value, SQLError> {
+        let mut element_type: Option<ColumnType> = None;
+        for col_type in values.iter().filter_map(Value::column_type) {
+            element_type = match element_type {
+                None => Some(col_type),
+                Some(current) if cast::is_implicit(&col_type, &current) => Some(current),
+                Some(current) if cast::is_implicit(&current, &col_type) => Some(col_type),
+                Some(current) => {
+                    return Err(SQLError::TypeMismatch(format!(
+                        "ARRAY types {} and {} cannot be matched",
+                        current, col_type
+                    )))
+                }
+            };
+        }
+
+        let element_type = element_type.unwrap_or(ColumnType::TextType);
+        if let ColumnType::ArrayType(_) = element_type {
+            return Err(SQLError::TypeMismatch(String::from(
+                "multidimensional arrays are not supported",
+            )));
+        }
+        let mut elements = Vec::new();
+        for value in &values {
+            elements.push(cast::cast(value, &element_type, CastLevel::Implicit)?);
+        }
+        Ok(Value::Array(element_type, elements))
+    }
+
+    pub fn from_bool(b: bool) -> Value {
+        Value::Bool(b)
+    }
+
+    /// A predicate result where `None` stands for an unknown (NULL) outcome.
+    pub fn from_option_bool(b: Option<bool>) -> Value {
+        b.map_or(Value::Null, Value::from_bool)
+    }
+
+    /// The outcome of a predicate, `None` when it is NULL.
+    pub fn as_bool(&self) -> Result<Option<bool>, SQLError> {
+        match self {
+            Value::Null => Ok(None),
+            Value::Bool(b) => Ok(Some(*b)),
+            other => Err(SQLError::TypeMismatch(format!(
+                "expected boolean, got {}",
+                other.column_type().unwrap()
+            ))),
+        }
+    }
+
+    /// The type of the value, `None` for NULL which fits any column.
+    pub fn column_type(&self) -> Option<ColumnType> {
+        match self {
+            Value::Null => None,
+            Value::Bool(_) => Some(ColumnType::BoolType),
+            Value::SmallInt(_) => Some(ColumnType::SmallIntType),
+            Value::Int(_) => Some(ColumnType::IntType),
+            Value::BigInt(_) => Some(ColumnType::BigIntType),
+            Value::Real(_) => Some(ColumnType::RealType),
+            Value::Double(_) => Some(ColumnType::DoubleType),
+            Value::Numeric(_) => Some(ColumnType::NumericType(None)),
+            Value::Date(_) => Some(ColumnType::DateType),
+            Value::Time(_) => Some(ColumnType::TimeType),
+            Value::Timestamp(_) => Some(ColumnType::TimestampType),
+            Value::Interval(_) => Some(ColumnType::IntervalType),
+            Value::Text(_) => Some(ColumnType::TextType),
+            Value::Blob(_) => Some(ColumnType::BlobType),
+            Value::Json(_) => Some(ColumnType::JsonType),
+            Value::Uuid(_) => Some(ColumnType::UuidType),
+            Value::Array(element, _) => Some(ColumnType::ArrayType(Box::new(element.clone()))),
+            Value::Enum(definition, _) => Some(ColumnType::EnumType(definition.clone())),
+        }
+    }
+}
+
+impl Default for Table {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn evaluate_literal(
+    token: &Token,
+    table: &Table,
+    row: &[Option<MemCell>],
+) -> Result<Value, SQLError> {
+    match token.token_kind {
+        TokenKind::Identifier => {
+            let i = table.column_position(&token.literal)?;
+            Ok(Value::from_cell(&row[i], &table.column_types[i]))
+        }
+        // Integer literals are int when they fit and bigint otherwise. Like
+        // PostgreSQL, anything else is numeric.
+        TokenKind::Numeric => match token.literal.parse::<i64>() {
+            Ok(i) => Ok(Value::integer(i, &ColumnType::IntType).unwrap_or(Value::BigInt(i))),
+            Err(_) => match Decimal::parse(&token.literal) {
+                Some(d) => Ok(Value::Numeric(d)),
+                None => Err(SQLError::OutOfRange(format!(
+                    "{} is out of range for type numeric",
+                    token.literal
+                ))),
+            },
+        },
+        TokenKind::String => Ok(Value::Text(unquote(&token.literal))),
+        TokenKind::Blob => Ok(Value::Blob(
+            cast::decode_hex(&token.literal[2..token.literal.len() - 1]).unwrap(),
+        )),
+        TokenKind::Keyword if token.literal == Keyword::Null.to_string() => Ok(Value::Null),
+        TokenKind::Keyword if token.literal == Keyword::True.to_string() => Ok(Value::Bool(true)),
+        TokenKind::Keyword if token.literal == Keyword::False.to_string() => Ok(Value::Bool(false)),
+        _ => Err(SQLError::ColumnDoesNotExist(token.literal.clone())),
+    }
+}
+
+fn evaluate_binary(op: &Token, a: &Value, b: &Value) -> Result<Value, SQLError> {
+    // Three-valued logic: NULL is unknown and only decides the result when
+    // the other operand does not
+    match (op.literal.as_str(), a.as_bool(), b.as_bool()) {
+        ("and", Ok(a), Ok(b)) => {
+            return Ok(match (a, b) {
+                (Some(false), _) | (_, Some(false)) => Value::from_bool(false),
+                (Some(true), Some(true)) => Value::from_bool(true),
+                _ => Value::Null,
+            })
+        }
+        ("or", Ok(a), Ok(b)) => {
+            return Ok(match (a, b) {
+                (Some(true), _) | (_, Some(true)) => Value::from_bool(true),
+                (Some(false), Some(false)) => Value::from_bool(false),
+                _ => Value::Null,
+            })
+        }
+        ("and" | "or", Err(e), _) | ("and" | "or", _, Err(e)) => return Err(e),
+        _ => {}
+    }
+
+    if *a == Value::Null || *b == Value::Null {
+        return Ok(Value::Null);
+    }
+
+    match op.literal.as_str() {
+        "+" | "-" | "*" | "/" | "%" => evaluate_arithmetic(&op.literal, a, b),
+        "=" => Ok(Value::from_bool(compare(a, b)? == Ordering::Equal)),
+        "<>" | "!=" => Ok(Value::from_bool(compare(a, b)? != Ordering::Equal)),
+        "<" => Ok(Value::from_bool(compare(a, b)? == Ordering::Less)),
+        "<=" => Ok(Value::from_bool(compare(a, b)? != Ordering::Greater)),
+        ">" => Ok(Value::from_bool(compare(a, b)? == Ordering::Greater)),
+        ">=" => Ok(Value::from_bool(compare(a, b)? != Ordering::Less)),
+        "->" | "->>" => evaluate_json_access(&op.literal, a, b),
+        op => Err(SQLError::TypeMismatch(format!("unknown operator {}", op))),
+    }
+}
+
+/// `json -> key` and `json -> index` pick an object member or an array
+/// element, `->>` returns it as text. Missing ones are NULL.
+fn evaluate_json_access(op: &str, a: &Value, b: &Value) -> Result<Value, SQLError> {
+    let json = match cast::cast(a, &ColumnType::JsonType, CastLevel::Implicit)? {
+        Value::Json(json) => json,
+        _ => unreachable!(),
+    };
+    let step = match b {
+        Value::Text(key) => PathStep::Key(key.clone()),
+        b => match b.as_i64() {
+            Some(i) => PathStep::Index(i),
+            None => {
+                return Err(SQLError::TypeMismatch(format!(
+                    "operator {} expects a text key or an integer index, got {}",
+                    op,
+                    b.column_type().unwrap()
+                )))
+            }
+        },
+    };
+    Ok(match (op, json.step(&step)) {
+        (_, None) => Value::Null,
+        ("->", Some(json)) => Value::Json(json.clone()),
+        (_, Some(json)) => json.as_text().map_or(Value::Null, Value::Text),
+    })
+}
+
+/// Arithmetic in the wider of the two operand types. Results that do not fit
+/// that type are an error rather than wrapping around.
+pub fn evaluate_arithmetic(op: &str, a: &Value, b: &Value) -> Result<Value, SQLError> {
+    if *a == Value::Null || *b == Value::Null {
+        return Ok(Value::Null);
+    }
+    let is_temporal = |value: &Value| value.column_type().is_some_and(|t| t.is_temporal());
+    if is_temporal(a) || is_temporal(b) {
+        return evaluate_datetime_arithmetic(op, a, b);
+    }
+    let (a, b) = cast::unify(a, b)?;
+    let col_type = a.column_type().unwrap();
+    let is_zero = b.as_f64() == Some(0.0);
+    if matches!(op, "/" | "%") && is_zero {
+        return Err(SQLError::DivisionByZero);
+    }
+
+    match col_type {
+        ColumnType::NumericType(_) => {
+            let (x, y) = (a.as_decimal().unwrap(), b.as_decimal().unwrap());
+            let result = match op {
+                "+" => x.checked_add(&y),
+                "-" => x.checked_sub(&y),
+                "*" => x.checked_mul(&y),
+                "/" => x.checked_div(&y),
+                _ => x.checked_rem(&y),
+            };
+            return result
+                .map(Value::Numeric)
+                .ok_or_else(|| out_of_range(&col_type));
+        }
+        ColumnType::RealType | ColumnType::DoubleType => {
+            let (x, y) = (a.as_f64().unwrap(), b.as_f64().unwrap());
+            let result = match op {
+                "+" => x + y,
+                "-" => x - y,
+                "*" => x * y,
+                "/" => x / y,
+                _ => {
+                    return Err(SQLError::TypeMismatch(format!(
+                        "operator % is not defined for {}",
+                        col_type
+                    )))
+                }
+            };
+            if result.is_infinite() && x.is_finite() && y.is_finite() {
+                return Err(out_of_range(&col_type));
+            }
+            return match col_type {
+                ColumnType::RealType => Value::real(result),
+                _ => Ok(Value::Double(Float(result))),
+            };
+        }
+        _ => {}
+    }
+
+    let (x, y) = match (a.as_i64(), b.as_i64()) {
+        (Some(x), Some(y)) => (x, y),
+        _ => {
+            return Err(SQLError::TypeMismatch(format!(
+                "operator {} expects numbers, got {}",
+                op, col_type
+            )))
+        }
+    };
+    let result = match op {
+        "+" => x.checked_add(y),
+        "-" => x.checked_sub(y),
+        "*" => x.checked_mul(y),
+        "/" => x.checked_div(y),
+        _ => x.checked_rem(y),
+    };
+    match result {
+        Some(result) => Value::integer(result, &col_type),
+        None => Err(out_of_range(&col_type)),
+    }
+}
+
+/// Arithmetic on dates and times, which unlike numbers combines operands of
+/// different types. Dates are widened to timestamps when combined with an
+/// interval or a timestamp.
+fn evaluate_datetime_arithmetic(op: &str, a: &Value, b: &Value) -> Result<Value, SQLError> {
+    let widen = |value: &Value| match value {
+        Value::Date(d) => Value::Timestamp(Timestamp::new(*d, Time(0))),
+        value => value.clone(),
+    };
+    let (a, b) = match (a, b) {
+        (Value::Date(_), Value::Interval(_) | Value::Timestamp(_))
+        | (Value::Interval(_) | Value::Timestamp(_), Value::Date(_)) => (widen(a), widen(b)),
+        _ => (a.clone(), b.clone()),
+    };
+    let number = |value: &Value| value.column_type().is_some_and(|t| t.is_number());
+
+    let result = match (op, &a, &b) {
+        ("+", Value::Date(d), n) | ("+", n, Value::Date(d)) if n.as_i64().is_some() => {
+            d.checked_add_days(n.as_i64().unwrap()).map(Value::Date)
+        }
+        ("-", Value::Date(d), n) if n.as_i64().is_some() => {
+            d.checked_add_days(-n.as_i64().unwrap()).map(Value::Date)
+        }
+        ("-", Value::Date(x), Value::Date(y)) => Some(Value::Int(x.0 - y.0)),
+        ("+", Value::Date(d), Value::Time(t)) | ("+", Value::Time(t), Value::Date(d)) => {
+            Some(Value::Timestamp(Timestamp::new(*d, *t)))
+        }
+        ("+", Value::Timestamp(ts), Value::Interval(i))
+        | ("+", Value::Interval(i), Value::Timestamp(ts)) => {
+            ts.checked_add(i).map(Value::Timestamp)
+        }
+        ("-", Value::Timestamp(ts), Value::Interval(i)) => i
+            .checked_neg()
+            .and_then(|i| ts.checked_add(&i))
+            .map(Value::Timestamp),
+        ("-", Value::Timestamp(x), Value::Timestamp(y)) => x.checked_sub(y).map(Value::Interval),
+        ("+", Value::Time(t), Value::Interval(i)) | ("+", Value::Interval(i), Value::Time(t)) => {
+            Some(Value::Time(t.wrapping_add_micros(i.micros)))
+        }
+        ("-", Value::Time(t), Value::Interval(i)) => {
+            Some(Value::Time(t.wrapping_add_micros(-i.micros)))
+        }
+        ("-", Value::Time(x), Value::Time(y)) => {
+            Some(Value::Interval(Interval::new(0, 0, x.0 - y.0)))
+        }
+        ("+", Value::Interval(x), Value::Interval(y)) => x.checked_add(y).map(Value::Interval),
+        ("-", Value::Interval(x), Value::Interval(y)) => x.checked_sub(y).map(Value::Interval),
+        ("*", Value::Interval(i), n) | ("*", n, Value::Interval(i)) if number(n) => {
+            i.checked_mul(n.as_f64().unwrap()).map(Value::Interval)
+        }
+        ("/", Value::Interval(i), n) if number(n) => {
+            if n.as_f64() == Some(0.0) {
+                return Err(SQLError::DivisionByZero);
+            }
+            i.checked_div(n.as_f64().unwrap()).map(Value::Interval)
+        }
+        _ => {
+            return Err(SQLError::TypeMismatch(format!(
+                "operator {} is not defined for {} and {}",
+                op,
+                a.column_type().unwrap(),
+                b.column_type().unwrap()
+            )))
+        }
+    };
+
+    // Every case that can fail keeps the type of its temporal operand
+    let col_type = match (&a, &b) {
+        (Value::Interval(_), Value::Timestamp(_)) => ColumnType::TimestampType,
+        (Value::Timestamp(_), Value::Timestamp(_)) => ColumnType::IntervalType,
+        (a, _) if a.column_type().unwrap().is_temporal() => a.column_type().unwrap(),
+        (_, b) => b.column_type().unwrap(),
+    };
+    result.ok_or_else(|| out_of_range(&col_type))
+}
+
+pub fn out_of_range(col_type: &ColumnType) -> SQLError {
+    SQLError::OutOfRange(format!("{} out of range", col_type))
+}
+
+fn compare(a: &Value, b: &Value) -> Result<Ordering, SQLError> {
+    let (a, b) = cast::unify(a, b)?;
+    Ok(a.cmp(&b))
+}
+
+/// The order used for sorting: like `compare`, with NULLs after every other
+/// value and values that cannot be compared ordered by type.
+pub fn sort_order(a: &Value, b: &Value) -> Ordering {
+    match (a, b) {
+        (Value::Null, Value::Null) => Ordering::Equal,
+        (Value::Null, _) => Ordering::Greater,
+        (_, Value::Null) => Ordering::Less,
+        _ => compare(a, b).unwrap_or_else(|_| a.cmp(b)),
+    }
+}
+
+/// Compares ORDER BY keys term by term. Like PostgreSQL, NULLs come last in
+/// ascending order and first in descending order.
+pub fn compare_keys(a: &[Value], b: &[Value], descending: &[bool]) -> Ordering {
+    for ((x, y), desc) in a.iter().zip(b).zip(descending) {
+        let ordering = sort_order(x, y);
+        if ordering != Ordering::Equal {
+            return if *desc { ordering.reverse() } else { ordering };
+        }
+    }
+    Ordering::Equal
+}
+
+/// `value` as it is hashed in an IN list, integers widened to bigint.
+fn in_set_key(value: &Value) -> Value {
+    match value {
+        Value::SmallInt(_) | Value::Int(_) => Value::BigInt(value.as_i64().unwrap()),
+        _ => value.clone(),
+    }
+}
+
+/// Window function calls within `exp`, innermost first.
+fn collect_windows<'a>(exp: &'a Expression, windows: &mut Vec<&'a Expression>) {
+    for child in exp.children() {
+        collect_windows(child, windows);
+    }
+    if exp.kind == ExpressionKind::Window {
+        windows.push(exp);
+    }
+}
+
+/// The columns an expression refers to.
+pub fn column_references<'a>(exp: &'a Expression, columns: &mut Vec<&'a str>) {
+    if exp.kind == ExpressionKind::Literal && exp.literal.token_kind == TokenKind::Identifier {
+        columns.push(&exp.literal.literal);
+    }
+    for child in exp.children() {
+        column_references(child, columns);
+    }
+}
+
+/// Key values with their column names, such as `(a, b)=(1, x)`.
+/// A key of an index as text, naming expressions after their function.
+fn index_key_text(table: &Table, index: &Index, key: &[Value]) -> String {
+    if index.expressions.is_empty() {
+        return key_text(table, &index.columns, key);
+    }
+    let names: Vec<String> = index.expressions.iter().map(expression_name).collect();
+    let values: Vec<String> = key.iter().map(value_text).collect();
+    format!("({})=({})", names.join(", "), values.join(", "))
+}
+
+fn key_text(table: &Table, columns: &[usize], key: &[Value]) -> String {
+    let columns: Vec<&str> = columns.iter().map(|i| table.columns[*i].as_str()).collect();
+    let values: Vec<String> = key.iter().map(value_text).collect();
+    format!("({})=({})", columns.join(", "), values.join(", "))
+}
+
+/// `rows` scaled by `selectivity`, at least one unless there are no rows.
+fn estimate(rows: usize, selectivity: f64) -> usize {
+    match rows {
+        0 => 0,
+        _ => ((rows as f64 * selectivity).round() as usize).max(1),
+    }
+}
+
+/// A value as a SQL literal, quoted unless it is a number or a boolean.
+fn value_literal(value: &Value) -> String {
+    match value {
+        Value::Null => String::from("null"),
+        Value::Bool(_)
+        | Value::SmallInt(_)
+        | Value::Int(_)
+        | Value::BigInt(_)
+        | Value::Real(_)
+        | Value::Double(_)
+        | Value::Numeric(_) => value_text(value),
+        _ => format!("'{}'", value_text(value).replace('\'', "''")),
+    }
+}
+
+/// A value the way it is cast to text, for error messages.
+fn value_text(value: &Value) -> String {
+    match cast::cast(value, &ColumnType::TextType, CastLevel::Explicit) {
+        Ok(Value::Text(text)) => text,
+        _ => format!("{:?}", value),
+    }
+}
+
+/// Whether an expression evaluates to the same value for every row.
+pub fn is_constant(exp: &Expression) -> bool {
+    match exp.kind {
+        ExpressionKind::Literal => exp.literal.token_kind != TokenKind::Identifier,
+        ExpressionKind::Function | ExpressionKind::Window => false,
+        _ => exp.children().into_iter().all(is_constant),
+    }
+}
+
+/// A comparison of an expression with a constant, which an index on the
+/// expression can answer.
+struct IndexCondition<'a> {
+    target: &'a Expression,
+    // One of =, <, <=, > and >=, with the target on the left
+    op: &'static str,
+    value: Value,
+}
+
+/// The conditions ANDed together in `exp`.
+fn conjuncts(exp: &Expression) -> Vec<&Expression> {
+    match exp.kind {
+        ExpressionKind::Binary if exp.literal.literal == Keyword::And.to_string() => {
+            let binary = exp.binary.as_ref().unwrap();
+            let mut found = conjuncts(&binary.a);
+            found.extend(conjuncts(&binary.b));
+            found
+        }
+        _ => vec![exp],
+    }
+}
+
+/// `value` as a key of a column of type `col_type`, when converting it loses
+/// nothing. Otherwise an index on the column cannot look it up.
+fn index_key_value(value: &Value, col_type: &ColumnType) -> Option<Value> {
+    if *value == Value::Null {
+        return None;
+    }
+    let value = match col_type {
+        ColumnType::CharType(_) => &cast::without_padding(value.clone()),
+        _ => value,
+    };
+    let converted = cast::cast(value, col_type, CastLevel::Assignment).ok()?;
+    match compare(&converted, value) {
+        Ok(Ordering::Equal) => Some(converted),
+        _ => None,
+    }
+}
+
+/// Names and types the result columns of `items`, whose values are `values`.
+fn result_columns(items: &[Expression], values: &[Vec<Value>], table: &Table) -> Vec<Column> {
+    let mut columns = Vec::new();
+    for (i, exp) in items.iter().enumerate() {
+        // Values do not carry numeric modifiers nor tell char(n) from text,
+        // so the declared type is kept when it has them
+        let col_type = match values.iter().find_map(|result| result[i].column_type()) {
+            Some(ColumnType::NumericType(_)) => match expression_type(exp, table) {
+                col_type @ ColumnType::NumericType(_) => col_type,
+                _ => ColumnType::NumericType(None),
+            },
+            Some(ColumnType::TextType) => match expression_type(exp, table) {
+                col_type @ ColumnType::CharType(_) => col_type,
+                _ => ColumnType::TextType,
+            },
+            Some(col_type) => col_type,
+            None => expression_type(exp, table),
+        };
+        columns.push(Column {
+            col_type,
+            col_name: expression_name(exp),
+        });
+    }
+    columns
+}
+
+/// The cells of result rows, with char(n) values padded to their length.
+fn result_rows(columns: &[Column], values: Vec<Vec<Value>>) -> Vec<Row> {
+    values
+        .into_iter()
+        .map(|result| {
+            result
+                .into_iter()
+                .zip(columns)
+                .map(|(value, column)| cast::pad_char(value, &column.col_type).to_cell())
+                .collect()
+        })
+        .collect()
+}
+
+fn expression_name(exp: &Expression) -> String {
+    match exp.kind {
+        // Qualified columns are named without the qualifier
+        ExpressionKind::Literal if exp.literal.token_kind == TokenKind::Identifier => {
+            match exp.literal.literal.rsplit_once('.') {
+                Some((_, column)) => column.to_string(),
+                None => exp.literal.literal.clone(),
+            }
+        }
+        ExpressionKind::Literal | ExpressionKind::Function | ExpressionKind::Window => {
+            exp.literal.literal.clone()
+        }
+        // Typed literals such as DATE '2024-03-04' are named after the type
+        ExpressionKind::Cast => {
+            let cast = exp.cast.as_ref().unwrap();
+            match cast.value.literal.token_kind {
+                TokenKind::String if cast.value.kind == ExpressionKind::Literal => {
+                    cast.data_type.name.literal.clone()
+                }
+                _ => expression_name(&cast.value),
+            }
+        }
+        _ => String::from("?column?"),
+    }
+}
+
+fn is_comparison(op: &Token) -> bool {
+    op.token_kind == TokenKind::Symbol
+        && matches!(
+            op.literal.as_str(),
+            "=" | "<>" | "!=" | "<" | "<=" | ">" | ">="
+        )
+}
+
+/// Whether `exp` is of type char(n), which ignores trailing spaces when
+/// compared.
+fn is_char(exp: &Expression, table: &Table) -> bool {
+    matches!(expression_type(exp, table), ColumnType::CharType(_))
+}
+
+fn is_arithmetic(op: &Token) -> bool {
+    op.token_kind == TokenKind::Symbol && matches!(op.literal.as_str(), "+" | "-" | "*" | "/" | "%")
+}
+
+/// Strips the delimiters of a string token and collapses doubled delimiters.
+fn unquote(literal: &str) -> String {
+    let delimiter = match literal.chars().next() {
+        Some(c) => c.to_string(),
+        None => return String::new(),
+    };
+    literal[1..literal.len() - 1].replace(&delimiter.repeat(2), &delimiter)
+}
+
+/// Best effort type of a result column when there are no rows to look at.
+fn expression_type(exp: &Expression, table: &Table) -> ColumnType {
+    if exp.kind == ExpressionKind::Literal && exp.literal.token_kind == TokenKind::Identifier {
+        if let Ok(i) = table.column_position(&exp.literal.literal) {
+            return table.column_types[i].clone();
+        }
+    }
+    if exp.kind == ExpressionKind::Literal && exp.literal.token_kind == TokenKind::Numeric {
+        return match (
+            exp.literal.literal.parse::<i32>(),
+            exp.literal.literal.parse::<i64>(),
+        ) {
+            (Ok(_), _) => ColumnType::IntType,
+            (_, Ok(_)) => ColumnType::BigIntType,
+            _ => ColumnType::NumericType(None),
+        };
+    }
+    if exp.kind == ExpressionKind::Cast {
+        if let Ok(col_type) = ColumnType::from_data_type(&exp.cast.as_ref().unwrap().data_type) {
+            return col_type;
+        }
+    }
+    if exp.kind == ExpressionKind::Literal
+        && (exp.literal.literal == Keyword::True.to_string()
+            || exp.literal.literal == Keyword::False.to_string())
+    {
+        return ColumnType::BoolType;
+    }
+    match exp.kind {
+        ExpressionKind::Array => {
+            let element = match exp.array.as_ref().unwrap().first() {
+                Some(element) => expression_type(element, table),
+                None => ColumnType::TextType,
+            };
+            ColumnType::ArrayType(Box::new(element))
+        }
+        ExpressionKind::Subscript => {
+            match expression_type(&exp.subscript.as_ref().unwrap().value, table) {
+                ColumnType::ArrayType(element) => *element,
+                _ => ColumnType::TextType,
+            }
+        }
+        ExpressionKind::Binary if exp.literal.literal == "->" => ColumnType::JsonType,
+        ExpressionKind::Binary if exp.literal.literal == "->>" => ColumnType::TextType,
+        ExpressionKind::Binary if is_arithmetic(&exp.literal) => {
+            expression_type(&exp.binary.as_ref().unwrap().a, table)
+        }
+        ExpressionKind::Unary if is_arithmetic(&exp.literal) => {
+            expression_type(&exp.unary.as_ref().unwrap().operand, table)
+        }
+        ExpressionKind::Binary
+        | ExpressionKind::Unary
+        | ExpressionKind::PatternMatch
+        | ExpressionKind::InList
+        | ExpressionKind::Between
+        | ExpressionKind::IsNull
+        | ExpressionKind::Quantified => ColumnType::BoolType,
+        _ => ColumnType::TextType,
+    }
+}
+
+#[cfg(test)]
+mod mem_backend_test {
+    use crate::ast::StatementKind;
+    use crate::cast::{self, CastLevel};
+    use crate::json::Json;
+    use crate::mem_backend::{
+        Backend, Cell, ColumnType, MemCell, MemoryBackend, Results, SQLError, Value,
+    };
+    use crate::numeric::Decimal;
+    use crate::parser::parse;
+    use crate::uuid::Uuid;
+    use std::collections::HashSet;
+
+    fn execute(mb: &mut MemoryBackend, source: &str) -> Result<Option<Results>, SQLError> {
+        let ast = parse(source.to_string()).expect("failed to parse");
+        let mut results = None;
+        for statement in ast.statements {
+            match statement.kind {
+                StatementKind::Create => mb.create(&statement.create.unwrap())?,
+                StatementKind::Insert => results = mb.insert(&statement.insert.unwrap())?,
+                StatementKind::Select => results = Some(mb.select(&statement.select.unwrap())?),
+                StatementKind::Update => results = mb.update(&statement.update.unwrap())?,
+                StatementKind::Delete => results = mb.delete(&statement.delete.unwrap())?,
+                StatementKind::CreateType => mb.create_type(&statement.create_type.unwrap())?,
+                StatementKind::CreateSequence => {
+                    mb.create_sequence(&statement.create_sequence.unwrap())?
+                }
+                StatementKind::CreateIndex => mb.create_index(&statement.create_index.unwrap())?,
+                StatementKind::DropIndex => mb.drop_index(&statement.drop_index.unwrap())?,
+                StatementKind::Explain => results = Some(mb.explain(&statement.explain.unwrap())?),
+            }
+        }
+        Ok(results)
+    }
+
+    #[test]
+    fn test_builtin_functions() {
+        let mut mb = MemoryBackend::new();
+        execute(
+            &mut mb,
+            "create table users (id int, name text); insert into users values (1, 'Ann'); insert into users values (2, 'bob');",
+        )
+        .unwrap();
+
+        let results = execute(&mut mb, "select upper(name), length(name) from users;")
+            .unwrap()
+            .unwrap();
+        assert_eq!(results.columns[0].col_name, "upper");
+        assert_eq!(results.rows[0][0].as_ref().unwrap().as_text(), "ANN");
+        assert_eq!(results.rows[1][1].as_ref().unwrap().as_int(), 3);
+
+        let results = execute(&mut mb, "select count(*), sum(id), max(name) from users;")
+            .unwrap()
+            .unwrap();
+        assert_eq!(results.rows.len(), 1);
+        assert_eq!(results.rows[0][0].as_ref().unwrap().as_int(), 2);
+        assert_eq!(results.rows[0][1].as_ref().unwrap().as_int(), 3);
+        assert_eq!(results.rows[0][2].as_ref().unwrap().as_text(), "bob");
+
+        assert!(execute(&mut mb, "select id, count(*) from users;").is_err());
+        assert!(execute(&mut mb, "select nope(id) from users;").is_err());
+    }
+
+    #[test]
+    fn test_user_defined_functions() {
+        let mut mb = MemoryBackend::new();
+        mb.register_scalar_function("double", |args| match args {
+            [Value::Int(i)] => Ok(Value::Int(i * 2)),
+            _ => Err(SQLError::InvalidFunctionArguments(String::from("double"))),
+        });
+        mb.register_aggregate_function(
+            "concat_all",
+            Vec::new,
+            |parts: &mut Vec<String>, args| {
+                if let [Value::Text(s)] = args {
+                    parts.push(s.clone());
+                }
+                Ok(())
+            },
+            |parts| Ok(Value::Text(parts.join(","))),
+        );
+        execute(
+            &mut mb,
+            "create table t (n int, s text); insert into t values (double(2), 'a'); insert into t values (5, 'b');",
+        )
+        .unwrap();
+
+        let results = execute(&mut mb, "select double(n) from t;")
+            .unwrap()
+            .unwrap();
+        assert_eq!(results.rows[0][0].as_ref().unwrap().as_int(), 8);
+        assert_eq!(results.rows[1][0].as_ref().unwrap().as_int(), 10);
+
+        let results = execute(&mut mb, "select concat_all(upper(s)) from t;")
+            .unwrap()
+            .unwrap();
+        assert_eq!(results.rows[0][0].as_ref().unwrap().as_text(), "A,B");
+    }
+
+    #[test]
+    fn test_pattern_matching() {
+        let mut mb = MemoryBackend::new();
+        execute(
+            &mut mb,
+            "create table users (name text, notes text); insert into users values ('Alice', '100% done'); insert into users values ('bob', 'pending'); insert into users values ('Carol', 'done');",
+        )
+        .unwrap();
+
+        let tests = vec![
+            (
+                "select name from users where name like 'A%';",
+                vec!["Alice"],
+            ),
+            (
+                "select name from users where name ilike '%o%';",
+                vec!["bob", "Carol"],
+            ),
+            (
+                "select name from users where name not like '_o%';",
+                vec!["Alice", "Carol"],
+            ),
+            (
+                "select name from users where notes like '%\\%%';",
+                vec!["Alice"],
+            ),
+            (
+                "select name from users where notes like '%!%%' escape '!';",
+                vec!["Alice"],
+            ),
+            (
+                "select name from users where name glob '[A-Z]*';",
+                vec!["Alice", "Carol"],
+            ),
+            ("select name from users where name ~ '^[ab]';", vec!["bob"]),
+            (
+                "select name from users where name ~* '^[ab]';",
+                vec!["Alice", "bob"],
+            ),
+            (
+                "select name from users where name !~ 'o' and not notes regexp 'ing$';",
+                vec!["Alice"],
+            ),
+            (
+                "select name from users where name = 'bob' or (notes = 'done' and name <> 'x');",
+                vec!["bob", "Carol"],
+            ),
+        ];
+
+        for (source, expected) in tests {
+            let results = execute(&mut mb, source).unwrap().unwrap();
+            let names: Vec<String> = results
+                .rows
+                .iter()
+                .map(|row| row[0].as_ref().unwrap().as_text())
+                .collect();
+            assert_eq!(names, expected, "{}", source);
+        }
+
+        assert!(execute(&mut mb, "select name from users where name ~ '(';").is_err());
+        assert!(execute(&mut mb, "select name from users where name like 1;").is_err());
+    }
+
+    #[test]
+    fn test_in_and_between() {
+        let mut mb = MemoryBackend::new();
+        execute(
+            &mut mb,
+            "create table items (id int, name text); insert into items values (1, 'a'); insert into items values (2, 'b'); insert into items values (3, 'c'); insert into items values (4, 'd');",
+        )
+        .unwrap();
+
+        let tests = vec![
+            ("select id from items where id in (1, 3, 5);", vec![1, 3]),
+            ("select id from items where id not in (1, 3);", vec![2, 4]),
+            (
+                "select id from items where name in ('b', upper('d'), lower('D'));",
+                vec![2, 4],
+            ),
+            ("select id from items where id in (id);", vec![1, 2, 3, 4]),
+            ("select id from items where id between 2 and 3;", vec![2, 3]),
+            (
+                "select id from items where id not between 2 and 3 and id <> 4;",
+                vec![1],
+            ),
+            (
+                "select id from items where name between 'b' and 'c' or id = 1;",
+                vec![1, 2, 3],
+            ),
+        ];
+
+        for (source, expected) in tests {
+            let results = execute(&mut mb, source).unwrap().unwrap();
+            let ids: Vec<i64> = results
+                .rows
+                .iter()
+                .map(|row| row[0].as_ref().unwrap().as_int())
+                .collect();
+            assert_eq!(ids, expected, "{}", source);
+        }
+
+        assert!(execute(&mut mb, "select id from items where id in ('a');").is_err());
+
+        execute(
+            &mut mb,
+            "create table wide (b bigint, n numeric(4, 1)); insert into wide values (1, 1.5); insert into wide values (3000000000, 2); insert into wide values (7, 3.0);",
+        )
+        .unwrap();
+        let tests = vec![
+            ("select b from wide where b in (1, 7);", vec![1, 7]),
+            (
+                "select b from wide where b in (3000000000, 2::smallint);",
+                vec![3000000000],
+            ),
+            ("select b from wide where b in (7, 1.5);", vec![7]),
+            (
+                "select b from wide where n in (2, 1.5);",
+                vec![1, 3000000000],
+            ),
+            ("select b from wide where n in (3, null);", vec![7]),
+            ("select b from wide where n not in (3, null);", vec![]),
+        ];
+        for (source, expected) in tests {
+            let results = execute(&mut mb, source).unwrap().unwrap();
+            let values: Vec<i64> = results
+                .rows
+                .iter()
+                .map(|row| row[0].as_ref().unwrap().as_int())
+                .collect();
+            assert_eq!(values, expected, "{}", source);
+        }
+    }
+
+    #[test]
+    fn test_cast() {
+        let mut mb = MemoryBackend::new();
+        execute(
+            &mut mb,
+            "create table t (id int, code text); insert into t values ('7', 10); insert into t values (8, '0042');",
+        )
+        .unwrap();
+
+        let results = execute(&mut mb, "select id, code from t;")
+            .unwrap()
+            .unwrap();
+        assert_eq!(results.rows[0][0].as_ref().unwrap().as_int(), 7);
+        assert_eq!(results.rows[0][1].as_ref().unwrap().as_text(), "10");
+
+        let results = execute(
+            &mut mb,
+            "select cast(code as int), id::text from t where id = '8';",
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(results.columns[0].col_name, "code");
+        assert_eq!(results.columns[0].col_type, ColumnType::IntType);
+        assert_eq!(results.rows[0][0].as_ref().unwrap().as_int(), 42);
+        assert_eq!(results.rows[0][1].as_ref().unwrap().as_text(), "8");
+
+        let results = execute(&mut mb, "select id from t where code::int in (10, 42);")
+            .unwrap()
+            .unwrap();
+        assert_eq!(results.rows.len(), 2);
+
+        assert!(matches!(
+            execute(&mut mb, "select 'abc'::int;"),
+            Err(SQLError::InvalidCast(_))
+        ));
+        assert!(execute(&mut mb, "insert into t values ('x', 'y');").is_err());
+    }
+
+    fn values(results: &Results) -> Vec<Vec<Value>> {
+        results
+            .rows
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .zip(&results.columns)
+                    .map(|(cell, column)| Value::from_cell(cell, &column.col_type))
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_null() {
+        let mut mb = MemoryBackend::new();
+        execute(
+            &mut mb,
+            "create table t (id int, name text); insert into t values (1, 'a'); insert into t values (2, null); insert into t values (null, 'c');",
+        )
+        .unwrap();
+
+        let tests = vec![
+            (
+                "select id from t where name is null;",
+                vec![vec![Value::Int(2)]],
+            ),
+            (
+                "select name from t where id is not null and name is not null;",
+                vec![vec![Value::Text(String::from("a"))]],
+            ),
+            (
+                "select id from t where id > 1 or name = 'x';",
+                vec![vec![Value::Int(2)]],
+            ),
+            ("select id from t where not (id in (1, null));", vec![]),
+            (
+                "select count(*), count(id), sum(id), min(name) from t;",
+                vec![vec![
+                    Value::BigInt(3),
+                    Value::BigInt(2),
+                    Value::BigInt(3),
+                    Value::Text(String::from("a")),
+                ]],
+            ),
+            (
+                "select sum(id), max(name) from t where id > 5;",
+                vec![vec![Value::Null, Value::Null]],
+            ),
+            (
+                "select upper(name), null = null, null::int from t where id = 2;",
+                vec![vec![Value::Null, Value::Null, Value::Null]],
+            ),
+        ];
+
+        for (source, expected) in tests {
+            let results = execute(&mut mb, source).unwrap().unwrap();
+            assert_eq!(values(&results), expected, "{}", source);
+        }
+    }
+
+    #[test]
+    fn test_integer_types() {
+        let mut mb = MemoryBackend::new();
+        execute(
+            &mut mb,
+            "create table nums (s smallint, i integer, b bigint); insert into nums values (1, 2, 3); insert into nums values (-32768, 2147483647, 9223372036854775807);",
+        )
+        .unwrap();
+
+        let results = execute(&mut mb, "select s, i, b from nums;")
+            .unwrap()
+            .unwrap();
+        let types: Vec<ColumnType> = results.columns.iter().map(|c| c.col_type.clone()).collect();
+        assert_eq!(
+            types,
+            vec![
+                ColumnType::SmallIntType,
+                ColumnType::IntType,
+                ColumnType::BigIntType
+            ]
+        );
+        assert_eq!(
+            values(&results)[1],
+            vec![
+                Value::SmallInt(-32768),
+                Value::Int(2147483647),
+                Value::BigInt(9223372036854775807)
+            ]
+        );
+
+        let tests = vec![
+            ("select 1 + 2 * 3;", Value::Int(7)),
+            ("select (1 + 2) * 3 - -1;", Value::Int(10)),
+            ("select 7 / 2, 7 % 2;", Value::Int(3)),
+            ("select s + i from nums where b = 3;", Value::Int(3)),
+            ("select s * b from nums where s = 1;", Value::BigInt(3)),
+            ("select 3000000000;", Value::BigInt(3000000000)),
+            ("select '40' + 2;", Value::Int(42)),
+            ("select sum(b - 1) from nums where i = 2;", Value::BigInt(2)),
+        ];
+        for (source, expected) in tests {
+            let results = execute(&mut mb, source).unwrap().unwrap();
+            assert_eq!(values(&results)[0][0], expected, "{}", source);
+        }
+
+        let errors = vec![
+            "select i + 1 from nums where s < 0;",
+            "select -s from nums where s < 0;",
+            "select b * 2 from nums where s < 0;",
+            "select sum(b) from nums;",
+            "insert into nums values (32768, 1, 1);",
+            "select 99999999999999999999::bigint;",
+        ];
+        for source in errors {
+            assert!(
+                matches!(execute(&mut mb, source), Err(SQLError::OutOfRange(_))),
+                "{}",
+                source
+            );
+        }
+        assert!(matches!(
+            execute(&mut mb, "select 1 / 0;"),
+            Err(SQLError::DivisionByZero)
+        ));
+        assert!(matches!(
+            execute(&mut mb, "select 'a' + 'b';"),
+            Err(SQLError::TypeMismatch(_))
+        ));
+    }
+
+    #[test]
+    fn test_numeric_types() {
+        let mut mb = MemoryBackend::new();
+        execute(
+            &mut mb,
+            "create table prices (amount numeric(10, 2), ratio real, score double precision); insert into prices values (12.345, 0.5, 1e10); insert into prices values (-0.005, .25, 2.5E-3);",
+        )
+        .unwrap();
+
+        let results = execute(&mut mb, "select amount, ratio, score from prices;")
+            .unwrap()
+            .unwrap();
+        let types: Vec<ColumnType> = results.columns.iter().map(|c| c.col_type.clone()).collect();
+        assert_eq!(
+            types,
+            vec![
+                ColumnType::NumericType(Some((10, 2))),
+                ColumnType::RealType,
+                ColumnType::DoubleType
+            ]
+        );
+        let amounts: Vec<String> = results
+            .rows
+            .iter()
+            .map(|row| row[0].as_ref().unwrap().as_decimal().to_string())
+            .collect();
+        assert_eq!(amounts, vec!["12.35", "-0.01"]);
+        assert_eq!(results.rows[0][1].as_ref().unwrap().as_float(), 0.5);
+        assert_eq!(results.rows[0][2].as_ref().unwrap().as_float(), 1e10);
+
+        let tests = vec![
+            ("select 0.1 + 0.2 = 0.3;", "true"),
+            ("select 1.5 * 2;", "3.0"),
+            ("select 1 / 3.0;", "0.3333333333333333"),
+            ("select 2.5::int, 2.5::double precision::int;", "3"),
+            ("select cast(3.14159 as numeric(4, 2));", "3.14"),
+            ("select '1.5'::real + 1;", "2.5"),
+            ("select '1e300'::float8 * 10;", "1e+301"),
+            ("select sum(amount) from prices;", "12.34"),
+            ("select sum(ratio) from prices;", "0.75"),
+            ("select round(2.675, 2);", "2.68"),
+            ("select round(-2.5);", "-3"),
+            ("select abs(-1.25);", "1.25"),
+            ("select max(score)::text from prices;", "10000000000"),
+        ];
+        for (source, expected) in tests {
+            let results = execute(&mut mb, source).unwrap().unwrap();
+            let value = cast::cast(
+                &values(&results)[0][0],
+                &ColumnType::TextType,
+                CastLevel::Explicit,
+            )
+            .unwrap();
+            assert_eq!(value, Value::Text(expected.to_string()), "{}", source);
+        }
+
+        let errors = vec![
+            "insert into prices values (123456789.5, 1, 1);",
+            "select 1e39::real;",
+            "select '1e300'::float8 * '1e300'::float8;",
+        ];
+        for source in errors {
+            assert!(
+                matches!(execute(&mut mb, source), Err(SQLError::OutOfRange(_))),
+                "{}",
+                source
+            );
+        }
+        assert!(matches!(
+            execute(&mut mb, "select 1.0 / 0;"),
+            Err(SQLError::DivisionByZero)
+        ));
+        assert!(matches!(
+            execute(&mut mb, "select 1.5::float % 1;"),
+            Err(SQLError::TypeMismatch(_))
+        ));
+        assert!(matches!(
+            execute(&mut mb, "create table bad (n numeric(2, 3));"),
+            Err(SQLError::InvalidDataType(_))
+        ));
+    }
+
+    #[test]
+    fn test_order_by() {
+        let mut mb = MemoryBackend::new();
+        execute(
+            &mut mb,
+            "create table scores (name text, score int); insert into scores values ('b', 20); insert into scores values ('a', null); insert into scores values ('c', 10); insert into scores values ('d', 20);",
+        )
+        .unwrap();
+
+        let tests = vec![
+            (
+                "select name from scores order by score, name;",
+                vec!["c", "b", "d", "a"],
+            ),
+            (
+                "select name from scores order by score desc, name desc;",
+                vec!["a", "d", "b", "c"],
+            ),
+            (
+                "select name, score from scores order by 2, 1 desc;",
+                vec!["c", "d", "b", "a"],
+            ),
+            (
+                "select name from scores where score > 5 order by -score, name;",
+                vec!["b", "d", "c"],
+            ),
+            (
+                "select name from scores order by row_number() over (order by name) desc;",
+                vec!["d", "c", "b", "a"],
+            ),
+        ];
+        for (source, expected) in tests {
+            let results = execute(&mut mb, source).unwrap().unwrap();
+            let names: Vec<String> = results
+                .rows
+                .iter()
+                .map(|row| row[0].as_ref().unwrap().as_text())
+                .collect();
+            assert_eq!(names, expected, "{}", source);
+        }
+
+        assert!(matches!(
+            execute(&mut mb, "select name from scores order by 3;"),
+            Err(SQLError::ColumnDoesNotExist(_))
+        ));
+    }
+
+    #[test]
+    fn test_datetime_types() {
+        let mut mb = MemoryBackend::new();
+        execute(
+            &mut mb,
+            "create table events (name text, day date, starts timestamp, length interval, at time); insert into events values ('launch', '2024-10-01', '2024-10-01 09:30:00', '90 minutes', '09:30'); insert into events values ('review', DATE '2024-9-15', TIMESTAMP '2024-09-15T14:00:00', INTERVAL '1 day', '14:00'); insert into events values ('retro', '2023-12-31', '2023-12-31 23:59:59.5', '45 minutes', null);",
+        )
+        .unwrap();
+
+        let results = execute(&mut mb, "select name from events order by day;")
+            .unwrap()
+            .unwrap();
+        let names: Vec<String> = results
+            .rows
+            .iter()
+            .map(|row| row[0].as_ref().unwrap().as_text())
+            .collect();
+        assert_eq!(names, vec!["retro", "review", "launch"]);
+
+        let tests = vec![
+            ("select name from events order by length desc;", "review"),
+            (
+                "select name from events where day > '2024-01-01' and day < DATE '2024-10-01';",
+                "review",
+            ),
+            (
+                "select name from events where starts >= DATE '2024-10-01';",
+                "launch",
+            ),
+            (
+                "select day + 31 from events where name = 'retro';",
+                "2024-01-31",
+            ),
+            ("select DATE '2024-03-01' - DATE '2024-02-01';", "29"),
+            (
+                "select starts + length from events where name = 'launch';",
+                "2024-10-01 11:00:00",
+            ),
+            (
+                "select starts + INTERVAL '1 month' from events where name = 'retro';",
+                "2024-01-31 23:59:59.5",
+            ),
+            (
+                "select DATE '2024-01-31' + INTERVAL '1 month';",
+                "2024-02-29 00:00:00",
+            ),
+            (
+                "select starts - TIMESTAMP '2024-09-14 12:00' from events where name = 'review';",
+                "1 day 02:00:00",
+            ),
+            (
+                "select at - INTERVAL '10 hours' from events where name = 'launch';",
+                "23:30:00",
+            ),
+            (
+                "select length * 2 from events where name = 'launch';",
+                "03:00:00",
+            ),
+            ("select INTERVAL '1 hour' / 4;", "00:15:00"),
+            (
+                "select extract(year from day) from events where name = 'retro';",
+                "2023",
+            ),
+            ("select extract(dow from DATE '2024-10-01');", "2"),
+            (
+                "select extract(minute from length) from events where name = 'retro';",
+                "45",
+            ),
+            (
+                "select extract(epoch from INTERVAL '1 day');",
+                "86400.000000",
+            ),
+            (
+                "select date_trunc('month', starts) from events where name = 'review';",
+                "2024-09-01 00:00:00",
+            ),
+            (
+                "select date_trunc('hour', TIMESTAMP '2024-03-04 10:30:15');",
+                "2024-03-04 10:00:00",
+            ),
+            (
+                "select CAST(starts AS date) from events where name = 'retro';",
+                "2023-12-31",
+            ),
+            (
+                "select starts::time from events where name = 'launch';",
+                "09:30:00",
+            ),
+            ("select now() > TIMESTAMP '2024-01-01';", "true"),
+            ("select current_date >= DATE '2024-01-01';", "true"),
+        ];
+        for (source, expected) in tests {
+            let results = execute(&mut mb, source).unwrap().unwrap();
+            let value = cast::cast(
+                &values(&results)[0][0],
+                &ColumnType::TextType,
+                CastLevel::Explicit,
+            )
+            .unwrap();
+            assert_eq!(value, Value::Text(expected.to_string()), "{}", source);
+        }
+
+        let results = execute(&mut mb, "select DATE '2024-03-04', INTERVAL '2 days';")
+            .unwrap()
+            .unwrap();
+        assert_eq!(results.columns[0].col_name, "date");
+        assert_eq!(results.columns[1].col_type, ColumnType::IntervalType);
+
+        let errors = vec![
+            "insert into events values ('bad', '2024-02-30', null, null, null);",
+            "select DATE '2024-03-04' + DATE '2024-03-04';",
+            "select extract(fortnight from DATE '2024-03-04');",
+            "select INTERVAL 'soon';",
+        ];
+        for source in errors {
+            assert!(execute(&mut mb, source).is_err(), "{}", source);
+        }
+        assert!(matches!(
+            execute(&mut mb, "select DATE '9999-12-31' + 1;"),
+            Err(SQLError::OutOfRange(_))
+        ));
+    }
+
+    #[test]
+    fn test_update() {
+        let mut mb = MemoryBackend::new();
+        execute(
+            &mut mb,
+            "create table stock (item text, qty int); insert into stock values ('bolt', 10); insert into stock values ('nut', 5); insert into stock values ('gear', null);",
+        )
+        .unwrap();
+
+        execute(
+            &mut mb,
+            "update stock set qty = qty * 2, item = upper(item) where qty > 6;",
+        )
+        .unwrap();
+        execute(&mut mb, "update stock set qty = 0 where qty is null;").unwrap();
+        let results = execute(&mut mb, "select item, qty from stock order by item;")
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            values(&results),
+            vec![
+                vec![Value::Text(String::from("BOLT")), Value::Int(20)],
+                vec![Value::Text(String::from("gear")), Value::Int(0)],
+                vec![Value::Text(String::from("nut")), Value::Int(5)],
+            ]
+        );
+
+        // A failing row leaves every row unchanged
+        assert!(matches!(
+            execute(&mut mb, "update stock set qty = 100 / qty;"),
+            Err(SQLError::DivisionByZero)
+        ));
+        let results = execute(&mut mb, "select sum(qty) from stock;")
+            .unwrap()
+            .unwrap();
+        assert_eq!(values(&results)[0][0], Value::BigInt(25));
+
+        assert!(matches!(
+            execute(&mut mb, "update stock set price = 1;"),
+            Err(SQLError::ColumnDoesNotExist(_))
+        ));
+        assert!(matches!(
+            execute(&mut mb, "update stock set qty = 'many';"),
+            Err(SQLError::InvalidCast(_))
+        ));
+    }
+
+    #[test]
+    fn test_character_types() {
+        let mut mb = MemoryBackend::new();
+        execute(
+            &mut mb,
+            "create table codes (code char(4), name varchar(5), note character varying); insert into codes values ('ab', 'short', 'anything'); insert into codes values ('abcd', 'pad  ', null);",
+        )
+        .unwrap();
+
+        let results = execute(&mut mb, "select code, name from codes;")
+            .unwrap()
+            .unwrap();
+        let texts: Vec<Vec<String>> = results
+            .rows
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|cell| cell.as_ref().unwrap().as_text())
+                    .collect()
+            })
+            .collect();
+        assert_eq!(texts, vec![vec!["ab  ", "short"], vec!["abcd", "pad  "]]);
+
+        let tests = vec![
+            ("select cast('abcdef' as varchar(3));", "abc"),
+            ("select 'xy'::char(3);", "xy "),
+            ("select 12345::varchar(2);", "12"),
+            ("select name from codes where code = 'ab  ';", "short"),
+        ];
+        for (source, expected) in tests {
+            let results = execute(&mut mb, source).unwrap().unwrap();
+            assert_eq!(
+                values(&results)[0][0],
+                Value::Text(expected.to_string()),
+                "{}",
+                source
+            );
+        }
+
+        let errors = vec![
+            "insert into codes values ('abcde', 'x', null);",
+            "insert into codes values ('a', 'toolong', null);",
+            "update codes set code = 'abcdef' where name = 'short';",
+        ];
+        for source in errors {
+            assert!(
+                matches!(execute(&mut mb, source), Err(SQLError::ValueTooLong(_))),
+                "{}",
+                source
+            );
+        }
+
+        execute(
+            &mut mb,
+            "update codes set name = 'fits     ' where code = 'abcd';",
+        )
+        .unwrap();
+        let results = execute(&mut mb, "select name from codes where code = 'abcd';")
+            .unwrap()
+            .unwrap();
+        assert_eq!(values(&results)[0][0], Value::Text(String::from("fits ")));
+
+        // Trailing spaces of char(n) are not significant
+        execute(
+            &mut mb,
+            "create table v (c char(4) unique, t text); insert into v values ('ab', 'ab'); create index v_c_idx on v using hash (c);",
+        )
+        .unwrap();
+        let tests = vec![
+            "select c, length(c) from v where c = 'ab';",
+            "select c, length(c) from v where c = 'ab ';",
+            "select c, length(c) from v where c in ('x', 'ab   ');",
+            "select c, length(c) from v where c between 'ab ' and 'ab';",
+            "select c, length(c) from v where c = t;",
+        ];
+        for source in tests {
+            let results = execute(&mut mb, source).unwrap().unwrap();
+            assert_eq!(
+                values(&results),
+                vec![vec![Value::Text(String::from("ab  ")), Value::Int(2)]],
+                "{}",
+                source
+            );
+        }
+        let results = execute(&mut mb, "explain select c from v where c = 'ab  ';")
+            .unwrap()
+            .unwrap();
+        assert!(matches!(
+            &values(&results)[2][0],
+            Value::Text(plan) if plan.contains("Index Scan using v_c_")
+        ));
+        assert!(matches!(
+            execute(&mut mb, "insert into v values ('ab  ', null);"),
+            Err(SQLError::UniqueViolation(_, _))
+        ));
+
+        assert!(matches!(
+            execute(&mut mb, "create table bad (c varchar(0));"),
+            Err(SQLError::InvalidDataType(_))
+        ));
+    }
+
+    #[test]
+    fn test_blob_types() {
+        let mut mb = MemoryBackend::new();
+        execute(
+            &mut mb,
+            "create table files (id int, data bytea, raw blob); insert into files values (1, X'DEADBEEF', x'00ff80'); insert into files values (2, '\\x0102', 'hi');",
+        )
+        .unwrap();
+
+        let results = execute(&mut mb, "select data, raw from files;")
+            .unwrap()
+            .unwrap();
+        assert_eq!(results.columns[0].col_type, ColumnType::BlobType);
+        assert_eq!(
+            values(&results),
+            vec![
+                vec![
+                    Value::Blob(vec![0xde, 0xad, 0xbe, 0xef]),
+                    Value::Blob(vec![0x00, 0xff, 0x80])
+                ],
+                vec![Value::Blob(vec![1, 2]), Value::Blob(b"hi".to_vec())],
+            ]
+        );
+        // Invalid UTF-8 no longer panics when read as text
+        assert_eq!(
+            results.rows[0][1].as_ref().unwrap().as_text(),
+            "\0\u{fffd}\u{fffd}"
+        );
+
+        let tests = vec![
+            (
+                "select length(data) from files where id = 1;",
+                Value::Int(4),
+            ),
+            ("select length(raw) from files where id = 1;", Value::Int(3)),
+            (
+                "select substr(data, 2, 2) from files where id = 1;",
+                Value::Blob(vec![0xad, 0xbe]),
+            ),
+            (
+                "select substr(data, 3) from files where id = 1;",
+                Value::Blob(vec![0xbe, 0xef]),
+            ),
+            (
+                "select substr('hello', 2, 3);",
+                Value::Text(String::from("ell")),
+            ),
+            (
+                "select substr('hello', 0, 3);",
+                Value::Text(String::from("he")),
+            ),
+            ("select substr('hello', 9);", Value::Text(String::new())),
+            ("select substr(null, 1);", Value::Null),
+            (
+                "select cast(data as text) from files where id = 1;",
+                Value::Text(String::from("\\xdeadbeef")),
+            ),
+            ("select id from files where data = X'0102';", Value::Int(2)),
+            (
+                "select id from files where data = '\\xdeadbeef';",
+                Value::Int(1),
+            ),
+            ("select id from files order by data;", Value::Int(2)),
+        ];
+        for (source, expected) in tests {
+            let results = execute(&mut mb, source).unwrap().unwrap();
+            assert_eq!(values(&results)[0][0], expected, "{}", source);
+        }
+
+        let errors = vec![
+            "select substr('hello', 1, -1);",
+            "select X'AB' + 1;",
+            "insert into files values (3, '\\xzz', null);",
+        ];
+        for source in errors {
+            assert!(execute(&mut mb, source).is_err(), "{}", source);
+        }
+    }
+
+    #[test]
+    fn test_json_type() {
+        let mut mb = MemoryBackend::new();
+        execute(
+            &mut mb,
+            r#"create table events (id int, payload json); insert into events values (1, '{"type": "click", "n": 3, "tags": ["a", "b"], "user": {"name": "ann"}}'); insert into events values (2, '{"type":"view","n":10,"tags":[]}'); insert into events values (3, null);"#,
+        )
+        .unwrap();
+
+        let results = execute(&mut mb, "select payload from events where id = 2;")
+            .unwrap()
+            .unwrap();
+        assert_eq!(results.columns[0].col_type, ColumnType::JsonType);
+        assert_eq!(
+            results.rows[0][0].as_ref().unwrap().as_text(),
+            r#"{"type": "view", "n": 10, "tags": []}"#
+        );
+
+        let text = |s: &str| Value::Text(s.to_string());
+        let json = |s: &str| Value::Json(Json::parse(s).unwrap());
+        let tests = vec![
+            (
+                "select id from events where payload->>'type' = 'click';",
+                vec![vec![Value::Int(1)]],
+            ),
+            (
+                "select payload->'tags'->>0, payload->'user'->>'name' from events where id = 1;",
+                vec![vec![text("a"), text("ann")]],
+            ),
+            (
+                "select payload->'tags'->-1 from events where id = 1;",
+                vec![vec![json(r#""b""#)]],
+            ),
+            (
+                "select id from events order by (payload->>'n')::int desc;",
+                vec![vec![Value::Int(3)], vec![Value::Int(2)], vec![Value::Int(1)]],
+            ),
+            (
+                "select id from events order by cast(payload->'n' as int);",
+                vec![vec![Value::Int(1)], vec![Value::Int(2)], vec![Value::Int(3)]],
+            ),
+            (
+                "select payload->>'missing', payload->'n' from events where id = 2;",
+                vec![vec![Value::Null, json("10")]],
+            ),
+            (
+                "select json_extract(payload, '$.user.name'), json_array_length(payload->'tags') from events where id = 1;",
+                vec![vec![json(r#""ann""#), Value::Int(2)]],
+            ),
+            (
+                "select json_extract(payload, '$.tags[5]') from events where id = 1;",
+                vec![vec![Value::Null]],
+            ),
+            (
+                "select id from events where json_array_length(payload->'tags') = 0;",
+                vec![vec![Value::Int(2)]],
+            ),
+            (
+                r#"select key, value from json_each('{"a": 1, "b": [true]}') order by key desc;"#,
+                vec![
+                    vec![text("b"), json("[true]")],
+                    vec![text("a"), json("1")],
+                ],
+            ),
+            (
+                r#"select value->>'x' from json_each('{"p": {"x": "y"}}') where key = 'p';"#,
+                vec![vec![text("y")]],
+            ),
+        ];
+        for (source, expected) in tests {
+            let results = execute(&mut mb, source).unwrap().unwrap();
+            assert_eq!(values(&results), expected, "{}", source);
+        }
+
+        let results = execute(&mut mb, "select key from json_each('{}');")
+            .unwrap()
+            .unwrap();
+        assert_eq!(results.columns[0].col_type, ColumnType::TextType);
+        assert!(results.rows.is_empty());
+
+        // JSON numbers order and compare by value
+        execute(
+            &mut mb,
+            "create table docs (doc json); insert into docs values ('{\"n\": 10}'); insert into docs values ('{\"n\": 2}'); insert into docs values ('{\"n\": 9.5}');",
+        )
+        .unwrap();
+        let results = execute(&mut mb, "select doc->'n' from docs order by doc->'n';")
+            .unwrap()
+            .unwrap();
+        let texts: Vec<String> = results
+            .rows
+            .iter()
+            .map(|row| row[0].as_ref().unwrap().as_text())
+            .collect();
+        assert_eq!(texts, vec!["2", "9.5", "10"]);
+        let results = execute(
+            &mut mb,
+            "select count(*) from docs where doc->'n' = '10.0'::json or doc->'n' in ('2.00'::json);",
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(values(&results)[0][0].as_i64(), Some(2));
+
+        let errors = vec![
+            "insert into events values (4, '{\"type\": ');",
+            "insert into events values (4, 'not json');",
+            "select json_array_length(payload) from events where id = 1;",
+            "select json_extract(payload, 'user') from events where id = 1;",
+            "select key from json_each('[1, 2]');",
+            "select key from json_each(payload);",
+            "select key from no_such_function(1);",
+            "select payload->true from events;",
+        ];
+        for source in errors {
+            assert!(execute(&mut mb, source).is_err(), "{}", source);
+        }
+    }
+
+    #[test]
+    fn test_uuid_type() {
+        let mut mb = MemoryBackend::new();
+        execute(
+            &mut mb,
+            "create table users (id uuid, name text); insert into users values ('a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11', 'ann'); insert into users values ('{B0EEBC99-9C0B-4EF8-BB6D-6BB9BD380A11}', 'bob'); insert into users values (gen_random_uuid(), 'cy'); insert into users values (gen_random_uuid(), 'di');",
+        )
+        .unwrap();
+
+        let table = &mb.tables["users"];
+        assert!(table
+            .rows
+            .iter()
+            .all(|row| row[0].as_ref().unwrap().len() == 16));
+
+        let results = execute(&mut mb, "select id from users where name = 'bob';")
+            .unwrap()
+            .unwrap();
+        assert_eq!(results.columns[0].col_type, ColumnType::UuidType);
+        let bob = Uuid::parse("b0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11").unwrap();
+        assert_eq!(values(&results), vec![vec![Value::Uuid(bob)]]);
+
+        let tests = vec![
+            (
+                "select name from users where id = 'A0EEBC99-9C0B-4EF8-BB6D-6BB9BD380A11';",
+                vec![vec![Value::Text(String::from("ann"))]],
+            ),
+            (
+                "select id::text from users where name = 'ann';",
+                vec![vec![Value::Text(String::from(
+                    "a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11",
+                ))]],
+            ),
+            (
+                "select name from users where id in ('b0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11', 'a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11') order by id desc;",
+                vec![
+                    vec![Value::Text(String::from("bob"))],
+                    vec![Value::Text(String::from("ann"))],
+                ],
+            ),
+            (
+                "select count(*) from users where id <> gen_random_uuid();",
+                vec![vec![Value::BigInt(4)]],
+            ),
+        ];
+        for (source, expected) in tests {
+            let results = execute(&mut mb, source).unwrap().unwrap();
+            assert_eq!(values(&results), expected, "{}", source);
+        }
+
+        let results = execute(&mut mb, "select id from users;").unwrap().unwrap();
+        let ids: HashSet<Value> = values(&results).into_iter().flatten().collect();
+        assert_eq!(ids.len(), 4);
+
+        assert!(matches!(
+            execute(&mut mb, "insert into users values ('not-a-uuid', 'x');"),
+            Err(SQLError::InvalidCast(_))
+        ));
+    }
+
+    #[test]
+    fn test_array_types() {
+        let mut mb = MemoryBackend::new();
+        execute(
+            &mut mb,
+            "create table posts (id int, tags text[], scores int[]); insert into posts values (1, array['rust', 'db'], array[3, 5, 8]); insert into posts values (2, '{sql,\"two words\",NULL}', '{}'); insert into posts values (3, null, array[1, null]);",
+        )
+        .unwrap();
+
+        let results = execute(&mut mb, "select tags, scores from posts where id = 1;")
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            results.columns[0].col_type,
+            ColumnType::ArrayType(Box::new(ColumnType::TextType))
+        );
+        assert_eq!(
+            results.columns[1].col_type.to_string(),
+            String::from("int[]")
+        );
+
+        let text = |s: &str| Value::Text(s.to_string());
+        let tests = vec![
+            (
+                "select tags[1], tags[2], tags[3], scores[0] from posts where id = 1;",
+                vec![vec![text("rust"), text("db"), Value::Null, Value::Null]],
+            ),
+            (
+                "select tags::text, scores::text from posts where id = 2;",
+                vec![vec![text("{sql,\"two words\",NULL}"), text("{}")]],
+            ),
+            (
+                "select array_length(tags, 1), array_length(scores) from posts order by id;",
+                vec![
+                    vec![Value::Int(2), Value::Int(3)],
+                    vec![Value::Int(3), Value::Null],
+                    vec![Value::Null, Value::Int(2)],
+                ],
+            ),
+            (
+                "select id from posts where 'db' = any(tags);",
+                vec![vec![Value::Int(1)]],
+            ),
+            (
+                "select id from posts where 2 < all(scores) order by id;",
+                vec![vec![Value::Int(1)], vec![Value::Int(2)]],
+            ),
+            (
+                "select 1 = any(scores), 5 <> all(scores), 9 > all(scores) from posts where id = 3;",
+                vec![vec![Value::Bool(true), Value::Null, Value::Null]],
+            ),
+            (
+                "select id from posts where scores[1] > 2;",
+                vec![vec![Value::Int(1)]],
+            ),
+            (
+                "select array[1, 2.5][2], array[1, 2] = array[1, 2], array[1, 2] < array[1, 3];",
+                vec![vec![
+                    Value::Numeric(Decimal::parse("2.5").unwrap()),
+                    Value::Bool(true),
+                    Value::Bool(true),
+                ]],
+            ),
+            (
+                "select unnest from unnest(array['b', 'a', 'c']) where unnest <> 'c' order by unnest;",
+                vec![vec![text("a")], vec![text("b")]],
+            ),
+            (
+                "select unnest * 2 from unnest('{1,2,3}'::int[]);",
+                vec![vec![Value::Int(2)], vec![Value::Int(4)], vec![Value::Int(6)]],
+            ),
+        ];
+        for (source, expected) in tests {
+            let results = execute(&mut mb, source).unwrap().unwrap();
+            assert_eq!(values(&results), expected, "{}", source);
+        }
+
+        execute(
+            &mut mb,
+            "update posts set scores = array[9], tags = '{x}' where id = 3;",
+        )
+        .unwrap();
+        let results = execute(
+            &mut mb,
+            "select tags[1], scores[1] from posts where id = 3;",
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(values(&results), vec![vec![text("x"), Value::Int(9)]]);
+
+        let errors = vec![
+            "insert into posts values (4, '{a', null);",
+            "insert into posts values (4, null, '{1,x}');",
+            "select array[1, true];",
+            "select array[array[1]];",
+            "select id[1] from posts;",
+            "select tags['a'] from posts;",
+            "select 1 = any(id) from posts;",
+            "select unnest from unnest(1);",
+        ];
+        for source in errors {
+            assert!(execute(&mut mb, source).is_err(), "{}", source);
+        }
+    }
+
+    #[test]
+    fn test_enum_types() {
+        let mut mb = MemoryBackend::new();
+        execute(
+            &mut mb,
+            "create type status as enum ('new', 'active', 'closed'); create table tickets (id int, state status, history status[]); insert into tickets values (1, 'closed', array['new', 'closed']); insert into tickets values (2, 'new', '{}'); insert into tickets values (3, 'active', null);",
+        )
+        .unwrap();
+
+        let results = execute(&mut mb, "select id, state from tickets order by state;")
+            .unwrap()
+            .unwrap();
+        assert_eq!(results.columns[1].col_type.to_string(), "status");
+        let ids: Vec<i64> = results
+            .rows
+            .iter()
+            .map(|row| row[0].as_ref().unwrap().as_int())
+            .collect();
+        assert_eq!(ids, vec![2, 3, 1]);
+
+        let text = |s: &str| Value::Text(s.to_string());
+        let tests = vec![
+            (
+                "select id from tickets where state > 'new' order by id;",
+                vec![vec![Value::Int(1)], vec![Value::Int(3)]],
+            ),
+            (
+                "select state::text, history[2]::text from tickets where id = 1;",
+                vec![vec![text("closed"), text("closed")]],
+            ),
+            (
+                "select id from tickets where 'new' = any(history);",
+                vec![vec![Value::Int(1)]],
+            ),
+            (
+                "select max(state)::text, min(state)::text from tickets;",
+                vec![vec![text("closed"), text("new")]],
+            ),
+            (
+                "select cast('active' as status) < 'closed';",
+                vec![vec![Value::Bool(true)]],
+            ),
+        ];
+        for (source, expected) in tests {
+            let results = execute(&mut mb, source).unwrap().unwrap();
+            assert_eq!(values(&results), expected, "{}", source);
+        }
+
+        execute(&mut mb, "update tickets set state = 'closed' where id = 3;").unwrap();
+        let results = execute(
+            &mut mb,
+            "select id from tickets where state = 'closed' order by id;",
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(
+            values(&results),
+            vec![vec![Value::Int(1)], vec![Value::Int(3)]]
+        );
+
+        let errors = vec![
+            "insert into tickets values (4, 'open', null);",
+            "insert into tickets values (4, 'Active', null);",
+            "update tickets set state = 'done';",
+            "insert into tickets values (4, 'new', '{new,open}');",
+            "select 'pending'::status;",
+            "select state + 1 from tickets;",
+        ];
+        for source in errors {
+            assert!(
+                matches!(
+                    execute(&mut mb, source),
+                    Err(SQLError::InvalidCast(_)) | Err(SQLError::TypeMismatch(_))
+                ),
+                "{}",
+                source
+            );
+        }
+
+        assert!(matches!(
+            execute(&mut mb, "create type status as enum ('x');"),
+            Err(SQLError::TypeAlreadyExists(_))
+        ));
+        assert!(matches!(
+            execute(&mut mb, "create type dup as enum ('a', 'a');"),
+            Err(SQLError::InvalidDataType(_))
+        ));
+        assert!(matches!(
+            execute(&mut mb, "create table bad (s mood);"),
+            Err(SQLError::InvalidDataType(_))
+        ));
+    }
+
+    #[test]
+    fn test_unique_constraints() {
+        let mut mb = MemoryBackend::new();
+        execute(
+            &mut mb,
+            "create table users (id int primary key, email text unique, org int, login text, unique (org, login)); insert into users values (1, 'a@x', 1, 'ann'); insert into users values (2, null, 1, 'bob'); insert into users values (3, null, null, 'bob');",
+        )
+        .unwrap();
+
+        let tests = vec![
+            (
+                "insert into users values (1, 'b@x', 2, 'cy');",
+                "users_pkey",
+                "(id)=(1)",
+            ),
+            (
+                "insert into users values (4, 'a@x', 2, 'cy');",
+                "users_email_key",
+                "(email)=(a@x)",
+            ),
+            (
+                "insert into users values (4, 'b@x', 1, 'ann');",
+                "users_org_login_key",
+                "(org, login)=(1, ann)",
+            ),
+            (
+                "update users set id = 1 where id = 2;",
+                "users_pkey",
+                "(id)=(1)",
+            ),
+            (
+                "update users set email = 'b@x';",
+                "users_email_key",
+                "(email)=(b@x)",
+            ),
+        ];
+        for (source, constraint, key) in tests {
+            match execute(&mut mb, source) {
+                Err(SQLError::UniqueViolation(c, k)) => {
+                    assert_eq!((c.as_str(), k.as_str()), (constraint, key), "{}", source)
+                }
+                other => panic!("{}: {:?}", source, other.map(|_| ())),
+            }
+        }
+        assert!(matches!(
+            execute(&mut mb, "insert into users values (null, 'c@x', 3, 'dee');"),
+            Err(SQLError::NotNullViolation(_, _))
+        ));
+
+        // Failed statements leave rows and indexes as they were, and
+        // updates may move keys between rows
+        execute(&mut mb, "update users set id = id + 1;").unwrap();
+        execute(&mut mb, "update users set id = 5 - id;").unwrap();
+        execute(&mut mb, "insert into users values (4, null, null, 'bob');").unwrap();
+        let results = execute(&mut mb, "select id, email from users order by id;")
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            values(&results),
+            vec![
+                vec![Value::Int(1), Value::Null],
+                vec![Value::Int(2), Value::Null],
+                vec![Value::Int(3), Value::Text(String::from("a@x"))],
+                vec![Value::Int(4), Value::Null],
+            ]
+        );
+        assert!(matches!(
+            execute(&mut mb, "insert into users values (3, null, null, 'x');"),
+            Err(SQLError::UniqueViolation(_, _))
+        ));
+
+        let errors = vec![
+            "create table bad (a int primary key, b int primary key);",
+            "create table bad (a int, b int, primary key (a), primary key (b));",
+        ];
+        for source in errors {
+            assert!(
+                matches!(
+                    execute(&mut mb, source),
+                    Err(SQLError::InvalidConstraint(_))
+                ),
+                "{}",
+                source
+            );
+        }
+        assert!(matches!(
+            execute(&mut mb, "create table bad (a int, unique (b));"),
+            Err(SQLError::ColumnDoesNotExist(_))
+        ));
+    }
+
+    #[test]
+    fn test_delete() {
+        let mut mb = MemoryBackend::new();
+        execute(
+            &mut mb,
+            "create table users (id int primary key, name text); insert into users values (1, 'ann'); insert into users values (2, 'bob'); insert into users values (3, 'cy');",
+        )
+        .unwrap();
+
+        execute(&mut mb, "delete from users where id = 2;").unwrap();
+        let results = execute(&mut mb, "select id from users order by id;")
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            values(&results),
+            vec![vec![Value::Int(1)], vec![Value::Int(3)]]
+        );
+
+        // The key index follows the rows that moved
+        execute(&mut mb, "insert into users values (2, 'bo');").unwrap();
+        assert!(matches!(
+            execute(&mut mb, "insert into users values (3, 'cy');"),
+            Err(SQLError::UniqueViolation(_, _))
+        ));
+        execute(&mut mb, "update users set id = 4 where id = 3;").unwrap();
+        execute(&mut mb, "insert into users values (3, 'cy');").unwrap();
+
+        execute(&mut mb, "delete from users;").unwrap();
+        let results = execute(&mut mb, "select count(*) from users;")
+            .unwrap()
+            .unwrap();
+        assert_eq!(results.rows[0][0].as_ref().unwrap().as_int(), 0);
+        assert!(matches!(
+            execute(&mut mb, "delete from nope;"),
+            Err(SQLError::TableDoesNotExist(_))
+        ));
+    }
+
+    #[test]
+    fn test_foreign_keys() {
+        let mut mb = MemoryBackend::new();
+        execute(
+            &mut mb,
+            "create table teams (id int primary key, name text unique); create table players (id int primary key, team int references teams on delete cascade on update cascade, mentor int references players (id) on delete set null); create table badges (player int, team text, foreign key (team) references teams (name) on update restrict, foreign key (player) references players); insert into teams values (1, 'red'); insert into teams values (2, 'blue'); insert into players values (10, 1, null); insert into players values (11, 1, 10); insert into players values (12, 2, 11); insert into players values (13, null, 13); insert into badges values (12, 'blue');",
+        )
+        .unwrap();
+
+        let players = |mb: &mut MemoryBackend| {
+            let results = execute(mb, "select id, team, mentor from players order by id;")
+                .unwrap()
+                .unwrap();
+            values(&results)
+        };
+        let row = |id: i32, team: Option<i32>, mentor: Option<i32>| {
+            let value = |v: Option<i32>| v.map_or(Value::Null, Value::Int);
+            vec![Value::Int(id), value(team), value(mentor)]
+        };
+
+        let tests = vec![
+            (
+                "insert into players values (14, 3, null);",
+                "players_team_fkey",
+                "key (team)=(3) is not present in table teams",
+            ),
+            (
+                "update players set mentor = 99 where id = 10;",
+                "players_mentor_fkey",
+                "key (mentor)=(99) is not present in table players",
+            ),
+            (
+                "insert into badges values (10, 'green');",
+                "badges_team_fkey",
+                "key (team)=(green) is not present in table teams",
+            ),
+            (
+                "update teams set name = 'navy' where id = 2;",
+                "badges_team_fkey",
+                "key (name)=(blue) is still referenced from table badges",
+            ),
+            (
+                "delete from players where id = 12;",
+                "badges_player_fkey",
+                "key (id)=(12) is still referenced from table badges",
+            ),
+            (
+                "delete from teams where id = 2;",
+                "badges_team_fkey",
+                "key (name)=(blue) is still referenced from table badges",
+            ),
+        ];
+        for (source, constraint, detail) in tests {
+            match execute(&mut mb, source) {
+                Err(SQLError::ForeignKeyViolation(c, d)) => {
+                    assert_eq!((c.as_str(), d.as_str()), (constraint, detail), "{}", source)
+                }
+                other => panic!("{}: {:?}", source, other.map(|_| ())),
+            }
+        }
+        // Failed statements changed nothing, in any table
+        assert_eq!(
+            players(&mut mb),
+            vec![
+                row(10, Some(1), None),
+                row(11, Some(1), Some(10)),
+                row(12, Some(2), Some(11)),
+                row(13, None, Some(13)),
+            ]
+        );
+
+        execute(&mut mb, "update teams set id = 5 where id = 1;").unwrap();
+        execute(&mut mb, "delete from players where id = 10;").unwrap();
+        assert_eq!(
+            players(&mut mb),
+            vec![
+                row(11, Some(5), None),
+                row(12, Some(2), Some(11)),
+                row(13, None, Some(13)),
+            ]
+        );
+
+        // The cascade reaches a badge that would be left dangling
+        execute(&mut mb, "update badges set team = 'red';").unwrap();
+        assert!(matches!(
+            execute(&mut mb, "delete from teams where id = 2;"),
+            Err(SQLError::ForeignKeyViolation(c, _)) if c == "badges_player_fkey"
+        ));
+        // Undoing it put the rows back where the indexes find them, and the
+        // foreign key columns are indexed
+        for (source, expected) in [
+            (
+                "select name from teams where id = 2;",
+                Value::Text(String::from("blue")),
+            ),
+            ("select id from players where team = 2;", Value::Int(12)),
+        ] {
+            let results = execute(&mut mb, source).unwrap().unwrap();
+            assert_eq!(values(&results), vec![vec![expected]], "{}", source);
+        }
+        let results = execute(&mut mb, "explain select id from players where team = 2;")
+            .unwrap()
+            .unwrap();
+        assert!(values(&results).iter().any(|row| matches!(
+            &row[0],
+            Value::Text(plan) if plan.contains("Index Scan using players_team_fkey on players")
+        )));
+        execute(&mut mb, "update badges set player = 11;").unwrap();
+        execute(&mut mb, "delete from teams where id = 2;").unwrap();
+        assert_eq!(
+            players(&mut mb),
+            vec![row(11, Some(5), None), row(13, None, Some(13))]
+        );
+
+        let errors = vec![
+            "create table bad (a int references nope);",
+            "create table bad (a int references teams (nope));",
+            "create table bad (a date references teams);",
+            "create table bad (a int, b int, foreign key (a, b) references teams);",
+            "create table bad (a int references badges);",
+        ];
+        for source in errors {
+            assert!(
+                matches!(
+                    execute(&mut mb, source),
+                    Err(SQLError::InvalidConstraint(_))
+                        | Err(SQLError::TableDoesNotExist(_))
+                        | Err(SQLError::ColumnDoesNotExist(_))
+                ),
+                "{}",
+                source
+            );
+        }
+    }
+
+    #[test]
+    fn test_sequences() {
+        let mut mb = MemoryBackend::new();
+        execute(
+            &mut mb,
+            "create sequence ids; create sequence evens start with 10 increment by 2; create sequence down increment -5;",
+        )
+        .unwrap();
+
+        let tests = vec![
+            ("select nextval('ids'), nextval('ids');", vec![1, 2]),
+            ("select currval('ids');", vec![2]),
+            ("select nextval('evens'), nextval('evens');", vec![10, 12]),
+            ("select nextval('down'), nextval('down');", vec![-1, -6]),
+            ("select setval('ids', 100), nextval('ids');", vec![100, 101]),
+            (
+                "select setval('ids', 7, false), nextval('ids');",
+                vec![7, 7],
+            ),
+        ];
+        for (source, expected) in tests {
+            let results = execute(&mut mb, source).unwrap().unwrap();
+            let expected: Vec<Value> = expected.into_iter().map(Value::BigInt).collect();
+            assert_eq!(values(&results), vec![expected], "{}", source);
+        }
+
+        execute(&mut mb, "create sequence fresh;").unwrap();
+        let errors = vec![
+            "select nextval('nope');",
+            "select currval('fresh');",
+            "select setval('ids');",
+            "create sequence ids;",
+            "create sequence zero increment by 0;",
+        ];
+        for source in errors {
+            assert!(
+                matches!(
+                    execute(&mut mb, source),
+                    Err(SQLError::SequenceDoesNotExist(_))
+                        | Err(SQLError::SequenceAlreadyExists(_))
+                        | Err(SQLError::InvalidFunctionArguments(_))
+                        | Err(SQLError::OutOfRange(_))
+                ),
+                "{}",
+                source
+            );
+        }
+    }
+
+    #[test]
+    fn test_identity_columns() {
+        let mut mb = MemoryBackend::new();
+        execute(
+            &mut mb,
+            "create table users (id serial primary key, name text); create table orders (id bigint generated always as identity (start with 100 increment by 10), code int generated by default as identity, note text); insert into users (name) values ('ann'); insert into users (name) values ('bob'); insert into orders (note) values ('a'); insert into orders (code, note) values (50, 'b'); insert into orders (note) values ('c');",
+        )
+        .unwrap();
+
+        let results = execute(&mut mb, "select id, name from users order by id;")
+            .unwrap()
+            .unwrap();
+        assert_eq!(results.columns[0].col_type, ColumnType::IntType);
+        assert_eq!(
+            values(&results),
+            vec![
+                vec![Value::Int(1), Value::Text(String::from("ann"))],
+                vec![Value::Int(2), Value::Text(String::from("bob"))],
+            ]
+        );
+        let results = execute(&mut mb, "select id, code from orders order by id;")
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            values(&results),
+            vec![
+                vec![Value::BigInt(100), Value::Int(1)],
+                vec![Value::BigInt(110), Value::Int(50)],
+                vec![Value::BigInt(120), Value::Int(2)],
+            ]
+        );
+
+        // BY DEFAULT takes explicit values, and the sequence can be moved
+        // past them
+        execute(&mut mb, "insert into users values (3, 'cy');").unwrap();
+        assert!(matches!(
+            execute(&mut mb, "insert into users (name) values ('dee');"),
+            Err(SQLError::UniqueViolation(_, _))
+        ));
+        execute(&mut mb, "select setval('users_id_seq', 10);").unwrap();
+        execute(&mut mb, "insert into users (name) values ('dee');").unwrap();
+        let results = execute(
+            &mut mb,
+            "select max(id), currval('users_id_seq') from users;",
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(
+            values(&results),
+            vec![vec![Value::Int(11), Value::BigInt(11)]]
+        );
+
+        let errors = vec![
+            "insert into orders (id, note) values (1, 'x');",
+            "insert into orders values (1, 1, 'x');",
+            "update orders set id = 1;",
+        ];
+        for source in errors {
+            assert!(
+                matches!(execute(&mut mb, source), Err(SQLError::GeneratedAlways(_))),
+                "{}",
+                source
+            );
+        }
+        assert!(matches!(
+            execute(&mut mb, "insert into users (id, name) values (null, 'x');"),
+            Err(SQLError::NotNullViolation(_, _))
+        ));
+
+        let errors = vec![
+            "create table bad (a text generated always as identity);",
+            "create table bad (a int default 1 generated always as identity);",
+        ];
+        for source in errors {
+            assert!(
+                matches!(
+                    execute(&mut mb, source),
+                    Err(SQLError::InvalidConstraint(_))
+                ),
+                "{}",
+                source
+            );
+        }
+        execute(&mut mb, "create sequence things_id_seq;").unwrap();
+        assert!(matches!(
+            execute(&mut mb, "create table things (id serial);"),
+            Err(SQLError::SequenceAlreadyExists(_))
+        ));
+    }
+
+    #[test]
+    fn test_returning() {
+        let mut mb = MemoryBackend::new();
+        execute(
+            &mut mb,
+            "create table items (id serial primary key, name text, price numeric(5, 2) check (price > 0));",
+        )
+        .unwrap();
+
+        let results = execute(
+            &mut mb,
+            "insert into items (name, price) values ('pen', 1.50) returning id;",
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(results.columns[0].col_name, "id");
+        assert_eq!(results.columns[0].col_type, ColumnType::IntType);
+        assert_eq!(values(&results), vec![vec![Value::Int(1)]]);
+
+        let results = execute(
+            &mut mb,
+            "insert into items (name, price) values ('ink', 9.50) returning *, upper(name);",
+        )
+        .unwrap()
+        .unwrap();
+        let names: Vec<&str> = results
+            .columns
+            .iter()
+            .map(|c| c.col_name.as_str())
+            .collect();
+        assert_eq!(names, vec!["id", "name", "price", "upper"]);
+        assert_eq!(
+            results.columns[2].col_type,
+            ColumnType::NumericType(Some((5, 2)))
+        );
+        assert_eq!(
+            values(&results),
+            vec![vec![
+                Value::Int(2),
+                Value::Text(String::from("ink")),
+                Value::Numeric(Decimal::parse("9.50").unwrap()),
+                Value::Text(String::from("INK")),
+            ]]
+        );
+
+        // UPDATE returns the new rows, DELETE the removed ones
+        let results = execute(
+            &mut mb,
+            "update items set price = price * 2 where id = 2 returning id, price;",
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(
+            values(&results),
+            vec![vec![
+                Value::Int(2),
+                Value::Numeric(Decimal::parse("19.00").unwrap())
+            ]]
+        );
+        let results = execute(
+            &mut mb,
+            "update items set name = name where id = 5 returning id;",
+        )
+        .unwrap()
+        .unwrap();
+        assert!(results.rows.is_empty());
+        assert_eq!(results.columns[0].col_type, ColumnType::IntType);
+
+        let results = execute(&mut mb, "delete from items where id = 1 returning name;")
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            values(&results),
+            vec![vec![Value::Text(String::from("pen"))]]
+        );
+        let results = execute(&mut mb, "select count(*) from items;")
+            .unwrap()
+            .unwrap();
+        assert_eq!(values(&results), vec![vec![Value::BigInt(1)]]);
+
+        // Without RETURNING there are no results
+        assert!(execute(
+            &mut mb,
+            "insert into items (name, price) values ('cap', 2);"
+        )
+        .unwrap()
+        .is_none());
+
+        // A failing item or constraint leaves the table unchanged
+        let errors = vec![
+            "delete from items returning 1 / 0;",
+            "insert into items (name, price) values ('x', 1) returning count(*);",
+            "insert into items (name, price) values ('x', -1) returning id;",
+            "update items set price = 1 returning missing;",
+        ];
+        for source in errors {
+            assert!(execute(&mut mb, source).is_err(), "{}", source);
+        }
+        let results = execute(&mut mb, "select name, price from items order by id;")
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            values(&results),
+            vec![
+                vec![
+                    Value::Text(String::from("ink")),
+                    Value::Numeric(Decimal::parse("19.00").unwrap())
+                ],
+                vec![
+                    Value::Text(String::from("cap")),
+                    Value::Numeric(Decimal::parse("2.00").unwrap())
+                ],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_on_conflict() {
+        let mut mb = MemoryBackend::new();
+        execute(
+            &mut mb,
+            "create table stock (sku text primary key, qty int not null, code int unique, note text); insert into stock values ('a', 1, 10, null);",
+        )
+        .unwrap();
+
+        // DO NOTHING skips the row, on the named columns or on any of them
+        let results = execute(
+            &mut mb,
+            "insert into stock values ('a', 5, 11, 'x') on conflict (sku) do nothing returning sku;",
+        )
+        .unwrap()
+        .unwrap();
+        assert!(results.rows.is_empty());
+        execute(
+            &mut mb,
+            "insert into stock values ('b', 5, 10, 'x') on conflict do nothing;",
+        )
+        .unwrap();
+        assert!(matches!(
+            execute(
+                &mut mb,
+                "insert into stock values ('b', 5, 10, 'x') on conflict (sku) do nothing;"
+            ),
+            Err(SQLError::UniqueViolation(_, _))
+        ));
+
+        // DO UPDATE sees the proposed row through excluded
+        let results = execute(
+            &mut mb,
+            "insert into stock values ('a', 4, 10, 'y') on conflict (sku) do update set qty = stock.qty + excluded.qty, note = excluded.note returning qty, note;",
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(
+            values(&results),
+            vec![vec![Value::Int(5), Value::Text(String::from("y"))]]
+        );
+        execute(
+            &mut mb,
+            "insert into stock values ('a', 100, 10, 'z') on conflict (sku) do update set qty = excluded.qty where stock.qty > 10;",
+        )
+        .unwrap();
+        execute(
+            &mut mb,
+            "insert into stock values ('c', 2, 12, null) on conflict (sku) do update set qty = excluded.qty;",
+        )
+        .unwrap();
+        let results = execute(&mut mb, "select sku, qty, note from stock order by sku;")
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            values(&results),
+            vec![
+                vec![
+                    Value::Text(String::from("a")),
+                    Value::Int(5),
+                    Value::Text(String::from("y"))
+                ],
+                vec![Value::Text(String::from("c")), Value::Int(2), Value::Null],
+            ]
+        );
+
+        let errors = vec![
+            "insert into stock values ('a', 1, 20, null) on conflict (note) do nothing;",
+            "insert into stock values ('a', 1, 20, null) on conflict (missing) do nothing;",
+            "insert into stock values ('a', 1, 20, null) on conflict (sku) do update set qty = null;",
+            "insert into stock values ('a', 1, 20, null) on conflict (sku) do update set code = 12;",
+            "insert into stock values ('a', 1, 20, null) on conflict (sku) do update set missing = 1;",
+        ];
+        for source in errors {
+            assert!(execute(&mut mb, source).is_err(), "{}", source);
+        }
+        assert!(parse(String::from(
+            "insert into stock values ('a', 1, 20, null) on conflict do update set qty = 1;"
+        ))
+        .is_err());
+    }
+
+    #[test]
+    fn test_indexes() {
+        let mut mb = MemoryBackend::new();
+        execute(
+            &mut mb,
+            "create table t (id int primary key, grp int, name text, price numeric(6, 2)); insert into t values (1, 1, 'a', 1.00); insert into t values (2, 2, 'b', 2.50); insert into t values (3, 2, 'c', 4.00); insert into t values (4, 3, 'd', null); insert into t values (5, null, 'e', 4.50); insert into t values (6, 2, 'f', 9.99);",
+        )
+        .unwrap();
+        execute(
+            &mut mb,
+            "create index t_grp_idx on t using hash (grp); create index on t (price); create unique index t_name_idx on t (name);",
+        )
+        .unwrap();
+
+        let ids = |mb: &mut MemoryBackend, condition: &str| -> Vec<Value> {
+            let source = format!("select id from t where {};", condition);
+            let results = execute(mb, &source).unwrap().unwrap();
+            values(&results)
+                .into_iter()
+                .map(|row| row[0].clone())
+                .collect()
+        };
+        let tests = vec![
+            ("grp = 2", vec![2, 3, 6]),
+            ("2 = grp and id > 2", vec![3, 6]),
+            ("grp = 2.5", vec![]),
+            ("grp = 9000000000", vec![]),
+            ("grp = null", vec![]),
+            ("price >= 2 and price < 4.5", vec![2, 3]),
+            ("price between 1 and 2.5", vec![1, 2]),
+            ("4 < price", vec![5, 6]),
+            ("price <= 4", vec![1, 2, 3]),
+            ("price > 2 and price > 4", vec![5, 6]),
+            ("name = 'e' or id = 1", vec![1, 5]),
+            ("name = 'e'", vec![5]),
+            ("id = 4", vec![4]),
+        ];
+        for (condition, expected) in tests {
+            let expected: Vec<Value> = expected.into_iter().map(Value::Int).collect();
+            assert_eq!(ids(&mut mb, condition), expected, "{}", condition);
+        }
+
+        // Only the rows the index finds are checked, so the division below
+        // is never done for the row where grp is 1
+        assert_eq!(
+            ids(&mut mb, "10 / (grp - 1) > 0 and grp = 2"),
+            vec![Value::Int(2), Value::Int(3), Value::Int(6)]
+        );
+
+        // Indexes follow UPDATE and DELETE
+        execute(
+            &mut mb,
+            "update t set grp = 2, price = 3 where id = 1; delete from t where id = 3;",
+        )
+        .unwrap();
+        assert_eq!(
+            ids(&mut mb, "grp = 2"),
+            vec![Value::Int(1), Value::Int(2), Value::Int(6)]
+        );
+        assert_eq!(
+            ids(&mut mb, "price between 2 and 4"),
+            vec![Value::Int(1), Value::Int(2)]
+        );
+        let results = execute(
+            &mut mb,
+            "update t set price = price + 1 where grp = 2 returning id, price;",
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(results.rows.len(), 3);
+
+        assert!(matches!(
+            execute(&mut mb, "insert into t values (7, 1, 'a', 1);"),
+            Err(SQLError::UniqueViolation(_, _))
+        ));
+        assert_eq!(ids(&mut mb, "10 / (grp - 3) < 0 and grp = 2").len(), 3);
+        execute(&mut mb, "drop index t_grp_idx;").unwrap();
+        assert!(matches!(
+            execute(
+                &mut mb,
+                "select id from t where 10 / (grp - 3) < 0 and grp = 2;"
+            ),
+            Err(SQLError::DivisionByZero)
+        ));
+
+        let errors = vec![
+            ("drop index t_grp_idx;", "IndexDoesNotExist"),
+            ("drop index t_pkey;", "InvalidIndex"),
+            ("create index t_name_idx on t (id);", "IndexAlreadyExists"),
+            ("create unique index on t using hash (grp);", "InvalidIndex"),
+            ("create index on t using hash (grp, id);", "InvalidIndex"),
+            ("create index on t using gist (grp);", "InvalidIndex"),
+            ("create index on t (missing);", "ColumnDoesNotExist"),
+            ("create index on missing (id);", "TableDoesNotExist"),
+            ("create unique index on t (grp);", "UniqueViolation"),
+        ];
+        for (source, expected) in errors {
+            let err = match execute(&mut mb, source) {
+                Err(err) => err,
+                Ok(_) => panic!("{} succeeded", source),
+            };
+            assert!(
+                format!("{:?}", err).starts_with(expected),
+                "{}: {:?}",
+                source,
+                err
+            );
+        }
+
+        // Unnamed indexes are numbered when the name is taken
+        execute(&mut mb, "create index on t (grp); create index on t (grp);").unwrap();
+        execute(&mut mb, "drop index t_grp_idx; drop index t_grp_idx1;").unwrap();
+
+        // A foreign key keeps the unique index it references
+        execute(
+            &mut mb,
+            "create table u (tname text references t (name)); drop index t_price_idx;",
+        )
+        .unwrap();
+        assert!(matches!(
+            execute(&mut mb, "drop index t_name_idx;"),
+            Err(SQLError::InvalidIndex(_))
+        ));
+    }
+
+    #[test]
+    fn test_expression_and_partial_indexes() {
+        let mut mb = MemoryBackend::new();
+        execute(
+            &mut mb,
+            "create table users (id int primary key, email text, deleted boolean, score int); insert into users values (1, 'Ann@x.com', true, 3); insert into users values (2, 'BOB@x.com', false, 7); insert into users values (3, 'cy@x.com', false, 1); insert into users values (4, 'dee@x.com', false, 9);",
+        )
+        .unwrap();
+        execute(
+            &mut mb,
+            "create unique index on users (lower(email)); create index users_active_idx on users (score) where deleted = false;",
+        )
+        .unwrap();
+
+        let ids = |mb: &mut MemoryBackend, condition: &str| -> Result<Vec<Value>, SQLError> {
+            let source = format!("select id from users where {};", condition);
+            let results = execute(mb, &source)?.unwrap();
+            Ok(values(&results)
+                .into_iter()
+                .map(|row| row[0].clone())
+                .collect())
+        };
+        // The division fails for the row with id 1, so these only succeed
+        // when an index leaves that row out
+        let tests = vec![
+            ("10 / (id - 1) > 0 and lower(email) = 'bob@x.com'", vec![2]),
+            ("10 / (id - 1) > 0 and 'cy@x.com' = lower(email)", vec![3]),
+            ("10 / (id - 1) > 0 and deleted = false", vec![2, 3, 4]),
+            (
+                "10 / (id - 1) > 0 and score > 5 and deleted = false",
+                vec![2, 4],
+            ),
+            ("lower(email) = 'nobody'", vec![]),
+            ("score > 5", vec![2, 4]),
+            ("score < 5", vec![1, 3]),
+        ];
+        for (condition, expected) in tests {
+            let expected: Vec<Value> = expected.into_iter().map(Value::Int).collect();
+            assert_eq!(ids(&mut mb, condition).unwrap(), expected, "{}", condition);
+        }
+        // Other spellings of the predicate match it too
+        for condition in [
+            "10 / (id - 1) > 0 and false = deleted",
+            "10 / (id - 1) > 0 and not deleted",
+            "10 / (id - 1) > 0 and deleted = 'f'",
+            "10 / (id - 1) > 0 and deleted = 'false' and score > 5",
+        ] {
+            let expected = match condition.contains("score") {
+                true => vec![Value::Int(2), Value::Int(4)],
+                false => vec![Value::Int(2), Value::Int(3), Value::Int(4)],
+            };
+            assert_eq!(ids(&mut mb, condition).unwrap(), expected, "{}", condition);
+        }
+        execute(
+            &mut mb,
+            "create table flags (id int, on_call boolean); insert into flags values (1, true); insert into flags values (2, false); create index flags_idle on flags (id) where not on_call;",
+        )
+        .unwrap();
+        for condition in ["on_call = false", "false = on_call", "not on_call"] {
+            let source = format!(
+                "explain select id from flags where {} and id = 2;",
+                condition
+            );
+            let results = execute(&mut mb, &source).unwrap().unwrap();
+            assert!(
+                values(&results).iter().any(|row| matches!(
+                    &row[0],
+                    Value::Text(plan) if plan.contains("Index Scan using flags_idle on flags")
+                )),
+                "{}",
+                condition
+            );
+        }
+        // Without the predicate of the partial index it is not used
+        assert!(matches!(
+            ids(
+                &mut mb,
+                "10 / (id - 1) > 0 and deleted = true and score > 0"
+            ),
+            Err(SQLError::DivisionByZero)
+        ));
+
+        assert!(matches!(
+            execute(&mut mb, "insert into users values (5, 'ANN@X.COM', false, 0);"),
+            Err(SQLError::UniqueViolation(_, key)) if key == "(lower)=(ann@x.com)"
+        ));
+
+        // Rows move in and out of the partial index as they change
+        execute(
+            &mut mb,
+            "update users set deleted = true where id = 2; update users set deleted = false where id = 1; delete from users where id = 3;",
+        )
+        .unwrap();
+        assert_eq!(
+            ids(&mut mb, "deleted = false").unwrap(),
+            vec![Value::Int(1), Value::Int(4)]
+        );
+        assert_eq!(
+            ids(&mut mb, "lower(email) = 'dee@x.com'").unwrap(),
+            vec![Value::Int(4)]
+        );
+
+        // A unique partial index only constrains the rows it holds
+        execute(
+            &mut mb,
+            "create unique index users_live_score_key on users (score) where deleted = false; insert into users values (6, 'e@x.com', true, 9);",
+        )
+        .unwrap();
+        assert!(matches!(
+            execute(&mut mb, "insert into users values (7, 'f@x.com', false, 9);"),
+            Err(SQLError::UniqueViolation(name, _)) if name == "users_live_score_key"
+        ));
+        execute(&mut mb, "drop index users_lower_idx;").unwrap();
+
+        // Expressions match whatever the case of their function names
+        execute(
+            &mut mb,
+            "create table u (id int, email text); insert into u values (1, 'A@x.com'); insert into u values (2, 'b@x.com'); create index u_low on u (LOWER(email));",
+        )
+        .unwrap();
+        for condition in ["lower(email) = 'a@x.com'", "'a@x.com' = Lower(email)"] {
+            let source = format!("explain select id from u where {};", condition);
+            let results = execute(&mut mb, &source).unwrap().unwrap();
+            assert!(
+                matches!(
+                    &values(&results)[2][0],
+                    Value::Text(plan) if plan.contains("Index Scan using u_low on u")
+                ),
+                "{}",
+                condition
+            );
+        }
+
+        let errors = vec![
+            "create index on users (count(*));",
+            "create index on users (lower(missing));",
+            "create index on users (id) where missing;",
+            "create unique index on users (lower(email)) where id > 0; insert into users values (8, 'DEE@x.com', true, 0);",
+        ];
+        for source in errors {
+            assert!(execute(&mut mb, source).is_err(), "{}", source);
+        }
+    }
+
+    #[test]
+    fn test_planned_selects() {
+        let mut mb = MemoryBackend::new();
+        execute(
+            &mut mb,
+            "create table t (id int, name text, note text); insert into t values (1, 'a', 'x'); insert into t values (2, 'b', 'y'); insert into t values (3, 'c', 'x'); insert into t values (4, 'd', 'y'); insert into t values (5, 'e', 'x');",
+        )
+        .unwrap();
+
+        let tests = vec![
+            ("select id from t order by id desc limit 2;", vec![5, 4]),
+            ("select id from t order by id limit 2 offset 1;", vec![2, 3]),
+            ("select id from t offset 4;", vec![5]),
+            ("select id from t offset 10;", vec![]),
+            ("select id from t limit 0;", vec![]),
+            ("select id from t where id > 3 limit null;", vec![4, 5]),
+            ("select count(*) from t limit 1;", vec![5]),
+            ("select id from t where 1 = 1 and note = 'y';", vec![2, 4]),
+            ("select id from t where 1 = 0 or id = 3;", vec![3]),
+            ("select id from t where 1 = 0;", vec![]),
+            ("select id from t where null and id = 1;", vec![]),
+            (
+                "select row_number() over (order by id desc) from t where note = 'x' order by 1 limit 2;",
+                vec![1, 2],
+            ),
+        ];
+        for (source, expected) in tests {
+            let results = execute(&mut mb, source).unwrap().unwrap();
+            let ids: Vec<i64> = values(&results)
+                .iter()
+                .map(|row| row[0].as_i64().unwrap())
+                .collect();
+            assert_eq!(ids, expected, "{}", source);
+        }
+
+        // Columns keep their names and types when no row is read
+        let results = execute(&mut mb, "select id, name from t where 1 = 0;")
+            .unwrap()
+            .unwrap();
+        assert_eq!(results.columns[0].col_name, "id");
+        assert_eq!(results.columns[1].col_type, ColumnType::TextType);
+
+        let errors = vec![
+            "select id from t limit -1;",
+            "select id from t limit 'x';",
+            "select id from t offset id;",
+            "select id from missing where 1 = 0;",
+        ];
+        for source in errors {
+            assert!(execute(&mut mb, source).is_err(), "{}", source);
+        }
+    }
+
+    #[test]
+    fn test_joins() {
+        let mut mb = MemoryBackend::new();
+        execute(
+            &mut mb,
+            "create table users (id uuid primary key, name text); create table orders (id int, user_id uuid, total int); create table items (order_id int, sku text);",
+        )
+        .unwrap();
+        execute(
+            &mut mb,
+            "insert into users values ('a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11', 'ann'); insert into users values ('b0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11', 'bob'); insert into users values ('c0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11', 'cy');",
+        )
+        .unwrap();
+        execute(
+            &mut mb,
+            "insert into orders values (1, 'a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11', 10); insert into orders values (2, 'A0EEBC99-9C0B-4EF8-BB6D-6BB9BD380A11', 5); insert into orders values (3, 'b0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11', 7); insert into items values (1, 'x'); insert into items values (3, 'y'); insert into items values (3, 'z');",
+        )
+        .unwrap();
+
+        let text = |s: &str| Value::Text(String::from(s));
+        let tests = vec![
+            (
+                "select name, orders.id from users join orders on users.id = user_id order by orders.id;",
+                vec![
+                    vec![text("ann"), Value::Int(1)],
+                    vec![text("ann"), Value::Int(2)],
+                    vec![text("bob"), Value::Int(3)],
+                ],
+            ),
+            (
+                "select name, total from users inner join orders on users.id = orders.user_id and total > 5 where name <> 'bob';",
+                vec![vec![text("ann"), Value::Int(10)]],
+            ),
+            (
+                "select name, sku from users join orders on users.id = user_id join items on orders.id = order_id order by sku desc;",
+                vec![
+                    vec![text("bob"), text("z")],
+                    vec![text("bob"), text("y")],
+                    vec![text("ann"), text("x")],
+                ],
+            ),
+            (
+                "select sum(total) from users join orders on users.id = user_id where name = 'ann';",
+                vec![vec![Value::BigInt(15)]],
+            ),
+            (
+                "select name from users join orders on false;",
+                vec![],
+            ),
+        ];
+        for (source, expected) in tests {
+            let results = execute(&mut mb, source).unwrap().unwrap();
+            assert_eq!(values(&results), expected, "{}", source);
+        }
+
+        let results = execute(
+            &mut mb,
+            "explain select name from users join orders on users.id = user_id;",
+        )
+        .unwrap()
+        .unwrap();
+        let plan: Vec<Value> = values(&results)
+            .into_iter()
+            .map(|row| row[0].clone())
+            .collect();
+        assert_eq!(
+            plan,
+            vec![
+                text("Project  (rows=1)"),
+                text("  Output: name"),
+                text("  ->  Nested Loop  (rows=1)"),
+                text("        Join Filter: (users.id = user_id)"),
+                text("        ->  Seq Scan on users  (rows=3)"),
+                text("              Columns: id, name"),
+                text("        ->  Seq Scan on orders  (rows=3)"),
+                text("              Columns: user_id"),
+            ]
+        );
+
+        // A condition on one table is checked by its scan, through an index
+        // when there is one
+        execute(&mut mb, "create index orders_user_id on orders (user_id);").unwrap();
+        let source = "select name from users join orders on users.id = orders.user_id where orders.user_id = 'b0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11' and name <> 'x';";
+        let results = execute(&mut mb, source).unwrap().unwrap();
+        assert_eq!(values(&results), vec![vec![text("bob")]]);
+        let results = execute(&mut mb, &format!("explain {}", source))
+            .unwrap()
+            .unwrap();
+        let plan: Vec<Value> = values(&results)
+            .into_iter()
+            .map(|row| row[0].clone())
+            .collect();
+        assert_eq!(plan[2], text("  ->  Nested Loop  (rows=1)"));
+        assert_eq!(plan[4], text("        ->  Seq Scan on users  (rows=3)"));
+        assert_eq!(plan[5], text("              Filter: (name <> 'x')"));
+        assert!(
+            matches!(&plan[7], Value::Text(line) if line.contains("Index Scan using orders_user_id on orders")),
+            "{:?}",
+            plan
+        );
+
+        let errors = vec![
+            (
+                "select id from users join orders on users.id = user_id;",
+                "AmbiguousColumn",
+            ),
+            (
+                "select name from users join orders on users.id = orders.missing;",
+                "ColumnDoesNotExist",
+            ),
+            (
+                "select name from users join users on true;",
+                "DuplicateTable",
+            ),
+            (
+                "select name from users join missing on true;",
+                "TableDoesNotExist",
+            ),
+        ];
+        for (source, expected) in errors {
+            let err = match execute(&mut mb, source) {
+                Err(err) => err,
+                Ok(_) => panic!("{} succeeded", source),
+            };
+            assert!(
+                format!("{:?}", err).starts_with(expected),
+                "{}: {:?}",
+                source,
+                err
+            );
+        }
+    }
+
+    #[test]
+    fn test_explain() {
+        let mut mb = MemoryBackend::new();
+        execute(
+            &mut mb,
+            "create table t (id int primary key, name text, grp int); insert into t values (1, 'a', 1); insert into t values (2, 'b', 2); insert into t values (3, 'c', 1); create index t_grp on t (grp);",
+        )
+        .unwrap();
+        let lines = |results: Results| -> Vec<String> {
+            results
+                .rows
+                .iter()
+                .map(|row| row[0].as_ref().unwrap().as_text())
+                .collect()
+        };
+
+        let tests = vec![
+            (
+                "explain select name from t where id = 2;",
+                vec![
+                    "Project  (rows=1)",
+                    "  Output: name",
+                    "  ->  Index Scan using t_pkey on t  (rows=1)",
+                    "        Index Cond: (id = 2)",
+                    "        Filter: (id = 2)",
+                    "        Columns: id, name",
+                ],
+            ),
+            (
+                "explain select id from t where 1 = 1 and name in ('a', 'b') order by name desc limit 1;",
+                vec![
+                    "Limit  (rows=1)",
+                    "  Limit: 1",
+                    "  ->  Sort  (rows=1)",
+                    "        Sort Key: name desc",
+                    "        ->  Project  (rows=1)",
+                    "              Output: id",
+                    "              ->  Seq Scan on t  (rows=1)",
+                    "                    Filter: (name in ('a', 'b'))",
+                    "                    Columns: id, name",
+                ],
+            ),
+            (
+                "explain select count(*) from t where grp between 2 and 5 or 1 = 0;",
+                vec![
+                    "Project  (rows=1)",
+                    "  Output: count(*)",
+                    "  ->  Aggregate  (rows=1)",
+                    "        ->  Index Scan using t_grp on t  (rows=1)",
+                    "              Index Cond: (grp >= 2) and (grp <= 5)",
+                    "              Filter: (grp between 2 and 5)",
+                    "              Columns: grp",
+                ],
+            ),
+            (
+                "explain select -id, name::varchar(3) from t where false;",
+                vec![
+                    "Project  (rows=0)",
+                    "  Output: -id, name::varchar(3)",
+                    "  ->  Empty Scan on t  (rows=0)",
+                    "        Filter: false",
+                    "        Columns: id, name",
+                ],
+            ),
+            (
+                "explain select 1;",
+                vec!["Project  (rows=1)", "  Output: 1", "  ->  Result  (rows=1)"],
+            ),
+            (
+                "explain update t set name = 'x' where grp = 1;",
+                vec![
+                    "Update on t  (rows=2)",
+                    "  ->  Index Scan using t_grp on t  (rows=2)",
+                    "        Index Cond: (grp = 1)",
+                    "        Filter: (grp = 1)",
+                ],
+            ),
+            (
+                "explain delete from t;",
+                vec!["Delete on t  (rows=3)", "  ->  Seq Scan on t  (rows=3)"],
+            ),
+            (
+                "explain delete from t where false;",
+                vec![
+                    "Delete on t  (rows=0)",
+                    "  ->  Empty Scan on t  (rows=0)",
+                    "        Filter: false",
+                ],
+            ),
+            (
+                "explain insert into t values (4, 'd', 2);",
+                vec!["Insert on t  (rows=1)", "  ->  Result  (rows=1)"],
+            ),
+        ];
+        for (source, expected) in tests {
+            let results = execute(&mut mb, source).unwrap().unwrap();
+            assert_eq!(results.columns[0].col_name, "QUERY PLAN");
+            assert_eq!(lines(results), expected, "{}", source);
+        }
+
+        // ANALYZE runs a modification through the plan shown, then undoes it
+        let tests = vec![
+            (
+                "explain analyze update t set name = 'x' where grp = 1 returning id;",
+                vec![(0, 2), (1, 2)],
+            ),
+            (
+                "explain analyze delete from t where grp = 2;",
+                vec![(0, 0), (1, 1)],
+            ),
+            (
+                "explain analyze insert into t values (4, 'd', 2);",
+                vec![(0, 0), (1, 1)],
+            ),
+        ];
+        for (source, expected) in tests {
+            let lines = lines(execute(&mut mb, source).unwrap().unwrap());
+            for (line, rows) in expected {
+                assert!(
+                    lines[line].contains(&format!("(actual rows={} time=", rows)),
+                    "{}: {}",
+                    source,
+                    lines[line]
+                );
+            }
+            assert!(lines.last().unwrap().starts_with("Execution Time: "));
+        }
+        assert!(execute(&mut mb, "explain analyze insert into t values (1, 'd', 2);").is_err());
+        assert!(execute(&mut mb, "explain analyze update t set id = 1 where id = 3;").is_err());
+        let results = execute(&mut mb, "select id from t where grp = 1;")
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            values(&results),
+            vec![vec![Value::Int(1)], vec![Value::Int(3)]]
+        );
+
+        // ANALYZE runs the query and reports what each operator did
+        let results = execute(
+            &mut mb,
+            "explain analyze select name from t where grp = 1 order by name;",
+        )
+        .unwrap()
+        .unwrap();
+        let lines = lines(results);
+        assert_eq!(lines.len(), 9);
+        for (line, rows) in [(0, 2), (2, 2), (4, 2)] {
+            assert!(
+                lines[line].contains(&format!("(actual rows={} time=", rows)),
+                "{}",
+                lines[line]
+            );
+        }
+        assert!(lines[4].starts_with("        ->  Index Scan using t_grp on t  (rows=2)"));
+        assert!(lines[8].starts_with("Execution Time: "));
+
+        assert!(execute(&mut mb, "explain select id from missing;").is_err());
+        assert!(execute(&mut mb, "explain delete from missing;").is_err());
+        assert!(execute(&mut mb, "explain insert into missing values (1);").is_err());
+
+        let results = execute(&mut mb, "select name from t order by id;")
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            values(&results),
+            vec![
+                vec![Value::Text(String::from("a"))],
+                vec![Value::Text(String::from("b"))],
+                vec![Value::Text(String::from("c"))],
+            ]
+        );
+        assert!(execute(&mut mb, "explain analyze select 1 / 0;").is_err());
+    }
+
+    #[test]
+    fn test_non_reserved_keywords_as_names() {
+        let mut mb = MemoryBackend::new();
+        execute(
+            &mut mb,
+            "create table x (date date, time time, rows int, range int, row int, current int, type text, index int); insert into x (date, time, rows, range, row, current, type, index) values (date '2024-03-04', time '10:00:00', 1, 2, 3, 4, 'a', 5);",
+        )
+        .unwrap();
+        execute(&mut mb, "create index type on x (type);").unwrap();
+        execute(
+            &mut mb,
+            "update x set type = 'b', index = index + 1 where rows = 1;",
+        )
+        .unwrap();
+
+        let results = execute(
+            &mut mb,
+            "select type, index, range + row + current from x where type = 'b' and date < date '2025-01-01' order by time;",
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(
+            values(&results),
+            vec![vec![
+                Value::Text(String::from("b")),
+                Value::Int(6),
+                Value::Int(9),
+            ]]
+        );
+        assert_eq!(results.columns[0].col_name, "type");
+
+        // Typed literals still parse as casts
+        let results = execute(&mut mb, "select interval '1 day' = interval '24 hours';")
+            .unwrap()
+            .unwrap();
+        assert_eq!(values(&results), vec![vec![Value::Bool(true)]]);
+
+        execute(
+            &mut mb,
+            "create table y (generated int, identity int, analyze int, cascade int, restrict int, always int, escape text, glob text, unbounded int, preceding int, following int, partition int);",
+        )
+        .unwrap();
+        execute(
+            &mut mb,
+            "insert into y values (1, 2, 3, 4, 5, 6, 'ab', 'cd', 7, 8, 9, 10);",
+        )
+        .unwrap();
+        let results = execute(
+            &mut mb,
+            "select generated + identity + analyze, cascade + restrict + always, unbounded + preceding + following, sum(partition) over (partition by partition) from y where escape like 'a%' escape '!' and glob glob 'c*';",
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(
+            values(&results),
+            vec![vec![
+                Value::Int(6),
+                Value::Int(15),
+                Value::Int(24),
+                Value::BigInt(10),
+            ]]
+        );
+    }
+
+    #[test]
+    fn test_column_constraints() {
+        let mut mb = MemoryBackend::new();
+        execute(
+            &mut mb,
+            "create table items (id int not null, name text default 'unnamed' not null, price numeric(6, 2) check (price > 0), qty int default 1 + 1, constraint stock check (qty >= 0 or price is null)); insert into items (id, price) values (1, 9.5); insert into items (qty, id, name) values (0, 2, 'pen'); insert into items values (3, 'cup', null, -1);",
+        )
+        .unwrap();
+
+        let results = execute(
+            &mut mb,
+            "select id, name, price, qty from items order by id;",
+        )
+        .unwrap()
+        .unwrap();
+        let text = |s: &str| Value::Text(s.to_string());
+        assert_eq!(
+            values(&results),
+            vec![
+                vec![
+                    Value::Int(1),
+                    text("unnamed"),
+                    Value::Numeric(Decimal::parse("9.50").unwrap()),
+                    Value::Int(2)
+                ],
+                vec![Value::Int(2), text("pen"), Value::Null, Value::Int(0)],
+                vec![Value::Int(3), text("cup"), Value::Null, Value::Int(-1)],
+            ]
+        );
+
+        let tests = vec![
+            (
+                "insert into items (name) values ('box');",
+                SQLError::NotNullViolation(
+                    String::from("id"),
+                    String::from("(null, box, null, 2)"),
+                ),
+            ),
+            (
+                "insert into items values (4, null, 1, 1);",
+                SQLError::NotNullViolation(
+                    String::from("name"),
+                    String::from("(4, null, 1.00, 1)"),
+                ),
+            ),
+            (
+                "insert into items (id, price) values (4, 0);",
+                SQLError::CheckViolation(
+                    String::from("items_price_check"),
+                    String::from("(4, unnamed, 0.00, 2)"),
+                ),
+            ),
+            (
+                "update items set qty = -1 where id = 1;",
+                SQLError::CheckViolation(
+                    String::from("stock"),
+                    String::from("(1, unnamed, 9.50, -1)"),
+                ),
+            ),
+            (
+                "update items set id = null;",
+                SQLError::NotNullViolation(
+                    String::from("id"),
+                    String::from("(null, unnamed, 9.50, 2)"),
+                ),
+            ),
+        ];
+        for (source, expected) in tests {
+            match execute(&mut mb, source) {
+                Err(err) => assert_eq!(err.to_string(), expected.to_string(), "{}", source),
+                Ok(_) => panic!("{} succeeded", source),
+            }
+        }
+        let results = execute(&mut mb, "select count(*) from items where id is not null;")
+            .unwrap()
+            .unwrap();
+        assert_eq!(results.rows[0][0].as_ref().unwrap().as_int(), 3);
+
+        let errors = vec![
+            ("insert into items (id, id) values (5, 5);", "duplicate"),
+            ("insert into items (id, nope) values (5, 5);", "missing"),
+            ("insert into items (id, name) values (5);", "values"),
+            ("create table bad (a int default a + 1);", "constraint"),
+            ("create table bad (a int check (b > 0));", "missing"),
+            (
+                "create table bad (a int constraint c unique, b int constraint c check (b > 0));",
+                "constraint",
+            ),
+        ];
+        for (source, kind) in errors {
+            let result = execute(&mut mb, source);
+            let matched = match kind {
+                "duplicate" => matches!(result, Err(SQLError::DuplicateColumn(_))),
+                "missing" => matches!(result, Err(SQLError::ColumnDoesNotExist(_))),
+                "values" => matches!(result, Err(SQLError::MissingValues)),
+                _ => matches!(result, Err(SQLError::InvalidConstraint(_))),
+            };
+            assert!(matched, "{}", source);
+        }
+    }
+
+    #[test]
+    fn test_boolean() {
+        let mut mb = MemoryBackend::new();
+        execute(
+            &mut mb,
+            "create table flags (id int, active boolean); insert into flags values (1, true); insert into flags values (2, 'f'); insert into flags values (3, null);",
+        )
+        .unwrap();
+
+        let tests = vec![
+            (
+                "select id from flags where active;",
+                vec![vec![Value::Int(1)]],
+            ),
+            (
+                "select id from flags where not active or active is null;",
+                vec![vec![Value::Int(2)], vec![Value::Int(3)]],
+            ),
+            (
+                "select id > 1, active from flags where id < 3;",
+                vec![
+                    vec![Value::Bool(false), Value::Bool(true)],
+                    vec![Value::Bool(true), Value::Bool(false)],
+                ],
+            ),
+            (
+                "select active::text, true::int, 'yes'::bool from flags where id = 1;",
+                vec![vec![
+                    Value::Text(String::from("true")),
+                    Value::Int(1),
+                    Value::Bool(true),
+                ]],
+            ),
+            (
+                "select count(*) from flags where active = false;",
+                vec![vec![Value::BigInt(1)]],
+            ),
+        ];
+
+        for (source, expected) in tests {
+            let results = execute(&mut mb, source).unwrap().unwrap();
+            assert_eq!(values(&results), expected, "{}", source);
+        }
+
+        let results = execute(&mut mb, "select id = 1 from flags where false;")
+            .unwrap()
+            .unwrap();
+        assert_eq!(results.columns[0].col_type, ColumnType::BoolType);
+
+        assert!(matches!(
+            execute(&mut mb, "select id from flags where id;"),
+            Err(SQLError::TypeMismatch(_))
+        ));
+        assert!(execute(&mut mb, "insert into flags values (4, 'maybe');").is_err());
+    }
+
+    #[test]
+    fn test_window_functions() {
+        let mut mb = MemoryBackend::new();
+        execute(
+            &mut mb,
+            "create table scores (team text, player text, points int); insert into scores values ('red', 'ann', 10); insert into scores values ('red', 'bob', 30); insert into scores values ('blue', 'cid', 20); insert into scores values ('red', 'dan', 30); insert into scores values ('blue', 'eve', 5);",
+        )
+        .unwrap();
+
+        let tests = vec![
+            (
+                "select row_number() over (partition by team order by points desc) from scores;",
+                vec![3, 1, 1, 2, 2],
+            ),
+            (
+                "select rank() over (order by points desc) from scores;",
+                vec![4, 1, 3, 1, 5],
+            ),
+            (
+                "select dense_rank() over (order by points desc) from scores;",
+                vec![3, 1, 2, 1, 4],
+            ),
+            (
+                "select sum(points) over (partition by team) from scores;",
+                vec![70, 70, 25, 70, 25],
+            ),
+            (
+                "select sum(points) over (order by points rows between unbounded preceding and current row) from scores;",
+                vec![15, 65, 35, 95, 5],
+            ),
+            (
+                "select sum(points) over (order by points range between 10 preceding and current row) from scores;",
+                vec![15, 80, 30, 80, 5],
+            ),
+            (
+                "select lag(points, 1, 0) over (partition by team order by points) from scores;",
+                vec![0, 10, 5, 30, 0],
+            ),
+
+            (
+                "select first_value(points) over (partition by team order by points desc) from scores;",
+                vec![30, 30, 20, 30, 20],
+            ),
+            (
+                "select last_value(points) over (order by points rows between current row and 1 following) from scores;",
+                vec![20, 30, 30, 30, 10],
+            ),
+            (
+                "select count(*) over () from scores where team = 'blue';",
+                vec![2, 2],
+            ),
+            ("select rank() over (order by count(*)) from scores;", vec![1]),
+        ];
+
+        for (source, expected) in tests {
+            let results = execute(&mut mb, source).unwrap().unwrap();
+            let ints: Vec<i64> = results
+                .rows
+                .iter()
+                .map(|row| row[0].as_ref().unwrap().as_int())
+                .collect();
+            assert_eq!(ints, expected, "{}", source);
+        }
+
+        let results = execute(
+            &mut mb,
+            "select lead(points) over (order by points) is null from scores;",
+        )
+        .unwrap()
+        .unwrap();
+        let is_last: Vec<Value> = [false, false, false, true, false]
+            .into_iter()
+            .map(Value::Bool)
+            .collect();
+        assert_eq!(values(&results).concat(), is_last);
+
+        let results = execute(&mut mb, "select rank() over (order by points) from scores;")
+            .unwrap()
+            .unwrap();
+        assert_eq!(results.columns[0].col_name, "rank");
+
+        assert!(matches!(
+            execute(&mut mb, "select row_number() from scores;"),
+            Err(SQLError::InvalidWindowFunction(_))
+        ));
+        assert!(matches!(
+            execute(&mut mb, "select upper(player) over () from scores;"),
+            Err(SQLError::InvalidWindowFunction(_))
+        ));
+        assert!(execute(
+            &mut mb,
+            "select player from scores where rank() over (order by points) = 1;"
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_as_text() {
+        let mc: MemCell = [72, 101, 108, 108, 111].to_vec(); // ASCII for "Hello"
+        assert_eq!(mc.as_text(), String::from("Hello"));
+    }
+
+    #[test]
     fn test_as_int() {
         let number: i32 = -32;
         let bytes = number.to_le_bytes();
         let mc: MemCell = bytes.to_vec();
         assert_eq!(-32, mc.as_int());
+
+        let mc: MemCell = (-2i16).to_le_bytes().to_vec();
+        assert_eq!(-2, mc.as_int());
+
+        let mc: MemCell = i64::MAX.to_le_bytes().to_vec();
+        assert_eq!(i64::MAX, mc.as_int());
     }
 }
@@ -0,0 +1,384 @@
+use core::fmt;
+use std::{
+    cmp::Ordering,
+    hash::{Hash, Hasher},
+};
+
+/// Most digits a `Decimal` can hold.
+pub const MAX_PRECISION: u32 = 38;
+
+/// An exact decimal number: `mantissa * 10^-scale`.
+#[derive(Debug, Clone, Copy)]
+pub struct Decimal {
+    mantissa: i128,
+    scale: u32,
+}
+
+impl Decimal {
+    pub fn new(mantissa: i128, scale: u32) -> Decimal {
+        Decimal { mantissa, scale }
+    }
+
+    pub fn from_i64(i: i64) -> Decimal {
+        Decimal::new(i as i128, 0)
+    }
+
+    pub fn mantissa(&self) -> i128 {
+        self.mantissa
+    }
+
+    pub fn scale(&self) -> u32 {
+        self.scale
+    }
+
+    /// Parses `123`, `-1.50`, `.5` and `1e10` style numbers.
+    pub fn parse(s: &str) -> Option<Decimal> {
+        let s = s.trim();
+        let (number, exponent) = match s.find(['e', 'E']) {
+            Some(i) => (&s[..i], s[i + 1..].parse::<i32>().ok()?),
+            None => (s, 0),
+        };
+        let (negative, number) = match number.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, number.strip_prefix('+').unwrap_or(number)),
+        };
+        let (whole, fraction) = match number.split_once('.') {
+            Some((whole, fraction)) => (whole, fraction),
+            None => (number, ""),
+        };
+        if whole.is_empty() && fraction.is_empty() {
+            return None;
+        }
+        if !whole
+            .chars()
+            .chain(fraction.chars())
+            .all(|c| c.is_ascii_digit())
+        {
+            return None;
+        }
+
+        let mut mantissa: i128 = 0;
+        for c in whole.chars().chain(fraction.chars()) {
+            mantissa = mantissa
+                .checked_mul(10)?
+                .checked_add(c.to_digit(10).unwrap() as i128)?;
+        }
+        if negative {
+            mantissa = -mantissa;
+        }
+
+        let scale = fraction.len() as i32 - exponent;
+        if scale >= 0 {
+            Some(Decimal::new(mantissa, scale as u32))
+        } else {
+            let mantissa = mantissa.checked_mul(10i128.checked_pow((-scale) as u32)?)?;
+            Some(Decimal::new(mantissa, 0))
+        }
+    }
+
+    /// Rounds half away from zero, or pads with zeros, to `scale` digits
+    /// after the point.
+    pub fn rescale(&self, scale: u32) -> Option<Decimal> {
+        if scale >= self.scale {
+            let factor = 10i128.checked_pow(scale - self.scale)?;
+            return Some(Decimal::new(self.mantissa.checked_mul(factor)?, scale));
+        }
+
+        let factor = 10i128.checked_pow(self.scale - scale)?;
+        let quotient = self.mantissa / factor;
+        let remainder = self.mantissa % factor;
+        let rounded = if remainder.abs() * 2 >= factor {
+            quotient + self.mantissa.signum()
+        } else {
+            quotient
+        };
+        Some(Decimal::new(rounded, scale))
+    }
+
+    /// The number of digits before the point.
+    pub fn whole_digits(&self) -> u32 {
+        let whole = self.mantissa.abs() / 10i128.pow(self.scale.min(MAX_PRECISION));
+        if whole == 0 {
+            0
+        } else {
+            whole.ilog10() + 1
+        }
+    }
+
+    /// Rounds to an integer, half away from zero.
+    pub fn round(&self) -> Option<i128> {
+        self.rescale(0).map(|d| d.mantissa)
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        self.to_string().parse().unwrap()
+    }
+
+    pub fn from_f64(f: f64) -> Option<Decimal> {
+        if !f.is_finite() {
+            return None;
+        }
+        Decimal::parse(&f.to_string())
+    }
+
+    fn aligned(&self, other: &Decimal) -> Option<(i128, i128, u32)> {
+        let scale = self.scale.max(other.scale);
+        Some((
+            self.rescale(scale)?.mantissa,
+            other.rescale(scale)?.mantissa,
+            scale,
+        ))
+    }
+
+    pub fn checked_add(&self, other: &Decimal) -> Option<Decimal> {
+        let (a, b, scale) = self.aligned(other)?;
+        Some(Decimal::new(a.checked_add(b)?, scale))
+    }
+
+    pub fn checked_sub(&self, other: &Decimal) -> Option<Decimal> {
+        let (a, b, scale) = self.aligned(other)?;
+        Some(Decimal::new(a.checked_sub(b)?, scale))
+    }
+
+    pub fn checked_mul(&self, other: &Decimal) -> Option<Decimal> {
+        let mantissa = self.mantissa.checked_mul(other.mantissa)?;
+        Some(Decimal::new(mantissa, self.scale + other.scale))
+    }
+
+    /// Division keeps at least 16 digits after the point, or the larger
+    /// scale of the operands. `None` on overflow or division by zero.
+    pub fn checked_div(&self, other: &Decimal) -> Option<Decimal> {
+        if other.mantissa == 0 {
+            return None;
+        }
+        let scale = self.scale.max(other.scale).max(16);
+        // a / b at `scale` digits is a * 10^(scale + 1 + b.scale - a.scale) / b,
+        // with one extra digit for rounding
+        let shift = scale + 1 + other.scale;
+        let numerator = if shift >= self.scale {
+            self.mantissa
+                .checked_mul(10i128.checked_pow(shift - self.scale)?)?
+        } else {
+            self.mantissa / 10i128.pow(self.scale - shift)
+        };
+        Decimal::new(numerator / other.mantissa, scale + 1).rescale(scale)
+    }
+
+    pub fn checked_rem(&self, other: &Decimal) -> Option<Decimal> {
+        let (a, b, scale) = self.aligned(other)?;
+        Some(Decimal::new(a.checked_rem(b)?, scale))
+    }
+
+    pub fn checked_neg(&self) -> Option<Decimal> {
+        Some(Decimal::new(self.mantissa.checked_neg()?, self.scale))
+    }
+
+    pub fn abs(&self) -> Decimal {
+        Decimal::new(self.mantissa.abs(), self.scale)
+    }
+
+    /// Drops trailing zeros after the point, so equal numbers compare and
+    /// hash alike regardless of scale.
+    fn normalized(&self) -> Decimal {
+        let mut normalized = *self;
+        while normalized.scale > 0 && normalized.mantissa % 10 == 0 {
+            normalized.mantissa /= 10;
+            normalized.scale -= 1;
+        }
+        normalized
+    }
+}
+
+impl PartialEq for Decimal {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Decimal {}
+
+impl PartialOrd for Decimal {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Decimal {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let (a, b) = (self.normalized(), other.normalized());
+        match a.aligned(&b) {
+            Some((a, b, _)) => a.cmp(&b),
+            // Too far apart to align exactly
+            None => self.to_f64().total_cmp(&other.to_f64()),
+        }
+    }
+}
+
+impl Hash for Decimal {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let normalized = self.normalized();
+        normalized.mantissa.hash(state);
+        normalized.scale.hash(state);
+    }
+}
+
+impl fmt::Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let digits = self.mantissa.unsigned_abs().to_string();
+        let sign = if self.mantissa < 0 { "-" } else { "" };
+        if self.scale == 0 {
+            return write!(f, "{}{}", sign, digits);
+        }
+
+        let scale = self.scale as usize;
+        let digits = format!("{:0>width$}", digits, width = scale + 1);
+        let (whole, fraction) = digits.split_at(digits.len() - scale);
+        write!(f, "{}{}.{}", sign, whole, fraction)
+    }
+}
+
+/// A floating point number with a total order, so that values can be sorted
+/// and hashed. Like PostgreSQL, NaN equals itself and sorts above every other
+/// number, and -0 equals 0.
+#[derive(Debug, Clone, Copy)]
+pub struct Float(pub f64);
+
+impl PartialEq for Float {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Float {}
+
+impl PartialOrd for Float {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Float {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.0.is_nan(), other.0.is_nan()) {
+            (true, true) => Ordering::Equal,
+            (true, false) => Ordering::Greater,
+            (false, true) => Ordering::Less,
+            _ => self.0.partial_cmp(&other.0).unwrap(),
+        }
+    }
+}
+
+impl Hash for Float {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        if self.0 == 0.0 {
+            0.0f64.to_bits().hash(state)
+        } else if self.0.is_nan() {
+            f64::NAN.to_bits().hash(state)
+        } else {
+            self.0.to_bits().hash(state)
+        }
+    }
+}
+
+/// Formats a float the way PostgreSQL prints it: the shortest exact digits,
+/// in exponent form when very small or beyond the type's precision. `single`
+/// formats with the precision of a real.
+pub fn format_float(f: f64, single: bool) -> String {
+    if f.is_nan() {
+        return String::from("NaN");
+    }
+    if f.is_infinite() {
+        return String::from(if f > 0.0 { "Infinity" } else { "-Infinity" });
+    }
+
+    let (scientific, plain, digits) = if single {
+        (format!("{:e}", f as f32), (f as f32).to_string(), 6)
+    } else {
+        (format!("{:e}", f), f.to_string(), 15)
+    };
+    let (mantissa, exponent) = scientific.split_once('e').unwrap();
+    let exponent: i32 = exponent.parse().unwrap();
+    if (-4..digits).contains(&exponent) {
+        return plain;
+    }
+    let sign = if exponent < 0 { '-' } else { '+' };
+    format!("{}e{}{:02}", mantissa, sign, exponent.abs())
+}
+
+#[cfg(test)]
+mod numeric_test {
+    use crate::numeric::{format_float, Decimal, Float};
+
+    #[test]
+    fn test_decimal() {
+        let tests = vec![
+            ("3.14", "3.14"),
+            (".5", "0.5"),
+            ("-0.05", "-0.05"),
+            ("1e3", "1000"),
+            ("1.5e-2", "0.015"),
+            ("007", "7"),
+        ];
+        for (input, expected) in tests {
+            assert_eq!(Decimal::parse(input).unwrap().to_string(), expected);
+        }
+        assert!(Decimal::parse("1.2.3").is_none());
+        assert!(Decimal::parse(".").is_none());
+
+        let a = Decimal::parse("1.005").unwrap();
+        assert_eq!(a.rescale(2).unwrap().to_string(), "1.01");
+        assert_eq!(
+            a.checked_neg().unwrap().rescale(2).unwrap().to_string(),
+            "-1.01"
+        );
+        assert_eq!(a.rescale(5).unwrap().to_string(), "1.00500");
+
+        let b = Decimal::parse("0.1").unwrap();
+        let c = Decimal::parse("0.2").unwrap();
+        assert_eq!(b.checked_add(&c).unwrap(), Decimal::parse("0.3").unwrap());
+        assert_eq!(
+            Decimal::from_i64(1)
+                .checked_div(&Decimal::from_i64(3))
+                .unwrap()
+                .to_string(),
+            "0.3333333333333333"
+        );
+        assert_eq!(
+            Decimal::parse("2.50")
+                .unwrap()
+                .checked_mul(&c)
+                .unwrap()
+                .to_string(),
+            "0.500"
+        );
+        assert!(Decimal::from_i64(1)
+            .checked_div(&Decimal::from_i64(0))
+            .is_none());
+        assert_eq!(Decimal::parse("123.45").unwrap().whole_digits(), 3);
+        assert_eq!(Decimal::parse("0.45").unwrap().whole_digits(), 0);
+    }
+
+    #[test]
+    fn test_float_order() {
+        assert_eq!(Float(0.0), Float(-0.0));
+        assert_eq!(Float(f64::NAN), Float(f64::NAN));
+        assert!(Float(f64::NAN) > Float(f64::INFINITY));
+        assert!(Float(1.5) < Float(2.0));
+    }
+
+    #[test]
+    fn test_format_float() {
+        let tests = vec![
+            (1e10, false, "10000000000"),
+            (1e15, false, "1e+15"),
+            (0.0001, false, "0.0001"),
+            (0.000015, false, "1.5e-05"),
+            (0.1, true, "0.1"),
+            (1234567.0, true, "1.234567e+06"),
+            (f64::NEG_INFINITY, false, "-Infinity"),
+        ];
+        for (f, single, expected) in tests {
+            assert_eq!(format_float(f, single), expected);
+        }
+    }
+}
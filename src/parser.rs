@@ -1,17 +1,19 @@
 use std::vec;
 
 use crate::ast::{
-    ColDefinition, Create, Expression, ExpressionKind, Insert, Select, StatementKind,
+    Assignment, Between, BinaryExpression, Cast, ColDefinition, ConflictAction, Constraint,
+    ConstraintKind, Create, CreateIndex, CreateSequence, CreateType, DataType, Delete, DropIndex,
+    Explain, Expression, ExpressionKind, FrameBound, FrameBoundKind, FunctionCall, Identity,
+    InList, Insert, IsNull, Join, OnConflict, OrderingTerm, PatternMatch, Quantified, References,
+    ReferentialAction, Select, SequenceOptions, StatementKind, Subscript, UnaryExpression, Update,
+    WindowFrame, WindowSpec,
 };
-use crate::lexer::{Keyword, Location, Symbol, TokenKind};
+use crate::lexer::{Keyword, Location, Symbol, TokenKind, NON_RESERVED_KEYWORDS};
 use crate::{ast::Ast, lexer::lex};
 use crate::{ast::Statement, lexer::Token};
 
 pub fn parse(source: String) -> Result<Ast, String> {
-    let tokens = match lex(&source) {
-        Ok(tokens) => tokens,
-        Err(e) => return Err(e),
-    };
+    let tokens = lex(&source)?;
 
     let mut cursor = 0;
     let mut ast = Ast {
@@ -19,36 +21,21 @@ pub fn parse(source: String) -> Result<Ast, String> {
     };
 
     while cursor < tokens.len() {
-        let (statement, new_cursor) = match parse_statement(
-            &tokens,
-            cursor,
-            Token {
-                literal: Symbol::Semicolon.to_string(),
-                token_kind: TokenKind::Symbol,
-                loc: Location::new(),
-            },
-        ) {
-            Ok((statement, new_cursor)) => (statement, new_cursor),
-            Err(_) => {
-                help_message(&tokens, cursor, String::from("Expected a statement"));
-                return Err(String::from("Expected a statement"));
-            }
-        };
+        let (statement, new_cursor) =
+            match parse_statement(&tokens, cursor, symbol_token(Symbol::Semicolon)) {
+                Ok((statement, new_cursor)) => (statement, new_cursor),
+                Err(_) => {
+                    help_message(&tokens, cursor, String::from("Expected a statement"));
+                    return Err(String::from("Expected a statement"));
+                }
+            };
 
         cursor = new_cursor;
 
         ast.statements.push(statement);
 
         let mut at_least_one_semicolon = false;
-        while expect_token(
-            &tokens,
-            cursor,
-            Token {
-                literal: Symbol::Semicolon.to_string(),
-                token_kind: TokenKind::Symbol,
-                loc: Location::new(),
-            },
-        ) {
+        while expect_token(&tokens, cursor, symbol_token(Symbol::Semicolon)) {
             at_least_one_semicolon = true;
             cursor += 1;
         }
@@ -66,45 +53,85 @@ pub fn parse(source: String) -> Result<Ast, String> {
 }
 
 fn parse_statement(
-    tokens: &Vec<Token>,
+    tokens: &[Token],
     cursor_in: usize,
     delimiter: Token,
 ) -> Result<(Statement, usize), ()> {
-    match parse_select(&tokens, cursor_in, &delimiter) {
-        Ok((select, new_cursor)) => {
-            let stmt = Statement {
-                select: Some(select),
-                create: None,
-                insert: None,
-                kind: StatementKind::Select,
-            };
-            return Ok((stmt, new_cursor));
-        }
-        Err(_) => {}
+    if let Ok((explain, new_cursor)) = parse_explain(tokens, cursor_in, &delimiter) {
+        return Ok((explain, new_cursor));
+    }
+
+    if let Ok((select, new_cursor)) = parse_select(tokens, cursor_in, &delimiter) {
+        let stmt = Statement {
+            select: Some(select),
+            create: None,
+            insert: None,
+            update: None,
+            delete: None,
+            create_type: None,
+            create_sequence: None,
+            create_index: None,
+            drop_index: None,
+            explain: None,
+            kind: StatementKind::Select,
+        };
+        return Ok((stmt, new_cursor));
     };
 
-    match parse_insert(&tokens, cursor_in) {
-        Ok((insert, new_cursor)) => {
-            return Ok((insert, new_cursor));
-        }
-        Err(_) => {}
+    if let Ok((insert, new_cursor)) = parse_insert(tokens, cursor_in) {
+        return Ok((insert, new_cursor));
     }
 
-    match parse_create(&tokens, cursor_in) {
-        Ok((select, new_cursor)) => {
-            return Ok((select, new_cursor));
-        }
-        Err(_) => {}
+    if let Ok((create_type, new_cursor)) = parse_create_type(tokens, cursor_in) {
+        return Ok((create_type, new_cursor));
+    }
+
+    if let Ok((create_sequence, new_cursor)) = parse_create_sequence(tokens, cursor_in) {
+        return Ok((create_sequence, new_cursor));
+    }
+
+    if let Ok((create_index, new_cursor)) = parse_create_index(tokens, cursor_in) {
+        return Ok((create_index, new_cursor));
+    }
+
+    if let Ok((drop_index, new_cursor)) = parse_drop_index(tokens, cursor_in) {
+        return Ok((drop_index, new_cursor));
+    }
+
+    if let Ok((select, new_cursor)) = parse_create(tokens, cursor_in) {
+        return Ok((select, new_cursor));
+    }
+
+    if let Ok((update, new_cursor)) = parse_update(tokens, cursor_in, &delimiter) {
+        return Ok((update, new_cursor));
+    }
+
+    if let Ok((delete, new_cursor)) = parse_delete(tokens, cursor_in, &delimiter) {
+        return Ok((delete, new_cursor));
     }
 
     Err(())
 }
 
-fn parse_token(
-    tokens: &Vec<Token>,
-    cursor_in: usize,
-    kind: TokenKind,
-) -> Result<(Token, usize), ()> {
+/// An identifier, or a keyword that is not reserved and so may still name a
+/// column or table, such as date, type or index. The keyword is returned as
+/// an identifier.
+fn parse_identifier(tokens: &[Token], cursor: usize) -> Result<(Token, usize), ()> {
+    let token = tokens.get(cursor).ok_or(())?;
+    let non_reserved = token.token_kind == TokenKind::Keyword
+        && NON_RESERVED_KEYWORDS
+            .iter()
+            .any(|keyword| token.literal == keyword.to_string());
+    if token.token_kind != TokenKind::Identifier && !non_reserved {
+        return Err(());
+    }
+
+    let mut token = token.clone();
+    token.token_kind = TokenKind::Identifier;
+    Ok((token, cursor + 1))
+}
+
+fn parse_token(tokens: &[Token], cursor_in: usize, kind: TokenKind) -> Result<(Token, usize), ()> {
     let cursor = cursor_in;
 
     if cursor >= tokens.len() {
@@ -126,7 +153,7 @@ fn parse_token(
 }
 
 fn parse_expressions(
-    tokens: &Vec<Token>,
+    tokens: &[Token],
     cursor_in: usize,
     delimiters: &Vec<Token>,
 ) -> Result<(Vec<Expression>, usize), ()> {
@@ -151,24 +178,20 @@ fn parse_expressions(
             }
         }
 
-        if expressions.len() > 0 {
-            let comma = Token {
-                literal: Symbol::Comma.to_string(),
-                token_kind: TokenKind::Symbol,
-                loc: Location::new(),
-            };
+        if !expressions.is_empty() {
+            let comma = symbol_token(Symbol::Comma);
 
-            if !expect_token(&tokens, cursor, comma) {
-                help_message(&tokens, cursor, String::from("Expected comma"));
+            if !expect_token(tokens, cursor, comma) {
+                help_message(tokens, cursor, String::from("Expected comma"));
                 return Err(());
             }
             cursor += 1;
         }
 
-        let (exp, new_cursor) = match parse_expression(&tokens, cursor) {
+        let (exp, new_cursor) = match parse_expression(tokens, cursor, 0) {
             Ok((exp, new_cursor)) => (exp, new_cursor),
             Err(()) => {
-                help_message(&tokens, cursor_in, String::from("Expected expression"));
+                help_message(tokens, cursor_in, String::from("Expected expression"));
                 return Err(());
             }
         };
@@ -179,357 +202,2284 @@ fn parse_expressions(
     Ok((expressions, cursor))
 }
 
-fn parse_expression(tokens: &Vec<Token>, cursor_in: usize) -> Result<(Expression, usize), ()> {
-    let allowed_kinds = vec![TokenKind::Identifier, TokenKind::Numeric, TokenKind::String];
+// Binding powers of the infix operators, higher binds tighter.
+const OR_POWER: u32 = 1;
+const AND_POWER: u32 = 2;
+const NOT_POWER: u32 = 3;
+const COMPARISON_POWER: u32 = 4;
+const OPERATOR_POWER: u32 = 5;
+const ADDITIVE_POWER: u32 = 6;
+const MULTIPLICATIVE_POWER: u32 = 7;
+const NEGATION_POWER: u32 = 8;
+const CAST_POWER: u32 = 10;
+const SUBSCRIPT_POWER: u32 = 11;
+
+fn binding_power(token: &Token) -> u32 {
+    match token.token_kind {
+        TokenKind::Keyword => match token.literal.as_str() {
+            "or" => OR_POWER,
+            "and" => AND_POWER,
+            _ => 0,
+        },
+        TokenKind::Symbol => match token.literal.as_str() {
+            "=" | "<>" | "!=" | "<" | "<=" | ">" | ">=" => COMPARISON_POWER,
+            "->" | "->>" => OPERATOR_POWER,
+            "+" | "-" => ADDITIVE_POWER,
+            "*" | "/" | "%" => MULTIPLICATIVE_POWER,
+            _ => 0,
+        },
+        _ => 0,
+    }
+}
 
-    for kind in allowed_kinds {
-        if let Ok((token, cursor)) = parse_token(&tokens, cursor_in, kind) {
-            return Ok((
-                Expression {
-                    literal: token,
-                    kind: ExpressionKind::Literal,
-                },
-                cursor,
-            ));
+fn is_pattern_operator(token: &Token) -> bool {
+    match token.token_kind {
+        TokenKind::Keyword => {
+            matches!(token.literal.as_str(), "like" | "ilike" | "glob" | "regexp")
         }
+        TokenKind::Symbol => matches!(token.literal.as_str(), "~" | "~*" | "!~" | "!~*"),
+        _ => false,
     }
-
-    Err(())
 }
 
-fn parse_select(
-    tokens: &Vec<Token>,
+/// Parses an expression using precedence climbing. Infix operators binding
+/// less tightly than `min_bp` are left for the caller.
+fn parse_expression(
+    tokens: &[Token],
     cursor_in: usize,
-    delimiter: &Token,
-) -> Result<(Select, usize), ()> {
-    let mut cursor = cursor_in;
+    min_bp: u32,
+) -> Result<(Expression, usize), ()> {
+    let (mut exp, mut cursor) = parse_primary_expression(tokens, cursor_in)?;
 
-    if !expect_token(
-        tokens,
-        cursor,
-        Token {
-            literal: Keyword::Select.to_string(),
-            token_kind: TokenKind::Keyword,
-            loc: Location::new(),
-        },
-    ) {
-        return Err(());
-    }
+    loop {
+        if let Some((pattern_match, new_cursor)) = parse_predicate(tokens, cursor, &exp, min_bp)? {
+            exp = pattern_match;
+            cursor = new_cursor;
+            continue;
+        }
 
-    cursor += 1;
-    let from_token = Token {
-        literal: Keyword::From.to_string(),
-        token_kind: TokenKind::Keyword,
-        loc: Location::new(),
-    };
-    let delimiters = vec![from_token, delimiter.clone()];
+        let op = match tokens.get(cursor) {
+            Some(op) => op.clone(),
+            None => break,
+        };
 
-    let (expressions, new_cursor) = match parse_expressions(tokens, cursor, &delimiters) {
-        Ok((expressions, new_cursor)) => (expressions, new_cursor),
-        Err(_) => return Err(()),
-    };
+        if op.token_kind == TokenKind::Keyword && op.literal == Keyword::Is.to_string() {
+            if COMPARISON_POWER <= min_bp {
+                break;
+            }
+            let (is_null, new_cursor) = parse_is_null(tokens, cursor, exp)?;
+            exp = is_null;
+            cursor = new_cursor;
+            continue;
+        }
 
-    cursor = new_cursor;
+        if op.literal == Symbol::DoubleColon.to_string() {
+            if CAST_POWER <= min_bp {
+                break;
+            }
+            let (data_type, new_cursor) = parse_data_type(tokens, cursor + 1)?;
+            cursor = new_cursor;
+
+            let mut cast = Expression::new(op, ExpressionKind::Cast);
+            cast.cast = Some(Box::new(Cast {
+                value: exp,
+                data_type,
+            }));
+            exp = cast;
+            continue;
+        }
 
-    if expect_token(
-        tokens,
-        cursor,
-        Token {
-            literal: Keyword::From.to_string(),
-            token_kind: TokenKind::Keyword,
-            loc: Location::new(),
-        },
-    ) {
-        cursor += 1;
-        let (from_token, new_cursor) = match parse_token(tokens, cursor, TokenKind::Identifier) {
-            Ok((from_token, new_cursor)) => (from_token, new_cursor),
+        if op.literal == Symbol::LeftBracket.to_string() {
+            if SUBSCRIPT_POWER <= min_bp {
+                break;
+            }
+            let (subscript, new_cursor) = parse_subscript(tokens, cursor, exp)?;
+            exp = subscript;
+            cursor = new_cursor;
+            continue;
+        }
+
+        let bp = binding_power(&op);
+        if bp == 0 || bp <= min_bp {
+            break;
+        }
+
+        if bp == COMPARISON_POWER {
+            if let Some((quantified, new_cursor)) = parse_quantified(tokens, cursor, &exp)? {
+                exp = quantified;
+                cursor = new_cursor;
+                continue;
+            }
+        }
+
+        let (b, new_cursor) = match parse_expression(tokens, cursor + 1, bp) {
+            Ok((b, new_cursor)) => (b, new_cursor),
             Err(_) => {
-                help_message(tokens, cursor, String::from("Expected table name"));
+                help_message(tokens, cursor + 1, String::from("Expected right operand"));
                 return Err(());
             }
         };
         cursor = new_cursor;
-        let select = Select {
-            from: from_token,
-            items: expressions,
-        };
 
-        return Ok((select, cursor));
+        let mut binary = Expression::new(op.clone(), ExpressionKind::Binary);
+        binary.binary = Some(Box::new(BinaryExpression { a: exp, b, op }));
+        exp = binary;
     }
-    Ok((
-        Select {
-            from: Token::nil(),
-            items: expressions,
-        },
-        cursor,
-    ))
+
+    Ok((exp, cursor))
 }
 
-fn parse_insert(tokens: &Vec<Token>, cursor_in: usize) -> Result<(Statement, usize), ()> {
+/// Parses the predicates that can be negated with a leading NOT: the `LIKE`
+/// family, `IN` lists and `BETWEEN`. Returns `None` when the tokens at
+/// `cursor_in` are none of these.
+fn parse_predicate(
+    tokens: &[Token],
+    cursor_in: usize,
+    value: &Expression,
+    min_bp: u32,
+) -> Result<Option<(Expression, usize)>, ()> {
     let mut cursor = cursor_in;
 
-    if !expect_token(
-        tokens,
-        cursor,
-        Token {
-            literal: Keyword::Insert.to_string(),
-            token_kind: TokenKind::Keyword,
-            loc: Location::new(),
-        },
-    ) {
-        return Err(());
+    let negated = expect_token(tokens, cursor, keyword_token(Keyword::Not));
+    if negated {
+        cursor += 1;
     }
-    cursor += 1;
 
-    if !expect_token(
-        tokens,
-        cursor,
-        Token {
-            literal: Keyword::Into.to_string(),
-            token_kind: TokenKind::Keyword,
-            loc: Location::new(),
-        },
-    ) {
-        help_message(tokens, cursor, String::from("Expected INTO"));
-        return Err(());
+    let op = match tokens.get(cursor) {
+        Some(op) => op.clone(),
+        None => return Ok(None),
+    };
+    if op.token_kind != TokenKind::Keyword && (negated || !is_pattern_operator(&op)) {
+        return Ok(None);
+    }
+    if COMPARISON_POWER <= min_bp {
+        return Ok(None);
+    }
+
+    if op.literal == Keyword::In.to_string() {
+        return parse_in_list(tokens, cursor + 1, value, negated).map(Some);
+    }
+    if op.literal == Keyword::Between.to_string() {
+        return parse_between(tokens, cursor + 1, value, negated).map(Some);
+    }
+    if !is_pattern_operator(&op) {
+        return Ok(None);
     }
     cursor += 1;
 
-    let (table, new_cursor) = match parse_token(tokens, cursor, TokenKind::Identifier) {
-        Ok((table, new_cursor)) => (table, new_cursor),
+    let (pattern, new_cursor) = match parse_expression(tokens, cursor, COMPARISON_POWER) {
+        Ok((pattern, new_cursor)) => (pattern, new_cursor),
         Err(_) => {
-            help_message(tokens, cursor, String::from("Expected table name"));
+            help_message(tokens, cursor, String::from("Expected pattern"));
             return Err(());
         }
     };
     cursor = new_cursor;
 
-    if !expect_token(
-        tokens,
-        cursor,
-        Token {
-            literal: Keyword::Values.to_string(),
-            token_kind: TokenKind::Keyword,
-            loc: Location::new(),
-        },
-    ) {
-        help_message(tokens, cursor, String::from("Expected VALUES"));
-        return Err(());
-    }
-    cursor += 1;
+    let mut escape = None;
+    if expect_token(tokens, cursor, keyword_token(Keyword::Escape)) {
+        if op.literal != Keyword::Like.to_string() && op.literal != Keyword::Ilike.to_string() {
+            help_message(
+                tokens,
+                cursor,
+                String::from("ESCAPE is only allowed with LIKE"),
+            );
+            return Err(());
+        }
+        cursor += 1;
 
-    if !expect_token(
-        tokens,
-        cursor,
-        Token {
-            literal: Symbol::LeftParen.to_string(),
-            token_kind: TokenKind::Symbol,
-            loc: Location::new(),
-        },
-    ) {
-        help_message(tokens, cursor, String::from("Expected LPAREN"));
-        return Err(());
+        let (exp, new_cursor) = match parse_expression(tokens, cursor, COMPARISON_POWER) {
+            Ok((exp, new_cursor)) => (exp, new_cursor),
+            Err(_) => {
+                help_message(tokens, cursor, String::from("Expected escape character"));
+                return Err(());
+            }
+        };
+        escape = Some(exp);
+        cursor = new_cursor;
     }
-    cursor += 1;
 
-    let (values, new_cursor) = match parse_expressions(
-        &tokens,
-        cursor,
-        &Vec::from([Token {
-            literal: Symbol::RightParen.to_string(),
-            token_kind: TokenKind::Symbol,
-            loc: Location::new(),
-        }]),
-    ) {
-        Ok((expressions, new_cursor)) => (expressions, new_cursor),
-        Err(_) => {
-            return Err(());
-        }
-    };
+    let mut exp = Expression::new(op.clone(), ExpressionKind::PatternMatch);
+    exp.pattern_match = Some(Box::new(PatternMatch {
+        value: value.clone(),
+        pattern,
+        escape,
+        op,
+        negated,
+    }));
 
-    cursor = new_cursor;
+    Ok(Some((exp, cursor)))
+}
 
-    if !expect_token(
-        tokens,
-        cursor,
-        Token {
-            literal: Symbol::RightParen.to_string(),
-            token_kind: TokenKind::Symbol,
-            loc: Location::new(),
-        },
-    ) {
-        help_message(tokens, cursor, String::from("Expected RIGHTPAREN"));
+fn parse_is_null(
+    tokens: &[Token],
+    cursor_in: usize,
+    value: Expression,
+) -> Result<(Expression, usize), ()> {
+    let is_token = tokens[cursor_in].clone();
+    let mut cursor = cursor_in + 1;
+
+    let negated = expect_token(tokens, cursor, keyword_token(Keyword::Not));
+    if negated {
+        cursor += 1;
+    }
+
+    if !expect_token(tokens, cursor, keyword_token(Keyword::Null)) {
+        help_message(tokens, cursor, String::from("Expected NULL"));
         return Err(());
     }
     cursor += 1;
-    let stmt = Statement {
-        select: None,
-        insert: Some(Insert { table, values }),
-        create: None,
-        kind: StatementKind::Insert,
-    };
 
-    Ok((stmt, cursor))
+    let mut exp = Expression::new(is_token, ExpressionKind::IsNull);
+    exp.is_null = Some(Box::new(IsNull { value, negated }));
+    Ok((exp, cursor))
 }
 
-fn parse_create(tokens: &Vec<Token>, cursor_in: usize) -> Result<(Statement, usize), ()> {
+fn parse_in_list(
+    tokens: &[Token],
+    cursor_in: usize,
+    value: &Expression,
+    negated: bool,
+) -> Result<(Expression, usize), ()> {
     let mut cursor = cursor_in;
+    let in_token = tokens[cursor - 1].clone();
 
-    if !expect_token(
-        tokens,
-        cursor,
-        Token {
-            literal: Keyword::Create.to_string(),
-            token_kind: TokenKind::Keyword,
-            loc: Location::new(),
-        },
-    ) {
+    if !expect_token(tokens, cursor, symbol_token(Symbol::LeftParen)) {
+        help_message(tokens, cursor, String::from("Expected LEFTPAREN"));
         return Err(());
     }
     cursor += 1;
 
-    if !expect_token(
-        tokens,
-        cursor,
-        Token {
-            literal: Keyword::Table.to_string(),
-            token_kind: TokenKind::Keyword,
-            loc: Location::new(),
-        },
-    ) {
-        help_message(tokens, cursor, String::from("Expected TABLE"));
+    let right_paren = symbol_token(Symbol::RightParen);
+    let (list, new_cursor) = parse_expressions(tokens, cursor, &vec![right_paren.clone()])?;
+    cursor = new_cursor;
+
+    if list.is_empty() {
+        help_message(tokens, cursor, String::from("Expected at least one value"));
+        return Err(());
+    }
+    if !expect_token(tokens, cursor, right_paren) {
+        help_message(tokens, cursor, String::from("Expected RIGHTPAREN"));
         return Err(());
     }
     cursor += 1;
 
-    let (table, new_cursor) = match parse_token(tokens, cursor, TokenKind::Identifier) {
-        Ok((table, new_cursor)) => (table, new_cursor),
+    let mut exp = Expression::new(in_token, ExpressionKind::InList);
+    exp.in_list = Some(Box::new(InList {
+        value: value.clone(),
+        list,
+        negated,
+    }));
+    Ok((exp, cursor))
+}
+
+fn parse_between(
+    tokens: &[Token],
+    cursor_in: usize,
+    value: &Expression,
+    negated: bool,
+) -> Result<(Expression, usize), ()> {
+    let mut cursor = cursor_in;
+    let between_token = tokens[cursor - 1].clone();
+
+    // Both bounds bind tighter than AND so that the AND separating them is
+    // not taken as a boolean operator.
+    let (low, new_cursor) = match parse_expression(tokens, cursor, COMPARISON_POWER) {
+        Ok((low, new_cursor)) => (low, new_cursor),
         Err(_) => {
-            help_message(tokens, cursor, String::from("Expected table name"));
+            help_message(tokens, cursor, String::from("Expected lower bound"));
             return Err(());
         }
     };
-
     cursor = new_cursor;
 
-    if !expect_token(
-        tokens,
-        cursor,
-        Token {
-            literal: Symbol::LeftParen.to_string(),
-            token_kind: TokenKind::Symbol,
-            loc: Location::new(),
-        },
-    ) {
-        help_message(tokens, cursor, String::from("Expected LEFTPAREN"));
+    if !expect_token(tokens, cursor, keyword_token(Keyword::And)) {
+        help_message(tokens, cursor, String::from("Expected AND"));
         return Err(());
     }
     cursor += 1;
 
-    let (cols, new_cursor) = match parse_column_defs(
-        tokens,
-        cursor,
-        &Token {
-            literal: Symbol::RightParen.to_string(),
-            token_kind: TokenKind::Symbol,
-            loc: Location::new(),
-        },
-    ) {
-        Ok((col_defs, new_cursor)) => (col_defs, new_cursor),
+    let (high, new_cursor) = match parse_expression(tokens, cursor, COMPARISON_POWER) {
+        Ok((high, new_cursor)) => (high, new_cursor),
         Err(_) => {
+            help_message(tokens, cursor, String::from("Expected upper bound"));
             return Err(());
         }
     };
     cursor = new_cursor;
 
-    if !expect_token(
-        tokens,
-        cursor,
-        Token {
-            literal: Symbol::RightParen.to_string(),
-            token_kind: TokenKind::Symbol,
-            loc: Location::new(),
-        },
-    ) {
-        help_message(tokens, cursor, String::from("Expected RIGHTPAREN"));
-        return Err(());
-    }
-    cursor += 1;
-
-    let stmt = Statement {
-        create: Some(Create { name: table, cols }),
-        select: None,
-        insert: None,
-        kind: StatementKind::Create,
-    };
-    Ok((stmt, cursor))
+    let mut exp = Expression::new(between_token, ExpressionKind::Between);
+    exp.between = Some(Box::new(Between {
+        value: value.clone(),
+        low,
+        high,
+        negated,
+    }));
+    Ok((exp, cursor))
 }
 
-fn parse_column_defs(
-    tokens: &Vec<Token>,
-    cursor_in: usize,
-    delimiter: &Token,
-) -> Result<(Vec<ColDefinition>, usize), ()> {
-    let mut col_defs = Vec::new();
+fn parse_primary_expression(tokens: &[Token], cursor_in: usize) -> Result<(Expression, usize), ()> {
     let mut cursor = cursor_in;
 
-    loop {
-        if cursor >= tokens.len() {
-            break;
+    if expect_token(tokens, cursor, symbol_token(Symbol::LeftParen)) {
+        cursor += 1;
+        let (exp, new_cursor) = parse_expression(tokens, cursor, 0)?;
+        cursor = new_cursor;
+
+        if !expect_token(tokens, cursor, symbol_token(Symbol::RightParen)) {
+            help_message(tokens, cursor, String::from("Expected RIGHTPAREN"));
+            return Err(());
         }
+        return Ok((exp, cursor + 1));
+    }
 
-        let current = tokens.get(cursor).unwrap();
+    if expect_token(tokens, cursor, keyword_token(Keyword::Not)) {
+        let op = tokens[cursor].clone();
+        let (operand, new_cursor) = parse_expression(tokens, cursor + 1, NOT_POWER)?;
 
-        if current.literal == delimiter.literal {
-            break;
-        }
+        let mut exp = Expression::new(op.clone(), ExpressionKind::Unary);
+        exp.unary = Some(Box::new(UnaryExpression { operand, op }));
+        return Ok((exp, new_cursor));
+    }
 
-        if col_defs.len() > 0 {
-            if !expect_token(
-                tokens,
-                cursor,
-                Token {
-                    literal: Symbol::Comma.to_string(),
-                    token_kind: TokenKind::Symbol,
-                    loc: Location::new(),
-                },
-            ) {
-                help_message(tokens, cursor, String::from("Expected comma"));
-                return Err(());
+    if expect_token(tokens, cursor, symbol_token(Symbol::Minus)) {
+        let op = tokens[cursor].clone();
+
+        // A negative number is a single literal, so that the smallest value
+        // of each integer type can be written
+        if let Some(number) = tokens.get(cursor + 1) {
+            if number.token_kind == TokenKind::Numeric {
+                let mut literal = number.clone();
+                literal.literal = format!("-{}", number.literal);
+                literal.loc = op.loc;
+                return Ok((
+                    Expression::new(literal, ExpressionKind::Literal),
+                    cursor + 2,
+                ));
             }
-            cursor += 1;
         }
 
-        let (col_name, new_cursor) = match parse_token(tokens, cursor, TokenKind::Identifier) {
-            Ok((col_name, new_cursor)) => (col_name, new_cursor),
-            Err(_) => {
-                help_message(tokens, cursor, String::from("Expected column name"));
-                return Err(());
-            }
-        };
-        cursor = new_cursor;
+        let (operand, new_cursor) = parse_expression(tokens, cursor + 1, NEGATION_POWER)?;
+        let mut exp = Expression::new(op.clone(), ExpressionKind::Unary);
+        exp.unary = Some(Box::new(UnaryExpression { operand, op }));
+        return Ok((exp, new_cursor));
+    }
 
-        let (col_type, new_cursor) = match parse_token(tokens, cursor, TokenKind::Keyword) {
-            Ok((col_type, new_cursor)) => (col_type, new_cursor),
-            Err(_) => {
-                help_message(tokens, cursor, String::from("Expected column type"));
-                return Err(());
-            }
-        };
-        cursor = new_cursor;
+    if expect_token(tokens, cursor, keyword_token(Keyword::Null))
+        || expect_token(tokens, cursor, keyword_token(Keyword::True))
+        || expect_token(tokens, cursor, keyword_token(Keyword::False))
+    {
+        let exp = Expression::new(tokens[cursor].clone(), ExpressionKind::Literal);
+        return Ok((exp, cursor + 1));
+    }
+
+    if expect_token(tokens, cursor, keyword_token(Keyword::Cast)) {
+        return parse_cast(tokens, cursor);
+    }
+
+    if expect_token(tokens, cursor, keyword_token(Keyword::Array)) {
+        return parse_array(tokens, cursor);
+    }
+
+    // A type name followed by a string, such as DATE '2024-03-04', is a cast
+    // of the string
+    let is_typed_literal = [
+        Keyword::Date,
+        Keyword::Time,
+        Keyword::Timestamp,
+        Keyword::Interval,
+    ]
+    .into_iter()
+    .any(|keyword| expect_token(tokens, cursor, keyword_token(keyword)));
+    if is_typed_literal
+        && tokens
+            .get(cursor + 1)
+            .is_some_and(|token| token.token_kind == TokenKind::String)
+    {
+        let name = tokens[cursor].clone();
+        let value = Expression::new(tokens[cursor + 1].clone(), ExpressionKind::Literal);
+        let mut exp = Expression::new(name.clone(), ExpressionKind::Cast);
+        exp.cast = Some(Box::new(Cast {
+            value,
+            data_type: DataType {
+                name,
+                modifiers: Vec::new(),
+                array: false,
+            },
+        }));
+        return Ok((exp, cursor + 2));
+    }
+
+    if expect_token(tokens, cursor, keyword_token(Keyword::CurrentDate)) {
+        let name = tokens[cursor].clone();
+        let mut exp = Expression::new(name.clone(), ExpressionKind::Function);
+        exp.function = Some(FunctionCall {
+            name,
+            args: Vec::new(),
+            over: None,
+        });
+        return Ok((exp, cursor + 1));
+    }
+
+    if expect_token(tokens, cursor, keyword_token(Keyword::Extract)) {
+        return parse_extract(tokens, cursor);
+    }
+
+    if let Ok((function, cursor)) = parse_function_call(tokens, cursor_in) {
+        let kind = match function.over {
+            Some(_) => ExpressionKind::Window,
+            None => ExpressionKind::Function,
+        };
+        let mut exp = Expression::new(function.name.clone(), kind);
+        exp.function = Some(function);
+        return Ok((exp, cursor));
+    }
+
+    // A qualified column such as excluded.price is kept as one identifier
+    if let (Ok((mut token, _)), Ok((column, _))) = (
+        parse_identifier(tokens, cursor),
+        parse_identifier(tokens, cursor + 2),
+    ) {
+        if expect_token(tokens, cursor + 1, symbol_token(Symbol::Dot)) {
+            token.literal = format!("{}.{}", token.literal, column.literal);
+            return Ok((Expression::new(token, ExpressionKind::Literal), cursor + 3));
+        }
+    }
+
+    if let Ok((token, cursor)) = parse_identifier(tokens, cursor_in) {
+        return Ok((Expression::new(token, ExpressionKind::Literal), cursor));
+    }
+
+    let allowed_kinds = vec![TokenKind::Numeric, TokenKind::String, TokenKind::Blob];
+
+    for kind in allowed_kinds {
+        if let Ok((token, cursor)) = parse_token(tokens, cursor_in, kind) {
+            return Ok((Expression::new(token, ExpressionKind::Literal), cursor));
+        }
+    }
+
+    Err(())
+}
+
+/// `ARRAY[element, ...]`
+fn parse_array(tokens: &[Token], cursor_in: usize) -> Result<(Expression, usize), ()> {
+    let array_token = tokens[cursor_in].clone();
+    let mut cursor = cursor_in + 1;
+
+    if !expect_token(tokens, cursor, symbol_token(Symbol::LeftBracket)) {
+        help_message(tokens, cursor, String::from("Expected LEFTBRACKET"));
+        return Err(());
+    }
+    cursor += 1;
+
+    let right_bracket = symbol_token(Symbol::RightBracket);
+    let (elements, new_cursor) = parse_expressions(tokens, cursor, &vec![right_bracket.clone()])?;
+    cursor = new_cursor;
+
+    if !expect_token(tokens, cursor, right_bracket) {
+        help_message(tokens, cursor, String::from("Expected RIGHTBRACKET"));
+        return Err(());
+    }
+    cursor += 1;
+
+    let mut exp = Expression::new(array_token, ExpressionKind::Array);
+    exp.array = Some(elements);
+    Ok((exp, cursor))
+}
+
+/// The `[index]` after `value`.
+fn parse_subscript(
+    tokens: &[Token],
+    cursor_in: usize,
+    value: Expression,
+) -> Result<(Expression, usize), ()> {
+    let bracket = tokens[cursor_in].clone();
+    let (index, cursor) = match parse_expression(tokens, cursor_in + 1, 0) {
+        Ok((index, cursor)) => (index, cursor),
+        Err(_) => {
+            help_message(
+                tokens,
+                cursor_in + 1,
+                String::from("Expected array subscript"),
+            );
+            return Err(());
+        }
+    };
+
+    if !expect_token(tokens, cursor, symbol_token(Symbol::RightBracket)) {
+        help_message(tokens, cursor, String::from("Expected RIGHTBRACKET"));
+        return Err(());
+    }
+
+    let mut exp = Expression::new(bracket, ExpressionKind::Subscript);
+    exp.subscript = Some(Box::new(Subscript { value, index }));
+    Ok((exp, cursor + 1))
+}
+
+/// `op ANY (array)` or `op ALL (array)` after `value`. Returns `None` when
+/// the comparison is not followed by ANY or ALL.
+fn parse_quantified(
+    tokens: &[Token],
+    cursor_in: usize,
+    value: &Expression,
+) -> Result<Option<(Expression, usize)>, ()> {
+    let op = tokens[cursor_in].clone();
+    let mut cursor = cursor_in + 1;
+
+    let all = expect_token(tokens, cursor, keyword_token(Keyword::All));
+    if !all && !expect_token(tokens, cursor, keyword_token(Keyword::Any)) {
+        return Ok(None);
+    }
+    let quantifier = tokens[cursor].clone();
+    cursor += 1;
+
+    if !expect_token(tokens, cursor, symbol_token(Symbol::LeftParen)) {
+        help_message(tokens, cursor, String::from("Expected LEFTPAREN"));
+        return Err(());
+    }
+    cursor += 1;
+
+    let (array, new_cursor) = match parse_expression(tokens, cursor, 0) {
+        Ok((array, new_cursor)) => (array, new_cursor),
+        Err(_) => {
+            help_message(tokens, cursor, String::from("Expected array expression"));
+            return Err(());
+        }
+    };
+    cursor = new_cursor;
+
+    if !expect_token(tokens, cursor, symbol_token(Symbol::RightParen)) {
+        help_message(tokens, cursor, String::from("Expected RIGHTPAREN"));
+        return Err(());
+    }
+    cursor += 1;
+
+    let mut exp = Expression::new(quantifier, ExpressionKind::Quantified);
+    exp.quantified = Some(Box::new(Quantified {
+        value: value.clone(),
+        op,
+        array,
+        all,
+    }));
+    Ok(Some((exp, cursor)))
+}
+
+/// `EXTRACT(field FROM source)`, which becomes a call to
+/// `extract('field', source)`.
+fn parse_extract(tokens: &[Token], cursor_in: usize) -> Result<(Expression, usize), ()> {
+    let name = tokens[cursor_in].clone();
+    let mut cursor = cursor_in + 1;
+
+    if !expect_token(tokens, cursor, symbol_token(Symbol::LeftParen)) {
+        help_message(tokens, cursor, String::from("Expected LEFTPAREN"));
+        return Err(());
+    }
+    cursor += 1;
+
+    let mut field = match tokens.get(cursor) {
+        Some(token) if token.token_kind != TokenKind::Symbol => token.clone(),
+        _ => {
+            help_message(tokens, cursor, String::from("Expected field name"));
+            return Err(());
+        }
+    };
+    if field.token_kind != TokenKind::String {
+        field.literal = format!("'{}'", field.literal);
+        field.token_kind = TokenKind::String;
+    }
+    cursor += 1;
+
+    if !expect_token(tokens, cursor, keyword_token(Keyword::From)) {
+        help_message(tokens, cursor, String::from("Expected FROM"));
+        return Err(());
+    }
+    cursor += 1;
+
+    let (source, new_cursor) = match parse_expression(tokens, cursor, 0) {
+        Ok((source, new_cursor)) => (source, new_cursor),
+        Err(_) => {
+            help_message(tokens, cursor, String::from("Expected expression"));
+            return Err(());
+        }
+    };
+    cursor = new_cursor;
+
+    if !expect_token(tokens, cursor, symbol_token(Symbol::RightParen)) {
+        help_message(tokens, cursor, String::from("Expected RIGHTPAREN"));
+        return Err(());
+    }
+    cursor += 1;
+
+    let mut exp = Expression::new(name.clone(), ExpressionKind::Function);
+    exp.function = Some(FunctionCall {
+        name,
+        args: vec![Expression::new(field, ExpressionKind::Literal), source],
+        over: None,
+    });
+    Ok((exp, cursor))
+}
+
+fn parse_cast(tokens: &[Token], cursor_in: usize) -> Result<(Expression, usize), ()> {
+    let cast_token = tokens[cursor_in].clone();
+    let mut cursor = cursor_in + 1;
+
+    if !expect_token(tokens, cursor, symbol_token(Symbol::LeftParen)) {
+        help_message(tokens, cursor, String::from("Expected LEFTPAREN"));
+        return Err(());
+    }
+    cursor += 1;
+
+    let (value, new_cursor) = match parse_expression(tokens, cursor, 0) {
+        Ok((value, new_cursor)) => (value, new_cursor),
+        Err(_) => {
+            help_message(tokens, cursor, String::from("Expected expression"));
+            return Err(());
+        }
+    };
+    cursor = new_cursor;
+
+    if !expect_token(tokens, cursor, keyword_token(Keyword::As)) {
+        help_message(tokens, cursor, String::from("Expected AS"));
+        return Err(());
+    }
+    cursor += 1;
+
+    let (data_type, new_cursor) = parse_data_type(tokens, cursor)?;
+    cursor = new_cursor;
+
+    if !expect_token(tokens, cursor, symbol_token(Symbol::RightParen)) {
+        help_message(tokens, cursor, String::from("Expected RIGHTPAREN"));
+        return Err(());
+    }
+    cursor += 1;
+
+    let mut exp = Expression::new(cast_token, ExpressionKind::Cast);
+    exp.cast = Some(Box::new(Cast { value, data_type }));
+    Ok((exp, cursor))
+}
+
+fn parse_data_type(tokens: &[Token], cursor_in: usize) -> Result<(DataType, usize), ()> {
+    // DOUBLE PRECISION and CHARACTER VARYING use words that are not
+    // keywords, so that double and varying stay usable as names
+    let is_word = |cursor: usize, word: &str| {
+        tokens.get(cursor).is_some_and(|token| {
+            token.token_kind == TokenKind::Identifier && token.literal.eq_ignore_ascii_case(word)
+        })
+    };
+    if is_word(cursor_in, "double") && is_word(cursor_in + 1, "precision") {
+        let mut name = tokens[cursor_in].clone();
+        name.literal = String::from("double precision");
+        return parse_type_modifiers(tokens, cursor_in + 2, name);
+    }
+    if expect_token(tokens, cursor_in, keyword_token(Keyword::Character))
+        && is_word(cursor_in + 1, "varying")
+    {
+        let mut name = tokens[cursor_in].clone();
+        name.literal = String::from("character varying");
+        return parse_type_modifiers(tokens, cursor_in + 2, name);
+    }
+
+    // Built-in types are keywords, user-defined ones identifiers
+    let name = parse_token(tokens, cursor_in, TokenKind::Keyword)
+        .or_else(|_| parse_token(tokens, cursor_in, TokenKind::Identifier));
+    match name {
+        Ok((name, cursor)) => parse_type_modifiers(tokens, cursor, name),
+        Err(_) => {
+            help_message(tokens, cursor_in, String::from("Expected column type"));
+            Err(())
+        }
+    }
+}
+
+fn parse_type_modifiers(
+    tokens: &[Token],
+    cursor_in: usize,
+    name: Token,
+) -> Result<(DataType, usize), ()> {
+    let mut cursor = cursor_in;
+    let mut modifiers = Vec::new();
+    if expect_token(tokens, cursor, symbol_token(Symbol::LeftParen)) {
+        cursor += 1;
+        loop {
+            match parse_token(tokens, cursor, TokenKind::Numeric) {
+                Ok((modifier, new_cursor)) => {
+                    modifiers.push(modifier);
+                    cursor = new_cursor;
+                }
+                Err(_) => {
+                    help_message(tokens, cursor, String::from("Expected type modifier"));
+                    return Err(());
+                }
+            }
+
+            if !expect_token(tokens, cursor, symbol_token(Symbol::Comma)) {
+                break;
+            }
+            cursor += 1;
+        }
+
+        if !expect_token(tokens, cursor, symbol_token(Symbol::RightParen)) {
+            help_message(tokens, cursor, String::from("Expected RIGHTPAREN"));
+            return Err(());
+        }
+        cursor += 1;
+    }
+
+    let array = expect_token(tokens, cursor, symbol_token(Symbol::LeftBracket))
+        && expect_token(tokens, cursor + 1, symbol_token(Symbol::RightBracket));
+    if array {
+        cursor += 2;
+    }
+
+    Ok((
+        DataType {
+            name,
+            modifiers,
+            array,
+        },
+        cursor,
+    ))
+}
+
+fn parse_function_call(tokens: &[Token], cursor_in: usize) -> Result<(FunctionCall, usize), ()> {
+    let (name, mut cursor) = parse_token(tokens, cursor_in, TokenKind::Identifier)?;
+
+    if !expect_token(tokens, cursor, symbol_token(Symbol::LeftParen)) {
+        return Err(());
+    }
+    cursor += 1;
+
+    let right_paren = symbol_token(Symbol::RightParen);
+
+    // count(*) is treated as a call without arguments
+    let mut args = Vec::new();
+    if expect_token(tokens, cursor, symbol_token(Symbol::Asterisk)) {
+        cursor += 1;
+    } else {
+        let (exps, new_cursor) = parse_expressions(tokens, cursor, &vec![right_paren.clone()])?;
+        args = exps;
+        cursor = new_cursor;
+    }
+
+    if !expect_token(tokens, cursor, right_paren) {
+        help_message(tokens, cursor, String::from("Expected RIGHTPAREN"));
+        return Err(());
+    }
+    cursor += 1;
+
+    let mut over = None;
+    if expect_token(tokens, cursor, keyword_token(Keyword::Over)) {
+        let (window, new_cursor) = parse_window_spec(tokens, cursor + 1)?;
+        over = Some(Box::new(window));
+        cursor = new_cursor;
+    }
+
+    Ok((FunctionCall { name, args, over }, cursor))
+}
+
+fn parse_window_spec(tokens: &[Token], cursor_in: usize) -> Result<(WindowSpec, usize), ()> {
+    let mut cursor = cursor_in;
+
+    if !expect_token(tokens, cursor, symbol_token(Symbol::LeftParen)) {
+        help_message(tokens, cursor, String::from("Expected LEFTPAREN"));
+        return Err(());
+    }
+    cursor += 1;
+
+    let mut partition_by = Vec::new();
+    if expect_token(tokens, cursor, keyword_token(Keyword::Partition)) {
+        cursor += 1;
+        if !expect_token(tokens, cursor, keyword_token(Keyword::By)) {
+            help_message(tokens, cursor, String::from("Expected BY"));
+            return Err(());
+        }
+        cursor += 1;
+
+        let delimiters = vec![
+            keyword_token(Keyword::Order),
+            keyword_token(Keyword::Rows),
+            keyword_token(Keyword::Range),
+            symbol_token(Symbol::RightParen),
+        ];
+        let (exps, new_cursor) = parse_expressions(tokens, cursor, &delimiters)?;
+        partition_by = exps;
+        cursor = new_cursor;
+    }
+
+    let mut order_by = Vec::new();
+    if expect_token(tokens, cursor, keyword_token(Keyword::Order)) {
+        let delimiters = vec![
+            keyword_token(Keyword::Rows),
+            keyword_token(Keyword::Range),
+            symbol_token(Symbol::RightParen),
+        ];
+        let (terms, new_cursor) = parse_order_by(tokens, cursor, &delimiters)?;
+        order_by = terms;
+        cursor = new_cursor;
+    }
+
+    let mut frame = None;
+    if expect_token(tokens, cursor, keyword_token(Keyword::Rows))
+        || expect_token(tokens, cursor, keyword_token(Keyword::Range))
+    {
+        let (window_frame, new_cursor) = parse_window_frame(tokens, cursor)?;
+        frame = Some(window_frame);
+        cursor = new_cursor;
+    }
+
+    if !expect_token(tokens, cursor, symbol_token(Symbol::RightParen)) {
+        help_message(tokens, cursor, String::from("Expected RIGHTPAREN"));
+        return Err(());
+    }
+    cursor += 1;
+
+    Ok((
+        WindowSpec {
+            partition_by,
+            order_by,
+            frame,
+        },
+        cursor,
+    ))
+}
+
+/// Parses `ORDER BY expr [ASC|DESC], ...` up to one of `delimiters`.
+fn parse_order_by(
+    tokens: &[Token],
+    cursor_in: usize,
+    delimiters: &[Token],
+) -> Result<(Vec<OrderingTerm>, usize), ()> {
+    let mut cursor = cursor_in;
+
+    if !expect_token(tokens, cursor, keyword_token(Keyword::Order)) {
+        return Err(());
+    }
+    cursor += 1;
+    if !expect_token(tokens, cursor, keyword_token(Keyword::By)) {
+        help_message(tokens, cursor, String::from("Expected BY"));
+        return Err(());
+    }
+    cursor += 1;
+
+    let mut terms = Vec::new();
+    loop {
+        let (expression, new_cursor) = match parse_expression(tokens, cursor, 0) {
+            Ok((expression, new_cursor)) => (expression, new_cursor),
+            Err(_) => {
+                help_message(tokens, cursor, String::from("Expected expression"));
+                return Err(());
+            }
+        };
+        cursor = new_cursor;
+
+        let mut descending = false;
+        if expect_token(tokens, cursor, keyword_token(Keyword::Desc)) {
+            descending = true;
+            cursor += 1;
+        } else if expect_token(tokens, cursor, keyword_token(Keyword::Asc)) {
+            cursor += 1;
+        }
+
+        terms.push(OrderingTerm {
+            expression,
+            descending,
+        });
+
+        if !expect_token(tokens, cursor, symbol_token(Symbol::Comma)) {
+            break;
+        }
+        cursor += 1;
+    }
+
+    if cursor < tokens.len()
+        && !delimiters
+            .iter()
+            .any(|delimiter| expect_token(tokens, cursor, delimiter.clone()))
+    {
+        help_message(tokens, cursor, String::from("Expected comma"));
+        return Err(());
+    }
+
+    Ok((terms, cursor))
+}
+
+fn parse_window_frame(tokens: &[Token], cursor_in: usize) -> Result<(WindowFrame, usize), ()> {
+    let units = tokens[cursor_in].clone();
+    let mut cursor = cursor_in + 1;
+
+    if !expect_token(tokens, cursor, keyword_token(Keyword::Between)) {
+        let (start, new_cursor) = parse_frame_bound(tokens, cursor)?;
+        let end = FrameBound {
+            kind: FrameBoundKind::CurrentRow,
+            offset: None,
+        };
+        return Ok((WindowFrame { units, start, end }, new_cursor));
+    }
+    cursor += 1;
+
+    let (start, new_cursor) = parse_frame_bound(tokens, cursor)?;
+    cursor = new_cursor;
+
+    if !expect_token(tokens, cursor, keyword_token(Keyword::And)) {
+        help_message(tokens, cursor, String::from("Expected AND"));
+        return Err(());
+    }
+    cursor += 1;
+
+    let (end, new_cursor) = parse_frame_bound(tokens, cursor)?;
+    cursor = new_cursor;
+
+    Ok((WindowFrame { units, start, end }, cursor))
+}
+
+fn parse_frame_bound(tokens: &[Token], cursor_in: usize) -> Result<(FrameBound, usize), ()> {
+    let mut cursor = cursor_in;
+
+    if expect_token(tokens, cursor, keyword_token(Keyword::Current)) {
+        if !expect_token(tokens, cursor + 1, keyword_token(Keyword::Row)) {
+            help_message(tokens, cursor + 1, String::from("Expected ROW"));
+            return Err(());
+        }
+        let bound = FrameBound {
+            kind: FrameBoundKind::CurrentRow,
+            offset: None,
+        };
+        return Ok((bound, cursor + 2));
+    }
+
+    let mut offset = None;
+    if expect_token(tokens, cursor, keyword_token(Keyword::Unbounded)) {
+        cursor += 1;
+    } else {
+        let (exp, new_cursor) = match parse_expression(tokens, cursor, COMPARISON_POWER) {
+            Ok((exp, new_cursor)) => (exp, new_cursor),
+            Err(_) => {
+                help_message(tokens, cursor, String::from("Expected frame offset"));
+                return Err(());
+            }
+        };
+        offset = Some(exp);
+        cursor = new_cursor;
+    }
+
+    let kind = if expect_token(tokens, cursor, keyword_token(Keyword::Preceding)) {
+        match offset {
+            Some(_) => FrameBoundKind::Preceding,
+            None => FrameBoundKind::UnboundedPreceding,
+        }
+    } else if expect_token(tokens, cursor, keyword_token(Keyword::Following)) {
+        match offset {
+            Some(_) => FrameBoundKind::Following,
+            None => FrameBoundKind::UnboundedFollowing,
+        }
+    } else {
+        help_message(
+            tokens,
+            cursor,
+            String::from("Expected PRECEDING or FOLLOWING"),
+        );
+        return Err(());
+    };
+    cursor += 1;
+
+    Ok((FrameBound { kind, offset }, cursor))
+}
+
+fn parse_select(
+    tokens: &[Token],
+    cursor_in: usize,
+    delimiter: &Token,
+) -> Result<(Select, usize), ()> {
+    let mut cursor = cursor_in;
+
+    if !expect_token(tokens, cursor, keyword_token(Keyword::Select)) {
+        return Err(());
+    }
+
+    cursor += 1;
+    let delimiters = vec![
+        keyword_token(Keyword::From),
+        keyword_token(Keyword::Order),
+        keyword_token(Keyword::Limit),
+        keyword_token(Keyword::Offset),
+        delimiter.clone(),
+    ];
+
+    let (expressions, new_cursor) = match parse_expressions(tokens, cursor, &delimiters) {
+        Ok((expressions, new_cursor)) => (expressions, new_cursor),
+        Err(_) => return Err(()),
+    };
+
+    cursor = new_cursor;
+
+    let mut from = Token::nil();
+    let mut from_function = None;
+    let mut joins = Vec::new();
+    let mut where_clause = None;
+    if expect_token(tokens, cursor, keyword_token(Keyword::From)) {
+        cursor += 1;
+        let (from_token, new_cursor) = match parse_identifier(tokens, cursor) {
+            Ok((from_token, new_cursor)) => (from_token, new_cursor),
+            Err(_) => {
+                help_message(tokens, cursor, String::from("Expected table name"));
+                return Err(());
+            }
+        };
+        from = from_token;
+        cursor = new_cursor;
+
+        // A table-valued function such as json_each(...)
+        if expect_token(tokens, cursor, symbol_token(Symbol::LeftParen)) {
+            let (function, new_cursor) = parse_function_call(tokens, cursor - 1)?;
+            from_function = Some(function);
+            cursor = new_cursor;
+        }
+
+        while let Ok((join, new_cursor)) = parse_join(tokens, cursor) {
+            joins.push(join);
+            cursor = new_cursor;
+        }
+        if expect_token(tokens, cursor, keyword_token(Keyword::Inner))
+            || expect_token(tokens, cursor, keyword_token(Keyword::Join))
+        {
+            return Err(());
+        }
+
+        if expect_token(tokens, cursor, keyword_token(Keyword::Where)) {
+            cursor += 1;
+            let (exp, new_cursor) = match parse_expression(tokens, cursor, 0) {
+                Ok((exp, new_cursor)) => (exp, new_cursor),
+                Err(_) => {
+                    help_message(tokens, cursor, String::from("Expected WHERE conditionals"));
+                    return Err(());
+                }
+            };
+            where_clause = Some(exp);
+            cursor = new_cursor;
+        }
+    }
+
+    let mut order_by = Vec::new();
+    if expect_token(tokens, cursor, keyword_token(Keyword::Order)) {
+        let (terms, new_cursor) = parse_order_by(tokens, cursor, &delimiters[2..])?;
+        order_by = terms;
+        cursor = new_cursor;
+    }
+
+    let mut limit = None;
+    if expect_token(tokens, cursor, keyword_token(Keyword::Limit)) {
+        let (exp, new_cursor) = match parse_expression(tokens, cursor + 1, 0) {
+            Ok((exp, new_cursor)) => (exp, new_cursor),
+            Err(_) => {
+                help_message(tokens, cursor + 1, String::from("Expected LIMIT count"));
+                return Err(());
+            }
+        };
+        limit = Some(exp);
+        cursor = new_cursor;
+    }
+
+    let mut offset = None;
+    if expect_token(tokens, cursor, keyword_token(Keyword::Offset)) {
+        let (exp, new_cursor) = match parse_expression(tokens, cursor + 1, 0) {
+            Ok((exp, new_cursor)) => (exp, new_cursor),
+            Err(_) => {
+                help_message(tokens, cursor + 1, String::from("Expected OFFSET count"));
+                return Err(());
+            }
+        };
+        offset = Some(exp);
+        cursor = new_cursor;
+    }
+
+    Ok((
+        Select {
+            from,
+            from_function,
+            joins,
+            items: expressions,
+            where_clause,
+            order_by,
+            limit,
+            offset,
+        },
+        cursor,
+    ))
+}
+
+/// `[INNER] JOIN table ON condition`
+fn parse_join(tokens: &[Token], cursor_in: usize) -> Result<(Join, usize), ()> {
+    let mut cursor = cursor_in;
+    if expect_token(tokens, cursor, keyword_token(Keyword::Inner)) {
+        cursor += 1;
+    }
+    if !expect_token(tokens, cursor, keyword_token(Keyword::Join)) {
+        if cursor != cursor_in {
+            help_message(tokens, cursor, String::from("Expected JOIN"));
+        }
+        return Err(());
+    }
+    cursor += 1;
+
+    let (table, new_cursor) = match parse_identifier(tokens, cursor) {
+        Ok((table, new_cursor)) => (table, new_cursor),
+        Err(_) => {
+            help_message(tokens, cursor, String::from("Expected table name"));
+            return Err(());
+        }
+    };
+    cursor = new_cursor;
+
+    if !expect_token(tokens, cursor, keyword_token(Keyword::On)) {
+        help_message(tokens, cursor, String::from("Expected ON"));
+        return Err(());
+    }
+    cursor += 1;
+
+    let (on, new_cursor) = match parse_expression(tokens, cursor, 0) {
+        Ok((on, new_cursor)) => (on, new_cursor),
+        Err(_) => {
+            help_message(tokens, cursor, String::from("Expected join condition"));
+            return Err(());
+        }
+    };
+    Ok((Join { table, on }, new_cursor))
+}
+
+fn parse_insert(tokens: &[Token], cursor_in: usize) -> Result<(Statement, usize), ()> {
+    let mut cursor = cursor_in;
+
+    if !expect_token(tokens, cursor, keyword_token(Keyword::Insert)) {
+        return Err(());
+    }
+    cursor += 1;
+
+    if !expect_token(tokens, cursor, keyword_token(Keyword::Into)) {
+        help_message(tokens, cursor, String::from("Expected INTO"));
+        return Err(());
+    }
+    cursor += 1;
+
+    let (table, new_cursor) = match parse_identifier(tokens, cursor) {
+        Ok((table, new_cursor)) => (table, new_cursor),
+        Err(_) => {
+            help_message(tokens, cursor, String::from("Expected table name"));
+            return Err(());
+        }
+    };
+    cursor = new_cursor;
+
+    let mut columns = Vec::new();
+    if expect_token(tokens, cursor, symbol_token(Symbol::LeftParen)) {
+        let (names, new_cursor) = parse_column_list(tokens, cursor)?;
+        columns = names;
+        cursor = new_cursor;
+    }
+
+    if !expect_token(tokens, cursor, keyword_token(Keyword::Values)) {
+        help_message(tokens, cursor, String::from("Expected VALUES"));
+        return Err(());
+    }
+    cursor += 1;
+
+    if !expect_token(tokens, cursor, symbol_token(Symbol::LeftParen)) {
+        help_message(tokens, cursor, String::from("Expected LPAREN"));
+        return Err(());
+    }
+    cursor += 1;
+
+    let (values, new_cursor) = match parse_expressions(
+        tokens,
+        cursor,
+        &Vec::from([symbol_token(Symbol::RightParen)]),
+    ) {
+        Ok((expressions, new_cursor)) => (expressions, new_cursor),
+        Err(_) => {
+            return Err(());
+        }
+    };
+
+    cursor = new_cursor;
+
+    if !expect_token(tokens, cursor, symbol_token(Symbol::RightParen)) {
+        help_message(tokens, cursor, String::from("Expected RIGHTPAREN"));
+        return Err(());
+    }
+    cursor += 1;
+
+    let (on_conflict, new_cursor) = parse_on_conflict(tokens, cursor)?;
+    cursor = new_cursor;
+
+    let (returning, new_cursor) = parse_returning(tokens, cursor)?;
+    cursor = new_cursor;
+
+    let stmt = Statement {
+        select: None,
+        insert: Some(Insert {
+            table,
+            columns,
+            values,
+            on_conflict,
+            returning,
+        }),
+        create: None,
+        update: None,
+        delete: None,
+        create_type: None,
+        create_sequence: None,
+        create_index: None,
+        drop_index: None,
+        explain: None,
+        kind: StatementKind::Insert,
+    };
+
+    Ok((stmt, cursor))
+}
+
+fn parse_update(
+    tokens: &[Token],
+    cursor_in: usize,
+    delimiter: &Token,
+) -> Result<(Statement, usize), ()> {
+    let mut cursor = cursor_in;
+
+    if !expect_token(tokens, cursor, keyword_token(Keyword::Update)) {
+        return Err(());
+    }
+    cursor += 1;
+
+    let (table, new_cursor) = match parse_identifier(tokens, cursor) {
+        Ok((table, new_cursor)) => (table, new_cursor),
+        Err(_) => {
+            help_message(tokens, cursor, String::from("Expected table name"));
+            return Err(());
+        }
+    };
+    cursor = new_cursor;
+
+    if !expect_token(tokens, cursor, keyword_token(Keyword::Set)) {
+        help_message(tokens, cursor, String::from("Expected SET"));
+        return Err(());
+    }
+    cursor += 1;
+
+    let (assignments, new_cursor) = parse_assignments(tokens, cursor)?;
+    cursor = new_cursor;
+
+    let mut where_clause = None;
+    if expect_token(tokens, cursor, keyword_token(Keyword::Where)) {
+        cursor += 1;
+        let (exp, new_cursor) = match parse_expression(tokens, cursor, 0) {
+            Ok((exp, new_cursor)) => (exp, new_cursor),
+            Err(_) => {
+                help_message(tokens, cursor, String::from("Expected WHERE conditionals"));
+                return Err(());
+            }
+        };
+        where_clause = Some(exp);
+        cursor = new_cursor;
+    }
+
+    let (returning, new_cursor) = parse_returning(tokens, cursor)?;
+    cursor = new_cursor;
+
+    if cursor < tokens.len() && !expect_token(tokens, cursor, delimiter.clone()) {
+        help_message(tokens, cursor, String::from("Expected end of UPDATE"));
+        return Err(());
+    }
+
+    let stmt = Statement {
+        update: Some(Update {
+            table,
+            assignments,
+            where_clause,
+            returning,
+        }),
+        select: None,
+        insert: None,
+        create: None,
+        delete: None,
+        create_type: None,
+        create_sequence: None,
+        create_index: None,
+        drop_index: None,
+        explain: None,
+        kind: StatementKind::Update,
+    };
+    Ok((stmt, cursor))
+}
+
+/// Parses `column = value, ...`
+fn parse_assignments(tokens: &[Token], cursor_in: usize) -> Result<(Vec<Assignment>, usize), ()> {
+    let mut cursor = cursor_in;
+    let mut assignments = Vec::new();
+    loop {
+        let (column, new_cursor) = match parse_identifier(tokens, cursor) {
+            Ok((column, new_cursor)) => (column, new_cursor),
+            Err(_) => {
+                help_message(tokens, cursor, String::from("Expected column name"));
+                return Err(());
+            }
+        };
+        cursor = new_cursor;
+
+        if !expect_token(tokens, cursor, symbol_token(Symbol::Equal)) {
+            help_message(tokens, cursor, String::from("Expected ="));
+            return Err(());
+        }
+        cursor += 1;
+
+        let (value, new_cursor) = match parse_expression(tokens, cursor, 0) {
+            Ok((value, new_cursor)) => (value, new_cursor),
+            Err(_) => {
+                help_message(tokens, cursor, String::from("Expected expression"));
+                return Err(());
+            }
+        };
+        cursor = new_cursor;
+        assignments.push(Assignment { column, value });
+
+        if !expect_token(tokens, cursor, symbol_token(Symbol::Comma)) {
+            break;
+        }
+        cursor += 1;
+    }
+    Ok((assignments, cursor))
+}
+
+/// Parses an optional `ON CONFLICT` clause. CONFLICT, DO and NOTHING are not
+/// keywords, so that they stay usable as names.
+fn parse_on_conflict(
+    tokens: &[Token],
+    cursor_in: usize,
+) -> Result<(Option<OnConflict>, usize), ()> {
+    let mut cursor = cursor_in;
+    let is_word = |cursor: usize, word: &str| {
+        tokens.get(cursor).is_some_and(|token| {
+            token.token_kind == TokenKind::Identifier && token.literal.eq_ignore_ascii_case(word)
+        })
+    };
+    if !expect_token(tokens, cursor, keyword_token(Keyword::On)) || !is_word(cursor + 1, "conflict")
+    {
+        return Ok((None, cursor));
+    }
+    cursor += 2;
+
+    let mut columns = Vec::new();
+    if expect_token(tokens, cursor, symbol_token(Symbol::LeftParen)) {
+        let (names, new_cursor) = parse_column_list(tokens, cursor)?;
+        columns = names;
+        cursor = new_cursor;
+    }
+
+    if !is_word(cursor, "do") {
+        help_message(tokens, cursor, String::from("Expected DO"));
+        return Err(());
+    }
+    cursor += 1;
+
+    let mut on_conflict = OnConflict {
+        columns,
+        action: ConflictAction::DoNothing,
+        assignments: Vec::new(),
+        where_clause: None,
+    };
+    if is_word(cursor, "nothing") {
+        return Ok((Some(on_conflict), cursor + 1));
+    }
+
+    if !expect_token(tokens, cursor, keyword_token(Keyword::Update))
+        || !expect_token(tokens, cursor + 1, keyword_token(Keyword::Set))
+    {
+        help_message(
+            tokens,
+            cursor,
+            String::from("Expected NOTHING or UPDATE SET"),
+        );
+        return Err(());
+    }
+    if on_conflict.columns.is_empty() {
+        help_message(
+            tokens,
+            cursor,
+            String::from("ON CONFLICT DO UPDATE requires the conflict columns"),
+        );
+        return Err(());
+    }
+    cursor += 2;
+
+    let (assignments, new_cursor) = parse_assignments(tokens, cursor)?;
+    cursor = new_cursor;
+    on_conflict.action = ConflictAction::DoUpdate;
+    on_conflict.assignments = assignments;
+
+    if expect_token(tokens, cursor, keyword_token(Keyword::Where)) {
+        cursor += 1;
+        let (exp, new_cursor) = match parse_expression(tokens, cursor, 0) {
+            Ok((exp, new_cursor)) => (exp, new_cursor),
+            Err(_) => {
+                help_message(tokens, cursor, String::from("Expected WHERE conditionals"));
+                return Err(());
+            }
+        };
+        on_conflict.where_clause = Some(exp);
+        cursor = new_cursor;
+    }
+    Ok((Some(on_conflict), cursor))
+}
+
+fn parse_delete(
+    tokens: &[Token],
+    cursor_in: usize,
+    delimiter: &Token,
+) -> Result<(Statement, usize), ()> {
+    let mut cursor = cursor_in;
+
+    if !expect_token(tokens, cursor, keyword_token(Keyword::Delete)) {
+        return Err(());
+    }
+    cursor += 1;
+
+    if !expect_token(tokens, cursor, keyword_token(Keyword::From)) {
+        help_message(tokens, cursor, String::from("Expected FROM"));
+        return Err(());
+    }
+    cursor += 1;
+
+    let (table, new_cursor) = match parse_identifier(tokens, cursor) {
+        Ok((table, new_cursor)) => (table, new_cursor),
+        Err(_) => {
+            help_message(tokens, cursor, String::from("Expected table name"));
+            return Err(());
+        }
+    };
+    cursor = new_cursor;
+
+    let mut where_clause = None;
+    if expect_token(tokens, cursor, keyword_token(Keyword::Where)) {
+        cursor += 1;
+        let (exp, new_cursor) = match parse_expression(tokens, cursor, 0) {
+            Ok((exp, new_cursor)) => (exp, new_cursor),
+            Err(_) => {
+                help_message(tokens, cursor, String::from("Expected WHERE conditionals"));
+                return Err(());
+            }
+        };
+        where_clause = Some(exp);
+        cursor = new_cursor;
+    }
+
+    let (returning, new_cursor) = parse_returning(tokens, cursor)?;
+    cursor = new_cursor;
+
+    if cursor < tokens.len() && !expect_token(tokens, cursor, delimiter.clone()) {
+        help_message(tokens, cursor, String::from("Expected end of DELETE"));
+        return Err(());
+    }
+
+    let stmt = Statement {
+        delete: Some(Delete {
+            table,
+            where_clause,
+            returning,
+        }),
+        select: None,
+        insert: None,
+        create: None,
+        update: None,
+        create_type: None,
+        create_sequence: None,
+        create_index: None,
+        drop_index: None,
+        explain: None,
+        kind: StatementKind::Delete,
+    };
+    Ok((stmt, cursor))
+}
+
+/// Parses an optional `RETURNING item, ...` clause, where `*` stands for
+/// every column of the table.
+fn parse_returning(tokens: &[Token], cursor_in: usize) -> Result<(Vec<Expression>, usize), ()> {
+    let mut cursor = cursor_in;
+    let mut items = Vec::new();
+    if !expect_token(tokens, cursor, keyword_token(Keyword::Returning)) {
+        return Ok((items, cursor));
+    }
+    cursor += 1;
+
+    loop {
+        if expect_token(tokens, cursor, symbol_token(Symbol::Asterisk)) {
+            items.push(Expression::new(
+                tokens[cursor].clone(),
+                ExpressionKind::Literal,
+            ));
+            cursor += 1;
+        } else {
+            let (item, new_cursor) = match parse_expression(tokens, cursor, 0) {
+                Ok((item, new_cursor)) => (item, new_cursor),
+                Err(_) => {
+                    help_message(tokens, cursor, String::from("Expected RETURNING item"));
+                    return Err(());
+                }
+            };
+            items.push(item);
+            cursor = new_cursor;
+        }
+
+        if !expect_token(tokens, cursor, symbol_token(Symbol::Comma)) {
+            break;
+        }
+        cursor += 1;
+    }
+    Ok((items, cursor))
+}
+
+fn parse_create_sequence(tokens: &[Token], cursor_in: usize) -> Result<(Statement, usize), ()> {
+    let mut cursor = cursor_in;
+
+    if !expect_token(tokens, cursor, keyword_token(Keyword::Create))
+        || !expect_token(tokens, cursor + 1, keyword_token(Keyword::Sequence))
+    {
+        return Err(());
+    }
+    cursor += 2;
+
+    let (name, new_cursor) = match parse_identifier(tokens, cursor) {
+        Ok((name, new_cursor)) => (name, new_cursor),
+        Err(_) => {
+            help_message(tokens, cursor, String::from("Expected sequence name"));
+            return Err(());
+        }
+    };
+    cursor = new_cursor;
+
+    let (options, new_cursor) = parse_sequence_options(tokens, cursor)?;
+    cursor = new_cursor;
+
+    let stmt = Statement {
+        create_sequence: Some(CreateSequence { name, options }),
+        create_index: None,
+        drop_index: None,
+        explain: None,
+        select: None,
+        insert: None,
+        create: None,
+        update: None,
+        delete: None,
+        create_type: None,
+        kind: StatementKind::CreateSequence,
+    };
+    Ok((stmt, cursor))
+}
+
+fn parse_create_index(tokens: &[Token], cursor_in: usize) -> Result<(Statement, usize), ()> {
+    let mut cursor = cursor_in;
+
+    if !expect_token(tokens, cursor, keyword_token(Keyword::Create)) {
+        return Err(());
+    }
+    cursor += 1;
+    let unique = expect_token(tokens, cursor, keyword_token(Keyword::Unique));
+    if unique {
+        cursor += 1;
+    }
+    if !expect_token(tokens, cursor, keyword_token(Keyword::Index)) {
+        return Err(());
+    }
+    cursor += 1;
+
+    let mut name = None;
+    if let Ok((token, new_cursor)) = parse_identifier(tokens, cursor) {
+        name = Some(token);
+        cursor = new_cursor;
+    }
+
+    if !expect_token(tokens, cursor, keyword_token(Keyword::On)) {
+        help_message(tokens, cursor, String::from("Expected ON"));
+        return Err(());
+    }
+    cursor += 1;
+
+    let (table, new_cursor) = match parse_identifier(tokens, cursor) {
+        Ok((table, new_cursor)) => (table, new_cursor),
+        Err(_) => {
+            help_message(tokens, cursor, String::from("Expected table name"));
+            return Err(());
+        }
+    };
+    cursor = new_cursor;
+
+    let mut method = None;
+    if expect_token(tokens, cursor, keyword_token(Keyword::Using)) {
+        cursor += 1;
+        let (token, new_cursor) = match parse_identifier(tokens, cursor) {
+            Ok((token, new_cursor)) => (token, new_cursor),
+            Err(_) => {
+                help_message(tokens, cursor, String::from("Expected index method"));
+                return Err(());
+            }
+        };
+        method = Some(token);
+        cursor = new_cursor;
+    }
+
+    if !expect_token(tokens, cursor, symbol_token(Symbol::LeftParen)) {
+        help_message(tokens, cursor, String::from("Expected LPAREN"));
+        return Err(());
+    }
+    cursor += 1;
+
+    let right_paren = symbol_token(Symbol::RightParen);
+    let (elements, new_cursor) = parse_expressions(tokens, cursor, &vec![right_paren.clone()])?;
+    cursor = new_cursor;
+
+    if !expect_token(tokens, cursor, right_paren) {
+        help_message(tokens, cursor, String::from("Expected RIGHTPAREN"));
+        return Err(());
+    }
+    cursor += 1;
+
+    let mut where_clause = None;
+    if expect_token(tokens, cursor, keyword_token(Keyword::Where)) {
+        cursor += 1;
+        let (exp, new_cursor) = match parse_expression(tokens, cursor, 0) {
+            Ok((exp, new_cursor)) => (exp, new_cursor),
+            Err(_) => {
+                help_message(tokens, cursor, String::from("Expected WHERE conditionals"));
+                return Err(());
+            }
+        };
+        where_clause = Some(exp);
+        cursor = new_cursor;
+    }
+
+    let stmt = Statement {
+        create_index: Some(CreateIndex {
+            name,
+            table,
+            unique,
+            method,
+            elements,
+            where_clause,
+        }),
+        select: None,
+        insert: None,
+        create: None,
+        update: None,
+        delete: None,
+        create_type: None,
+        create_sequence: None,
+        drop_index: None,
+        explain: None,
+        kind: StatementKind::CreateIndex,
+    };
+    Ok((stmt, cursor))
+}
+
+fn parse_drop_index(tokens: &[Token], cursor_in: usize) -> Result<(Statement, usize), ()> {
+    let mut cursor = cursor_in;
+
+    if !expect_token(tokens, cursor, keyword_token(Keyword::Drop))
+        || !expect_token(tokens, cursor + 1, keyword_token(Keyword::Index))
+    {
+        return Err(());
+    }
+    cursor += 2;
+
+    let (name, new_cursor) = match parse_identifier(tokens, cursor) {
+        Ok((name, new_cursor)) => (name, new_cursor),
+        Err(_) => {
+            help_message(tokens, cursor, String::from("Expected index name"));
+            return Err(());
+        }
+    };
+    cursor = new_cursor;
+
+    let stmt = Statement {
+        drop_index: Some(DropIndex { name }),
+        explain: None,
+        select: None,
+        insert: None,
+        create: None,
+        update: None,
+        delete: None,
+        create_type: None,
+        create_sequence: None,
+        create_index: None,
+        kind: StatementKind::DropIndex,
+    };
+    Ok((stmt, cursor))
+}
+
+/// Parses `EXPLAIN [ANALYZE]` followed by a SELECT, INSERT, UPDATE or
+/// DELETE.
+fn parse_explain(
+    tokens: &[Token],
+    cursor_in: usize,
+    delimiter: &Token,
+) -> Result<(Statement, usize), ()> {
+    let mut cursor = cursor_in;
+
+    if !expect_token(tokens, cursor, keyword_token(Keyword::Explain)) {
+        return Err(());
+    }
+    cursor += 1;
+
+    let analyze = expect_token(tokens, cursor, keyword_token(Keyword::Analyze));
+    if analyze {
+        cursor += 1;
+    }
+
+    let mut explain = Explain {
+        select: None,
+        insert: None,
+        update: None,
+        delete: None,
+        analyze,
+    };
+    if let Ok((select, new_cursor)) = parse_select(tokens, cursor, delimiter) {
+        explain.select = Some(select);
+        cursor = new_cursor;
+    } else if let Ok((statement, new_cursor)) = parse_insert(tokens, cursor)
+        .or_else(|_| parse_update(tokens, cursor, delimiter))
+        .or_else(|_| parse_delete(tokens, cursor, delimiter))
+    {
+        explain.insert = statement.insert;
+        explain.update = statement.update;
+        explain.delete = statement.delete;
+        cursor = new_cursor;
+    } else {
+        help_message(
+            tokens,
+            cursor,
+            String::from("Expected SELECT, INSERT, UPDATE or DELETE"),
+        );
+        return Err(());
+    }
+
+    let stmt = Statement {
+        explain: Some(explain),
+        select: None,
+        insert: None,
+        create: None,
+        update: None,
+        delete: None,
+        create_type: None,
+        create_sequence: None,
+        create_index: None,
+        drop_index: None,
+        kind: StatementKind::Explain,
+    };
+    Ok((stmt, cursor))
+}
+
+/// Parses `[START [WITH] n] [INCREMENT [BY] n]` in either order. START,
+/// WITH and INCREMENT are not keywords, so that they stay usable as names.
+fn parse_sequence_options(
+    tokens: &[Token],
+    cursor_in: usize,
+) -> Result<(SequenceOptions, usize), ()> {
+    let mut cursor = cursor_in;
+    let is_word = |cursor: usize, word: &str| {
+        tokens.get(cursor).is_some_and(|token| {
+            token.token_kind == TokenKind::Identifier && token.literal.eq_ignore_ascii_case(word)
+        })
+    };
+
+    let mut options = SequenceOptions::default();
+    loop {
+        let start = is_word(cursor, "start");
+        if !start && !is_word(cursor, "increment") {
+            break;
+        }
+        cursor += 1;
+        if (start && is_word(cursor, "with"))
+            || (!start && expect_token(tokens, cursor, keyword_token(Keyword::By)))
+        {
+            cursor += 1;
+        }
+
+        let (value, new_cursor) = match parse_expression(tokens, cursor, 0) {
+            Ok((value, new_cursor)) => (value, new_cursor),
+            Err(_) => {
+                help_message(
+                    tokens,
+                    cursor,
+                    String::from("Expected sequence option value"),
+                );
+                return Err(());
+            }
+        };
+        cursor = new_cursor;
+        match start {
+            true => options.start = Some(value),
+            false => options.increment = Some(value),
+        }
+    }
+    Ok((options, cursor))
+}
+
+fn parse_create_type(tokens: &[Token], cursor_in: usize) -> Result<(Statement, usize), ()> {
+    let mut cursor = cursor_in;
+
+    if !expect_token(tokens, cursor, keyword_token(Keyword::Create))
+        || !expect_token(tokens, cursor + 1, keyword_token(Keyword::Type))
+    {
+        return Err(());
+    }
+    cursor += 2;
+
+    let (name, new_cursor) = match parse_identifier(tokens, cursor) {
+        Ok((name, new_cursor)) => (name, new_cursor),
+        Err(_) => {
+            help_message(tokens, cursor, String::from("Expected type name"));
+            return Err(());
+        }
+    };
+    cursor = new_cursor;
+
+    for keyword in [Keyword::As, Keyword::Enum] {
+        if !expect_token(tokens, cursor, keyword_token(keyword)) {
+            help_message(tokens, cursor, String::from("Expected AS ENUM"));
+            return Err(());
+        }
+        cursor += 1;
+    }
+
+    if !expect_token(tokens, cursor, symbol_token(Symbol::LeftParen)) {
+        help_message(tokens, cursor, String::from("Expected LEFTPAREN"));
+        return Err(());
+    }
+    cursor += 1;
+
+    let mut labels = Vec::new();
+    while !expect_token(tokens, cursor, symbol_token(Symbol::RightParen)) {
+        if !labels.is_empty() {
+            if !expect_token(tokens, cursor, symbol_token(Symbol::Comma)) {
+                help_message(tokens, cursor, String::from("Expected comma"));
+                return Err(());
+            }
+            cursor += 1;
+        }
+        match parse_token(tokens, cursor, TokenKind::String) {
+            Ok((label, new_cursor)) => {
+                labels.push(label);
+                cursor = new_cursor;
+            }
+            Err(_) => {
+                help_message(tokens, cursor, String::from("Expected enum label"));
+                return Err(());
+            }
+        }
+    }
+    cursor += 1;
+
+    let stmt = Statement {
+        delete: None,
+        create_type: Some(CreateType { name, labels }),
+        create_sequence: None,
+        create_index: None,
+        drop_index: None,
+        explain: None,
+        create: None,
+        select: None,
+        insert: None,
+        update: None,
+        kind: StatementKind::CreateType,
+    };
+    Ok((stmt, cursor))
+}
+
+fn parse_create(tokens: &[Token], cursor_in: usize) -> Result<(Statement, usize), ()> {
+    let mut cursor = cursor_in;
+
+    if !expect_token(tokens, cursor, keyword_token(Keyword::Create)) {
+        return Err(());
+    }
+    cursor += 1;
+
+    if !expect_token(tokens, cursor, keyword_token(Keyword::Table)) {
+        help_message(tokens, cursor, String::from("Expected TABLE"));
+        return Err(());
+    }
+    cursor += 1;
+
+    let (table, new_cursor) = match parse_identifier(tokens, cursor) {
+        Ok((table, new_cursor)) => (table, new_cursor),
+        Err(_) => {
+            help_message(tokens, cursor, String::from("Expected table name"));
+            return Err(());
+        }
+    };
+
+    cursor = new_cursor;
+
+    if !expect_token(tokens, cursor, symbol_token(Symbol::LeftParen)) {
+        help_message(tokens, cursor, String::from("Expected LEFTPAREN"));
+        return Err(());
+    }
+    cursor += 1;
+
+    let (cols, constraints, new_cursor) =
+        match parse_column_defs(tokens, cursor, &symbol_token(Symbol::RightParen)) {
+            Ok((col_defs, constraints, new_cursor)) => (col_defs, constraints, new_cursor),
+            Err(_) => {
+                return Err(());
+            }
+        };
+    cursor = new_cursor;
+
+    if !expect_token(tokens, cursor, symbol_token(Symbol::RightParen)) {
+        help_message(tokens, cursor, String::from("Expected RIGHTPAREN"));
+        return Err(());
+    }
+    cursor += 1;
+
+    let stmt = Statement {
+        create: Some(Create {
+            name: table,
+            cols,
+            constraints,
+        }),
+        select: None,
+        insert: None,
+        update: None,
+        delete: None,
+        create_type: None,
+        create_sequence: None,
+        create_index: None,
+        drop_index: None,
+        explain: None,
+        kind: StatementKind::Create,
+    };
+    Ok((stmt, cursor))
+}
+
+/// Parses the column definitions of CREATE TABLE, and the table
+/// constraints that may be mixed in with them.
+fn parse_column_defs(
+    tokens: &[Token],
+    cursor_in: usize,
+    delimiter: &Token,
+) -> Result<(Vec<ColDefinition>, Vec<Constraint>, usize), ()> {
+    let mut col_defs = Vec::new();
+    let mut constraints = Vec::new();
+    let mut cursor = cursor_in;
+
+    loop {
+        if cursor >= tokens.len() {
+            break;
+        }
+
+        let current = tokens.get(cursor).unwrap();
+
+        if current.literal == delimiter.literal {
+            break;
+        }
+
+        if !col_defs.is_empty() || !constraints.is_empty() {
+            if !expect_token(tokens, cursor, symbol_token(Symbol::Comma)) {
+                help_message(tokens, cursor, String::from("Expected comma"));
+                return Err(());
+            }
+            cursor += 1;
+        }
+
+        if let Some((constraint, new_cursor)) = parse_constraint(tokens, cursor, true)? {
+            cursor = new_cursor;
+            constraints.push(constraint);
+            continue;
+        }
+
+        let (col_name, new_cursor) = match parse_identifier(tokens, cursor) {
+            Ok((col_name, new_cursor)) => (col_name, new_cursor),
+            Err(_) => {
+                help_message(tokens, cursor, String::from("Expected column name"));
+                return Err(());
+            }
+        };
+        cursor = new_cursor;
+
+        let (col_type, new_cursor) = parse_data_type(tokens, cursor)?;
+        cursor = new_cursor;
+
+        let mut col_constraints = Vec::new();
+        while let Some((constraint, new_cursor)) = parse_constraint(tokens, cursor, false)? {
+            cursor = new_cursor;
+            col_constraints.push(constraint);
+        }
 
         let col_def = ColDefinition {
             name: col_name,
             data_type: col_type,
+            constraints: col_constraints,
         };
 
         col_defs.push(col_def);
     }
 
-    Ok((col_defs, cursor))
+    Ok((col_defs, constraints, cursor))
+}
+
+/// Parses a constraint, if the tokens start with one. Table constraints
+/// list their columns in parentheses and cannot be NOT NULL or DEFAULT.
+fn parse_constraint(
+    tokens: &[Token],
+    cursor_in: usize,
+    table_level: bool,
+) -> Result<Option<(Constraint, usize)>, ()> {
+    let mut cursor = cursor_in;
+
+    let mut name = None;
+    if expect_token(tokens, cursor, keyword_token(Keyword::Constraint)) {
+        match parse_identifier(tokens, cursor + 1) {
+            Ok((token, new_cursor)) => {
+                name = Some(token);
+                cursor = new_cursor;
+            }
+            Err(_) => {
+                help_message(tokens, cursor + 1, String::from("Expected constraint name"));
+                return Err(());
+            }
+        }
+    }
+
+    // KEY is not a keyword, so that it stays usable as a name
+    let is_key = tokens.get(cursor + 1).is_some_and(|token| {
+        token.token_kind == TokenKind::Identifier && token.literal.eq_ignore_ascii_case("key")
+    });
+    let (kind, new_cursor) = if expect_token(tokens, cursor, keyword_token(Keyword::Primary)) {
+        if !is_key {
+            help_message(tokens, cursor + 1, String::from("Expected KEY"));
+            return Err(());
+        }
+        (ConstraintKind::PrimaryKey, cursor + 2)
+    } else if expect_token(tokens, cursor, keyword_token(Keyword::Unique)) {
+        (ConstraintKind::Unique, cursor + 1)
+    } else if expect_token(tokens, cursor, keyword_token(Keyword::Check)) {
+        (ConstraintKind::Check, cursor + 1)
+    } else if !table_level && expect_token(tokens, cursor, keyword_token(Keyword::References)) {
+        (ConstraintKind::ForeignKey, cursor)
+    } else if table_level && expect_token(tokens, cursor, keyword_token(Keyword::Foreign)) {
+        if !is_key {
+            help_message(tokens, cursor + 1, String::from("Expected KEY"));
+            return Err(());
+        }
+        (ConstraintKind::ForeignKey, cursor + 2)
+    } else if !table_level && expect_token(tokens, cursor, keyword_token(Keyword::Not)) {
+        if !expect_token(tokens, cursor + 1, keyword_token(Keyword::Null)) {
+            help_message(tokens, cursor + 1, String::from("Expected NULL"));
+            return Err(());
+        }
+        (ConstraintKind::NotNull, cursor + 2)
+    } else if !table_level && expect_token(tokens, cursor, keyword_token(Keyword::Default)) {
+        (ConstraintKind::Default, cursor + 1)
+    } else if !table_level && expect_token(tokens, cursor, keyword_token(Keyword::Generated)) {
+        (ConstraintKind::Identity, cursor + 1)
+    } else if name.is_some() {
+        help_message(tokens, cursor, String::from("Expected constraint"));
+        return Err(());
+    } else {
+        return Ok(None);
+    };
+    cursor = new_cursor;
+
+    let mut constraint = Constraint {
+        name,
+        kind,
+        columns: Vec::new(),
+        expression: None,
+        references: None,
+        identity: None,
+    };
+    match kind {
+        ConstraintKind::Identity => {
+            let always = expect_token(tokens, cursor, keyword_token(Keyword::Always));
+            if always {
+                cursor += 1;
+            } else if expect_token(tokens, cursor, keyword_token(Keyword::By))
+                && expect_token(tokens, cursor + 1, keyword_token(Keyword::Default))
+            {
+                cursor += 2;
+            } else {
+                help_message(
+                    tokens,
+                    cursor,
+                    String::from("Expected ALWAYS or BY DEFAULT"),
+                );
+                return Err(());
+            }
+            if !expect_token(tokens, cursor, keyword_token(Keyword::As))
+                || !expect_token(tokens, cursor + 1, keyword_token(Keyword::Identity))
+            {
+                help_message(tokens, cursor, String::from("Expected AS IDENTITY"));
+                return Err(());
+            }
+            cursor += 2;
+
+            let mut options = SequenceOptions::default();
+            if expect_token(tokens, cursor, symbol_token(Symbol::LeftParen)) {
+                let (parsed, new_cursor) = parse_sequence_options(tokens, cursor + 1)?;
+                if !expect_token(tokens, new_cursor, symbol_token(Symbol::RightParen)) {
+                    help_message(tokens, new_cursor, String::from("Expected RIGHTPAREN"));
+                    return Err(());
+                }
+                options = parsed;
+                cursor = new_cursor + 1;
+            }
+            constraint.identity = Some(Identity { always, options });
+        }
+        ConstraintKind::ForeignKey => {
+            if table_level {
+                let (columns, new_cursor) = parse_column_list(tokens, cursor)?;
+                constraint.columns = columns;
+                cursor = new_cursor;
+            }
+            let (references, new_cursor) = parse_references(tokens, cursor)?;
+            constraint.references = Some(references);
+            cursor = new_cursor;
+        }
+        ConstraintKind::PrimaryKey | ConstraintKind::Unique if table_level => {
+            let (columns, new_cursor) = parse_column_list(tokens, cursor)?;
+            constraint.columns = columns;
+            cursor = new_cursor;
+        }
+        ConstraintKind::Check => {
+            if !expect_token(tokens, cursor, symbol_token(Symbol::LeftParen)) {
+                help_message(tokens, cursor, String::from("Expected LEFTPAREN"));
+                return Err(());
+            }
+            let (condition, new_cursor) = parse_expression(tokens, cursor + 1, 0)?;
+            if !expect_token(tokens, new_cursor, symbol_token(Symbol::RightParen)) {
+                help_message(tokens, new_cursor, String::from("Expected RIGHTPAREN"));
+                return Err(());
+            }
+            constraint.expression = Some(condition);
+            cursor = new_cursor + 1;
+        }
+        ConstraintKind::Default => match parse_expression(tokens, cursor, 0) {
+            Ok((value, new_cursor)) => {
+                constraint.expression = Some(value);
+                cursor = new_cursor;
+            }
+            Err(_) => {
+                help_message(tokens, cursor, String::from("Expected default value"));
+                return Err(());
+            }
+        },
+        _ => {}
+    }
+    Ok(Some((constraint, cursor)))
+}
+
+/// Parses `REFERENCES table [(column, ...)]` followed by any of
+/// `ON DELETE action` and `ON UPDATE action`.
+fn parse_references(tokens: &[Token], cursor_in: usize) -> Result<(References, usize), ()> {
+    let mut cursor = cursor_in;
+    if !expect_token(tokens, cursor, keyword_token(Keyword::References)) {
+        help_message(tokens, cursor, String::from("Expected REFERENCES"));
+        return Err(());
+    }
+    cursor += 1;
+
+    let (table, new_cursor) = match parse_identifier(tokens, cursor) {
+        Ok((table, new_cursor)) => (table, new_cursor),
+        Err(_) => {
+            help_message(tokens, cursor, String::from("Expected table name"));
+            return Err(());
+        }
+    };
+    cursor = new_cursor;
+
+    let mut references = References {
+        table,
+        columns: Vec::new(),
+        on_delete: ReferentialAction::NoAction,
+        on_update: ReferentialAction::NoAction,
+    };
+    if expect_token(tokens, cursor, symbol_token(Symbol::LeftParen)) {
+        let (columns, new_cursor) = parse_column_list(tokens, cursor)?;
+        references.columns = columns;
+        cursor = new_cursor;
+    }
+
+    // NO ACTION uses words that are not keywords
+    let is_word = |cursor: usize, word: &str| {
+        tokens.get(cursor).is_some_and(|token| {
+            token.token_kind == TokenKind::Identifier && token.literal.eq_ignore_ascii_case(word)
+        })
+    };
+    while expect_token(tokens, cursor, keyword_token(Keyword::On)) {
+        let on_delete = expect_token(tokens, cursor + 1, keyword_token(Keyword::Delete));
+        if !on_delete && !expect_token(tokens, cursor + 1, keyword_token(Keyword::Update)) {
+            help_message(
+                tokens,
+                cursor + 1,
+                String::from("Expected DELETE or UPDATE"),
+            );
+            return Err(());
+        }
+        cursor += 2;
+
+        let (action, new_cursor) = if expect_token(tokens, cursor, keyword_token(Keyword::Cascade))
+        {
+            (ReferentialAction::Cascade, cursor + 1)
+        } else if expect_token(tokens, cursor, keyword_token(Keyword::Restrict)) {
+            (ReferentialAction::Restrict, cursor + 1)
+        } else if expect_token(tokens, cursor, keyword_token(Keyword::Set))
+            && expect_token(tokens, cursor + 1, keyword_token(Keyword::Null))
+        {
+            (ReferentialAction::SetNull, cursor + 2)
+        } else if is_word(cursor, "no") && is_word(cursor + 1, "action") {
+            (ReferentialAction::NoAction, cursor + 2)
+        } else {
+            help_message(tokens, cursor, String::from("Expected referential action"));
+            return Err(());
+        };
+        cursor = new_cursor;
+        match on_delete {
+            true => references.on_delete = action,
+            false => references.on_update = action,
+        }
+    }
+    Ok((references, cursor))
+}
+
+/// Parses a parenthesized, comma separated list of column names.
+fn parse_column_list(tokens: &[Token], cursor_in: usize) -> Result<(Vec<Token>, usize), ()> {
+    let mut cursor = cursor_in;
+    if !expect_token(tokens, cursor, symbol_token(Symbol::LeftParen)) {
+        help_message(tokens, cursor, String::from("Expected LEFTPAREN"));
+        return Err(());
+    }
+    cursor += 1;
+
+    let mut columns = Vec::new();
+    loop {
+        match parse_identifier(tokens, cursor) {
+            Ok((column, new_cursor)) => {
+                columns.push(column);
+                cursor = new_cursor;
+            }
+            Err(_) => {
+                help_message(tokens, cursor, String::from("Expected column name"));
+                return Err(());
+            }
+        }
+        if !expect_token(tokens, cursor, symbol_token(Symbol::Comma)) {
+            break;
+        }
+        cursor += 1;
+    }
+
+    if !expect_token(tokens, cursor, symbol_token(Symbol::RightParen)) {
+        help_message(tokens, cursor, String::from("Expected RIGHTPAREN"));
+        return Err(());
+    }
+    Ok((columns, cursor + 1))
 }
 
-fn help_message(tokens: &Vec<Token>, cursor: usize, msg: String) {
+fn help_message(tokens: &[Token], cursor: usize, msg: String) {
     if cursor < tokens.len() {
         let token = tokens.get(cursor).unwrap();
         println!(
@@ -548,7 +2498,23 @@ fn help_message(tokens: &Vec<Token>, cursor: usize, msg: String) {
     }
 }
 
-fn expect_token(tokens: &Vec<Token>, cursor: usize, token: Token) -> bool {
+fn keyword_token(keyword: Keyword) -> Token {
+    Token {
+        literal: keyword.to_string(),
+        token_kind: TokenKind::Keyword,
+        loc: Location::new(),
+    }
+}
+
+fn symbol_token(symbol: Symbol) -> Token {
+    Token {
+        literal: symbol.to_string(),
+        token_kind: TokenKind::Symbol,
+        loc: Location::new(),
+    }
+}
+
+fn expect_token(tokens: &[Token], cursor: usize, token: Token) -> bool {
     if let Some(t) = tokens.get(cursor) {
         *t.literal == token.literal
     } else {
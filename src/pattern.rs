@@ -0,0 +1,207 @@
+use crate::mem_backend::SQLError;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum PatternToken {
+    // Any sequence of characters, `%` in LIKE and `*` in GLOB
+    Any,
+    // Exactly one character, `_` in LIKE and `?` in GLOB
+    One,
+    Char(char),
+    // A GLOB character class such as `[a-z]` or `[^0-9]`
+    Class {
+        negated: bool,
+        ranges: Vec<(char, char)>,
+    },
+}
+
+/// Matches `value` against a LIKE pattern. `escape` makes the following
+/// `%`, `_` or escape character match literally.
+pub fn like(
+    value: &str,
+    pattern: &str,
+    escape: Option<char>,
+    case_insensitive: bool,
+) -> Result<bool, SQLError> {
+    let (value, pattern) = if case_insensitive {
+        (value.to_lowercase(), pattern.to_lowercase())
+    } else {
+        (value.to_string(), pattern.to_string())
+    };
+
+    let mut tokens = Vec::new();
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        if Some(c) == escape {
+            match chars.next() {
+                Some(escaped) => tokens.push(PatternToken::Char(escaped)),
+                None => {
+                    return Err(SQLError::InvalidPattern(String::from(
+                        "LIKE pattern must not end with escape character",
+                    )))
+                }
+            }
+            continue;
+        }
+
+        tokens.push(match c {
+            '%' => PatternToken::Any,
+            '_' => PatternToken::One,
+            c => PatternToken::Char(c),
+        });
+    }
+
+    Ok(wildcard_match(&value.chars().collect::<Vec<_>>(), &tokens))
+}
+
+/// Matches `value` against a case sensitive GLOB pattern using `*`, `?` and
+/// `[...]` character classes.
+pub fn glob(value: &str, pattern: &str) -> Result<bool, SQLError> {
+    let mut tokens = Vec::new();
+    let chars: Vec<char> = pattern.chars().collect();
+    let mut i = 0;
+
+    while i < chars.len() {
+        match chars[i] {
+            '*' => tokens.push(PatternToken::Any),
+            '?' => tokens.push(PatternToken::One),
+            '[' => {
+                let mut j = i + 1;
+                let negated = j < chars.len() && chars[j] == '^';
+                if negated {
+                    j += 1;
+                }
+
+                let mut ranges = Vec::new();
+                // A `]` right after the opening bracket is part of the class
+                let mut first = true;
+                while j < chars.len() && (chars[j] != ']' || first) {
+                    first = false;
+                    if j + 2 < chars.len() && chars[j + 1] == '-' && chars[j + 2] != ']' {
+                        ranges.push((chars[j], chars[j + 2]));
+                        j += 3;
+                    } else {
+                        ranges.push((chars[j], chars[j]));
+                        j += 1;
+                    }
+                }
+
+                if j >= chars.len() {
+                    return Err(SQLError::InvalidPattern(String::from(
+                        "unterminated character class in GLOB pattern",
+                    )));
+                }
+                tokens.push(PatternToken::Class { negated, ranges });
+                i = j;
+            }
+            c => tokens.push(PatternToken::Char(c)),
+        }
+        i += 1;
+    }
+
+    Ok(wildcard_match(&value.chars().collect::<Vec<_>>(), &tokens))
+}
+
+fn token_matches(token: &PatternToken, c: char) -> bool {
+    match token {
+        PatternToken::Any | PatternToken::One => true,
+        PatternToken::Char(p) => *p == c,
+        PatternToken::Class { negated, ranges } => {
+            ranges.iter().any(|(lo, hi)| *lo <= c && c <= *hi) != *negated
+        }
+    }
+}
+
+// Greedy matching that backtracks to the most recent `Any`, which keeps the
+// work linear in the common case.
+fn wildcard_match(value: &[char], pattern: &[PatternToken]) -> bool {
+    let mut v = 0;
+    let mut p = 0;
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while v < value.len() {
+        if p < pattern.len() {
+            if pattern[p] == PatternToken::Any {
+                backtrack = Some((p, v));
+                p += 1;
+                continue;
+            }
+            if token_matches(&pattern[p], value[v]) {
+                p += 1;
+                v += 1;
+                continue;
+            }
+        }
+
+        match backtrack {
+            Some((any_p, any_v)) => {
+                p = any_p + 1;
+                v = any_v + 1;
+                backtrack = Some((any_p, any_v + 1));
+            }
+            None => return false,
+        }
+    }
+
+    pattern[p..].iter().all(|token| *token == PatternToken::Any)
+}
+
+#[cfg(test)]
+mod pattern_test {
+    use crate::pattern::{glob, like};
+
+    #[test]
+    fn test_like() {
+        let tests = vec![
+            ("hello", "h%", None, false, true),
+            ("hello", "h_llo", None, false, true),
+            ("hello", "%ll%", None, false, true),
+            ("hello", "%lo", None, false, true),
+            ("hello", "h%x", None, false, false),
+            ("hello", "HELLO", None, false, false),
+            ("hello", "HE%", None, true, true),
+            ("100%", "100\\%", Some('\\'), false, true),
+            ("1000", "100\\%", Some('\\'), false, false),
+            ("a_b", "a!_b", Some('!'), false, true),
+            ("axb", "a!_b", Some('!'), false, false),
+            ("", "%", None, false, true),
+            ("", "_", None, false, false),
+        ];
+
+        for (value, pattern, escape, case_insensitive, expected) in tests {
+            assert_eq!(
+                like(value, pattern, escape, case_insensitive).unwrap(),
+                expected,
+                "{} LIKE {}",
+                value,
+                pattern
+            );
+        }
+
+        assert!(like("a", "a\\", Some('\\'), false).is_err());
+    }
+
+    #[test]
+    fn test_glob() {
+        let tests = vec![
+            ("hello", "h*", true),
+            ("hello", "H*", false),
+            ("hello", "h?llo", true),
+            ("hello", "[a-h]ello", true),
+            ("hello", "[^a-h]ello", false),
+            ("h]llo", "h[]]llo", true),
+            ("hello", "*l[lo]*", true),
+        ];
+
+        for (value, pattern, expected) in tests {
+            assert_eq!(
+                glob(value, pattern).unwrap(),
+                expected,
+                "{} GLOB {}",
+                value,
+                pattern
+            );
+        }
+
+        assert!(glob("a", "[a").is_err());
+    }
+}
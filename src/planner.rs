@@ -0,0 +1,992 @@
+use std::{ops::Bound, time::Duration};
+
+use crate::{
+    ast::{BinaryExpression, Expression, ExpressionKind, FunctionCall, OrderingTerm, Select},
+    lexer::{Keyword, Location, Token, TokenKind},
+    mem_backend::{column_references, is_constant, SQLError, Value},
+};
+
+/// Where a scan reads its rows from.
+#[derive(Debug, Clone)]
+pub enum Source {
+    // No FROM: a single row without columns
+    Nothing,
+    Table(String),
+    Function(FunctionCall),
+}
+
+/// What a SELECT computes, as a tree of operators each reading the rows of
+/// its input.
+#[derive(Debug, Clone)]
+pub enum LogicalPlan {
+    Scan {
+        source: Source,
+        // Conditions checked while scanning
+        filter: Option<Expression>,
+        // The columns read, every one when None
+        columns: Option<Vec<String>>,
+    },
+    Filter {
+        input: Box<LogicalPlan>,
+        predicate: Expression,
+    },
+    // Pairs every row of `left` with the rows of `right` satisfying `on`.
+    // Columns are qualified by their table, such as users.id.
+    Join {
+        left: Box<LogicalPlan>,
+        right: Box<LogicalPlan>,
+        on: Expression,
+    },
+    // Folds every input row into a single group
+    Aggregate {
+        input: Box<LogicalPlan>,
+    },
+    Project {
+        input: Box<LogicalPlan>,
+        items: Vec<Expression>,
+    },
+    // Orders the rows of a projection. Terms may name its result columns
+    // by position or be evaluated against its input rows.
+    Sort {
+        input: Box<LogicalPlan>,
+        terms: Vec<OrderingTerm>,
+    },
+    Limit {
+        input: Box<LogicalPlan>,
+        limit: Option<Expression>,
+        offset: Option<Expression>,
+    },
+}
+
+impl LogicalPlan {
+    /// The plan of a SELECT as written, before any rewriting. `aggregate`
+    /// tells whether its items fold the rows into a group.
+    pub fn from_select(select: &Select, aggregate: bool) -> LogicalPlan {
+        let source = if select.from.token_kind == TokenKind::Nil {
+            Source::Nothing
+        } else if let Some(function) = &select.from_function {
+            Source::Function(function.clone())
+        } else {
+            Source::Table(select.from.literal.clone())
+        };
+        let mut plan = LogicalPlan::Scan {
+            source,
+            filter: None,
+            columns: None,
+        };
+        for join in &select.joins {
+            plan = LogicalPlan::Join {
+                left: Box::new(plan),
+                right: Box::new(LogicalPlan::Scan {
+                    source: Source::Table(join.table.literal.clone()),
+                    filter: None,
+                    columns: None,
+                }),
+                on: join.on.clone(),
+            };
+        }
+        if let Some(predicate) = &select.where_clause {
+            plan = LogicalPlan::Filter {
+                input: Box::new(plan),
+                predicate: predicate.clone(),
+            };
+        }
+        if aggregate {
+            plan = LogicalPlan::Aggregate {
+                input: Box::new(plan),
+            };
+        }
+        plan = LogicalPlan::Project {
+            input: Box::new(plan),
+            items: select.items.clone(),
+        };
+        if !select.order_by.is_empty() {
+            plan = LogicalPlan::Sort {
+                input: Box::new(plan),
+                terms: select.order_by.clone(),
+            };
+        }
+        if select.limit.is_some() || select.offset.is_some() {
+            plan = LogicalPlan::Limit {
+                input: Box::new(plan),
+                limit: select.limit.clone(),
+                offset: select.offset.clone(),
+            };
+        }
+        plan
+    }
+
+    /// Applies the rewrite rules. `evaluate` gives the value of a constant
+    /// expression, or `None` when it fails, and `columns` the columns of a
+    /// table.
+    pub fn optimize<F, G>(self, evaluate: &F, columns: &G) -> LogicalPlan
+    where
+        F: Fn(&Expression) -> Option<Value>,
+        G: Fn(&str) -> Vec<String>,
+    {
+        self.push_down_predicates(columns)
+            .fold_constants(evaluate)
+            .prune_columns(Vec::new(), columns)
+    }
+
+    /// The plan with `rewrite` applied to its inputs.
+    fn map_input<F>(self, mut rewrite: F) -> LogicalPlan
+    where
+        F: FnMut(LogicalPlan) -> LogicalPlan,
+    {
+        match self {
+            LogicalPlan::Scan { .. } => self,
+            LogicalPlan::Filter { input, predicate } => LogicalPlan::Filter {
+                input: Box::new(rewrite(*input)),
+                predicate,
+            },
+            LogicalPlan::Join { left, right, on } => LogicalPlan::Join {
+                left: Box::new(rewrite(*left)),
+                right: Box::new(rewrite(*right)),
+                on,
+            },
+            LogicalPlan::Aggregate { input } => LogicalPlan::Aggregate {
+                input: Box::new(rewrite(*input)),
+            },
+            LogicalPlan::Project { input, items } => LogicalPlan::Project {
+                input: Box::new(rewrite(*input)),
+                items,
+            },
+            LogicalPlan::Sort { input, terms } => LogicalPlan::Sort {
+                input: Box::new(rewrite(*input)),
+                terms,
+            },
+            LogicalPlan::Limit {
+                input,
+                limit,
+                offset,
+            } => LogicalPlan::Limit {
+                input: Box::new(rewrite(*input)),
+                limit,
+                offset,
+            },
+        }
+    }
+
+    /// Moves filters into the scans below them, so that rows are dropped
+    /// as soon as they are read and an index can find them. Below a join
+    /// each condition of a filter goes to the side of the one table it
+    /// refers to; conditions on both sides stay above the join.
+    fn push_down_predicates<G>(self, columns: &G) -> LogicalPlan
+    where
+        G: Fn(&str) -> Vec<String>,
+    {
+        match self {
+            LogicalPlan::Filter { input, predicate } => match input.push_down_predicates(columns) {
+                LogicalPlan::Scan {
+                    source,
+                    filter,
+                    columns,
+                } => LogicalPlan::Scan {
+                    source,
+                    filter: Some(and(filter, predicate)),
+                    columns,
+                },
+                LogicalPlan::Filter {
+                    input,
+                    predicate: below,
+                } => LogicalPlan::Filter {
+                    input,
+                    predicate: and(Some(below), predicate),
+                },
+                LogicalPlan::Join { left, right, on } => {
+                    let (mut left, mut right) = (*left, *right);
+                    let mut kept = None;
+                    for condition in split_conjuncts(predicate) {
+                        let filter = |input: LogicalPlan, predicate: Expression| {
+                            LogicalPlan::Filter {
+                                input: Box::new(input),
+                                predicate,
+                            }
+                            .push_down_predicates(columns)
+                        };
+                        match single_table(&condition, &left, &right, columns) {
+                            Some(table) if left.tables().contains(&table.as_str()) => {
+                                left = filter(left, condition)
+                            }
+                            Some(_) => right = filter(right, condition),
+                            None => kept = Some(and(kept, condition)),
+                        }
+                    }
+                    let join = LogicalPlan::Join {
+                        left: Box::new(left),
+                        right: Box::new(right),
+                        on,
+                    };
+                    match kept {
+                        Some(predicate) => LogicalPlan::Filter {
+                            input: Box::new(join),
+                            predicate,
+                        },
+                        None => join,
+                    }
+                }
+                input => LogicalPlan::Filter {
+                    input: Box::new(input),
+                    predicate,
+                },
+            },
+            plan => plan.map_input(|input| input.push_down_predicates(columns)),
+        }
+    }
+
+    /// The tables the scans of the plan read.
+    fn tables(&self) -> Vec<&str> {
+        match self {
+            LogicalPlan::Scan {
+                source: Source::Table(name),
+                ..
+            } => vec![name],
+            LogicalPlan::Scan { .. } => vec![],
+            LogicalPlan::Join { left, right, .. } => {
+                let mut tables = left.tables();
+                tables.extend(right.tables());
+                tables
+            }
+            LogicalPlan::Filter { input, .. }
+            | LogicalPlan::Aggregate { input }
+            | LogicalPlan::Project { input, .. }
+            | LogicalPlan::Sort { input, .. }
+            | LogicalPlan::Limit { input, .. } => input.tables(),
+        }
+    }
+
+    /// Evaluates the constant conditions of filters. A filter that always
+    /// holds is dropped and one that never does becomes `false`.
+    fn fold_constants<F>(self, evaluate: &F) -> LogicalPlan
+    where
+        F: Fn(&Expression) -> Option<Value>,
+    {
+        match self {
+            LogicalPlan::Scan {
+                source,
+                filter,
+                columns,
+            } => LogicalPlan::Scan {
+                source,
+                filter: filter.and_then(|filter| fold_filter(filter, evaluate)),
+                columns,
+            },
+            LogicalPlan::Filter { input, predicate } => {
+                let input = input.fold_constants(evaluate);
+                match fold_filter(predicate, evaluate) {
+                    Some(predicate) => LogicalPlan::Filter {
+                        input: Box::new(input),
+                        predicate,
+                    },
+                    None => input,
+                }
+            }
+            plan => plan.map_input(|input| input.fold_constants(evaluate)),
+        }
+    }
+
+    /// Narrows scans to the columns that the operators above them use.
+    /// A scan of a table keeps those of its columns named either way, with
+    /// or without its name as a qualifier.
+    fn prune_columns<G>(self, mut used: Vec<String>, columns: &G) -> LogicalPlan
+    where
+        G: Fn(&str) -> Vec<String>,
+    {
+        let mut add = |exp: &Expression| {
+            let mut columns = Vec::new();
+            column_references(exp, &mut columns);
+            used.extend(columns.into_iter().map(String::from));
+        };
+        match &self {
+            LogicalPlan::Scan { filter, .. } => filter.iter().for_each(add),
+            LogicalPlan::Join { on, .. } => add(on),
+            LogicalPlan::Aggregate { .. } => {}
+            LogicalPlan::Filter { predicate, .. } => add(predicate),
+            LogicalPlan::Project { items, .. } => items.iter().for_each(add),
+            LogicalPlan::Sort { terms, .. } => terms.iter().for_each(|t| add(&t.expression)),
+            LogicalPlan::Limit { limit, offset, .. } => limit.iter().chain(offset).for_each(add),
+        }
+
+        match self {
+            LogicalPlan::Scan {
+                source,
+                filter,
+                columns: _,
+            } => {
+                if let Source::Table(name) = &source {
+                    let table_columns = columns(name);
+                    used = used
+                        .into_iter()
+                        .filter_map(|column| match column.split_once('.') {
+                            Some((table, column)) if table == name => Some(column.to_string()),
+                            Some(_) => None,
+                            None => Some(column),
+                        })
+                        .filter(|column| table_columns.contains(column))
+                        .collect();
+                }
+                used.sort();
+                used.dedup();
+                LogicalPlan::Scan {
+                    source,
+                    filter,
+                    columns: Some(used),
+                }
+            }
+            plan => plan.map_input(|input| input.prune_columns(used.clone(), columns)),
+        }
+    }
+}
+
+/// How a scan finds its rows.
+#[derive(Debug, Clone)]
+pub enum Access {
+    // Reads every row
+    Sequential,
+    // Looks the rows up in an index, then checks them against the filter
+    Index { name: String, lookup: IndexLookup },
+    // Reads nothing, the filter never holds
+    Empty,
+}
+
+/// What a scan looks up in an index.
+#[derive(Debug, Clone)]
+pub enum IndexLookup {
+    // Rows whose key equals the values
+    Key(Vec<Value>),
+    // Rows whose first key part lies between the bounds
+    Range(Bound<Value>, Bound<Value>),
+    // Every row of a partial index
+    All,
+}
+
+/// How a backend executes a SELECT, lowered from its logical plan.
+#[derive(Debug, Clone)]
+pub enum PhysicalPlan {
+    Scan {
+        source: Source,
+        access: Access,
+        filter: Option<Expression>,
+        columns: Option<Vec<String>>,
+    },
+    Filter {
+        input: Box<PhysicalPlan>,
+        predicate: Expression,
+    },
+    // Checks `on` for every pair of a row of `left` and one of `right`
+    NestedLoopJoin {
+        left: Box<PhysicalPlan>,
+        right: Box<PhysicalPlan>,
+        on: Expression,
+    },
+    Aggregate {
+        input: Box<PhysicalPlan>,
+    },
+    // Evaluates the items of every row, and the keys a sort above orders
+    // them by
+    Project {
+        input: Box<PhysicalPlan>,
+        items: Vec<Expression>,
+        keys: Vec<Expression>,
+    },
+    // Orders the rows of the projection below by its keys
+    Sort {
+        input: Box<PhysicalPlan>,
+        descending: Vec<bool>,
+    },
+    Limit {
+        input: Box<PhysicalPlan>,
+        limit: Option<Expression>,
+        offset: Option<Expression>,
+    },
+}
+
+impl PhysicalPlan {
+    /// Lowers a logical plan. `access` decides how a scan with the given
+    /// source and filter finds its rows.
+    pub fn from_logical<F>(plan: LogicalPlan, access: &F) -> Result<PhysicalPlan, SQLError>
+    where
+        F: Fn(&Source, Option<&Expression>) -> Result<Access, SQLError>,
+    {
+        let lower = |input: Box<LogicalPlan>| -> Result<Box<PhysicalPlan>, SQLError> {
+            Ok(Box::new(PhysicalPlan::from_logical(*input, access)?))
+        };
+        Ok(match plan {
+            LogicalPlan::Scan {
+                source,
+                filter,
+                columns,
+            } => {
+                let chosen = access(&source, filter.as_ref())?;
+                PhysicalPlan::Scan {
+                    access: match &filter {
+                        Some(filter) if is_false(filter) => Access::Empty,
+                        _ => chosen,
+                    },
+                    source,
+                    filter,
+                    columns,
+                }
+            }
+            LogicalPlan::Filter { input, predicate } => PhysicalPlan::Filter {
+                input: lower(input)?,
+                predicate,
+            },
+            LogicalPlan::Join { left, right, on } => PhysicalPlan::NestedLoopJoin {
+                left: lower(left)?,
+                right: lower(right)?,
+                on,
+            },
+            LogicalPlan::Aggregate { input } => PhysicalPlan::Aggregate {
+                input: lower(input)?,
+            },
+            LogicalPlan::Project { input, items } => PhysicalPlan::Project {
+                input: lower(input)?,
+                items,
+                keys: Vec::new(),
+            },
+            // The keys are computed by the projection, which sees both its
+            // result columns and its input rows
+            LogicalPlan::Sort { input, terms } => {
+                let input = match *input {
+                    LogicalPlan::Project { input, items } => PhysicalPlan::Project {
+                        input: lower(input)?,
+                        items,
+                        keys: terms.iter().map(|t| t.expression.clone()).collect(),
+                    },
+                    _ => unreachable!("ORDER BY sorts a projection"),
+                };
+                PhysicalPlan::Sort {
+                    input: Box::new(input),
+                    descending: terms.iter().map(|t| t.descending).collect(),
+                }
+            }
+            LogicalPlan::Limit {
+                input,
+                limit,
+                offset,
+            } => PhysicalPlan::Limit {
+                input: lower(input)?,
+                limit,
+                offset,
+            },
+        })
+    }
+}
+
+/// What a backend knows about an operator of a plan it explains.
+pub struct Annotation {
+    pub estimated_rows: usize,
+    // Rows produced and time taken, including the operators below, when the
+    // plan was run
+    pub actual: Option<(usize, Duration)>,
+    // Lines to show first, such as the condition an index scan looks up
+    pub details: Vec<String>,
+}
+
+impl PhysicalPlan {
+    /// The plan as indented lines: one per operator, followed by its details.
+    pub fn explain<F>(&self, annotate: &F) -> Vec<String>
+    where
+        F: Fn(&PhysicalPlan) -> Annotation,
+    {
+        let mut lines = Vec::new();
+        self.explain_into(annotate, 0, &mut lines);
+        lines
+    }
+
+    /// The lines of `explain` below one for an INSERT, UPDATE or DELETE of
+    /// the rows this plan finds, such as `Update on t`, with the rows it
+    /// returned and its time when it ran.
+    pub fn explain_modification<F>(
+        &self,
+        title: &str,
+        actual: Option<(usize, Duration)>,
+        annotate: &F,
+    ) -> Vec<String>
+    where
+        F: Fn(&PhysicalPlan) -> Annotation,
+    {
+        let estimated_rows = annotate(self).estimated_rows;
+        let mut line = format!("{}  (rows={})", title, estimated_rows);
+        line.push_str(&actual_text(actual));
+        let mut lines = vec![line];
+        self.explain_into(annotate, 1, &mut lines);
+        lines
+    }
+
+    fn explain_into<F>(&self, annotate: &F, depth: usize, lines: &mut Vec<String>)
+    where
+        F: Fn(&PhysicalPlan) -> Annotation,
+    {
+        let (title, details, inputs) = self.describe();
+        let annotation = annotate(self);
+
+        let (prefix, detail_prefix) = match depth {
+            0 => (String::new(), String::from("  ")),
+            _ => (
+                format!("{}->  ", " ".repeat(6 * depth - 4)),
+                " ".repeat(6 * depth + 2),
+            ),
+        };
+        let mut line = format!("{}{}  (rows={})", prefix, title, annotation.estimated_rows);
+        line.push_str(&actual_text(annotation.actual));
+        lines.push(line);
+        for detail in annotation.details.into_iter().chain(details) {
+            lines.push(format!("{}{}", detail_prefix, detail));
+        }
+
+        for input in inputs {
+            input.explain_into(annotate, depth + 1, lines);
+        }
+    }
+
+    /// The title of the operator, its details and its inputs.
+    fn describe(&self) -> (String, Vec<String>, Vec<&PhysicalPlan>) {
+        match self {
+            PhysicalPlan::Scan {
+                source,
+                access,
+                filter,
+                columns,
+            } => {
+                let title = match (source, access) {
+                    (Source::Nothing, _) => String::from("Result"),
+                    (Source::Function(function), _) => {
+                        format!("Function Scan on {}", function.name.literal)
+                    }
+                    (Source::Table(table), Access::Sequential) => format!("Seq Scan on {}", table),
+                    (Source::Table(table), Access::Index { name, .. }) => {
+                        format!("Index Scan using {} on {}", name, table)
+                    }
+                    (Source::Table(table), Access::Empty) => format!("Empty Scan on {}", table),
+                };
+                let mut details = Vec::new();
+                if let Some(filter) = filter {
+                    details.push(format!("Filter: {}", filter));
+                }
+                if let Some(columns) = columns.as_ref().filter(|c| !c.is_empty()) {
+                    details.push(format!("Columns: {}", columns.join(", ")));
+                }
+                (title, details, vec![])
+            }
+            PhysicalPlan::Filter { input, predicate } => (
+                String::from("Filter"),
+                vec![format!("Filter: {}", predicate)],
+                vec![input],
+            ),
+            PhysicalPlan::NestedLoopJoin { left, right, on } => (
+                String::from("Nested Loop"),
+                vec![format!("Join Filter: {}", on)],
+                vec![left, right],
+            ),
+            PhysicalPlan::Aggregate { input } => (String::from("Aggregate"), vec![], vec![input]),
+            PhysicalPlan::Project { input, items, .. } => {
+                let items: Vec<String> = items.iter().map(|exp| exp.to_string()).collect();
+                (
+                    String::from("Project"),
+                    vec![format!("Output: {}", items.join(", "))],
+                    vec![input],
+                )
+            }
+            PhysicalPlan::Sort { input, descending } => {
+                let mut terms = Vec::new();
+                if let PhysicalPlan::Project { keys, .. } = input.as_ref() {
+                    for (key, descending) in keys.iter().zip(descending) {
+                        let order = if *descending { " desc" } else { "" };
+                        terms.push(format!("{}{}", key, order));
+                    }
+                }
+                (
+                    String::from("Sort"),
+                    vec![format!("Sort Key: {}", terms.join(", "))],
+                    vec![input],
+                )
+            }
+            PhysicalPlan::Limit {
+                input,
+                limit,
+                offset,
+            } => {
+                let mut details = Vec::new();
+                if let Some(limit) = limit {
+                    details.push(format!("Limit: {}", limit));
+                }
+                if let Some(offset) = offset {
+                    details.push(format!("Offset: {}", offset));
+                }
+                (String::from("Limit"), details, vec![input])
+            }
+        }
+    }
+}
+
+/// The share of rows expected to satisfy `exp`. Without statistics on the
+/// data these are fixed guesses for each kind of condition.
+pub fn selectivity(exp: &Expression) -> f64 {
+    match exp.kind {
+        ExpressionKind::Literal if is_false(exp) => 0.0,
+        ExpressionKind::Binary => {
+            let binary = exp.binary.as_ref().unwrap();
+            let (a, b) = (selectivity(&binary.a), selectivity(&binary.b));
+            match binary.op.literal.as_str() {
+                "and" => a * b,
+                "or" => a + b - a * b,
+                "=" => 0.1,
+                "<>" | "!=" => 0.9,
+                "<" | "<=" | ">" | ">=" => 1.0 / 3.0,
+                _ => 0.5,
+            }
+        }
+        ExpressionKind::Unary if exp.literal.literal == Keyword::Not.to_string() => {
+            1.0 - selectivity(&exp.unary.as_ref().unwrap().operand)
+        }
+        ExpressionKind::InList => {
+            let in_list = exp.in_list.as_ref().unwrap();
+            let share = (0.1 * in_list.list.len() as f64).min(1.0);
+            if in_list.negated {
+                1.0 - share
+            } else {
+                share
+            }
+        }
+        ExpressionKind::Between if exp.between.as_ref().unwrap().negated => 0.75,
+        ExpressionKind::Between => 0.25,
+        ExpressionKind::PatternMatch => 0.1,
+        ExpressionKind::IsNull if exp.is_null.as_ref().unwrap().negated => 0.9,
+        ExpressionKind::IsNull => 0.1,
+        _ => 0.5,
+    }
+}
+
+/// What ANALYZE adds to the line of an operator that ran.
+fn actual_text(actual: Option<(usize, Duration)>) -> String {
+    match actual {
+        Some((rows, time)) => format!(
+            " (actual rows={} time={:.3} ms)",
+            rows,
+            time.as_secs_f64() * 1000.0
+        ),
+        None => String::new(),
+    }
+}
+
+/// The conditions ANDed together in `exp`.
+fn split_conjuncts(exp: Expression) -> Vec<Expression> {
+    if exp.kind == ExpressionKind::Binary && exp.literal.literal == Keyword::And.to_string() {
+        let binary = *exp.binary.unwrap();
+        let mut found = split_conjuncts(binary.a);
+        found.extend(split_conjuncts(binary.b));
+        return found;
+    }
+    vec![exp]
+}
+
+/// The one table of a join that every column in `exp` belongs to, either
+/// by its qualifier or as the only table with a column of that name.
+fn single_table<G>(
+    exp: &Expression,
+    left: &LogicalPlan,
+    right: &LogicalPlan,
+    columns: &G,
+) -> Option<String>
+where
+    G: Fn(&str) -> Vec<String>,
+{
+    let mut tables = left.tables();
+    tables.extend(right.tables());
+
+    let mut references = Vec::new();
+    column_references(exp, &mut references);
+    let mut found: Option<&str> = None;
+    for reference in references {
+        let table = match reference.split_once('.') {
+            Some((table, _)) => tables.iter().find(|t| **t == table)?,
+            None => {
+                let mut owners = tables
+                    .iter()
+                    .filter(|t| columns(t).iter().any(|c| c == reference));
+                match (owners.next(), owners.next()) {
+                    (Some(table), None) => table,
+                    _ => return None,
+                }
+            }
+        };
+        if found.is_some_and(|found| found != *table) {
+            return None;
+        }
+        found = Some(table);
+    }
+    found.map(String::from)
+}
+
+/// `a AND b`, or `b` alone without `a`.
+fn and(a: Option<Expression>, b: Expression) -> Expression {
+    let a = match a {
+        Some(a) => a,
+        None => return b,
+    };
+    let op = keyword(Keyword::And);
+    let mut exp = Expression::new(op.clone(), ExpressionKind::Binary);
+    exp.binary = Some(Box::new(BinaryExpression { a, b, op }));
+    exp
+}
+
+fn keyword(keyword: Keyword) -> Token {
+    Token {
+        literal: keyword.to_string(),
+        token_kind: TokenKind::Keyword,
+        loc: Location::new(),
+    }
+}
+
+fn is_false(exp: &Expression) -> bool {
+    exp.kind == ExpressionKind::Literal
+        && exp.literal.token_kind == TokenKind::Keyword
+        && exp.literal.literal == Keyword::False.to_string()
+}
+
+/// A filter with its constant parts folded, `None` when it always holds.
+/// One that never holds, also for being NULL, is `false`.
+fn fold_filter<F>(filter: Expression, evaluate: &F) -> Option<Expression>
+where
+    F: Fn(&Expression) -> Option<Value>,
+{
+    match fold_condition(filter, evaluate) {
+        Folded::Constant(Some(true)) => None,
+        Folded::Constant(_) => Some(Expression::new(
+            keyword(Keyword::False),
+            ExpressionKind::Literal,
+        )),
+        Folded::Expression(exp) => Some(*exp),
+    }
+}
+
+/// A condition folded to a constant boolean, NULL being `None`, or what is
+/// left of it.
+enum Folded {
+    Constant(Option<bool>),
+    Expression(Box<Expression>),
+}
+
+fn fold_condition<F>(exp: Expression, evaluate: &F) -> Folded
+where
+    F: Fn(&Expression) -> Option<Value>,
+{
+    if is_constant(&exp) {
+        // Constants that fail to evaluate are left to fail on each row
+        return match evaluate(&exp) {
+            Some(Value::Bool(b)) => Folded::Constant(Some(b)),
+            Some(Value::Null) => Folded::Constant(None),
+            _ => Folded::Expression(Box::new(exp)),
+        };
+    }
+    if exp.kind != ExpressionKind::Binary {
+        return Folded::Expression(Box::new(exp));
+    }
+    let is_and = exp.literal.literal == Keyword::And.to_string();
+    if !is_and && exp.literal.literal != Keyword::Or.to_string() {
+        return Folded::Expression(Box::new(exp));
+    }
+
+    let mut binary = *exp.binary.unwrap();
+    let a = fold_condition(binary.a, evaluate);
+    let b = fold_condition(binary.b, evaluate);
+    // The value that decides an AND or an OR whatever the other side is
+    let absorbing = !is_and;
+    match (a, b) {
+        (Folded::Constant(Some(c)), other) | (other, Folded::Constant(Some(c))) => {
+            if c == absorbing {
+                Folded::Constant(Some(c))
+            } else {
+                other
+            }
+        }
+        (a, b) => {
+            binary.a = unfold(a);
+            binary.b = unfold(b);
+            let mut exp = Expression::new(binary.op.clone(), ExpressionKind::Binary);
+            exp.binary = Some(Box::new(binary));
+            Folded::Expression(Box::new(exp))
+        }
+    }
+}
+
+fn unfold(folded: Folded) -> Expression {
+    match folded {
+        Folded::Expression(exp) => *exp,
+        Folded::Constant(None) => Expression::new(keyword(Keyword::Null), ExpressionKind::Literal),
+        Folded::Constant(Some(b)) => Expression::new(
+            keyword(if b { Keyword::True } else { Keyword::False }),
+            ExpressionKind::Literal,
+        ),
+    }
+}
+
+#[cfg(test)]
+mod planner_test {
+    use super::*;
+    use crate::{
+        ast::StatementKind, mem_backend::Backend, mem_backend::MemoryBackend, parser::parse,
+    };
+
+    fn plan(mb: &MemoryBackend, source: &str) -> PhysicalPlan {
+        let statement = parse(source.to_string()).unwrap().statements.remove(0);
+        assert_eq!(statement.kind, StatementKind::Select);
+        mb.plan(&statement.select.unwrap()).unwrap()
+    }
+
+    /// The operators of a plan from the top down, and its scan.
+    fn operators(plan: &PhysicalPlan) -> (Vec<&'static str>, &PhysicalPlan) {
+        let (name, input) = match plan {
+            PhysicalPlan::Scan { .. } => return (vec!["Scan"], plan),
+            PhysicalPlan::Filter { input, .. } => ("Filter", input),
+            PhysicalPlan::NestedLoopJoin { left, .. } => ("NestedLoopJoin", left),
+            PhysicalPlan::Aggregate { input } => ("Aggregate", input),
+            PhysicalPlan::Project { input, .. } => ("Project", input),
+            PhysicalPlan::Sort { input, .. } => ("Sort", input),
+            PhysicalPlan::Limit { input, .. } => ("Limit", input),
+        };
+        let (mut names, scan) = operators(input);
+        names.insert(0, name);
+        (names, scan)
+    }
+
+    #[test]
+    fn test_plans() {
+        let mut mb = MemoryBackend::new();
+        for source in [
+            "create table t (a int, b int, c text);",
+            "create index t_a on t (a);",
+            "create table u (a int, b int);",
+        ] {
+            let statement = parse(source.to_string()).unwrap().statements.remove(0);
+            match statement.kind {
+                StatementKind::Create => mb.create(&statement.create.unwrap()).unwrap(),
+                _ => mb.create_index(&statement.create_index.unwrap()).unwrap(),
+            }
+        }
+
+        // The operators, the columns the scan reads, the text of its filter
+        // and how it finds its rows
+        let tests = vec![
+            (
+                "select a from t where b = 1 and 1 = 1 order by c;",
+                vec!["Sort", "Project", "Scan"],
+                vec!["a", "b", "c"],
+                Some("b = 1"),
+                "seq",
+            ),
+            (
+                "select c from t where a = 2 limit 1;",
+                vec!["Limit", "Project", "Scan"],
+                vec!["a", "c"],
+                Some("a = 2"),
+                "index",
+            ),
+            (
+                "select a from t where 1 = 0 and b > a;",
+                vec!["Project", "Scan"],
+                vec!["a"],
+                Some("false"),
+                "empty",
+            ),
+            (
+                "select count(*) from t where 2 > 1 or b = 1;",
+                vec!["Project", "Aggregate", "Scan"],
+                vec![],
+                None,
+                "seq",
+            ),
+            (
+                "select 1 offset 0;",
+                vec!["Limit", "Project", "Scan"],
+                vec![],
+                None,
+                "seq",
+            ),
+        ];
+
+        for (source, expected, expected_columns, expected_filter, expected_access) in tests {
+            let plan = plan(&mb, source);
+            let (names, scan) = operators(&plan);
+            assert_eq!(names, expected, "{}", source);
+            let (access, filter, columns) = match scan {
+                PhysicalPlan::Scan {
+                    access,
+                    filter,
+                    columns,
+                    ..
+                } => (access, filter, columns),
+                _ => unreachable!(),
+            };
+            assert_eq!(columns.as_ref().unwrap(), &expected_columns, "{}", source);
+            let filter_text = filter.as_ref().map(|filter| match &filter.binary {
+                Some(binary) => format!(
+                    "{} {} {}",
+                    binary.a.literal.literal, binary.op.literal, binary.b.literal.literal
+                ),
+                None => filter.literal.literal.clone(),
+            });
+            assert_eq!(filter_text.as_deref(), expected_filter, "{}", source);
+            let access = match access {
+                Access::Sequential => "seq",
+                Access::Index { .. } => "index",
+                Access::Empty => "empty",
+            };
+            assert_eq!(access, expected_access, "{}", source);
+        }
+
+        // Conditions on one table of a join move into its scan, where an
+        // index can find the rows. Conditions on both stay above the join.
+        let plan = plan(
+            &mb,
+            "select t.c from t join u on t.a = u.a where u.b = 1 and t.a = 2 and t.b < u.b;",
+        );
+        let (names, _) = operators(&plan);
+        assert_eq!(names, vec!["Project", "Filter", "NestedLoopJoin", "Scan"]);
+        let (left, right) = match &plan {
+            PhysicalPlan::Project { input, .. } => match input.as_ref() {
+                PhysicalPlan::Filter { input, predicate } => {
+                    assert_eq!(predicate.to_string(), "(t.b < u.b)");
+                    match input.as_ref() {
+                        PhysicalPlan::NestedLoopJoin { left, right, .. } => (left, right),
+                        _ => unreachable!(),
+                    }
+                }
+                _ => unreachable!(),
+            },
+            _ => unreachable!(),
+        };
+        let scan = |plan: &PhysicalPlan| match plan {
+            PhysicalPlan::Scan {
+                access,
+                filter,
+                columns,
+                ..
+            } => (
+                matches!(access, Access::Index { .. }),
+                filter.as_ref().unwrap().to_string(),
+                columns.clone().unwrap(),
+            ),
+            _ => unreachable!(),
+        };
+        assert_eq!(
+            scan(left),
+            (
+                true,
+                String::from("(t.a = 2)"),
+                vec![String::from("a"), String::from("b"), String::from("c")]
+            )
+        );
+        assert_eq!(
+            scan(right),
+            (
+                false,
+                String::from("(u.b = 1)"),
+                vec![String::from("a"), String::from("b")]
+            )
+        );
+    }
+}
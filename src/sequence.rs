@@ -0,0 +1,153 @@
+use std::{cell::RefCell, collections::HashMap, ops::RangeInclusive, rc::Rc};
+
+use crate::{
+    functions::FunctionRegistry,
+    mem_backend::{SQLError, Value},
+};
+
+/// Sequences by name, shared with the functions that advance them.
+pub type Sequences = Rc<RefCell<HashMap<String, Sequence>>>;
+
+/// A generator of integers, from CREATE SEQUENCE or for an identity column.
+#[derive(Debug, Clone)]
+pub struct Sequence {
+    last_value: i64,
+    increment: i64,
+    // Whether last_value was handed out, so that the next call moves past it
+    is_called: bool,
+    // The value the latest nextval returned
+    current: Option<i64>,
+}
+
+impl Sequence {
+    pub fn new(start: i64, increment: i64) -> Sequence {
+        Sequence {
+            last_value: start,
+            increment,
+            is_called: false,
+            current: None,
+        }
+    }
+
+    /// Advances the sequence. `None` once it would overflow.
+    pub fn next_value(&mut self) -> Option<i64> {
+        if self.is_called {
+            self.last_value = self.last_value.checked_add(self.increment)?;
+        }
+        self.is_called = true;
+        self.current = Some(self.last_value);
+        self.current
+    }
+
+    pub fn current(&self) -> Option<i64> {
+        self.current
+    }
+
+    /// Like PostgreSQL's setval: the next value is `value` itself when
+    /// `is_called` is false, the one after it otherwise.
+    pub fn set(&mut self, value: i64, is_called: bool) {
+        self.last_value = value;
+        self.is_called = is_called;
+    }
+}
+
+/// Makes nextval, currval and setval available on `sequences`.
+pub fn register_functions(registry: &mut FunctionRegistry, sequences: &Sequences) {
+    // The name the arguments start with, checking how many there are
+    fn sequence_name(
+        function: &str,
+        args: &[Value],
+        count: RangeInclusive<usize>,
+    ) -> Result<String, SQLError> {
+        match args.first() {
+            Some(Value::Text(name)) if count.contains(&args.len()) => Ok(name.clone()),
+            _ => Err(SQLError::InvalidFunctionArguments(format!(
+                "{} expects a sequence name and {} argument(s) in all",
+                function,
+                match count.start() == count.end() {
+                    true => count.start().to_string(),
+                    false => format!("{} to {}", count.start(), count.end()),
+                }
+            ))),
+        }
+    }
+
+    let shared = sequences.clone();
+    registry.register_scalar("nextval", move |args| {
+        let name = sequence_name("nextval", args, 1..=1)?;
+        match shared.borrow_mut().get_mut(&name) {
+            Some(sequence) => match sequence.next_value() {
+                Some(value) => Ok(Value::BigInt(value)),
+                None => Err(SQLError::OutOfRange(format!(
+                    "nextval: sequence {} reached its limit",
+                    name
+                ))),
+            },
+            None => Err(SQLError::SequenceDoesNotExist(name)),
+        }
+    });
+
+    let shared = sequences.clone();
+    registry.register_scalar("currval", move |args| {
+        let name = sequence_name("currval", args, 1..=1)?;
+        match shared.borrow().get(&name) {
+            Some(sequence) => match sequence.current() {
+                Some(value) => Ok(Value::BigInt(value)),
+                None => Err(SQLError::InvalidFunctionArguments(format!(
+                    "currval of sequence {} is not yet defined",
+                    name
+                ))),
+            },
+            None => Err(SQLError::SequenceDoesNotExist(name)),
+        }
+    });
+
+    let shared = sequences.clone();
+    registry.register_scalar("setval", move |args| {
+        let name = sequence_name("setval", args, 2..=3)?;
+        let (value, is_called) = match (args[1].as_i64(), args.get(2)) {
+            (Some(value), None) => (value, true),
+            (Some(value), Some(Value::Bool(is_called))) => (value, *is_called),
+            _ => {
+                return Err(SQLError::InvalidFunctionArguments(String::from(
+                    "setval expects an integer value and an optional boolean",
+                )))
+            }
+        };
+        match shared.borrow_mut().get_mut(&name) {
+            Some(sequence) => sequence.set(value, is_called),
+            None => return Err(SQLError::SequenceDoesNotExist(name)),
+        }
+        Ok(Value::BigInt(value))
+    });
+}
+
+#[cfg(test)]
+mod sequence_test {
+    use crate::sequence::Sequence;
+
+    #[test]
+    fn test_sequence() {
+        let mut sequence = Sequence::new(5, 3);
+        assert_eq!(sequence.current(), None);
+        assert_eq!(sequence.next_value(), Some(5));
+        assert_eq!(sequence.next_value(), Some(8));
+        assert_eq!(sequence.current(), Some(8));
+
+        sequence.set(20, false);
+        assert_eq!(sequence.current(), Some(8));
+        assert_eq!(sequence.next_value(), Some(20));
+        sequence.set(20, true);
+        assert_eq!(sequence.next_value(), Some(23));
+
+        let mut descending = Sequence::new(-1, -1);
+        assert_eq!(descending.next_value(), Some(-1));
+        assert_eq!(descending.next_value(), Some(-2));
+
+        let mut near_end = Sequence::new(i64::MAX - 1, 1);
+        assert_eq!(near_end.next_value(), Some(i64::MAX - 1));
+        assert_eq!(near_end.next_value(), Some(i64::MAX));
+        assert_eq!(near_end.next_value(), None);
+        assert_eq!(near_end.current(), Some(i64::MAX));
+    }
+}
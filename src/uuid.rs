@@ -0,0 +1,133 @@
+use core::fmt;
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    sync::atomic::{AtomicU64, Ordering},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// A universally unique identifier, stored as its 16 bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Uuid(pub [u8; 16]);
+
+static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+impl Uuid {
+    /// A random (version 4) UUID. The randomness comes from the standard
+    /// library's randomly keyed hasher, fed with a counter and the time.
+    pub fn new_v4() -> Uuid {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+
+        let mut bytes = [0; 16];
+        for (i, half) in bytes.chunks_mut(8).enumerate() {
+            let mut hasher = RandomState::new().build_hasher();
+            hasher.write_u128(nanos);
+            hasher.write_u64(count);
+            hasher.write_usize(i);
+            half.copy_from_slice(&hasher.finish().to_le_bytes());
+        }
+        bytes[6] = (bytes[6] & 0x0f) | 0x40;
+        bytes[8] = (bytes[8] & 0x3f) | 0x80;
+        Uuid(bytes)
+    }
+
+    /// Parses the canonical form `a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11`.
+    /// Like PostgreSQL, upper case, missing hyphens and surrounding braces
+    /// are accepted too.
+    pub fn parse(s: &str) -> Option<Uuid> {
+        let s = s.trim();
+        let s = match s.strip_prefix('{') {
+            Some(inner) => inner.strip_suffix('}')?,
+            None => s,
+        };
+        let digits: Vec<u8> = if s.len() == 36 {
+            for (i, c) in s.char_indices() {
+                if matches!(i, 8 | 13 | 18 | 23) != (c == '-') {
+                    return None;
+                }
+            }
+            s.bytes().filter(|b| *b != b'-').collect()
+        } else {
+            s.bytes().collect()
+        };
+        if digits.len() != 32 || !digits.iter().all(u8::is_ascii_hexdigit) {
+            return None;
+        }
+
+        let mut bytes = [0; 16];
+        for (i, pair) in digits.chunks(2).enumerate() {
+            let pair = std::str::from_utf8(pair).ok()?;
+            bytes[i] = u8::from_str_radix(pair, 16).ok()?;
+        }
+        Some(Uuid(bytes))
+    }
+
+    pub fn from_slice(bytes: &[u8]) -> Option<Uuid> {
+        bytes.try_into().ok().map(Uuid)
+    }
+}
+
+impl fmt::Display for Uuid {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, byte) in self.0.iter().enumerate() {
+            if matches!(i, 4 | 6 | 8 | 10) {
+                write!(f, "-")?;
+            }
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod uuid_test {
+    use std::collections::HashSet;
+
+    use crate::uuid::Uuid;
+
+    #[test]
+    fn test_parse_and_format() {
+        let canonical = "a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11";
+        let tests = vec![
+            canonical,
+            "A0EEBC99-9C0B-4EF8-BB6D-6BB9BD380A11",
+            "{a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11}",
+            "a0eebc999c0b4ef8bb6d6bb9bd380a11",
+            " a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11 ",
+        ];
+        for input in tests {
+            assert_eq!(
+                Uuid::parse(input).map(|uuid| uuid.to_string()),
+                Some(canonical.to_string()),
+                "{}",
+                input
+            );
+        }
+
+        for bad in [
+            "",
+            "a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a1",
+            "a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a111",
+            "a0eebc999-c0b-4ef8-bb6d-6bb9bd380a11",
+            "g0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11",
+            "{a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11",
+        ] {
+            assert!(Uuid::parse(bad).is_none(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_new_v4() {
+        let uuids: HashSet<Uuid> = (0..1000).map(|_| Uuid::new_v4()).collect();
+        assert_eq!(uuids.len(), 1000);
+        for uuid in uuids {
+            assert_eq!(uuid.0[6] >> 4, 4);
+            assert_eq!(uuid.0[8] >> 6, 0b10);
+            assert_eq!(Uuid::parse(&uuid.to_string()), Some(uuid));
+        }
+    }
+}
@@ -0,0 +1,418 @@
+use std::cmp::Ordering;
+
+use crate::{
+    functions::{AggregateFunction, FunctionRegistry},
+    mem_backend::{compare_keys, SQLError, Value},
+};
+
+pub enum WindowFunction<'a> {
+    RowNumber,
+    Rank,
+    DenseRank,
+    Lag,
+    Lead,
+    FirstValue,
+    LastValue,
+    // Any aggregate, folded over the frame of every row
+    Aggregate(&'a AggregateFunction),
+}
+
+impl<'a> WindowFunction<'a> {
+    pub fn from_name(name: &str, functions: &'a FunctionRegistry) -> Option<WindowFunction<'a>> {
+        match name.to_lowercase().as_str() {
+            "row_number" => Some(WindowFunction::RowNumber),
+            "rank" => Some(WindowFunction::Rank),
+            "dense_rank" => Some(WindowFunction::DenseRank),
+            "lag" => Some(WindowFunction::Lag),
+            "lead" => Some(WindowFunction::Lead),
+            "first_value" => Some(WindowFunction::FirstValue),
+            "last_value" => Some(WindowFunction::LastValue),
+            name => functions.aggregate(name).map(WindowFunction::Aggregate),
+        }
+    }
+
+    /// Whether `name` can only be called with an OVER clause.
+    pub fn is_window_only(name: &str) -> bool {
+        matches!(
+            name.to_lowercase().as_str(),
+            "row_number" | "rank" | "dense_rank" | "lag" | "lead" | "first_value" | "last_value"
+        )
+    }
+
+    fn check_args(&self, count: usize) -> Result<(), SQLError> {
+        let (name, min, max) = match self {
+            WindowFunction::RowNumber => ("row_number", 0, 0),
+            WindowFunction::Rank => ("rank", 0, 0),
+            WindowFunction::DenseRank => ("dense_rank", 0, 0),
+            WindowFunction::Lag => ("lag", 1, 3),
+            WindowFunction::Lead => ("lead", 1, 3),
+            WindowFunction::FirstValue => ("first_value", 1, 1),
+            WindowFunction::LastValue => ("last_value", 1, 1),
+            WindowFunction::Aggregate(_) => return Ok(()),
+        };
+        if count < min || count > max {
+            return Err(SQLError::InvalidFunctionArguments(format!(
+                "{} expects between {} and {} argument(s), got {}",
+                name, min, max, count
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameUnits {
+    Rows,
+    Range,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bound {
+    UnboundedPreceding,
+    Preceding(i64),
+    CurrentRow,
+    Following(i64),
+    UnboundedFollowing,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Frame {
+    pub units: FrameUnits,
+    pub start: Bound,
+    pub end: Bound,
+}
+
+impl Default for Frame {
+    /// `RANGE BETWEEN UNBOUNDED PRECEDING AND CURRENT ROW`, which covers the
+    /// whole partition when there is no ORDER BY since every row is a peer.
+    fn default() -> Self {
+        Frame {
+            units: FrameUnits::Range,
+            start: Bound::UnboundedPreceding,
+            end: Bound::CurrentRow,
+        }
+    }
+}
+
+/// The inputs of a window function for one row, already evaluated.
+pub struct WindowRow {
+    pub partition: Vec<Value>,
+    pub order: Vec<Value>,
+    pub args: Vec<Value>,
+}
+
+/// Computes the window function for every row, returning the results in the
+/// order of `rows`.
+pub fn evaluate(
+    function: &WindowFunction,
+    rows: &[WindowRow],
+    descending: &[bool],
+    frame: &Frame,
+) -> Result<Vec<Value>, SQLError> {
+    if let Some(first) = rows.first() {
+        function.check_args(first.args.len())?;
+    }
+
+    let order_cmp = |a: &WindowRow, b: &WindowRow| compare_keys(&a.order, &b.order, descending);
+
+    let mut sorted: Vec<usize> = (0..rows.len()).collect();
+    sorted.sort_by(|a, b| {
+        let (a, b) = (&rows[*a], &rows[*b]);
+        a.partition.cmp(&b.partition).then_with(|| order_cmp(a, b))
+    });
+
+    let mut results = vec![Value::Null; rows.len()];
+    for partition in sorted.chunk_by(|a, b| rows[*a].partition == rows[*b].partition) {
+        // Peer groups share the same ORDER BY values
+        let mut peer_starts = Vec::with_capacity(partition.len());
+        let mut peer_ends = vec![0; partition.len()];
+        let mut peer_numbers = Vec::with_capacity(partition.len());
+        for (pos, index) in partition.iter().enumerate() {
+            if pos > 0 && order_cmp(&rows[partition[pos - 1]], &rows[*index]) == Ordering::Equal {
+                peer_starts.push(peer_starts[pos - 1]);
+                peer_numbers.push(peer_numbers[pos - 1]);
+            } else {
+                peer_starts.push(pos);
+                peer_numbers.push(peer_numbers.last().map_or(1, |n| n + 1));
+            }
+        }
+        for pos in (0..partition.len()).rev() {
+            peer_ends[pos] =
+                if pos + 1 < partition.len() && peer_starts[pos + 1] == peer_starts[pos] {
+                    peer_ends[pos + 1]
+                } else {
+                    pos
+                };
+        }
+
+        for (pos, index) in partition.iter().enumerate() {
+            let row = &rows[*index];
+            results[*index] = match function {
+                WindowFunction::RowNumber => Value::BigInt(pos as i64 + 1),
+                WindowFunction::Rank => Value::BigInt(peer_starts[pos] as i64 + 1),
+                WindowFunction::DenseRank => Value::BigInt(peer_numbers[pos]),
+                WindowFunction::Lag | WindowFunction::Lead => {
+                    let offset = match row.args.get(1) {
+                        Some(offset) if offset.as_i64().is_some() => offset.as_i64().unwrap(),
+                        Some(Value::Null) => {
+                            results[*index] = Value::Null;
+                            continue;
+                        }
+                        Some(_) => {
+                            return Err(SQLError::InvalidFunctionArguments(String::from(
+                                "offset must be an integer",
+                            )))
+                        }
+                        None => 1,
+                    };
+                    let offset = match function {
+                        WindowFunction::Lag => -offset,
+                        _ => offset,
+                    };
+                    let target = pos as i64 + offset;
+                    if target >= 0 && target < partition.len() as i64 {
+                        rows[partition[target as usize]].args[0].clone()
+                    } else {
+                        row.args.get(2).cloned().unwrap_or(Value::Null)
+                    }
+                }
+                WindowFunction::FirstValue
+                | WindowFunction::LastValue
+                | WindowFunction::Aggregate(_) => {
+                    let (start, end) = frame_bounds(
+                        frame,
+                        partition,
+                        rows,
+                        descending,
+                        pos,
+                        (peer_starts[pos], peer_ends[pos]),
+                    )?;
+                    let frame_rows = if start <= end {
+                        &partition[start..=end]
+                    } else {
+                        &partition[0..0]
+                    };
+
+                    match function {
+                        WindowFunction::FirstValue => frame_rows
+                            .first()
+                            .map_or(Value::Null, |first| rows[*first].args[0].clone()),
+                        WindowFunction::LastValue => frame_rows
+                            .last()
+                            .map_or(Value::Null, |last| rows[*last].args[0].clone()),
+                        WindowFunction::Aggregate(aggregate) => {
+                            let mut acc = aggregate.accumulator();
+                            for frame_row in frame_rows {
+                                acc.step(&rows[*frame_row].args)?;
+                            }
+                            acc.finalize()?
+                        }
+                        _ => unreachable!(),
+                    }
+                }
+            };
+        }
+    }
+
+    Ok(results)
+}
+
+/// The first and last position of the frame of the row at `pos`. The frame
+/// is empty when the start comes after the end.
+fn frame_bounds(
+    frame: &Frame,
+    partition: &[usize],
+    rows: &[WindowRow],
+    descending: &[bool],
+    pos: usize,
+    peers: (usize, usize),
+) -> Result<(usize, usize), SQLError> {
+    let last = partition.len() as i64 - 1;
+    let start = match frame.units {
+        FrameUnits::Rows => rows_bound(frame.start, pos, last),
+        FrameUnits::Range => {
+            range_bound(frame.start, true, partition, rows, descending, pos, peers)?
+        }
+    };
+    let end = match frame.units {
+        FrameUnits::Rows => rows_bound(frame.end, pos, last),
+        FrameUnits::Range => {
+            range_bound(frame.end, false, partition, rows, descending, pos, peers)?
+        }
+    };
+
+    let (start, end) = (start.max(0), end.min(last));
+    if start > end {
+        return Ok((1, 0));
+    }
+    Ok((start as usize, end as usize))
+}
+
+fn rows_bound(bound: Bound, pos: usize, last: i64) -> i64 {
+    let pos = pos as i64;
+    match bound {
+        Bound::UnboundedPreceding => 0,
+        Bound::Preceding(offset) => pos - offset,
+        Bound::CurrentRow => pos,
+        Bound::Following(offset) => pos + offset,
+        Bound::UnboundedFollowing => last,
+    }
+}
+
+fn range_bound(
+    bound: Bound,
+    is_start: bool,
+    partition: &[usize],
+    rows: &[WindowRow],
+    descending: &[bool],
+    pos: usize,
+    peers: (usize, usize),
+) -> Result<i64, SQLError> {
+    let offset = match bound {
+        Bound::UnboundedPreceding => return Ok(0),
+        Bound::UnboundedFollowing => return Ok(partition.len() as i64 - 1),
+        Bound::CurrentRow if is_start => return Ok(peers.0 as i64),
+        Bound::CurrentRow => return Ok(peers.1 as i64),
+        Bound::Preceding(offset) => -offset,
+        Bound::Following(offset) => offset,
+    };
+
+    if descending.len() != 1 {
+        return Err(SQLError::InvalidWindowFunction(String::from(
+            "RANGE with an offset requires exactly one ORDER BY column",
+        )));
+    }
+
+    // Keys are mapped so that the partition is always ascending in them
+    let key = |index: usize| -> Result<Option<i64>, SQLError> {
+        match &rows[partition[index]].order[0] {
+            Value::Null => Ok(None),
+            value => match value.as_i64() {
+                Some(i) if descending[0] => Ok(Some(-i)),
+                Some(i) => Ok(Some(i)),
+                None => Err(SQLError::InvalidWindowFunction(String::from(
+                    "RANGE with an offset requires an integer ORDER BY column",
+                ))),
+            },
+        }
+    };
+
+    // NULL keys only see their peers
+    let target = match key(pos)? {
+        Some(current) => current + offset,
+        None if is_start => return Ok(peers.0 as i64),
+        None => return Ok(peers.1 as i64),
+    };
+
+    let mut bound = if is_start { partition.len() as i64 } else { -1 };
+    for index in 0..partition.len() {
+        let value = match key(index)? {
+            Some(value) => value,
+            None => continue,
+        };
+        if is_start && value >= target {
+            bound = index as i64;
+            break;
+        }
+        if !is_start && value <= target {
+            bound = index as i64;
+        }
+    }
+    Ok(bound)
+}
+
+#[cfg(test)]
+mod window_test {
+    use crate::functions::FunctionRegistry;
+    use crate::mem_backend::Value;
+    use crate::window::{evaluate, Bound, Frame, FrameUnits, WindowFunction, WindowRow};
+
+    fn rows(keys: &[(i32, i32)]) -> Vec<WindowRow> {
+        keys.iter()
+            .map(|(partition, order)| WindowRow {
+                partition: vec![Value::Int(*partition)],
+                order: vec![Value::Int(*order)],
+                args: vec![Value::Int(*order)],
+            })
+            .collect()
+    }
+
+    fn ints(values: Vec<Value>) -> Vec<i64> {
+        values
+            .into_iter()
+            .map(|value| value.as_i64().unwrap_or(-1))
+            .collect()
+    }
+
+    #[test]
+    fn test_ranking() {
+        let functions = FunctionRegistry::new();
+        let rows = rows(&[(1, 20), (1, 10), (1, 20), (2, 5), (1, 30)]);
+
+        let tests = vec![
+            ("row_number", vec![2, 1, 3, 1, 4]),
+            ("rank", vec![2, 1, 2, 1, 4]),
+            ("dense_rank", vec![2, 1, 2, 1, 3]),
+        ];
+        for (name, expected) in tests {
+            let function = WindowFunction::from_name(name, &functions).unwrap();
+            let rows: Vec<WindowRow> = rows
+                .iter()
+                .map(|row| WindowRow {
+                    partition: row.partition.clone(),
+                    order: row.order.clone(),
+                    args: Vec::new(),
+                })
+                .collect();
+            let results = evaluate(&function, &rows, &[false], &Frame::default()).unwrap();
+            assert_eq!(ints(results), expected, "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_frames() {
+        let functions = FunctionRegistry::new();
+        let sum = WindowFunction::from_name("sum", &functions).unwrap();
+        let rows = rows(&[(1, 1), (1, 2), (1, 2), (1, 4), (1, 7)]);
+
+        let tests = vec![
+            (Frame::default(), vec![1, 5, 5, 9, 16]),
+            (
+                Frame {
+                    units: FrameUnits::Rows,
+                    start: Bound::UnboundedPreceding,
+                    end: Bound::CurrentRow,
+                },
+                vec![1, 3, 5, 9, 16],
+            ),
+            (
+                Frame {
+                    units: FrameUnits::Rows,
+                    start: Bound::Preceding(1),
+                    end: Bound::Following(1),
+                },
+                vec![3, 5, 8, 13, 11],
+            ),
+            (
+                Frame {
+                    units: FrameUnits::Range,
+                    start: Bound::Preceding(2),
+                    end: Bound::CurrentRow,
+                },
+                vec![1, 5, 5, 8, 7],
+            ),
+            (
+                Frame {
+                    units: FrameUnits::Rows,
+                    start: Bound::Following(1),
+                    end: Bound::UnboundedFollowing,
+                },
+                vec![15, 13, 11, 7, -1],
+            ),
+        ];
+        for (frame, expected) in tests {
+            let results = evaluate(&sum, &rows, &[false], &frame).unwrap();
+            assert_eq!(ints(results), expected, "{:?}", frame);
+        }
+    }
+}